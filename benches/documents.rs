@@ -0,0 +1,92 @@
+//! Time spent checking typical and pathological documents, run with `cargo bench --bench documents`
+//!
+//! like `wide_tables` this uses no benchmark framework, each case is run a fixed number of times and the mean time per
+//! document is printed, compare the numbers of two builds on the same machine
+
+use std::hint::black_box;
+use std::time::Instant;
+use toml::{Table, Value};
+use toml_schema::TomlSchema;
+
+
+fn bench(name: &str, schema: &TomlSchema, doc: &Value, runs: u32) {
+    for _ in 0..runs / 10 {
+        schema.check(black_box(doc)).expect("the benchmark document is valid");
+    }
+    let start = Instant::now();
+    for _ in 0..runs {
+        schema.check(black_box(doc)).expect("the benchmark document is valid");
+    }
+    println!("{:<40} {:>10.2?} per document", name, start.elapsed() / runs);
+}
+
+/// A manifest like the ones of large workspaces, checked with the Cargo schema of the tests
+fn cargo_manifest() {
+    let schema: TomlSchema = std::fs::read_to_string("test_files/test_schema.toml").expect("the test schema exists")
+        .parse().expect("the test schema is valid");
+    let mut text = String::from("[package]\nname = 'big'\nversion = '1.2.3'\nedition = '2021'\nauthors = ['a', 'b']\n");
+    text.push_str("[features]\ndefault = ['std']\nstd = []\n[dependencies]\n");
+    for i in 0..200 {
+        match i % 2 {
+            0 => text.push_str(&format!("dep-{} = '1.{}'\n", i, i)),
+            _ => text.push_str(&format!("dep-{} = {{version = '0.{}', features = ['a', 'b'], optional = true}}\n", i, i)),
+        }
+    }
+    let doc = Value::Table(text.parse().expect("the manifest is valid TOML"));
+    bench("manifest with 200 dependencies", &schema, &doc, 5_000);
+}
+
+/// Tables nested `depth` levels deep, each with a scalar and the next level
+fn deep_nesting(depth: usize) {
+    let mut schema = String::from("type = 'int'");
+    let mut doc = Value::Integer(1);
+    for _ in 0..depth {
+        schema = format!("name = 'string'\nnext = {{{}}}", schema.replace('\n', ", "));
+        let mut table = Table::new();
+        table.insert("name".into(), Value::String("n".into()));
+        table.insert("next".into(), doc);
+        doc = Value::Table(table);
+    }
+    let schema: TomlSchema = schema.parse().expect("the nested schema is valid");
+    bench(&format!("{} nested tables", depth), &schema, &doc, 20_000);
+}
+
+/// A table whose keys are all matched by extras with regexes, the last extras match most keys
+fn regex_extras() {
+    let mut schema = String::from("extras = [\n");
+    for i in 0..20 {
+        schema.push_str(&format!("    {{key = '^group{}_[a-z]+[0-9]*$', schema = 'int'}},\n", i));
+    }
+    schema.push_str("    {key = '^[a-z]+_[0-9]+$', schema = {type = 'string', regex = '^[A-Z][a-z]+$'}},\n]");
+    let schema: TomlSchema = schema.parse().expect("the extras schema is valid");
+
+    let mut table = Table::new();
+    for i in 0..200 {
+        table.insert(format!("key_{}", i), Value::String("Value".into()));
+    }
+    for i in 0..20 {
+        table.insert(format!("group{}_x{}", i, i), Value::Integer(i));
+    }
+    bench("220 keys matched by 21 regex extras", &schema, &Value::Table(table), 5_000);
+}
+
+/// Arrays of values matched by the last options of alternatives
+fn alternatives() {
+    let schema: TomlSchema = "
+        type = 'array'
+        child = {type = 'alternative', options = ['bool', 'string', {type = 'int', max = 0}, {type = 'int', min = 1}, {name = 'string'}]}
+    ".parse().expect("the alternative schema is valid");
+    let items: Vec<Value> = (0..500).map(|i| match i % 3 {
+        0 => Value::Integer(i),
+        1 => Value::Table(Table::from_iter([("name".to_string(), Value::String("x".into()))])),
+        _ => Value::String("s".into()),
+    }).collect();
+    bench("500 elements of a 5-option alternative", &schema, &Value::Array(items), 5_000);
+}
+
+fn main() {
+    cargo_manifest();
+    deep_nesting(64);
+    regex_extras();
+    alternatives();
+}
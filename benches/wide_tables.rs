@@ -0,0 +1,68 @@
+//! Time spent checking documents made of wide tables, run with `cargo bench --bench wide_tables`
+//!
+//! this uses no benchmark framework, each case is run a fixed number of times and the mean time per document is
+//! printed, compare the numbers of two builds on the same machine
+
+use std::hint::black_box;
+use std::time::Instant;
+use toml::{Table, Value};
+use toml_schema::TomlSchema;
+
+
+/// A schema with `required` keys without a default, `optional` keys with one and extras for the `x-` keys
+fn wide_schema(required: usize, optional: usize) -> TomlSchema {
+    let mut text = String::new();
+    for i in 0..required {
+        text.push_str(&format!("key_{} = {{type = 'int', min = 0}}\n", i));
+    }
+    for i in 0..optional {
+        text.push_str(&format!("opt_{} = {{type = 'string', default = 'x'}}\n", i));
+    }
+    text.push_str("extras = [{key = '^x-', schema = 'anything'}]\n");
+    text.parse().expect("the benchmark schema is valid")
+}
+
+/// A document with all the required keys, every other optional key and `extras` extra keys
+fn wide_doc(required: usize, optional: usize, extras: usize) -> Value {
+    let mut table = Table::new();
+    for i in 0..required {
+        table.insert(format!("key_{}", i), Value::Integer(i as i64));
+    }
+    for i in (0..optional).step_by(2) {
+        table.insert(format!("opt_{}", i), Value::String("y".into()));
+    }
+    for i in 0..extras {
+        table.insert(format!("x-{}", i), Value::Boolean(true));
+    }
+    Value::Table(table)
+}
+
+fn bench(name: &str, schema: &TomlSchema, doc: &Value, runs: u32) {
+    // warm up the caches and the lazy regexes
+    for _ in 0..runs / 10 {
+        schema.check(black_box(doc)).expect("the benchmark document is valid");
+    }
+    let start = Instant::now();
+    for _ in 0..runs {
+        schema.check(black_box(doc)).expect("the benchmark document is valid");
+    }
+    println!("{:<40} {:>10.2?} per document", name, start.elapsed() / runs);
+}
+
+fn main() {
+    for (required, optional, extras) in [(8, 8, 0), (64, 64, 16), (512, 256, 128)] {
+        let schema = wide_schema(required, optional);
+        let doc = wide_doc(required, optional, extras);
+        let runs = 2_000_000 / (required + optional + extras) as u32;
+        bench(&format!("{} required, {} optional, {} extras", required, optional, extras), &schema, &doc, runs);
+    }
+
+    // many small documents, the case where per-table overhead shows
+    let schema = wide_schema(4, 4);
+    let docs: Vec<Value> = (0..10_000).map(|_| wide_doc(4, 4, 1)).collect();
+    let start = Instant::now();
+    for doc in &docs {
+        schema.check(black_box(doc)).expect("the benchmark document is valid");
+    }
+    println!("{:<40} {:>10.2?} per document", "10000 small documents", start.elapsed() / docs.len() as u32);
+}
@@ -0,0 +1,34 @@
+//! Runs the test vectors of schema files, `cargo run --example test_vectors -- schema.toml...`
+//!
+//! the vectors of `name.toml` are read from `name.tests.toml`, the exit code is non-zero if any vector fails
+
+use std::process::ExitCode;
+use toml_schema::vectors::run_schema_tests;
+
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: test_vectors <schema.toml>...");
+        return ExitCode::FAILURE
+    }
+
+    let mut ok = true;
+    for path in &paths {
+        match run_schema_tests(path) {
+            Ok(failures) if failures.is_empty() => println!("{}: ok", path),
+            Ok(failures) => {
+                ok = false;
+                for failure in failures {
+                    println!("{}: {}", path, failure);
+                }
+            },
+            Err(e) => {
+                ok = false;
+                println!("{}", e);
+            }
+        }
+    }
+
+    if ok {ExitCode::SUCCESS} else {ExitCode::FAILURE}
+}
@@ -0,0 +1,200 @@
+use std::ops::Deref;
+use crate::*;
+
+
+/// The options of [TomlSchema::Alternative] with the types of values each one can accept, computed once so that
+/// checking a value skips the options of another type without running them
+///
+/// it dereferences to the slice of options, they are tried in order and the first one that matches is the one that
+/// counts
+#[derive(Debug, Clone)]
+pub struct AlternativeSchema {
+    options: Box<[TomlSchema]>,
+    /// for each option, the bits of [value_bit] for the values it may accept
+    accepted: Box<[u8]>,
+    /// whether an option matches any value, the alternative then never fails
+    anything: bool,
+}
+
+/// All value types, for the options whose accepted types are not known from the schema alone
+const ALL_VALUES: u8 = u8::MAX;
+
+impl AlternativeSchema {
+    pub fn new(options: Vec<TomlSchema>) -> Self {
+        let accepted = options.iter().map(accepted_values).collect();
+        let anything = options.iter().any(matches_anything);
+        AlternativeSchema { options: options.into(), accepted, anything }
+    }
+
+    pub fn options(&self) -> &[TomlSchema] {
+        &self.options
+    }
+
+    /// Whether an option matches any value, like `anything`
+    pub fn matches_anything(&self) -> bool {
+        self.anything
+    }
+
+    /// Whether the option at `index` can match a value of the type of `value`, when it cannot its check would only fail
+    /// with [SchemaError::TypeMismatch]
+    pub fn may_accept(&self, index: usize, value: &Value) -> bool {
+        self.accepted[index] & value_bit(value) != 0
+    }
+
+    /// The index of the first option that matches `value`, the one that counts for [TomlSchema::check]
+    pub(crate) fn matching(&self, value: &Value, depth: usize) -> Option<usize> {
+        (0..self.options.len()).find(|&i| self.may_accept(i, value) && self.options[i].check_depth(value, depth).is_ok())
+    }
+}
+
+impl TomlSchema {
+    /// The index of the option of an alternative schema that matches `value`, the first one in order, `None` when no
+    /// option matches or when this is not an alternative
+    pub fn matched_option(&self, value: &Value) -> Option<usize> {
+        match self.structure() {
+            TomlSchema::Alternative(options) => options.matching(value, DEFAULT_MAX_DEPTH),
+            _ => None
+        }
+    }
+}
+
+impl Deref for AlternativeSchema {
+    type Target = [TomlSchema];
+    fn deref(&self) -> &[TomlSchema] {
+        &self.options
+    }
+}
+
+impl From<Vec<TomlSchema>> for AlternativeSchema {
+    fn from(options: Vec<TomlSchema>) -> Self {
+        AlternativeSchema::new(options)
+    }
+}
+
+
+const STRING: u8 = 1;
+const INTEGER: u8 = 1 << 1;
+const FLOAT: u8 = 1 << 2;
+const BOOL: u8 = 1 << 3;
+const DATE: u8 = 1 << 4;
+const ARRAY: u8 = 1 << 5;
+const TABLE: u8 = 1 << 6;
+
+fn value_bit(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => STRING,
+        Value::Integer(_) => INTEGER,
+        Value::Float(_) => FLOAT,
+        Value::Boolean(_) => BOOL,
+        Value::Datetime(_) => DATE,
+        Value::Array(_) => ARRAY,
+        Value::Table(_) => TABLE,
+    }
+}
+
+/// The values a schema may accept, only the schemas that fail with a [SchemaError::TypeMismatch] on other values are
+/// restricted, nested alternatives, exact values and plugins are always checked to keep their errors
+fn accepted_values(schema: &TomlSchema) -> u8 {
+    match schema {
+        TomlSchema::String { .. } => STRING,
+        TomlSchema::Integer { coerce, .. } => INTEGER | if *coerce {STRING} else {0},
+        TomlSchema::Float { coerce, .. } => FLOAT | if *coerce {STRING} else {0},
+        TomlSchema::Bool { coerce } => BOOL | if *coerce {STRING} else {0},
+        TomlSchema::Date(_) => DATE,
+        TomlSchema::Array { .. } => ARRAY,
+        TomlSchema::Table(_) | TomlSchema::Map(_) => TABLE,
+        TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Custom { schema, .. } => {
+            accepted_values(schema)
+        },
+        TomlSchema::Graded { schema, severity: Severity::Error } => accepted_values(schema),
+        _ => ALL_VALUES
+    }
+}
+
+fn matches_anything(schema: &TomlSchema) -> bool {
+    match schema {
+        TomlSchema::Anything => true,
+        TomlSchema::Alternative(options) => options.matches_anything(),
+        TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. } => matches_anything(schema),
+        TomlSchema::Graded { schema, .. } => matches_anything(schema),
+        _ => false
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_filter() {
+        let schema: TomlSchema = "
+            type = 'alternative'
+            options = ['int', {type = 'array', child = 'int'}, {type = 'exact', value = 'auto'}, {x = 'int'}]
+        ".parse().unwrap();
+        let TomlSchema::Alternative(alternative) = &schema else {panic!("{:?}", schema)};
+        let value = Value::String("auto".into());
+
+        assert!(!alternative.may_accept(0, &value));
+        assert!(!alternative.may_accept(1, &value));
+        assert!(alternative.may_accept(2, &value));
+        assert!(alternative.may_accept(3, &Value::Table(toml::Table::new())));
+        assert!(!alternative.matches_anything());
+
+        schema.check(&value).unwrap();
+        schema.check(&Value::Array(vec![Value::Integer(1)])).unwrap();
+        // skipped options still report their type
+        let err = format!("{:?}", schema.check(&Value::Boolean(true)).unwrap_err());
+        assert!(err.contains("Expected array but got bool") && err.contains("is not exactly"), "{}", err);
+        let err = "type = 'alternative'\noptions = ['int', 'string']".parse::<TomlSchema>().unwrap()
+            .check_str("x = 1").unwrap_err();
+        assert!(err.contains("Expected int or string but got table"), "{}", err);
+    }
+
+    #[test]
+    fn anything_option() {
+        let schema: TomlSchema = "
+            type = 'alternative'
+            options = ['int', {type = 'anything', description = 'any value'}]
+        ".parse().unwrap();
+        let TomlSchema::Alternative(alternative) = &schema else {panic!("{:?}", schema)};
+        assert!(alternative.matches_anything());
+        schema.check(&Value::Boolean(true)).unwrap();
+    }
+
+    #[test]
+    fn graded_option() {
+        let schema: TomlSchema = "
+            type = 'alternative'
+            options = ['bool', {type = 'int', min = 10, severity = 'warning'}]
+        ".parse().unwrap();
+        let TomlSchema::Alternative(alternative) = &schema else {panic!("{:?}", schema)};
+        assert!(!alternative.matches_anything());
+
+        // the value of the graded option is accepted with a warning
+        let value = Value::Integer(5);
+        schema.check(&value).unwrap();
+        let report = schema.validate(&value);
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!(report.with_severity(Severity::Warning).count(), 1, "{:?}", report);
+        assert!(schema.validate(&Value::Boolean(true)).errors.is_empty());
+        assert!(schema.validate(&Value::Integer(10)).errors.is_empty());
+    }
+
+    #[test]
+    fn matched_option() {
+        let schema: TomlSchema = "
+            type = 'alternative'
+            options = [{type = 'int', max = 10}, 'int', {path = 'string'}, {url = 'string'}]
+            description = 'a size or a source'
+        ".parse().unwrap();
+        let option = |doc: &str| schema.matched_option(&doc.parse::<toml::Table>().unwrap()["v"]);
+        assert_eq!(option("v = 5"), Some(0));
+        assert_eq!(option("v = 50"), Some(1));
+        assert_eq!(option("v = {url = 'x'}"), Some(3));
+        assert_eq!(option("v = true"), None);
+        assert_eq!(TomlSchema::int(0, 1).matched_option(&Value::Integer(0)), None);
+    }
+}
@@ -0,0 +1,309 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::*;
+
+
+/// The comparison made by an [Assertion]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssertionKind {
+    Eq, Ne, Lt, Lte, Gt, Gte
+}
+
+impl AssertionKind {
+    /// The name of this kind in the `kind` key of assertions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssertionKind::Eq => "eq",
+            AssertionKind::Ne => "ne",
+            AssertionKind::Lt => "lt",
+            AssertionKind::Lte => "lte",
+            AssertionKind::Gt => "gt",
+            AssertionKind::Gte => "gte",
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            AssertionKind::Eq => "==",
+            AssertionKind::Ne => "!=",
+            AssertionKind::Lt => "<",
+            AssertionKind::Lte => "<=",
+            AssertionKind::Gt => ">",
+            AssertionKind::Gte => ">=",
+        }
+    }
+
+    fn holds(&self, ord: Ordering) -> bool {
+        match self {
+            AssertionKind::Eq => ord.is_eq(),
+            AssertionKind::Ne => ord.is_ne(),
+            AssertionKind::Lt => ord.is_lt(),
+            AssertionKind::Lte => ord.is_le(),
+            AssertionKind::Gt => ord.is_gt(),
+            AssertionKind::Gte => ord.is_ge(),
+        }
+    }
+}
+
+impl TryFrom<&str> for AssertionKind {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "eq" => Ok(AssertionKind::Eq),
+            "ne" => Ok(AssertionKind::Ne),
+            "lt" => Ok(AssertionKind::Lt),
+            "lte" => Ok(AssertionKind::Lte),
+            "gt" => Ok(AssertionKind::Gt),
+            "gte" => Ok(AssertionKind::Gte),
+            _ => Err(format!("Invalid assertion kind {}", value))
+        }
+    }
+}
+
+
+/// A comparison between the values of two keys of a table, written `{kind = "lte", left = "min", right = "max"}` in
+/// the `assert` array of table schemas
+///
+/// keys that are missing take their default value, assertions with a key that has neither a value nor a default are
+/// not checked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    pub kind: AssertionKind,
+    pub left: String,
+    pub right: String,
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.kind.symbol(), self.right)
+    }
+}
+
+impl Assertion {
+    /// Parses one element of the `assert` array of a table schema
+    pub(crate) fn from_value(value: &Value) -> Result<Assertion, String> {
+        let Value::Table(table) = value else {
+            return Err(format!("Assertions must be tables but got {:?}", value))
+        };
+        let get = |key: &str| match table.get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            other => Err(format!("Assertion {} must be a string but got {:?}", key, other))
+        };
+        if let Some(key) = table.keys().find(|k| !["kind", "left", "right"].contains(&k.as_str())) {
+            return Err(format!("Unknown key '{}' in assertion", key))
+        }
+        Ok(Assertion { kind: AssertionKind::try_from(get("kind")?.as_str())?, left: get("left")?, right: get("right")? })
+    }
+
+    /// Whether the assertion holds in `table`, values of different types never compare
+    pub(crate) fn holds(&self, table: &toml::Table, entries: &TableEntries) -> Result<(), (Value, Value)> {
+        let (Some(left), Some(right)) = (sibling(&self.left, table, entries), sibling(&self.right, table, entries)) else {
+            return Ok(())
+        };
+        match compare(left, right) {
+            Some(ord) if self.kind.holds(ord) => Ok(()),
+            // NaN and values that cannot be compared are only different
+            None if self.kind == AssertionKind::Ne => Ok(()),
+            _ => Err((left.clone(), right.clone()))
+        }
+    }
+}
+
+
+/// Keys that must be present when a sibling key has a given value, written `when = {key = "mode", equals = "file"}`
+/// with `require = ["path"]` in table schemas
+///
+/// a missing condition key takes its default value like in assertions, required keys must be in the table, they are
+/// usually optional keys with a default value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub key: String,
+    pub equals: Value,
+    pub require: Vec<String>,
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "when {} = {} require {}", self.key, self.equals, self.require.join(", "))
+    }
+}
+
+impl Requirement {
+    /// Whether the `when` key of a table schema is a condition and not the schema of a key named `when`
+    pub(crate) fn is_condition(when: &Value) -> bool {
+        match when {
+            Value::Table(table) => table.contains_key("equals") && !table.contains_key("type"),
+            Value::Array(_) => true,
+            _ => false
+        }
+    }
+
+    /// Parses the `when` key of a table schema, a single condition uses the `require` key next to it and an array
+    /// of conditions has a `require` key in each of them
+    pub(crate) fn from_values(when: &Value, require: Option<&Value>) -> Result<Vec<Requirement>, String> {
+        match (when, require) {
+            (Value::Table(_), Some(require)) => Ok(vec![Self::from_table(when, Some(require))?]),
+            (Value::Table(_), None) => Err("'when' needs a 'require' key next to it".to_string()),
+            (Value::Array(arr), None) => arr.iter().map(|when| Self::from_table(when, None)).collect(),
+            (Value::Array(_), Some(_)) => Err("Conditions in a 'when' array must have their own 'require' key".to_string()),
+            _ => Err(format!("'when' must be a table or an array of tables but got {:?}", when))
+        }
+    }
+
+    fn from_table(when: &Value, require: Option<&Value>) -> Result<Requirement, String> {
+        let Value::Table(table) = when else {
+            return Err(format!("Conditions must be tables but got {:?}", when))
+        };
+        let allowed: &[&str] = if require.is_some() {&["key", "equals"]} else {&["key", "equals", "require"]};
+        if let Some(key) = table.keys().find(|k| !allowed.contains(&k.as_str())) {
+            return Err(format!("Unknown key '{}' in condition", key))
+        }
+
+        let Some(Value::String(key)) = table.get("key") else {
+            return Err(format!("Condition key must be a string but got {:?}", table.get("key")))
+        };
+        let Some(equals) = table.get("equals") else {
+            return Err(format!("Condition on {} has no 'equals' value", key))
+        };
+        let require = match require.or_else(|| table.get("require")) {
+            Some(Value::Array(keys)) => keys.iter().map(|k| match k {
+                Value::String(s) => Ok(s.clone()),
+                other => Err(format!("Required keys must be strings but got {:?}", other))
+            }).collect::<Result<_, _>>()?,
+            other => return Err(format!("'require' must be an array of keys but got {:?}", other))
+        };
+        Ok(Requirement { key: key.clone(), equals: equals.clone(), require })
+    }
+
+    /// The first required key that is missing from `table` when the condition holds
+    pub(crate) fn missing<'s>(&'s self, table: &toml::Table, entries: &TableEntries) -> Option<&'s str> {
+        if sibling(&self.key, table, entries) != Some(&self.equals) {
+            return None
+        }
+        self.require.iter().find(|key| !table.contains_key(key.as_str())).map(String::as_str)
+    }
+}
+
+
+/// The value of a key of a table or its default value
+fn sibling<'a>(key: &str, table: &'a toml::Table, entries: &'a TableEntries) -> Option<&'a Value> {
+    table.get(key).or_else(|| entries.get(key).and_then(|(_, dv)| dv.as_ref()))
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Integer(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Datetime(a), Value::Datetime(b)) => a.partial_cmp(b),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assertions() {
+        let schema: TomlSchema = "
+            min_connections = {type = 'int', default = 1}
+            max_connections = 'int'
+            start = {type = 'date', default = 2024-01-01}
+            end = {type = 'date', default = 2030-01-01}
+            primary = {type = 'string', default = 'a'}
+            backup = {type = 'string', default = 'b'}
+            assert = [
+                {kind = 'lte', left = 'min_connections', right = 'max_connections'},
+                {kind = 'lt', left = 'start', right = 'end'},
+                {kind = 'ne', left = 'primary', right = 'backup'},
+            ]
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("max_connections = 10")).unwrap();
+        schema.check(&doc("min_connections = 10\nmax_connections = 10.5")).unwrap_err();
+        schema.check(&doc("min_connections = 10\nmax_connections = 10")).unwrap();
+
+        // defaults are used for missing keys
+        match schema.check(&doc("max_connections = 0")) {
+            Err(SchemaError::AssertionFailed { assertion, left, right }) => {
+                assert_eq!(assertion.to_string(), "min_connections <= max_connections");
+                assert_eq!((left, right), (Value::Integer(1), Value::Integer(0)));
+            },
+            other => panic!("expected an assertion error but got {:?}", other)
+        }
+        schema.check(&doc("max_connections = 5\nend = 2020-01-01")).unwrap_err();
+        schema.check(&doc("max_connections = 5\nbackup = 'a'")).unwrap_err();
+
+        let report = schema.validate(&doc("max_connections = 0\nbackup = 'a'"));
+        assert_eq!(report.errors.len(), 2, "{:?}", report);
+
+        let schema: TomlSchema = "a = 'int'\nb = 'int'\nassert = [{kind = 'gt', left = 'a', right = 'b'}]".parse().unwrap();
+        assert_eq!(schema.to_string(), "table { a: int, b: int, assert a > b }");
+
+        "assert = [{kind = 'less', left = 'a', right = 'b'}]".parse::<TomlSchema>().unwrap_err();
+        "assert = [{kind = 'lt', left = 'a'}]".parse::<TomlSchema>().unwrap_err();
+        "assert = {kind = 'lt', left = 'a', right = 'b'}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn requirements() {
+        let schema: TomlSchema = "
+            mode = {type = 'string', default = 'file'}
+            path = {type = 'string', default = ''}
+            host = {type = 'string', default = ''}
+            port = {type = 'int', default = 0}
+            when = [
+                {key = 'mode', equals = 'file', require = ['path']},
+                {key = 'mode', equals = 'net', require = ['host', 'port']},
+            ]
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        let missing = |s: &str| match schema.check(&doc(s)) {
+            Ok(()) => None,
+            Err(SchemaError::MissingRequired { key, .. }) => Some(key.to_string()),
+            Err(e) => panic!("unexpected error {:?}", e)
+        };
+
+        assert_eq!(missing("mode = 'file'\npath = 'a'"), None);
+        assert_eq!(missing("mode = 'file'"), Some("path".to_string()));
+        assert_eq!(missing("mode = 'net'\nhost = 'h'"), Some("port".to_string()));
+        assert_eq!(missing("mode = 'other'"), None);
+        // the condition uses the default mode
+        assert_eq!(missing("host = 'h'"), Some("path".to_string()));
+
+        let schema: TomlSchema = "
+            mode = 'string'
+            url = {type = 'string', default = ''}
+            when = {key = 'mode', equals = 'net'}
+            require = ['url']
+        ".parse().unwrap();
+        schema.check(&doc("mode = 'file'")).unwrap();
+        match schema.check(&doc("mode = 'net'")) {
+            Err(SchemaError::MissingRequired { key, requirement }) => {
+                assert_eq!(key, "url");
+                assert_eq!(requirement.to_string(), "when mode = \"net\" require url");
+            },
+            other => panic!("expected a missing key but got {:?}", other)
+        }
+        schema.check(&doc("mode = 'net'\nurl = 'x'")).unwrap();
+        assert_eq!(schema.validate(&doc("mode = 'net'")).errors.len(), 1);
+
+        // keys named like the keywords are still possible
+        let schema: TomlSchema = "when = 'date'\nrequire = {type = 'bool', default = false}".parse().unwrap();
+        schema.check(&doc("when = 2024-01-01\nrequire = true")).unwrap();
+
+        "a = 'int'\nrequire = ['a']".parse::<TomlSchema>().unwrap_err();
+        "a = 'int'\nwhen = {key = 'a', equals = 1}".parse::<TomlSchema>().unwrap_err();
+        "a = 'int'\nwhen = [{key = 'a', equals = 1, require = 'a'}]".parse::<TomlSchema>().unwrap_err();
+        "a = 'int'\nwhen = [{key = 'a', require = ['a']}]".parse::<TomlSchema>().unwrap_err();
+    }
+}
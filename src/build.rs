@@ -0,0 +1,122 @@
+//! Constructors of schemas in code, [TomlSchema] and its errors are `#[non_exhaustive]` so that new kinds of schemas
+//! can be added without breaking the crates that use them, these functions and [TomlSchema::info] are the stable way to
+//! build and look into schemas
+
+use crate::*;
+
+
+impl TomlSchema {
+    /// Any string, `{type = "string"}`
+    pub fn string() -> Self {
+        TomlSchema::String { regex: Pattern::new(".*").expect("the regex of any string is valid"), refers_to: None, rules: Arc::default() }
+    }
+
+    /// The strings where `regex` is found, `{type = "string", regex = ".."}`
+    pub fn string_matching(regex: &str) -> Result<Self, String> {
+        Ok(TomlSchema::String { regex: Pattern::new(regex)?, refers_to: None, rules: Arc::default() })
+    }
+
+    /// The integers in `[min, max]`
+    pub fn int(min: i64, max: i64) -> Self {
+        TomlSchema::Integer { min, max, width: None, coerce: false }
+    }
+
+    /// The floats in `[min, max]`, NaN is rejected
+    pub fn float(min: f64, max: f64) -> Self {
+        TomlSchema::Float { min, max, nan_ok: false, coerce: false }
+    }
+
+    pub fn bool() -> Self {
+        TomlSchema::Bool { coerce: false }
+    }
+
+    /// Any date, time or datetime
+    pub fn date() -> Self {
+        TomlSchema::Date(Arc::default())
+    }
+
+    pub fn anything() -> Self {
+        TomlSchema::Anything
+    }
+
+    /// Exactly `value`
+    pub fn exact(value: Value) -> Self {
+        TomlSchema::Exact { value, epsilon: 0.0 }
+    }
+
+    /// One of `values`, `{type = "exact", values = [..]}`
+    pub fn one_of(values: impl IntoIterator<Item = Value>) -> Self {
+        TomlSchema::Enum(values.into_iter().collect())
+    }
+
+    /// The arrays of `min` to `max` elements that all match `child`
+    pub fn array(child: TomlSchema, min: usize, max: usize) -> Self {
+        TomlSchema::Array { cond: Arc::new(child), min, max, multiple_of: 1, unique_by: None, append: false }
+    }
+
+    /// The tables whose values all match `value`, with any keys
+    pub fn map(value: TomlSchema) -> Self {
+        let key = Pattern::new(".*").expect("the regex of any key is valid");
+        TomlSchema::Map(Arc::new(MapSchema { key, key_type: KeyType::String, value, min: 0, max: usize::MAX }))
+    }
+
+    /// The values that match one of `options`
+    pub fn alternative(options: Vec<TomlSchema>) -> Self {
+        TomlSchema::Alternative(Arc::new(AlternativeSchema::new(options)))
+    }
+
+    /// The tables with the keys of `entries` and no other key, a key with a default may be missing
+    pub fn table(entries: impl IntoIterator<Item = (String, TomlSchema, Option<Value>)>) -> Self {
+        TomlSchema::Table(Arc::new(TableSchema {
+            entries: entries.into_iter().map(|(key, schema, default)| (key, (schema, default))).collect(),
+            extras: Box::default(), min: 0, max: usize::MAX, assertions: Box::default(), requirements: Box::default(),
+            policy: ExtrasPolicy::default()
+        }))
+    }
+
+    /// The kind of this schema, wrappers like extensions and custom validators are looked through
+    pub fn schema_type(&self) -> SchemaType {
+        SchemaType::from(self)
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_like_parsed() {
+        let built = TomlSchema::table([
+            ("name".to_string(), TomlSchema::string_matching("^[a-z]+$").unwrap(), None),
+            ("port".to_string(), TomlSchema::int(1, 65535), Some(Value::Integer(80))),
+            ("hosts".to_string(), TomlSchema::array(TomlSchema::string(), 0, 3), None),
+            ("mode".to_string(), TomlSchema::one_of(["fast".into(), "slow".into()]), None),
+            ("env".to_string(), TomlSchema::map(TomlSchema::alternative(vec![TomlSchema::bool(), TomlSchema::float(0.0, 1.0)])), None),
+        ]);
+        let parsed: TomlSchema = "
+            name = {type = 'string', regex = '^[a-z]+$'}
+            port = {type = 'int', min = 1, max = 65535, default = 80}
+            hosts = {type = 'array', child = 'string', max = 3}
+            mode = {type = 'exact', values = ['fast', 'slow']}
+            env = {type = 'map', value = {type = 'alternative', options = ['bool', {type = 'float', min = 0.0, max = 1.0}]}}
+        ".parse().unwrap();
+
+        let docs = [
+            "name = 'web'\nhosts = []\nmode = 'fast'\nenv = {a = true, b = 0.5}",
+            "name = 'web'\nhosts = ['a']\nmode = 'fast'\nenv = {a = 2.0}",
+            "name = 'Web'\nport = 0\nhosts = []\nmode = 'other'\nenv = {}",
+        ];
+        for doc in docs {
+            let doc = Value::Table(doc.parse().unwrap());
+            assert_eq!(built.check(&doc).is_ok(), parsed.check(&doc).is_ok(), "{}", doc);
+        }
+        assert_eq!(built.schema_type(), SchemaType::Table);
+        let mut required = built.required_keys();
+        required.sort();
+        assert_eq!(required, parsed.required_keys());
+        TomlSchema::string_matching("(").unwrap_err();
+    }
+}
@@ -0,0 +1,747 @@
+//! A compact binary format for compiled schemas, enabled with the `cache` feature
+//!
+//! Tools that validate on every invocation can store [TomlSchema::to_bytes] once and load it with
+//! [TomlSchema::from_bytes], which skips TOML parsing entirely, regexes are stored as their pattern and
+//! recompiled on load
+//!
+//! The format is versioned, blobs written by another version of the format are rejected
+
+use toml::value::Datetime;
+use crate::*;
+
+
+const MAGIC: &[u8; 4] = b"TSC\x1a";
+
+// node tags
+const ALTERNATIVE: u8 = 0;
+const STRING: u8 = 1;
+const INTEGER: u8 = 2;
+const DATE: u8 = 3;
+const BOOL: u8 = 4;
+const FLOAT: u8 = 5;
+const TABLE: u8 = 6;
+const ARRAY: u8 = 7;
+const ANYTHING: u8 = 8;
+const EXACT: u8 = 9;
+const ANNOTATED: u8 = 10;
+const CUSTOM: u8 = 11;
+const PLUGIN: u8 = 12;
+const MAP: u8 = 13;
+const DEFAULTED: u8 = 14;
+const ENUM: u8 = 15;
+const GRADED: u8 = 16;
+
+// value tags
+const V_STRING: u8 = 0;
+const V_INTEGER: u8 = 1;
+const V_FLOAT: u8 = 2;
+const V_BOOL: u8 = 3;
+const V_DATETIME: u8 = 4;
+const V_ARRAY: u8 = 5;
+const V_TABLE: u8 = 6;
+
+
+impl TomlSchema {
+
+    /// Serializes a compiled schema to a compact binary blob, see [TomlSchema::from_bytes]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        write_schema(&mut out, self);
+        out
+    }
+
+    /// Loads a schema serialized with [TomlSchema::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<TomlSchema, String> {
+        TomlSchema::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+
+    /// Same as [TomlSchema::from_bytes], custom validators and user-defined types are stored by name and looked up in
+    /// `options` again
+    pub fn from_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Result<TomlSchema, String> {
+        let mut reader = Reader { bytes, pos: 0, options, regexes: HashMap::new() };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("Invalid schema cache: bad header or unsupported format version".to_string())
+        }
+        let schema = reader.schema()?;
+
+        if reader.pos != bytes.len() {
+            return Err(format!("Invalid schema cache: {} trailing bytes", bytes.len() - reader.pos))
+        }
+        Ok(schema)
+    }
+}
+
+
+
+//=======================================================================================================================
+// writing
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_len(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_key_type(out: &mut Vec<u8>, key_type: &KeyType) {
+    match key_type {
+        KeyType::String => out.push(0),
+        KeyType::Integer { min, max } => {
+            out.push(1);
+            out.extend_from_slice(&min.to_le_bytes());
+            out.extend_from_slice(&max.to_le_bytes());
+        },
+        KeyType::Date => out.push(2),
+        KeyType::Semver => out.push(3),
+    }
+}
+
+fn write_schema(out: &mut Vec<u8>, schema: &TomlSchema) {
+    match schema {
+        TomlSchema::Alternative(options) => {
+            out.push(ALTERNATIVE);
+            write_len(out, options.len());
+            for opt in options.iter() {write_schema(out, opt);}
+        },
+        TomlSchema::String { regex, refers_to, rules } => {
+            out.push(STRING);
+            write_str(out, regex.as_str());
+            match &rules.not_regex {
+                Some(not_regex) => {out.push(1); write_str(out, not_regex.as_str());},
+                None => out.push(0)
+            }
+            for literal in [&rules.starts_with, &rules.ends_with, &rules.contains] {
+                match literal {
+                    Some(text) => {out.push(1); write_str(out, text);},
+                    None => out.push(0)
+                }
+            }
+            match refers_to {
+                Some(target) => {out.push(1); write_str(out, target);},
+                None => out.push(0)
+            }
+            out.push(rules.interpolate as u8);
+            out.push(rules.trim as u8);
+            #[cfg(feature = "unicode")]
+            out.push(rules.normalize.map_or(0, |form| form as u8 + 1));
+            #[cfg(not(feature = "unicode"))]
+            out.push(0);
+            match &rules.format {
+                Some(StringFormat::Glob { base: Some(base) }) => {out.push(2); write_str(out, base);},
+                Some(StringFormat::Glob { base: None }) => out.push(1),
+                #[cfg(feature = "cron")]
+                Some(StringFormat::Cron) => out.push(3),
+                Some(StringFormat::SocketAddr) => out.push(4),
+                Some(StringFormat::Cidr) => out.push(5),
+                Some(StringFormat::Base64 { min_bytes, max_bytes }) => {out.push(6); write_len(out, *min_bytes); write_len(out, *max_bytes);},
+                Some(StringFormat::Hex { min_bytes, max_bytes }) => {out.push(7); write_len(out, *min_bytes); write_len(out, *max_bytes);},
+                Some(StringFormat::LanguageTag) => out.push(8),
+                Some(StringFormat::CountryCode) => out.push(9),
+                None => out.push(0)
+            }
+        },
+        TomlSchema::Integer { min, max, width, coerce } => {
+            out.push(INTEGER);
+            out.extend_from_slice(&min.to_le_bytes());
+            out.extend_from_slice(&max.to_le_bytes());
+            out.push(width.map_or(0, |width| width.bits | (width.signed as u8) << 7));
+            out.push(*coerce as u8);
+        },
+        TomlSchema::Date(date) => {
+            out.push(DATE);
+            #[cfg(feature = "chrono")]
+            for bound in [&date.min, &date.max] {
+                write_datetime(out, bound.as_ref());
+            }
+            #[cfg(not(feature = "chrono"))]
+            out.extend_from_slice(&[0, 0]);
+            out.push(date.require_offset as u8 | (date.local_only as u8) << 1);
+        },
+        TomlSchema::Bool { coerce } => {
+            out.push(BOOL);
+            out.push(*coerce as u8);
+        },
+        TomlSchema::Float { min, max, nan_ok, coerce } => {
+            out.push(FLOAT);
+            out.extend_from_slice(&min.to_le_bytes());
+            out.extend_from_slice(&max.to_le_bytes());
+            out.push(*nan_ok as u8 | (*coerce as u8) << 1);
+        },
+        TomlSchema::Table(table_schema) => {
+            let TableSchema { extras, min, max, entries, assertions, requirements, policy } = &**table_schema;
+            out.push(TABLE);
+            write_len(out, *min);
+            write_len(out, *max);
+            write_len(out, extras.len());
+            for TableEntry { key, key_type, value } in extras.iter() {
+                write_str(out, key.as_str());
+                write_key_type(out, key_type);
+                write_schema(out, value);
+            }
+            write_len(out, entries.len());
+            for (key, (schema, default)) in entries.iter() {
+                write_str(out, key);
+                write_schema(out, schema);
+                match default {
+                    Some(v) => {out.push(1); write_value(out, v);},
+                    None => out.push(0)
+                }
+            }
+            write_len(out, assertions.len());
+            for Assertion { kind, left, right } in assertions.iter() {
+                write_str(out, kind.as_str());
+                write_str(out, left);
+                write_str(out, right);
+            }
+            write_len(out, requirements.len());
+            for Requirement { key, equals, require } in requirements.iter() {
+                write_str(out, key);
+                write_value(out, equals);
+                write_len(out, require.len());
+                for key in require {write_str(out, key);}
+            }
+            out.push(policy.matching as u8);
+            out.push(policy.count_entries as u8);
+            out.push(match policy.key_case {None => 0, Some(case) => case as u8 + 1});
+        },
+        TomlSchema::Array { cond, min, max, multiple_of, unique_by, append } => {
+            out.push(ARRAY);
+            write_len(out, *min);
+            write_len(out, *max);
+            write_len(out, *multiple_of as usize);
+            write_schema(out, cond);
+            match unique_by {
+                Some(key) => {out.push(1); write_str(out, key);},
+                None => out.push(0)
+            }
+            out.push(*append as u8);
+        },
+        TomlSchema::Map(map_schema) => {
+            let MapSchema { key, key_type, value, min, max } = &**map_schema;
+            out.push(MAP);
+            write_str(out, key.as_str());
+            write_key_type(out, key_type);
+            write_len(out, *min);
+            write_len(out, *max);
+            write_schema(out, value);
+        },
+        TomlSchema::Anything => out.push(ANYTHING),
+        TomlSchema::Exact { value, epsilon } => {
+            out.push(EXACT);
+            write_value(out, value);
+            out.extend_from_slice(&epsilon.to_le_bytes());
+        },
+        TomlSchema::Enum(values) => {
+            out.push(ENUM);
+            write_len(out, values.len());
+            values.iter().for_each(|value| write_value(out, value));
+        },
+        TomlSchema::Annotated { schema, extensions } => {
+            out.push(ANNOTATED);
+            write_schema(out, schema);
+            write_value(out, &Value::Table((**extensions).clone()));
+        },
+        TomlSchema::Defaulted { schema, env, merge } => {
+            out.push(DEFAULTED);
+            write_schema(out, schema);
+            match env {
+                Some(var) => {out.push(1); write_str(out, var);},
+                None => out.push(0)
+            }
+            out.push(*merge as u8);
+        },
+        TomlSchema::Custom { schema, validator } => {
+            out.push(CUSTOM);
+            write_schema(out, schema);
+            write_str(out, validator.name());
+        },
+        TomlSchema::Graded { schema, severity } => {
+            out.push(GRADED);
+            write_schema(out, schema);
+            write_str(out, severity.as_str());
+        },
+        TomlSchema::Plugin(plugin) => {
+            out.push(PLUGIN);
+            write_str(out, plugin.type_name());
+            write_value(out, &Value::Table(plugin.node().to_table()));
+        }
+    }
+}
+
+fn write_datetime(out: &mut Vec<u8>, date: Option<&Datetime>) {
+    match date {
+        Some(date) => {out.push(1); write_str(out, &date.to_string());},
+        None => out.push(0)
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(s) => {out.push(V_STRING); write_str(out, s);},
+        Value::Integer(i) => {out.push(V_INTEGER); out.extend_from_slice(&i.to_le_bytes());},
+        Value::Float(f) => {out.push(V_FLOAT); out.extend_from_slice(&f.to_le_bytes());},
+        Value::Boolean(b) => {out.push(V_BOOL); out.push(*b as u8);},
+        Value::Datetime(d) => {out.push(V_DATETIME); write_str(out, &d.to_string());},
+        Value::Array(arr) => {
+            out.push(V_ARRAY);
+            write_len(out, arr.len());
+            for v in arr {write_value(out, v);}
+        },
+        Value::Table(table) => {
+            out.push(V_TABLE);
+            write_len(out, table.len());
+            for (k, v) in table {
+                write_str(out, k);
+                write_value(out, v);
+            }
+        }
+    }
+}
+
+
+
+//=======================================================================================================================
+// reading
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    options: &'a ParseOptions,
+    /// the regexes read so far, identical patterns share one compiled regex like in parsed schemas
+    regexes: HashMap<String, Arc<LazyRegex>>
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        match self.pos.checked_add(n).and_then(|end| self.bytes.get(self.pos..end)) {
+            Some(slice) => {
+                self.pos += n;
+                Ok(slice)
+            },
+            None => Err("Invalid schema cache: unexpected end of data".to_string())
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("take returned the wrong length"))
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        usize::try_from(u64::from_le_bytes(self.array()?)).map_err(|e| format!("Invalid schema cache: {}", e))
+    }
+
+    fn str(&mut self) -> Result<&'a str, String> {
+        let len = self.len()?;
+        std::str::from_utf8(self.take(len)?).map_err(|e| format!("Invalid schema cache: {}", e))
+    }
+
+    #[cfg(feature = "unicode")]
+    fn normal_form(&mut self) -> Result<Option<NormalForm>, String> {
+        match self.byte()? {
+            0 => Ok(None),
+            i => NormalForm::NAMES.get(i as usize - 1).and_then(|name| NormalForm::from_name(name)).map(Some)
+                .ok_or_else(|| format!("Invalid schema cache: unknown normal form {}", i))
+        }
+    }
+
+    fn key_type(&mut self) -> Result<KeyType, String> {
+        Ok(match self.byte()? {
+            0 => KeyType::String,
+            1 => KeyType::Integer { min: i64::from_le_bytes(self.array()?), max: i64::from_le_bytes(self.array()?) },
+            2 => KeyType::Date,
+            3 => KeyType::Semver,
+            tag => return Err(format!("Invalid schema cache: unknown key type {}", tag))
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        let text = self.str()?;
+        Pattern::with_compiler(text, |text| self.compile(text))
+    }
+
+    fn compile(&mut self, text: &str) -> Result<Arc<LazyRegex>, String> {
+        if let Some(regex) = self.regexes.get(text) {
+            return Ok(regex.clone())
+        }
+        let regex = match self.options.lazy_regex {
+            true => LazyRegex::deferred(text, self.options),
+            false => LazyRegex::compiled(text, self.options)
+        }.map_err(|e| format!("Invalid schema cache: {}", e))?;
+        let regex = Arc::new(regex);
+        self.regexes.insert(text.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    fn schema(&mut self) -> Result<TomlSchema, String> {
+        let schema = match self.byte()? {
+            ALTERNATIVE => {
+                let count = self.len()?;
+                let options = (0..count).map(|_| self.schema()).collect::<Result<Vec<_>, _>>()?;
+                TomlSchema::Alternative(Arc::new(AlternativeSchema::new(options)))
+            },
+            STRING => {
+                let regex = self.pattern()?;
+                let not_regex = match self.byte()? {
+                    0 => None,
+                    _ => Some(self.pattern()?)
+                };
+                let mut literals = [None, None, None];
+                for literal in &mut literals {
+                    if self.byte()? != 0 {
+                        *literal = Some(self.str()?.to_string());
+                    }
+                }
+                let [starts_with, ends_with, contains] = literals;
+                let refers_to = match self.byte()? {
+                    0 => None,
+                    _ => Some(self.str()?.into())
+                };
+                let interpolate = self.byte()? != 0;
+                let trim = self.byte()? != 0;
+                #[cfg(feature = "unicode")]
+                let normalize = self.normal_form()?;
+                #[cfg(not(feature = "unicode"))]
+                if self.byte()? != 0 {
+                    return Err("Invalid schema cache: unicode normalization requires the `unicode` feature".to_string())
+                }
+                let format = match self.byte()? {
+                    0 => None,
+                    1 => Some(StringFormat::Glob { base: None }),
+                    2 => Some(StringFormat::Glob { base: Some(self.str()?.to_string()) }),
+                    #[cfg(feature = "cron")]
+                    3 => Some(StringFormat::Cron),
+                    #[cfg(not(feature = "cron"))]
+                    3 => return Err("Invalid schema cache: cron strings require the `cron` feature".to_string()),
+                    4 => Some(StringFormat::SocketAddr),
+                    5 => Some(StringFormat::Cidr),
+                    6 => Some(StringFormat::Base64 { min_bytes: self.len()?, max_bytes: self.len()? }),
+                    7 => Some(StringFormat::Hex { min_bytes: self.len()?, max_bytes: self.len()? }),
+                    8 => Some(StringFormat::LanguageTag),
+                    9 => Some(StringFormat::CountryCode),
+                    other => return Err(format!("Invalid schema cache: unknown string format {}", other))
+                };
+                let rules = Arc::new(StringRules {
+                    not_regex, starts_with, ends_with, contains, interpolate, trim,
+                    #[cfg(feature = "unicode")]
+                    normalize,
+                    format
+                });
+                TomlSchema::String { regex, refers_to, rules }
+            },
+            INTEGER => TomlSchema::Integer {
+                min: i64::from_le_bytes(self.array()?),
+                max: i64::from_le_bytes(self.array()?),
+                width: match self.byte()? {
+                    0 => None,
+                    b => Some(IntWidth { bits: b & 0x7f, signed: b & 0x80 != 0 })
+                },
+                coerce: self.byte()? != 0
+            },
+            DATE => {
+                let (min, max) = (self.datetime()?, self.datetime()?);
+                let flags = self.byte()?;
+                let (require_offset, local_only) = (flags & 1 != 0, flags & 2 != 0);
+                #[cfg(feature = "chrono")]
+                let date = DateSchema { min, max, require_offset, local_only };
+                #[cfg(not(feature = "chrono"))]
+                let date = match (min, max) {
+                    (None, None) => DateSchema { require_offset, local_only },
+                    _ => return Err("Invalid schema cache: date bounds require the `chrono` feature".to_string())
+                };
+                TomlSchema::Date(Arc::new(date))
+            },
+            BOOL => TomlSchema::Bool { coerce: self.byte()? != 0 },
+            FLOAT => {
+                let (min, max) = (f64::from_le_bytes(self.array()?), f64::from_le_bytes(self.array()?));
+                let flags = self.byte()?;
+                TomlSchema::Float { min, max, nan_ok: flags & 1 != 0, coerce: flags & 2 != 0 }
+            },
+            TABLE => {
+                let min = self.len()?;
+                let max = self.len()?;
+
+                let extra_count = self.len()?;
+                let mut extras = Vec::new();
+                for _ in 0..extra_count {
+                    let key = self.pattern()?;
+                    let key_type = self.key_type()?;
+                    extras.push(TableEntry { key, key_type, value: self.schema()? });
+                }
+
+                let entry_count = self.len()?;
+                let mut entries = Vec::new();
+                for _ in 0..entry_count {
+                    let key = self.str()?.to_string();
+                    let schema = self.schema()?;
+                    let default = match self.byte()? {
+                        0 => None,
+                        _ => Some(self.value()?)
+                    };
+                    entries.push((key, (schema, default)));
+                }
+
+                let assertion_count = self.len()?;
+                let mut assertions = Vec::new();
+                for _ in 0..assertion_count {
+                    let kind = AssertionKind::try_from(self.str()?).map_err(|e| format!("Invalid schema cache: {}", e))?;
+                    assertions.push(Assertion { kind, left: self.str()?.to_string(), right: self.str()?.to_string() });
+                }
+
+                let requirement_count = self.len()?;
+                let mut requirements = Vec::new();
+                for _ in 0..requirement_count {
+                    let key = self.str()?.to_string();
+                    let equals = self.value()?;
+                    let require_count = self.len()?;
+                    let require = (0..require_count).map(|_| self.str().map(str::to_string)).collect::<Result<_, _>>()?;
+                    requirements.push(Requirement { key, equals, require });
+                }
+
+                let matching = match self.byte()? {
+                    0 => ExtrasMatching::All,
+                    1 => ExtrasMatching::First,
+                    other => return Err(format!("Invalid schema cache: unknown extras matching {}", other))
+                };
+                let count_entries = self.byte()? != 0;
+                let key_case = match self.byte()? {
+                    0 => None,
+                    1 => Some(KeyCase::Snake),
+                    2 => Some(KeyCase::Kebab),
+                    3 => Some(KeyCase::Camel),
+                    other => return Err(format!("Invalid schema cache: unknown key case {}", other))
+                };
+                let policy = ExtrasPolicy { matching, count_entries, key_case };
+                TomlSchema::Table(Arc::new(TableSchema {
+                    entries: entries.into_iter().collect(), extras: extras.into(), min, max,
+                    assertions: assertions.into(), requirements: requirements.into(), policy
+                }))
+            },
+            ARRAY => {
+                let min = self.len()?;
+                let max = self.len()?;
+                let multiple_of = u32::try_from(self.len()?).ok().filter(|step| *step > 0)
+                    .ok_or("Invalid schema cache: array step is out of range")?;
+                let cond = Arc::new(self.schema()?);
+                let unique_by = match self.byte()? {
+                    0 => None,
+                    _ => Some(self.str()?.into())
+                };
+                TomlSchema::Array { cond, min, max, multiple_of, unique_by, append: self.byte()? != 0 }
+            },
+            MAP => {
+                let key = self.pattern()?;
+                let key_type = self.key_type()?;
+                let min = self.len()?;
+                let max = self.len()?;
+                TomlSchema::Map(Arc::new(MapSchema { key, key_type, value: self.schema()?, min, max }))
+            },
+            ANYTHING => TomlSchema::Anything,
+            EXACT => TomlSchema::Exact { value: self.value()?, epsilon: f64::from_le_bytes(self.array()?) },
+            ENUM => {
+                let count = self.len()?;
+                let values = (0..count).map(|_| self.value()).collect::<Result<Vec<_>, _>>()?;
+                TomlSchema::Enum(values.into())
+            },
+            ANNOTATED => {
+                let schema = self.schema()?;
+                let Value::Table(extensions) = self.value()? else {
+                    return Err("Invalid schema cache: extensions are not a table".to_string())
+                };
+                TomlSchema::Annotated { schema: Arc::new(schema), extensions: Arc::new(extensions) }
+            },
+            DEFAULTED => {
+                let schema = self.schema()?;
+                let env = match self.byte()? {
+                    0 => None,
+                    _ => Some(self.str()?.into())
+                };
+                TomlSchema::Defaulted { schema: Arc::new(schema), env, merge: self.byte()? != 0 }
+            },
+            CUSTOM => {
+                let schema = self.schema()?;
+                let name = self.str()?;
+                let Some(validator) = self.options.validators.get(name) else {
+                    return Err(format!("Invalid schema cache: unknown custom validator '{}'", name))
+                };
+                TomlSchema::Custom { schema: Arc::new(schema), validator: validator.clone() }
+            },
+            GRADED => {
+                let schema = self.schema()?;
+                let severity = self.str()?.parse().map_err(|e| format!("Invalid schema cache: {}", e))?;
+                TomlSchema::Graded { schema: Arc::new(schema), severity }
+            },
+            PLUGIN => {
+                let name = self.str()?;
+                let Value::Table(table) = self.value()? else {
+                    return Err("Invalid schema cache: plugin options are not a table".to_string())
+                };
+                match self.options.plugins.parse(name, &table) {
+                    Some(plugin) => TomlSchema::Plugin(plugin.map_err(|e| format!("Invalid schema cache: {}", e))?),
+                    None => return Err(format!("Invalid schema cache: unknown schema type '{}'", name))
+                }
+            },
+            tag => return Err(format!("Invalid schema cache: unknown schema tag {}", tag))
+        };
+        Ok(schema)
+    }
+
+    fn datetime(&mut self) -> Result<Option<Datetime>, String> {
+        match self.byte()? {
+            0 => Ok(None),
+            _ => self.str()?.parse().map(Some).map_err(|e| format!("Invalid schema cache: {}", e))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        let value = match self.byte()? {
+            V_STRING => Value::String(self.str()?.to_string()),
+            V_INTEGER => Value::Integer(i64::from_le_bytes(self.array()?)),
+            V_FLOAT => Value::Float(f64::from_le_bytes(self.array()?)),
+            V_BOOL => Value::Boolean(self.byte()? != 0),
+            V_DATETIME => Value::Datetime(
+                self.str()?.parse::<Datetime>().map_err(|e| format!("Invalid schema cache: {}", e))?
+            ),
+            V_ARRAY => {
+                let count = self.len()?;
+                Value::Array((0..count).map(|_| self.value()).collect::<Result<_, _>>()?)
+            },
+            V_TABLE => {
+                let count = self.len()?;
+                let mut table = toml::Table::new();
+                for _ in 0..count {
+                    let key = self.str()?.to_string();
+                    table.insert(key, self.value()?);
+                }
+                Value::Table(table)
+            },
+            tag => return Err(format!("Invalid schema cache: unknown value tag {}", tag))
+        };
+        Ok(value)
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_roundtrip() {
+        let schema = std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse::<TomlSchema>().unwrap();
+        let bytes = schema.to_bytes();
+        let loaded = TomlSchema::from_bytes(&bytes).unwrap();
+
+        // the loaded schema behaves the same and serializes to the same bytes
+        let mut manifest = Value::Table(std::fs::read_to_string("Cargo.toml").unwrap().parse().unwrap());
+        loaded.check(&manifest).unwrap();
+        loaded.check_and_complete(&mut manifest).unwrap();
+        assert_eq!(loaded.to_bytes().len(), bytes.len());
+
+        let bad = Value::Table(std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse().unwrap());
+        loaded.check(&bad).unwrap_err();
+    }
+
+    #[test]
+    fn cache_values() {
+        let schema: TomlSchema = "
+            when = {type = 'date', default = 1979-05-27T07:32:00Z}
+            ratio = {type = 'float', min = 0.0, max = 1.0, nan_ok = true, default = 0.5}
+            nested = {type = 'table', default = {a = [1, 2], b = {c = 'd'}}, default_merge = 'merge', extras = [{key = '.*', schema = {type = 'anything'}}], extras_match = 'first', count_entries = true, key_case = 'kebab'}
+            fixed = {type = 'exact', value = [true, 'x'], x-doc = {summary = 'fixed'}}
+            port = {type = 'int', default_env = 'PORT', default = 8080, severity = 'warning'}
+            url = {type = 'string', interpolate = true, default = 'http://localhost:${port}'}
+            either = {type = 'alternative', options = [{type = 'int', min = -3}, {type = 'string', regex = '^a'}]}
+            env = {type = 'map', key_regex = '^[A-Z_]+$', value = 'string', max = 10, default = {HOME = '/'}}
+            plugins = {type = 'array', child = 'string', unique_by = 'name', merge = 'append'}
+        ".parse().unwrap();
+
+        let loaded = TomlSchema::from_bytes(&schema.to_bytes()).unwrap();
+        let (TomlSchema::Table(table), TomlSchema::Table(loaded_table)) = (&schema, &loaded) else {
+            panic!("schema is not a table")
+        };
+        let (entries, loaded_entries) = (&table.entries, &loaded_table.entries);
+        for (key, (_, default)) in entries.iter() {
+            assert_eq!(default, &loaded_entries[key].1, "default of {} changed", key);
+        }
+        assert!(matches!(&loaded_entries["either"].0, TomlSchema::Alternative(opts) if opts.len() == 2));
+        assert_eq!(loaded_entries["fixed"].0.extensions(), entries["fixed"].0.extensions());
+        assert_eq!(loaded_entries["env"].0.to_string(), entries["env"].0.to_string());
+        assert_eq!(loaded_entries["nested"].0.to_string(), entries["nested"].0.to_string());
+        assert_eq!(loaded_entries["port"].0.default_env(), Some("PORT"));
+        assert_eq!(loaded_entries["port"].0.severity(), Severity::Warning);
+        assert_eq!(loaded_entries["url"].0.to_string(), entries["url"].0.to_string());
+        assert_eq!(loaded_entries["plugins"].0.to_string(), entries["plugins"].0.to_string());
+    }
+
+    #[test]
+    fn cache_invalid() {
+        let bytes = TomlSchema::Anything.to_bytes();
+
+        TomlSchema::from_bytes(&bytes).unwrap();
+        TomlSchema::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        TomlSchema::from_bytes(b"TSC\x0c\x08").unwrap_err();
+        TomlSchema::from_bytes(b"TSC\x0d\x08\x00").unwrap_err();
+        TomlSchema::from_bytes(b"TSC\x0d\x63").unwrap_err();
+    }
+
+    #[test]
+    fn cache_custom() {
+        let mut validators = CustomValidators::new();
+        validators.register("even", |v| if v.as_integer().is_some_and(|i| i % 2 == 0) {Ok(())} else {Err("odd".into())});
+        let options = ParseOptions { validators, ..ParseOptions::default() };
+
+        let table = "n = {type = 'int', custom = 'even'}".parse().unwrap();
+        let (schema, _, _) = TomlSchema::from_table_with_options(&table, &options).unwrap();
+        let bytes = schema.to_bytes();
+
+        TomlSchema::from_bytes(&bytes).unwrap_err();
+        let loaded = TomlSchema::from_bytes_with_options(&bytes, &options).unwrap();
+        loaded.check(&Value::Table("n = 2".parse().unwrap())).unwrap();
+        loaded.check(&Value::Table("n = 3".parse().unwrap())).unwrap_err();
+    }
+
+    #[derive(Debug)]
+    struct Even;
+
+    impl PluginNode for Even {
+        fn check(&self, value: &Value) -> Result<(), String> {
+            if value.as_integer().is_some_and(|i| i % 2 == 0) {Ok(())} else {Err("odd".into())}
+        }
+        fn to_table(&self) -> toml::Table {
+            toml::Table::new()
+        }
+    }
+
+    struct EvenType;
+
+    impl PluginType for EvenType {
+        fn parse(&self, _: &toml::Table) -> Result<Arc<dyn PluginNode>, String> {
+            Ok(Arc::new(Even))
+        }
+    }
+
+    #[test]
+    fn cache_plugin() {
+        let mut plugins = SchemaPlugins::new();
+        plugins.register("even", EvenType).unwrap();
+        let options = ParseOptions { plugins, ..ParseOptions::default() };
+
+        let table = "n = 'even'".parse().unwrap();
+        let (schema, _, _) = TomlSchema::from_table_with_options(&table, &options).unwrap();
+        let bytes = schema.to_bytes();
+
+        TomlSchema::from_bytes(&bytes).unwrap_err();
+        let loaded = TomlSchema::from_bytes_with_options(&bytes, &options).unwrap();
+        loaded.check(&Value::Table("n = 2".parse().unwrap())).unwrap();
+        loaded.check(&Value::Table("n = 3".parse().unwrap())).unwrap_err();
+    }
+}
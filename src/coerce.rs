@@ -0,0 +1,123 @@
+//! Numbers and bools written as strings, int, float and bool schemas with `coerce = true` accept `"8080"` like `8080`
+//! and `"yes"` like `true`, [TomlSchema::check_and_complete] rewrites them to values of their type
+
+use crate::*;
+
+
+/// The int written in `s`, an optional `+` or `-` and digits, whitespace and the `_` separators of TOML are rejected
+pub(crate) fn parse_int(s: &str) -> Option<i64> {
+    s.parse().ok()
+}
+
+/// The float written in `s` as read by [str::parse], an optional sign, digits with an optional `.` and exponent, or
+/// `inf`, `infinity` and `nan` in any case, whitespace and `_` are rejected
+pub(crate) fn parse_float(s: &str) -> Option<f64> {
+    s.parse().ok()
+}
+
+/// The bool written in `s` with one of the usual spellings, in any case, whitespace is rejected
+pub(crate) fn parse_bool(s: &str) -> Option<bool> {
+    match s.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None
+    }
+}
+
+/// The value a coercing schema reads from a string, `None` when `schema` does not coerce or cannot read `s`
+fn coerced(schema: &TomlSchema, s: &str) -> Option<Value> {
+    match schema {
+        TomlSchema::Integer { coerce: true, .. } => parse_int(s).map(Value::Integer),
+        TomlSchema::Float { coerce: true, .. } => parse_float(s).map(Value::Float),
+        TomlSchema::Bool { coerce: true } => parse_bool(s).map(Value::Boolean),
+        _ => None
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Rewrites the strings of `doc` that coercing schemas read as numbers or bools to these values
+    pub(crate) fn coerce(&self, doc: &mut Value) {
+        let pick = |schema: &TomlSchema, value: &Value| match value {
+            Value::String(s) => coerced(schema, s),
+            _ => None
+        };
+        let mut found = Vec::new();
+        self.collect_leaves(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &pick, &mut found);
+        for (path, value) in found {
+            if let Some(slot) = path.value_in_mut(doc) {
+                *slot = value;
+            }
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coerced_numbers() {
+        let schema: TomlSchema = "
+            port = {type = 'int', min = 1, max = 65535, coerce = true}
+            ratio = {type = 'float', coerce = true, default = '0.5'}
+            strict = {type = 'int', default = 0}
+            ports = {type = 'array', child = {type = 'port', coerce = true}, default = []}
+        ".parse().unwrap();
+
+        schema.check_str("port = '8080'\nports = ['1000', 22]").unwrap();
+        let e = schema.check_str("port = '80a'").unwrap_err();
+        assert!(e.contains("String \"80a\" cannot be read as int"), "{}", e);
+        let e = schema.check_str("port = '0'").unwrap_err();
+        assert!(e.contains("Int 0 does not match [1,65535]"), "{}", e);
+        schema.check_str("port = 1\nstrict = '1'").unwrap_err();
+
+        // the exact spellings that are read
+        for (s, int, float) in [
+            ("8080", Some(8080), Some(8080.0)), ("+5", Some(5), Some(5.0)), ("-5", Some(-5), Some(-5.0)),
+            (".5", None, Some(0.5)), ("5.", None, Some(5.0)), ("1e-3", None, Some(1e-3)), ("-Infinity", None, Some(f64::NEG_INFINITY)),
+            (" 8080 ", None, None), ("1_000", None, None), ("0x10", None, None), ("", None, None),
+        ] {
+            assert_eq!(parse_int(s), int, "{:?}", s);
+            assert_eq!(parse_float(s), float, "{:?}", s);
+        }
+        assert!(parse_float("NaN").is_some_and(f64::is_nan));
+
+        let mut doc = Value::Table("port = '8080'\nports = ['443']".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(8080));
+        assert_eq!(doc["ratio"].as_float(), Some(0.5));
+        assert_eq!(doc["ports"][0].as_integer(), Some(443));
+
+        // an alternative keeps a string for the option that takes strings first
+        let schema: TomlSchema = "x = {type = 'alternative', options = ['string', {type = 'int', coerce = true}]}".parse().unwrap();
+        let mut doc = Value::Table("x = '1'".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["x"].as_str(), Some("1"));
+    }
+
+    #[test]
+    fn coerced_bools() {
+        let schema: TomlSchema = "
+            enabled = {type = 'bool', coerce = true}
+            flags = {type = 'map', value = {type = 'bool', coerce = true}, default = {}}
+            strict = {type = 'bool', default = false}
+        ".parse().unwrap();
+
+        schema.check_str("enabled = 'Yes'\nflags = {a = 'OFF', b = 'on', c = true, d = 'False'}").unwrap();
+        let e = schema.check_str("enabled = 'y'").unwrap_err();
+        assert!(e.contains("String \"y\" cannot be read as bool"), "{}", e);
+        schema.check_str("enabled = ' on '").unwrap_err();
+        schema.check_str("enabled = true\nstrict = 'true'").unwrap_err();
+
+        let mut doc = Value::Table("enabled = 'no'\nflags = {a = 'ON', b = false}".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["enabled"].as_bool(), Some(false));
+        assert_eq!(doc["flags"]["a"].as_bool(), Some(true));
+        assert_eq!(doc["flags"]["b"].as_bool(), Some(false));
+    }
+}
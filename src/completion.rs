@@ -0,0 +1,117 @@
+use crate::*;
+
+
+/// A suggestion returned by [TomlSchema::completions_at]
+#[derive(Debug, Clone)]
+pub enum Completion<'s> {
+    /// A key that can be added to the table at the cursor, `required` keys are table entries without a default value
+    Key{name: &'s str, schema: &'s TomlSchema, default: Option<&'s Value>, required: bool},
+    /// A value that is valid at the cursor, from `bool`, `exact` and default values
+    Value(&'s Value),
+}
+
+
+const TRUE: Value = Value::Boolean(true);
+const FALSE: Value = Value::Boolean(false);
+
+impl TomlSchema {
+
+    /// Lists what can be written at a location of a document, for editor completions
+    ///
+    /// `path` points to the location of the cursor (see [TomlSchema::at_path]), when it is a table the fixed keys of the
+    /// table schemas that are not already in the document are suggested, and values are suggested from the schemas of the
+    /// location itself, keys come first (sorted) followed by values, without duplicates
+    pub fn completions_at<'s>(&'s self, doc: &Value, path: &str) -> Result<Vec<Completion<'s>>, String> {
+        let path: SchemaPath = path.parse()?;
+        let current = value_at(doc, &path);
+
+        let mut keys = Vec::new();
+        let mut values: Vec<&'s Value> = Vec::new();
+
+        for (schema, default) in self.resolve_path(&path) {
+            values.extend(default);
+
+            for schema in schema.flatten_alternatives() {
+                match schema {
+                    TomlSchema::Table(table_schema) => {
+                        for (name, (sub, default)) in table_schema.entries.iter() {
+                            let present = matches!(current, Some(Value::Table(t)) if t.contains_key(name));
+                            if !present {
+                                keys.push(Completion::Key { name, schema: sub, default: default.as_ref(), required: default.is_none() });
+                            }
+                        }
+                    },
+                    TomlSchema::Bool { .. } => values.extend([&TRUE, &FALSE]),
+                    TomlSchema::Exact { value, .. } => values.push(value),
+                    TomlSchema::Enum(options) => values.extend(options.iter()),
+                    _ => ()
+                }
+            }
+        }
+
+        keys.sort_by_key(|c| match c {Completion::Key { name, .. } => *name, Completion::Value(_) => ""});
+        keys.dedup_by(|a, b| match (a, b) {
+            (Completion::Key { name: a, .. }, Completion::Key { name: b, .. }) => a == b,
+            _ => false
+        });
+
+        let mut unique_values: Vec<&'s Value> = Vec::new();
+        for v in values {
+            if !unique_values.contains(&v) {unique_values.push(v);}
+        }
+
+        keys.extend(unique_values.into_iter().map(Completion::Value));
+        Ok(keys)
+    }
+}
+
+
+/// The value at a path of a document, [PathSegment::Key] and [PathSegment::Index] are followed and other segments fail
+pub(crate) fn value_at<'v>(doc: &'v Value, path: &SchemaPath) -> Option<&'v Value> {
+    let mut current = doc;
+    for segment in path.segments() {
+        current = match (current, segment) {
+            (Value::Table(t), PathSegment::Key(k)) => t.get(k)?,
+            (Value::Array(a), PathSegment::Index(i)) => a.get(*i)?,
+            _ => return None
+        };
+    }
+    Some(current)
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions() {
+        let schema: TomlSchema = "
+            [server]
+            host = {type = 'string', default = 'localhost'}
+            port = {type = 'int'}
+            tls = {type = 'bool', default = false}
+            mode = {type = 'alternative', options = [{type = 'exact', value = 'fast'}, {type = 'exact', value = 'safe'}]}
+        ".parse().unwrap();
+        let doc = Value::Table("[server]\nport = 80".parse().unwrap());
+
+        let found = schema.completions_at(&doc, "server").unwrap();
+        let names: Vec<_> = found.iter().filter_map(|c| match c {Completion::Key { name, .. } => Some(*name), _ => None}).collect();
+        assert_eq!(names, ["host", "mode", "tls"]);
+        assert!(found.iter().any(|c| matches!(c, Completion::Key { name: "mode", required: true, default: None, .. })));
+
+        let values = |path| -> Vec<Value> {
+            schema.completions_at(&doc, path).unwrap().into_iter()
+                .map(|c| match c {Completion::Value(v) => v.clone(), key => panic!("unexpected key {:?}", key)})
+                .collect()
+        };
+        assert_eq!(values("server.tls"), [FALSE, TRUE]);
+        assert_eq!(values("server.mode"), [Value::String("fast".to_string()), Value::String("safe".to_string())]);
+
+        assert!(schema.completions_at(&doc, "server.port").unwrap().is_empty());
+        assert!(schema.completions_at(&doc, "nowhere").unwrap().is_empty());
+        schema.completions_at(&doc, "server.").unwrap_err();
+    }
+}
@@ -0,0 +1,202 @@
+//! Drop-in configuration directories, a [ConfigDir] merges the `*.toml` files of a directory like `conf.d` into one
+//! document and reports errors with the file that set the wrong value
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::*;
+use crate::merge::merge_value;
+
+
+/// The files of a drop-in configuration directory, merged in the order of their names like [TomlSchema::merge]
+/// merges layers, so that `50-local.toml` overrides `10-defaults.toml`
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDir {
+    files: Vec<(PathBuf, Value)>,
+}
+
+/// An error of a [ConfigDir], `file` is the last file that set the value at `path` or one of its parents, `None`
+/// when no file did, like for a missing key
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirError {
+    pub file: Option<PathBuf>,
+    pub path: SchemaPath,
+    pub message: String,
+}
+
+impl fmt::Display for DirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}: ", file.display())?,
+            None => f.write_str("<merged>: ")?,
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl ConfigDir {
+    /// Reads the files of `dir` with the `toml` extension, sub-directories and other files are ignored
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        let read_dir = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let mut paths = Vec::new();
+        for entry in read_dir {
+            let path = entry.map_err(|e| format!("{}: {}", dir.display(), e))?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let table: toml::Table = text.parse().map_err(|e| format!("{}: {}", path.display(), e))?;
+            files.push((path, Value::Table(table)));
+        }
+        Ok(ConfigDir { files })
+    }
+
+    /// Files that are already parsed, in the order they are merged
+    pub fn from_files(files: Vec<(PathBuf, Value)>) -> Self {
+        ConfigDir { files }
+    }
+
+    pub fn files(&self) -> &[(PathBuf, Value)] {
+        &self.files
+    }
+
+    /// The files merged with the rules of `schema`, without defaults and without checking the result
+    pub fn merged(&self, schema: &TomlSchema) -> Value {
+        let mut merged = Value::Table(toml::Table::new());
+        for (_, value) in &self.files {
+            merge_value(schema, &mut merged, value);
+        }
+        merged
+    }
+
+    /// Merges the files, fills the defaults and checks the result, all errors are returned with the file they come from
+    pub fn check(&self, schema: &TomlSchema) -> Result<Value, Vec<DirError>> {
+        let mut merged = self.merged(schema);
+        if let Err((path, error)) = schema.complete(&mut merged) {
+            let message = format!("{:?}", error);
+            return Err(vec![DirError { file: self.origin(schema, &path).map(Path::to_path_buf), path, message }])
+        }
+        let report = schema.validate(&merged);
+        if report.is_ok() {
+            return Ok(merged)
+        }
+        // warnings do not fail the directory
+        Err(report.errors.into_iter().filter(|e| e.severity == Severity::Error).map(|ReportedError { path, message, .. }| {
+            DirError { file: self.origin(schema, &path).map(Path::to_path_buf), path, message }
+        }).collect())
+    }
+
+    /// The last file that set the value at `path` of the merged document, or the closest parent of it that a file set,
+    /// the elements of arrays with `merge = "append"` come from the file that added them
+    pub fn origin(&self, schema: &TomlSchema, path: &SchemaPath) -> Option<&Path> {
+        // the files that have the current location, with their value there
+        let mut candidates: Vec<(usize, &Value)> = self.files.iter().enumerate().map(|(i, (_, value))| (i, value)).collect();
+        let mut prefix = SchemaPath::new();
+        let mut origin = None;
+
+        for segment in path.segments() {
+            let next: Vec<(usize, &Value)> = match segment {
+                PathSegment::Key(key) => candidates.iter().filter_map(|(i, value)| Some((*i, value.get(key)?))).collect(),
+                PathSegment::Index(index) if schema.resolve_path(&prefix).iter().any(|(s, _)| s.appends()) => {
+                    // appended arrays are concatenated in the order of the files
+                    let mut offset = *index;
+                    candidates.iter().filter_map(|(i, value)| value.as_array().map(|arr| (*i, arr)))
+                        .find_map(|(i, arr)| match arr.get(offset) {
+                            Some(element) => Some((i, element)),
+                            None => {offset -= arr.len(); None}
+                        })
+                        .into_iter().collect()
+                },
+                // other arrays are replaced by the last file that has them
+                PathSegment::Index(index) => candidates.last().and_then(|(i, value)| Some((*i, value.get(*index)?))).into_iter().collect(),
+                _ => Vec::new()
+            };
+            if next.is_empty() {
+                break
+            }
+            candidates = next;
+            origin = candidates.last().map(|(i, _)| *i);
+            prefix.push(segment.clone());
+        }
+        origin.map(|i| self.files[i].0.as_path())
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = 'string'
+            plugins = {type = 'array', child = {type = 'string', regex = '^[a-z]+$'}, merge = 'append', default = []}
+            [server]
+            port = {type = 'int', min = 1, default = 80}
+        ".parse().unwrap()
+    }
+
+    fn dir(files: &[(&str, &str)]) -> ConfigDir {
+        ConfigDir::from_files(files.iter().map(|(name, text)| (PathBuf::from(name), Value::Table(text.parse().unwrap()))).collect())
+    }
+
+    #[test]
+    fn merged_files() {
+        let config = dir(&[
+            ("10-base.toml", "name = 'base'\nplugins = ['a']"),
+            ("20-site.toml", "plugins = ['b']\nserver = {port = 8080}"),
+        ]);
+        let merged = config.check(&schema()).unwrap();
+        assert_eq!(merged["plugins"], Value::Array(vec!["a".into(), "b".into()]));
+        assert_eq!(merged["server"]["port"].as_integer(), Some(8080));
+    }
+
+    #[test]
+    fn error_origins() {
+        let config = dir(&[
+            ("10-base.toml", "name = 'base'\nplugins = ['a', 'b']\nserver = {port = 0}"),
+            ("20-site.toml", "name = 1\nplugins = ['C']"),
+        ]);
+        let mut errors = config.check(&schema()).unwrap_err();
+        errors.sort_by_key(|e| e.path.to_string());
+        let found: Vec<(String, Option<&str>)> = errors.iter()
+            .map(|e| (e.path.to_string(), e.file.as_deref().and_then(Path::to_str)))
+            .collect();
+        assert_eq!(found, [
+            ("name".to_string(), Some("20-site.toml")),
+            ("plugins[2]".to_string(), Some("20-site.toml")),
+            ("server.port".to_string(), Some("10-base.toml")),
+        ]);
+        assert!(errors[0].to_string().starts_with("20-site.toml: name: "), "{}", errors[0]);
+
+        let missing = dir(&[("10-base.toml", "plugins = []")]).check(&schema()).unwrap_err();
+        assert_eq!(missing[0].file, None);
+    }
+
+    #[test]
+    fn load_directory() {
+        let path = std::env::temp_dir().join(format!("toml_schema_confdir_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("20-site.toml"), "name = 'site'").unwrap();
+        std::fs::write(path.join("10-base.toml"), "name = 'base'\nserver = {port = 8080}").unwrap();
+        std::fs::write(path.join("README"), "not toml").unwrap();
+
+        let config = ConfigDir::load(&path);
+        std::fs::remove_dir_all(&path).unwrap();
+        let merged = config.unwrap().check(&schema()).unwrap();
+        assert_eq!(merged["name"].as_str(), Some("site"));
+        assert_eq!(merged["server"]["port"].as_integer(), Some(8080));
+
+        ConfigDir::load(path.join("missing")).unwrap_err();
+    }
+}
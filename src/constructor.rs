@@ -1,45 +1,415 @@
+use std::borrow::Cow;
 use crate::*;
 
 
+/// Something odd but harmless found while building a schema, returned by [TomlSchema::from_table_with_warnings]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum SchemaParseWarning {
+    /// A key that means nothing for the schema at `path`, it was ignored (this is often a typo)
+    UnknownKey{path: SchemaPath, key: String},
+    /// A default value in a position where defaults are never used (array children, extras, alternative options, the root)
+    IgnoredDefault{path: SchemaPath, value: Value},
+    /// A default value that its own schema rejects (wrong type, out of bounds, regex miss), documents missing the key
+    /// would always fail [TomlSchema::check_and_complete], `error` is the [SchemaError::compact] form of the error
+    InvalidDefault{path: SchemaPath, value: Value, error: String},
+}
+
+impl std::fmt::Display for SchemaParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (path, what) = match self {
+            SchemaParseWarning::UnknownKey { path, key } => (path, format!("unexpected key '{}'", key)),
+            SchemaParseWarning::IgnoredDefault { path, value } => (path, format!("unexpected default {}", value)),
+            SchemaParseWarning::InvalidDefault { path, value, error } => {
+                return write!(f, "Schema parser found default {} at {} that does not match its schema: {}", value, path, error)
+            },
+        };
+        if path.is_empty() {
+            write!(f, "Schema parser ignored {} at the root", what)
+        } else {
+            write!(f, "Schema parser ignored {} at {}", what, path)
+        }
+    }
+}
+
+
+/// Options for building a schema with [TomlSchema::from_table_with_options], the default options are the ones
+/// used by [TomlSchema::from_table]
+///
+/// the regex limits protect against pathological patterns in untrusted schemas, matching itself always takes linear
+/// time, see [TomlSchema::check_with_budget] to also bound the time spent validating a document
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// unknown keys are errors instead of warnings
+    pub strict: bool,
+    /// the maximum size of a compiled regex in bytes, see [regex::RegexBuilder::size_limit]
+    pub regex_size_limit: Option<usize>,
+    /// the maximum size of the cache of the lazy DFA of a regex in bytes, see [regex::RegexBuilder::dfa_size_limit]
+    pub regex_dfa_size_limit: Option<usize>,
+    /// regexes are compiled the first time they are used instead of while parsing, which speeds up loading schemas
+    /// whose patterns are rarely all used, an invalid regex is then not a parse error, it logs an error and matches
+    /// nothing
+    pub lazy_regex: bool,
+    /// the validators that schemas can reference with `custom = "name"`
+    pub validators: CustomValidators,
+    /// the user-defined types that schemas can use in their `type` key
+    pub plugins: SchemaPlugins,
+    /// the schemas that other schemas can build on with `extends = "name"`
+    pub definitions: SchemaDefinitions,
+    /// the compiled schemas that schemas can use with `{type = "reference", schema = "name"}`
+    pub registry: SchemaRegistry,
+    /// how the schemas of `{type = "reference", url = ".."}` are fetched
+    #[cfg(feature = "remote")]
+    pub remote: RemoteSchemas,
+}
+
+
+/// The state of the parse functions, the location in the schema being built, the warnings found so far and the
+/// regexes compiled so far
+struct ParseContext<'o> {
+    path: SchemaPath,
+    warnings: Vec<SchemaParseWarning>,
+    options: &'o ParseOptions,
+    regexes: HashMap<String, Arc<LazyRegex>>,
+}
+
+impl<'o> ParseContext<'o> {
+    fn new(options: &'o ParseOptions) -> Self {
+        ParseContext { path: SchemaPath::new(), warnings: Vec::new(), options, regexes: HashMap::new() }
+    }
+
+    /// Compiles a regex (or prepares it to be compiled on first use with `lazy_regex`), identical patterns of a schema
+    /// share the regex compiled the first time
+    fn regex(&mut self, text: &str) -> Result<Arc<LazyRegex>, String> {
+        if let Some(regex) = self.regexes.get(text) {
+            return Ok(regex.clone())
+        }
+        let regex = match self.options.lazy_regex {
+            true => Arc::new(LazyRegex::deferred(text, self.options)?),
+            false => Arc::new(LazyRegex::compiled(text, self.options)?)
+        };
+        self.regexes.insert(text.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    fn unknown_key(&mut self, key: &str) -> Result<(), String> {
+        if self.options.strict {
+            return Err(match self.path.is_empty() {
+                true => format!("Unknown key '{}' in schema", key),
+                false => format!("Unknown key '{}' in schema at {}", key, self.path)
+            })
+        }
+        self.warnings.push(SchemaParseWarning::UnknownKey { path: self.path.clone(), key: key.to_string() });
+        Ok(())
+    }
+
+    fn ignored_default(&mut self, value: Option<Value>) {
+        if let Some(value) = value {
+            self.warnings.push(SchemaParseWarning::IgnoredDefault { path: self.path.clone(), value });
+        }
+    }
+
+    /// Checks the default of the entry `key` of a table against the schema of the entry, the default is completed like
+    /// a missing value would be, defaults with placeholders depend on the document and are not checked
+    fn check_default(&mut self, key: &str, schema: &TomlSchema, default: &Value) {
+        if interpolate::has_placeholders(default) {
+            return
+        }
+        let mut value = default.clone();
+        if schema.complete(&mut value).is_err() {
+            return
+        }
+        if let Err(e) = schema.check_depth(&value, DEFAULT_MAX_DEPTH) {
+            let path = self.path.join(PathSegment::Key(key.to_string()));
+            self.warnings.push(SchemaParseWarning::InvalidDefault { path, value: default.clone(), error: e.compact() });
+        }
+    }
+
+    /// Parses a sub-schema located at `segment` below the current location
+    fn parse_at(&mut self, segment: PathSegment, table: &toml::Table) -> Result<(TomlSchema, Option<Value>), String> {
+        self.path.push(segment);
+        let res = parse_schema(table, self);
+        self.path.pop();
+        res
+    }
+
+    /// Parses a sub-schema where defaults are not used, a default is reported as a warning
+    fn parse_at_no_default(&mut self, segment: PathSegment, table: &toml::Table) -> Result<TomlSchema, String> {
+        self.path.push(segment);
+        let res = parse_schema(table, self).map(|(schema, dv)| {
+            self.ignored_default(dv);
+            schema
+        });
+        self.path.pop();
+        res
+    }
+}
+
+
 impl TomlSchema {
     /// The internal constructor for a schema, from toml data, the returned values are
     /// - `Ok(..)` => the schema and it's default value
     /// - `Err(..)` => Some kind of indication on where parsing the schema falied
     /// 
-    /// note: no checking is done on the default values, that means the schema may contain default values that dont match it
-    /// and fail on valid data using check_and_complete(..)
+    /// warnings (ignored keys and defaults, defaults that do not match their schema) are logged with [log::warn], use
+    /// [TomlSchema::from_table_with_warnings] to get them instead
+    /// 
+    /// note: the default values of table keys are checked against their schema but only reported as warnings, a
+    /// schema may still contain default values that dont match it and fail on valid data using check_and_complete(..)
     pub fn from_table(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
     {
-        // get the type of the table if possible
-        let type_str = match table.get("type") {
-            Some(Value::String(s)) => &s,
-            None => "table",
-            _ => return Err(format!("Invalid schema format: type should be a string"))
-        };
+        let (schema, dv, warnings) = TomlSchema::from_table_with_warnings(table)?;
+        for warning in warnings {
+            log::warn!("{}", warning);
+        }
+        Ok((schema, dv))
+    }
 
-        match SchemaType::try_from(type_str)?
-        {
-            SchemaType::String => parse_string(table),
+    /// Same as [TomlSchema::from_table] but the warnings are returned instead of logged
+    pub fn from_table_with_warnings(table: &toml::Table) -> Result<(TomlSchema, Option<Value>, Vec<SchemaParseWarning>),String>
+    {
+        TomlSchema::from_table_with_options(table, &ParseOptions::default())
+    }
+
+    /// Same as [TomlSchema::from_table_with_warnings] with non-default [ParseOptions]
+    pub fn from_table_with_options(table: &toml::Table, options: &ParseOptions) -> Result<(TomlSchema, Option<Value>, Vec<SchemaParseWarning>),String>
+    {
+        let mut ctx = ParseContext::new(options);
+        let (schema, dv) = parse_schema(table, &mut ctx)?;
+        Ok((schema, dv, ctx.warnings))
+    }
+
+    /// Same as [TomlSchema::from_table] but unknown keys (like a misspelled `regx`) are errors instead of warnings,
+    /// ignored defaults are still logged
+    pub fn from_table_strict(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+    {
+        let options = ParseOptions { strict: true, ..ParseOptions::default() };
+        let (schema, dv, warnings) = TomlSchema::from_table_with_options(table, &options)?;
+        for warning in warnings {
+            log::warn!("{}", warning);
+        }
+        Ok((schema, dv))
+    }
+
+    /// Parses TOML text into a schema with [TomlSchema::from_table_strict], the strict version of [str::parse]
+    pub fn parse_strict(s: &str) -> Result<TomlSchema, String>
+    {
+        let table = s.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        let (schema, dv) = TomlSchema::from_table_strict(&table)?;
+        if let Some(value) = dv {
+            log::warn!("{}", SchemaParseWarning::IgnoredDefault { path: SchemaPath::new(), value })
+        }
+        Ok(schema)
+    }
+}
 
-            SchemaType::Integer => parse_int(table),
 
-            SchemaType::Date => parse_date(table),
+/// The table form of a schema, a bare string is a shorthand for a schema with only a type (`"int"` is `{type = "int"}`)
+fn schema_table(value: &Value) -> Option<Cow<'_, toml::Table>>
+{
+    match value {
+        Value::Table(t) => Some(Cow::Borrowed(t)),
+        Value::String(s) => Some(Cow::Owned(toml::Table::from_iter([("type".to_string(), Value::String(s.clone()))]))),
+        _ => None
+    }
+}
 
-            SchemaType::Bool => parse_bool(table),
+/// Keywords of table schemas that were entry names in earlier versions, an entry under one of them must now be
+/// written with a `$` like `$assert`
+const LATE_KEYWORDS: [&str; 4] = ["assert", "extras_match", "count_entries", "key_case"];
 
-            SchemaType::Float => parse_float(table),
+/// Whether `name` is the type of a built-in schema, including the types that are not a [SchemaType] of their own
+pub(crate) fn is_builtin_type(name: &str) -> bool {
+    SchemaType::try_from(name).is_ok() || [TABLE_ARRAY, REFERENCE, PORT].contains(&name) || StringFormat::from_type(name).is_some()
+}
 
-            // todo! : implement an Anything schema, that acs as default and allows child-less array
-            SchemaType::Array => parse_array(table),
+/// Whether the value of a keyword is rather the schema of an entry with the same name, a type name or a table, the
+/// keywords never take such values
+fn looks_like_entry(value: &Value, ctx: &ParseContext<'_>) -> bool {
+    match value {
+        Value::Table(_) => true,
+        Value::String(s) => is_builtin_type(s) || ctx.options.plugins.contains(s),
+        _ => false
+    }
+}
 
-            SchemaType::Table => parse_table(table),
+/// Whether the value of an `x-` key is the schema of an entry rather than an extension, extensions can be tables so
+/// only a type name, a table with a type name or a table of such schemas counts
+fn is_entry_schema(value: &Value, ctx: &ParseContext<'_>) -> bool {
+    match value {
+        Value::Table(t) => match t.get("type") {
+            Some(Value::String(name)) => is_builtin_type(name) || ctx.options.plugins.contains(name),
+            Some(_) => false,
+            None => !t.is_empty() && t.values().all(|v| is_entry_schema(v, ctx))
+        },
+        _ => looks_like_entry(value, ctx)
+    }
+}
 
-            SchemaType::Alternative => parse_alternative(table),
+/// Keywords of any schema that wrap the schema parsed from the other keys, in a table they may also be entries, see
+/// [wrapper_keyword]
+const WRAPPER_KEYWORDS: [&str; 5] = ["default_env", "default_merge", "severity", "custom", "extends"];
 
-            SchemaType::Anything => parse_anything(table),
+/// The value of a keyword that wraps the schema parsed from the other keys, `None` when `table` does not have it or
+/// when its value is the schema of an entry, tables that had an entry with this name before the keyword existed keep it
+fn wrapper_keyword<'t>(table: &'t toml::Table, key: &str, ctx: &ParseContext<'_>) -> Option<&'t Value> {
+    table.get(key).filter(|value| !looks_like_entry(value, ctx))
+}
+
+fn parse_schema(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
+{
+    // a schema that extends a definition is parsed from the keys of the definition with its own keys on top
+    // a definition named like a type is still extended
+    let definitions = &ctx.options.definitions;
+    let extends = |value: &Value| value.as_str().is_some_and(|name| definitions.get(name).is_some()) || !looks_like_entry(value, ctx);
+    if table.get("extends").is_some_and(extends) {
+        let merged = definitions.resolve(table, &extends)?;
+        return parse_schema(&merged, ctx)
+    }
 
-            SchemaType::Exact => parse_exact(table)
+    // extension keys are kept apart and wrap the schema parsed from the other keys, unless they hold the schema of an
+    // entry
+    let is_extension = |(k, v): &(&String, &Value)| k.starts_with("x-") && !is_entry_schema(v, ctx);
+    if table.iter().any(|kv| is_extension(&kv)) {
+        let (extensions, rest): (toml::Table, toml::Table) = table.clone().into_iter().partition(|(k, v)| is_extension(&(k, v)));
+        let (schema, dv) = parse_schema(&rest, ctx)?;
+        return Ok((TomlSchema::Annotated { schema: Arc::new(schema), extensions: Arc::new(extensions) }, dv))
+    }
+
+    // custom validators wrap the schema parsed from the other keys
+    if let Some(custom) = wrapper_keyword(table, "custom", ctx) {
+        let Value::String(name) = custom else {
+            return Err(format!("custom must be the name of a validator but got {:?}", custom))
+        };
+        let Some(validator) = ctx.options.validators.get(name) else {
+            return Err(format!("Unknown custom validator '{}'", name))
+        };
+        let mut rest = table.clone();
+        rest.remove("custom");
+        let (schema, dv) = parse_schema(&rest, ctx)?;
+        return Ok((TomlSchema::Custom { schema: Arc::new(schema), validator: validator.clone() }, dv))
+    }
+
+    // a lower severity wraps the schema parsed from the other keys, `severity = "error"` is the same as no severity
+    if let Some(severity) = wrapper_keyword(table, "severity", ctx) {
+        let Value::String(name) = severity else {
+            return Err(format!("severity must be \"error\", \"warning\" or \"info\" but got {:?}", severity))
+        };
+        let severity: Severity = name.parse()?;
+        let mut rest = table.clone();
+        rest.remove("severity");
+        let (schema, dv) = parse_schema(&rest, ctx)?;
+        if severity == Severity::Error {
+            return Ok((schema, dv))
         }
+        return Ok((TomlSchema::Graded { schema: Arc::new(schema), severity }, dv))
+    }
+
+    // the options of defaults wrap the schema parsed from the other keys, the `default` key is kept
+    let default_env = wrapper_keyword(table, "default_env", ctx);
+    let default_merge = wrapper_keyword(table, "default_merge", ctx);
+    if default_env.is_some() || default_merge.is_some() {
+        let mut rest = table.clone();
+        if default_env.is_some() {
+            rest.remove("default_env");
+        }
+        if default_merge.is_some() {
+            rest.remove("default_merge");
+        }
+        let env = match default_env {
+            Some(Value::String(var)) => Some(var.as_str().into()),
+            Some(other) => return Err(format!("default_env must be the name of an environment variable but got {:?}", other)),
+            None => None
+        };
+        let merge = match default_merge {
+            Some(Value::String(s)) if s == "replace" => false,
+            Some(Value::String(s)) if s == "merge" => true,
+            Some(other) => return Err(format!("default_merge must be \"replace\" or \"merge\" but got {:?}", other)),
+            None => false
+        };
+        let (schema, dv) = parse_schema(&rest, ctx)?;
+        return Ok((TomlSchema::Defaulted { schema: Arc::new(schema), env, merge }, dv))
+    }
+
+    // strict parsing checks each node against the schema of schema files, once the keys of wrappers are taken out,
+    // the entries named like them are escaped like in the schema of schema files
+    if ctx.options.strict {
+        match WRAPPER_KEYWORDS.iter().any(|k| table.contains_key(*k)) {
+            true => {
+                let escaped = table.iter().map(|(k, v)| match WRAPPER_KEYWORDS.contains(&k.as_str()) {
+                    true => (format!("${}", k), v.clone()),
+                    false => (k.clone(), v.clone())
+                }).collect();
+                meta::check_node(&escaped, &ctx.path)?;
+            },
+            false => meta::check_node(table, &ctx.path)?
+        }
+    }
+
+    // get the type of the table if possible
+    let type_str = match table.get("type") {
+        Some(Value::String(s)) => s,
+        Some(Value::Array(types)) => return parse_type_union(table, types, ctx),
+        None => "table",
+        _ => return Err("Invalid schema format: type should be a string or an array of strings".to_string())
+    };
+
+    // sugar for an array of tables, it is not a type of its own
+    if type_str == TABLE_ARRAY {
+        return parse_table_array(table, ctx)
+    }
+    if type_str == REFERENCE {
+        return parse_reference(table, ctx)
+    }
+    if StringFormat::from_type(type_str).is_some() {
+        return parse_string(table, ctx)
+    }
+    if type_str == PORT {
+        return parse_int(table, ctx)
+    }
+
+    let schema_type = match SchemaType::try_from(type_str) {
+        Ok(t) => t,
+        Err(e) => {
+            // user-defined types get their table without the keys handled here
+            let mut rest = table.clone();
+            rest.remove("type");
+            let dv = rest.remove("default");
+            return match ctx.options.plugins.parse(type_str, &rest) {
+                Some(Ok(plugin)) => Ok((TomlSchema::Plugin(plugin), dv)),
+                Some(Err(e)) => Err(format!("Invalid {} schema: {}", type_str, e)),
+                #[cfg(not(feature = "cron"))]
+                None if type_str == "cron" => Err("The cron type requires the `cron` feature".to_string()),
+                None => Err(e)
+            }
+        }
+    };
+
+    match schema_type
+    {
+        SchemaType::String => parse_string(table, ctx),
+
+        SchemaType::Integer => parse_int(table, ctx),
+
+        SchemaType::Date => parse_date(table, ctx),
+
+        SchemaType::Bool => parse_bool(table, ctx),
+
+        SchemaType::Float => parse_float(table, ctx),
+
+        SchemaType::Array => parse_array(table, ctx),
+
+        SchemaType::Table => parse_table(table, ctx),
+
+        SchemaType::Map => parse_map(table, ctx),
+
+        SchemaType::Alternative => parse_alternative(table, ctx),
+
+        SchemaType::Anything => parse_anything(table, ctx),
+
+        SchemaType::Exact => parse_exact(table, ctx),
+
+        SchemaType::Plugin => Err("plugin is the kind of user-defined types, the type of a schema is the name of a registered plugin".to_string())
     }
 }
 
@@ -51,9 +421,77 @@ impl TomlSchema {
 // parse methods
 
 
-fn parse_string(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+/// The options of regular expressions, for string schemas and extra keys
+#[derive(Default)]
+struct RegexFlags {
+    full_match: bool,
+    case_insensitive: bool,
+    multiline: bool,
+    dot_matches_newline: bool,
+}
+
+impl RegexFlags {
+    const KEYS: [&'static str; 4] = ["full_match", "case_insensitive", "multiline", "dot_matches_newline"];
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        let Value::Boolean(b) = value else {
+            return Err(format!("Regex option {} must be a boolean but got {:?}", key, value))
+        };
+        match key {
+            "full_match" => self.full_match = *b,
+            "case_insensitive" => self.case_insensitive = *b,
+            "multiline" => self.multiline = *b,
+            "dot_matches_newline" => self.dot_matches_newline = *b,
+            _ => unreachable!("not a regex option: {}", key)
+        }
+        Ok(())
+    }
+
+    /// Compiles `pattern` with these options, they are written in the pattern itself (as `(?ims)` flags and `\A..\z`
+    /// anchors) so the text of the compiled regex describes it completely
+    fn compile(&self, pattern: &str, ctx: &mut ParseContext<'_>) -> Result<Pattern, String> {
+        Pattern::with_compiler(&self.text(pattern), |text| ctx.regex(text))
+    }
+
+    /// The pattern with these options written in it
+    fn text(&self, pattern: &str) -> String {
+        let mut text = String::new();
+        let flags: String = [(self.case_insensitive, 'i'), (self.multiline, 'm'), (self.dot_matches_newline, 's')]
+            .iter().filter(|(on, _)| *on).map(|(_, c)| *c).collect();
+        if !flags.is_empty() {
+            text.push_str(&format!("(?{})", flags));
+        }
+        if self.full_match {
+            text.push_str(&format!("\\A(?:{})\\z", pattern));
+        } else {
+            text.push_str(pattern);
+        }
+        text
+    }
+}
+
+fn parse_string(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
 {
-    let mut res = Regex::new(".*").unwrap();
+    let mut pattern = ".*";
+    let mut not_pattern = None;
+    let mut literals = [None, None, None];
+    let mut trim = false;
+    #[cfg(feature = "unicode")]
+    let mut normalize = None;
+    let mut flags = RegexFlags::default();
+    let mut refers_to = None;
+    let mut interpolate = false;
+    // `type = "hex"` is short for `type = "string", format = "hex"`
+    let type_format = table.get("type").and_then(Value::as_str).and_then(StringFormat::from_type);
+    let mut format = match (type_format, table.get("format")) {
+        (Some(format), Some(_)) => return Err(format!("format cannot be used with type {}", format.name())),
+        (format, None) => format,
+        (None, Some(Value::String(name))) => match StringFormat::from_type(name) {
+            Some(format) => Some(format),
+            None => return Err(format!("Unknown string format {:?}", name))
+        },
+        (None, Some(other)) => return Err(format!("format must be the name of a string format but got {:?}", other))
+    };
     let mut dv = None;
 
     for k in table.keys() {
@@ -61,29 +499,127 @@ fn parse_string(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),Strin
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
+
+            "format" => (),
+
+            "base" if matches!(format, Some(StringFormat::Glob { .. })) => {
+                let (Value::String(dir), Some(StringFormat::Glob { base })) = (&table[k], &mut format) else {
+                    return Err(format!("base must be the path of a directory but got {:?}", &table[k]))
+                };
+                *base = Some(dir.clone());
+            },
+
+            bound @ ("min_bytes" | "max_bytes") if format.as_mut().is_some_and(|format| format.byte_bounds().is_some()) => {
+                let (min, max) = format.as_mut().and_then(StringFormat::byte_bounds).expect("checked");
+                let count = match &table[k] {
+                    Value::Integer(i) => usize::try_from(*i).map_err(|_| format!("{} must be a positive int but got {}", bound, i))?,
+                    other => return Err(format!("{} must be a positive int but got {:?}", bound, other))
+                };
+                if bound == "min_bytes" {*min = count} else {*max = count}
+            },
             
             "regex" => {
                 if let Value::String(re) = &table[k] {
-                    res = Regex::new(re).map_err(|e| e.to_string())?
+                    pattern = re;
                 } else {
                     return Err(format!("regex must be a string but got {:?}", &table[k]))
                 }
             },
+
+            "not_regex" => {
+                if let Value::String(re) = &table[k] {
+                    not_pattern = Some(re.as_str());
+                } else {
+                    return Err(format!("not_regex must be a string but got {:?}", &table[k]))
+                }
+            },
+
+            literal @ ("starts_with" | "ends_with" | "contains") => {
+                let Value::String(text) = &table[k] else {
+                    return Err(format!("{} must be a string but got {:?}", literal, &table[k]))
+                };
+                let i = ["starts_with", "ends_with", "contains"].iter().position(|key| key == &literal).unwrap();
+                literals[i] = Some(text.clone());
+            },
+
+            flag if RegexFlags::KEYS.contains(&flag) => flags.set(flag, &table[k])?,
+
+            "interpolate" => {
+                if let Value::Boolean(b) = &table[k] {
+                    interpolate = *b;
+                } else {
+                    return Err(format!("interpolate must be a boolean but got {:?}", &table[k]))
+                }
+            },
+
+            "trim" => {
+                if let Value::Boolean(b) = &table[k] {
+                    trim = *b;
+                } else {
+                    return Err(format!("trim must be a boolean but got {:?}", &table[k]))
+                }
+            },
+
+            #[cfg(feature = "unicode")]
+            "normalize" => match &table[k] {
+                Value::String(name) => match NormalForm::from_name(name) {
+                    Some(form) => normalize = Some(form),
+                    None => return Err(format!("normalize must be one of {:?} but got {:?}", NormalForm::NAMES, name))
+                },
+                other => return Err(format!("normalize must be a string but got {:?}", other))
+            },
+
+            #[cfg(not(feature = "unicode"))]
+            "normalize" => return Err("normalize requires the `unicode` feature".to_string()),
+
+            "refers_to" => {
+                if let Value::String(target) = &table[k] {
+                    reference::parse_target(target)?;
+                    refers_to = Some(target.as_str().into());
+                } else {
+                    return Err(format!("refers_to must be a path but got {:?}", &table[k]))
+                }
+            },
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in string config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::String { regex: res }, dv))
+    let regex = flags.compile(pattern, ctx)?;
+    let not_regex = match not_pattern {
+        Some(not_pattern) => Some(flags.compile(not_pattern, ctx)?),
+        None => None
+    };
+    if let Some((min, max)) = format.as_mut().and_then(StringFormat::byte_bounds).filter(|(min, max)| min > max) {
+        return Err(format!("min_bytes {} is larger than max_bytes {}", min, max))
+    }
+    let [starts_with, ends_with, contains] = literals;
+    let rules = Arc::new(StringRules {
+        not_regex, starts_with, ends_with, contains, interpolate, trim,
+        #[cfg(feature = "unicode")]
+        normalize,
+        format
+    });
+    Ok((TomlSchema::String { regex, refers_to, rules }, dv))
 }
 
 /* ------------------------------- */
 
-fn parse_int(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String> 
+/// The `type` of ints that are network ports
+pub(crate) const PORT: &str = "port";
+
+/// The first port that is not privileged, lower ports need special rights to listen on
+const FIRST_UNPRIVILEGED_PORT: i64 = 1024;
+
+fn parse_int(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String> 
 {
-    let mut min = i64::MIN;
-    let mut max = i64::MAX;
+    let port = table.get("type").and_then(Value::as_str) == Some(PORT);
+    let (mut min, mut max) = if port {(0, u16::MAX as i64)} else {(i64::MIN, i64::MAX)};
+    let mut allow_privileged = true;
+    let mut allow_negative = true;
+    let mut bits = None;
+    let mut coerce = false;
     let mut dv = None;
 
     for k in table.keys() {
@@ -91,7 +627,27 @@ fn parse_int(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
+
+            "coerce" => {
+                if let Value::Boolean(b) = &table[k] {coerce = *b;}
+                else {return Err(format!("Int coerce must be a boolean but got {:?}", &table[k]))}
+            },
+
+            "allow_negative" => {
+                if let Value::Boolean(b) = &table[k] {allow_negative = *b;}
+                else {return Err(format!("allow_negative must be a boolean but got {:?}", &table[k]))}
+            },
+
+            "bits" => match &table[k] {
+                Value::Integer(i) if IntWidth::BITS.contains(i) => bits = Some(*i as u8),
+                other => return Err(format!("bits must be 8, 16, 32 or 64 but got {}", other))
+            },
+
+            "allow_privileged" if port => {
+                if let Value::Boolean(b) = &table[k] {allow_privileged = *b;}
+                else {return Err(format!("allow_privileged must be a boolean but got {:?}", &table[k]))}
+            },
             
             "min" => {
                 if let Value::Integer(i) = &table[k] {min = *i;} 
@@ -103,20 +659,41 @@ fn parse_int(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
                 else {return Err(format!("Int max must be an int but got {:?}", &table[k]))} 
             },
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in int config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::Integer { min, max }, dv))
+    if !allow_privileged {
+        min = min.max(FIRST_UNPRIVILEGED_PORT);
+    }
+    if !allow_negative {
+        min = min.max(0);
+    }
+    let width = bits.map(|bits| IntWidth { bits, signed: allow_negative });
+    if let Some(width) = width {
+        // explicit bounds must fit in the width, the implicit ones of ports are clamped to it
+        for (name, bound) in [("min", min), ("max", max)] {
+            if table.contains_key(name) && !width.contains(bound) {
+                return Err(format!("Int {} {} does not fit in a {}", name, bound, width))
+            }
+        }
+        let (width_min, width_max) = width.range();
+        (min, max) = (min.max(width_min), max.min(width_max));
+    }
+    if min > max {
+        return Err(format!("Int min {} is larger than max {}", min, max))
+    }
+    Ok((TomlSchema::Integer { min, max, width, coerce }, dv))
 }
 
 /* ------------------------------- */
 
-fn parse_float(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+fn parse_float(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
 {
     let mut min = f64::NEG_INFINITY;
     let mut max = f64::INFINITY;
     let mut nan_ok = false;
+    let mut coerce = false;
     let mut dv = None;
 
     for k in table.keys() {
@@ -124,7 +701,7 @@ fn parse_float(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
             
             "min" => {
                 if let Value::Float(x) = &table[k] {min = *x;} 
@@ -140,18 +717,24 @@ fn parse_float(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
                 if let Value::Boolean(b) = &table[k] {nan_ok = *b} 
                 else {return Err(format!("Float nan_ok must be a boolean but got {:?}", &table[k]))}
             }
+
+            "coerce" => {
+                if let Value::Boolean(b) = &table[k] {coerce = *b}
+                else {return Err(format!("Float coerce must be a boolean but got {:?}", &table[k]))}
+            }
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in float config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::Float { min, max, nan_ok }, dv))
+    Ok((TomlSchema::Float { min, max, nan_ok, coerce }, dv))
 }
 
 /* ------------------------------- */
 
-fn parse_bool(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String> 
+fn parse_bool(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String> 
 {
+    let mut coerce = false;
     let mut dv = None;
 
     for k in table.keys() {
@@ -159,38 +742,75 @@ fn parse_bool(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
+
+            "coerce" => {
+                if let Value::Boolean(b) = &table[k] {coerce = *b}
+                else {return Err(format!("Bool coerce must be a boolean but got {:?}", &table[k]))}
+            },
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in float config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::Bool, dv))
+    Ok((TomlSchema::Bool { coerce }, dv))
 }
 
 /* ------------------------------- */
 
-fn parse_date(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String> 
+fn parse_date(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String> 
 {
     let mut dv = None;
+    let mut date = DateSchema::default();
 
     for k in table.keys() {
         match k.as_str()
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
+
+            #[cfg(feature = "chrono")]
+            bound @ ("min" | "max") => {
+                let Value::Datetime(d) = &table[k] else {
+                    return Err(format!("Date {} must be a date but got {:?}", bound, &table[k]))
+                };
+                if bound == "min" {date.min = Some(*d)} else {date.max = Some(*d)}
+            },
+
+            #[cfg(not(feature = "chrono"))]
+            "min" | "max" => return Err("Date bounds require the `chrono` feature".to_string()),
+
+            flag @ ("require_offset" | "local_only") => {
+                let Value::Boolean(b) = &table[k] else {
+                    return Err(format!("Date {} must be a boolean but got {:?}", flag, &table[k]))
+                };
+                if flag == "require_offset" {date.require_offset = *b} else {date.local_only = *b}
+            },
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in date config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::Date, dv))
+    if date.require_offset && date.local_only {
+        return Err("Date cannot have both require_offset and local_only".to_string())
+    }
+
+    #[cfg(feature = "chrono")]
+    if let (Some(min), Some(max)) = (&date.min, &date.max) {
+        match compare_dates(min, max) {
+            Some(std::cmp::Ordering::Greater) => return Err(format!("Date min {} is after max {}", min, max)),
+            None => return Err(format!("Date min {} cannot be compared to max {}", min, max)),
+            _ => ()
+        }
+    }
+
+    Ok((TomlSchema::Date(Arc::new(date)), dv))
 }
 
 /* ------------------------------- */
 
-fn parse_anything(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String> 
+fn parse_anything(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String> 
 {
     let mut dv = None;
 
@@ -199,9 +819,9 @@ fn parse_anything(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),Str
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in date config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
@@ -210,38 +830,59 @@ fn parse_anything(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),Str
 
 /* ------------------------------- */
 
-fn parse_exact(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String> 
+fn parse_exact(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String> 
 {
     let mut dv = None;
     let mut value = None;
+    let mut values = None;
+    let mut epsilon = None;
 
     for k in table.keys() {
         match k.as_str()
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
 
             "value" => {value = Some(&table[k])}
+
+            "values" => {values = Some(&table[k])}
+
+            "epsilon" => match &table[k] {
+                Value::Float(f) if *f >= 0.0 => epsilon = Some(*f),
+                Value::Integer(i) if *i >= 0 => epsilon = Some(*i as f64),
+                other => return Err(format!("Exact epsilon must be a positive number but got {:?}", other))
+            },
             
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in date config", other_key)
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    match value {
-        Some(v) => Ok((TomlSchema::Exact(v.clone()), dv)),
-        None => Err("Exact without a value is not allowed".to_string())
+    if epsilon.is_some() && values.is_some() {
+        return Err("Exact epsilon is only allowed with a single value".to_string())
+    }
+
+    match (value, values) {
+        (Some(v), None) => Ok((TomlSchema::Exact { value: v.clone(), epsilon: epsilon.unwrap_or(0.0) }, dv)),
+        (None, Some(Value::Array(values))) if !values.is_empty() => Ok((TomlSchema::Enum(values.clone().into()), dv)),
+        (None, Some(values)) => Err(format!("Exact values must be a non-empty array but got {:?}", values)),
+        (Some(_), Some(_)) => Err("Exact cannot have both a value and values".to_string()),
+        (None, None) => Err("Exact without a value is not allowed".to_string())
     }
     
 }
 
 /* ------------------------------- */
 
-fn parse_array(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+fn parse_array(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
 {
     let mut min = 0;
     let mut max = usize::MAX;
+    let mut len = None;
+    let mut multiple_of = 1;
     let mut cond = None;
+    let mut unique_by = None;
+    let mut append = false;
     let mut dv = None;
 
     for k in table.keys() {
@@ -249,7 +890,7 @@ fn parse_array(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
         {
             "type" => (),
             
-            "default" => {dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
             
             "min" => { 
                 match &table[k] {
@@ -264,45 +905,218 @@ fn parse_array(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
                     _ => {return Err(format!("Array max size must be a positive int but got {:?}", &table[k]))}
                 }
             },
+
+            "len" => {
+                match &table[k] {
+                    Value::Integer(i) if *i >= 0 => {len = Some(*i as usize);}
+                    _ => {return Err(format!("Array len must be a positive int but got {:?}", &table[k]))}
+                }
+            },
+
+            "len_multiple_of" => {
+                match &table[k] {
+                    Value::Integer(i) if *i > 0 && *i <= u32::MAX.into() => {multiple_of = *i as u32;}
+                    _ => {return Err(format!("Array len_multiple_of must be an int above 0 but got {:?}", &table[k]))}
+                }
+            },
             
             "child" => {
-                if let Value::Table(t) = &table[k] {
-                    match TomlSchema::from_table(t) {
+                match schema_table(&table[k]) {
+                    Some(t) => match ctx.parse_at_no_default(PathSegment::AnyIndex, &t) {
                         Err(e) => return Err(format!("Invalid array condition: {:?}", e)),
-                        Ok((schema, dv)) => {
-                            cond = Some(schema);
-                            if let Some(d) = dv {
-                                log::warn!("Schema parser got unexpected (ignored) default {:?} for array element", d)
-                            }
-                        }
-                    }
+                        Ok(schema) => {cond = Some(schema);}
+                    },
+                    None => return Err(format!("Array child must be a table or a type name but got {:?}", &table[k]))
                 }
-            }
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in array config", other_key)
+            },
+
+            "unique_by" => {
+                match &table[k] {
+                    Value::String(s) => {unique_by = Some(s.as_str().into());},
+                    _ => return Err(format!("Array unique_by must be a key name but got {:?}", &table[k]))
+                }
+            },
+
+            "merge" => {append = parse_array_merge(&table[k])?},
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    match cond {
-        Some(cond) => Ok((TomlSchema::Array { cond: Box::new(cond), min, max }, dv)),
-        None => return Err("Array without a 'child' key is not allowed".to_string())
+    if let Some(len) = len {
+        if table.contains_key("min") || table.contains_key("max") {
+            return Err("Array len cannot be used with min or max".to_string())
+        }
+        (min, max) = (len, len);
+    }
+
+    // without a child any element is allowed
+    let cond = cond.unwrap_or(TomlSchema::Anything);
+    Ok((TomlSchema::Array { cond: Arc::new(cond), min, max, multiple_of, unique_by, append }, dv))
+}
+
+/// The `merge` key of arrays, whether the arrays of later layers are appended in [TomlSchema::merge]
+fn parse_array_merge(value: &Value) -> Result<bool, String>
+{
+    match value {
+        Value::String(s) if s == "replace" => Ok(false),
+        Value::String(s) if s == "append" => Ok(true),
+        _ => Err(format!("Array merge must be \"replace\" or \"append\" but got {:?}", value))
+    }
+}
+
+/* ------------------------------- */
+
+/// The `type` of the shorthand for arrays of tables
+pub(crate) const TABLE_ARRAY: &str = "table_array";
+
+/// `type = "table_array"`, an array of tables written as the table schema of its elements with the bounds of the
+/// array in `min_items` and `max_items`
+fn parse_table_array(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
+{
+    let mut min = 0;
+    let mut max = usize::MAX;
+    let mut unique_by = None;
+    let mut append = false;
+    let mut dv = None;
+    let mut element = toml::Table::new();
+
+    for (k, v) in table {
+        match k.as_str()
+        {
+            "type" => (),
+
+            "default" => {dv = Some(v.clone())},
+
+            "min_items" => {
+                match v {
+                    Value::Integer(i) if *i >= 0 => {min = *i as usize;}
+                    _ => {return Err(format!("Table array min_items must be a positive int but got {:?}", v))}
+                }
+            },
+
+            "max_items" => {
+                match v {
+                    Value::Integer(i) if *i >= 0 => {max = *i as usize;}
+                    _ => {return Err(format!("Table array max_items must be a positive int but got {:?}", v))}
+                }
+            },
+
+            "unique_by" => {
+                match v {
+                    Value::String(s) => {unique_by = Some(s.as_str().into());},
+                    _ => return Err(format!("Table array unique_by must be a key name but got {:?}", v))
+                }
+            },
+
+            "merge" => {append = parse_array_merge(v)?},
+
+            // everything else describes the elements
+            _ => {element.insert(k.clone(), v.clone());}
+        }
+    }
+
+    let cond = match ctx.parse_at_no_default(PathSegment::AnyIndex, &element) {
+        Ok(schema) => schema,
+        Err(e) => return Err(format!("Invalid table array element: {}", e))
+    };
+    Ok((TomlSchema::Array { cond: Arc::new(cond), min, max, multiple_of: 1, unique_by, append }, dv))
+}
+
+/* ------------------------------- */
+
+/// The `type` of a schema of the [SchemaRegistry] of the options
+pub(crate) const REFERENCE: &str = "reference";
+
+/// `type = "reference"`, the registered schema named by `schema` or the schema at `url`, it is copied into the schema
+/// being parsed
+fn parse_reference(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
+{
+    let mut name = None;
+    #[cfg(feature = "remote")]
+    let mut url = None;
+    let mut dv = None;
+
+    for (k, v) in table {
+        match k.as_str() {
+            "type" => (),
+
+            "default" => {dv = Some(v.clone())},
+
+            "schema" => match v {
+                Value::String(s) => {name = Some(s.as_str())},
+                _ => return Err(format!("Reference schema must be the name of a registered schema but got {:?}", v))
+            },
+
+            #[cfg(feature = "remote")]
+            "url" => match v {
+                Value::String(s) => {url = Some(s.as_str())},
+                _ => return Err(format!("Reference url must be a string but got {:?}", v))
+            },
+
+            #[cfg(not(feature = "remote"))]
+            "url" => return Err("References by url require the `remote` feature".to_string()),
+
+            other_key => ctx.unknown_key(other_key)?
+        }
+    }
+
+    #[cfg(feature = "remote")]
+    if let Some(url) = url {
+        if name.is_some() {
+            return Err("Reference must have either a schema or a url, not both".to_string())
+        }
+        return Ok((ctx.options.remote.resolve(url, ctx.options)?, dv))
+    }
+    let Some(name) = name else {
+        return Err("Reference requires the name of a registered schema in `schema`".to_string())
+    };
+    let _resolving = registry::ResolvingGuard::enter(format!("schema '{}'", name))?;
+    match ctx.options.registry.get(name) {
+        Some(schema) => Ok(((*schema).clone(), dv)),
+        None => Err(format!("Unknown schema '{}' in reference", name))
     }
 }
 
 /* ------------------------------- */
 
-fn parse_table(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+fn parse_table(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
 {
     let mut min = 0;
     let mut max = usize::MAX;
-    let mut entries = HashMap::new();
+    let mut entries = Vec::new();
     let mut extras = Vec::new();
+    let mut assertions = Vec::new();
+    let mut requirements = Vec::new();
+    let mut policy = ExtrasPolicy::default();
     let mut dv = None;
 
+    // `require` is only a keyword next to a condition, the shape of `when` tells whether it is one
+    let conditional = table.get("when").is_some_and(Requirement::is_condition);
+    let keyword = |k: &str| LATE_KEYWORDS.contains(&k) || (conditional && k == "require");
+    if let Some(k) = table.keys().find(|k| keyword(k) && looks_like_entry(&table[k.as_str()], ctx)) {
+        return Err(format!("Table key {} is a keyword but its value is the schema of an entry, write \"${}\" to declare an entry with this name", k, k))
+    }
+
     for k in table.keys() {
         match k.as_str() {
             "type" => (),
 
-            "default" =>{ dv = Some((&table[k]).clone())},
+            "default" =>{ dv = Some(table[k].clone())},
+
+            "extras_match" => { match &table[k] {
+                Value::String(s) => {policy.matching = ExtrasMatching::try_from(s.as_str())?;},
+                _ => {return Err(format!("Table extras_match must be a string but got {:?}", &table[k]))}
+            }},
+
+            "key_case" => { match &table[k] {
+                Value::String(s) => {policy.key_case = Some(KeyCase::try_from(s.as_str())?);},
+                _ => {return Err(format!("Table key_case must be a string but got {:?}", &table[k]))}
+            }},
+
+            "count_entries" => { match &table[k] {
+                Value::Boolean(b) => {policy.count_entries = *b;},
+                _ => {return Err(format!("Table count_entries must be a bool but got {:?}", &table[k]))}
+            }},
 
             "min" => { 
                 match &table[k] {
@@ -327,12 +1141,20 @@ fn parse_table(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
                             // declare these variables here for scoping
                             let extra_key;
                             let extra_schema;
+                            let key_type = match extra_table.get("key_type") {
+                                Some(v) => KeyType::from_value(v)?,
+                                None => KeyType::String
+                            };
 
                             // get key regex
+                            let mut flags = RegexFlags::default();
+                            for flag in RegexFlags::KEYS {
+                                if let Some(v) = extra_table.get(flag) {flags.set(flag, v)?;}
+                            }
                             match extra_table.get("key") {
                                 Some(Value::String(s)) =>
                                 {
-                                    match Regex::new(s) {
+                                    match flags.compile(s, ctx) {
                                         Ok(re) => {extra_key = re;},
                                         Err(e) => {return Err(format!("Regex error : {:?}", e))}
                                     }
@@ -341,29 +1163,28 @@ fn parse_table(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
                             }
 
                             // parse sub-schema
-                            match extra_table.get("schema") {
-                                Some(Value::Table(t)) =>
+                            match extra_table.get("schema").and_then(schema_table) {
+                                Some(t) =>
                                 {
-                                    match TomlSchema::from_table(t) {
-                                        Ok((sch, dv)) => {
-                                            if let Some(d) = dv {
-                                                log::warn!("Schema parser got unexpected (ignored) default {:?} in table extras", d);
-                                            }
+                                    match ctx.parse_at_no_default(PathSegment::Extra(extra_key.as_str().to_string()), &t) {
+                                        Ok(sch) => {
                                             extra_schema = sch;
                                         },
                                         Err(e) => {return Err(format!("In table extra with key {:?} \n{}", extra_key, e))}
                                     }
                                 },
-                                _ => {return Err(format!("Extra entry schemas must be tables but got {:?}", extra_table.get("schema")))}
+                                None => {return Err(format!("Extra entry schemas must be tables or type names but got {:?}", extra_table.get("schema")))}
                             }
 
-                            //throw a vague warning if there are other keys
-                            if extra_table.len() > 2 {
-                                log::warn!("Table extra contains unused keys (got {} but expected 2)", extra_table.len())
+                            // other keys are ignored
+                            ctx.path.push(PathSegment::Extra(extra_key.as_str().to_string()));
+                            for other_key in extra_table.keys().filter(|k| !["key", "key_type", "schema"].contains(&k.as_str()) && !RegexFlags::KEYS.contains(&k.as_str())) {
+                                ctx.unknown_key(other_key)?;
                             }
+                            ctx.path.pop();
 
                             // and add to registered
-                            extras.push(TableEntry { key: extra_key, value: extra_schema });
+                            extras.push(TableEntry { key: extra_key, key_type, value: extra_schema });
                         }
                         _ => {return Err(format!("Extra entries must be tables but got {:?}", extra))}
                     }}
@@ -371,71 +1192,187 @@ fn parse_table(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String
                 _ => {return Err(format!("Table entries must be an array but got {:?}", &table[k]))}
             }}
 
+            "assert" => { match &table[k] {
+                Value::Array(arr) => {
+                    for assertion in arr {assertions.push(Assertion::from_value(assertion)?);}
+                },
+                _ => {return Err(format!("Table assertions must be an array but got {:?}", &table[k]))}
+            }}
+
+            // `when` and `require` are also common key names, they are keywords only when they look like a condition
+            "when" if Requirement::is_condition(&table[k]) => {
+                requirements = Requirement::from_values(&table[k], table.get("require"))?;
+            },
+
+            "require" if conditional => (),
+
             // extra params do not cause errors table only
             _ => {
-                let custom_key = 
-                    if k.chars().next() == Some('$') {k[1..].to_string()} 
-                    else {k.to_string()};
+                let custom_key = k.strip_prefix('$').unwrap_or(k).to_string();
 
-                match &table[k] {
-                    Value::Table(t) => {
-                        match TomlSchema::from_table(t)
+                match schema_table(&table[k]) {
+                    Some(t) => {
+                        match ctx.parse_at(PathSegment::Key(custom_key.clone()), &t)
                         {
                             Ok((schema, dv)) => {
-                                entries.insert(custom_key, (schema, dv));
+                                if let Some(default) = &dv {
+                                    ctx.check_default(&custom_key, &schema, default);
+                                }
+                                entries.push((custom_key, (schema, dv)));
                             },
                             Err(e) => {return Err(format!("In schema for key {}\n{}", custom_key, e));}
                         }
                     },
-                    _ => {return Err(format!("Schema for key {} should be a table but got {:?}", custom_key, &table[k]))}
+                    None => {return Err(format!("Schema for key {} should be a table or a type name but got {:?}", custom_key, &table[k]))}
                 }
             }
         }
     }
 
-    Ok((TomlSchema::Table { extras, min, max, entries }, dv))
+    let schema = TableSchema {
+        entries: entries.into_iter().collect(), extras: extras.into(), min, max,
+        assertions: assertions.into(), requirements: requirements.into(), policy
+    };
+    Ok((TomlSchema::Table(Arc::new(schema)), dv))
 }
 
 /* ------------------------------- */
 
-fn parse_alternative(table: &toml::Table) -> Result<(TomlSchema, Option<Value>),String>
+fn parse_map(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
+{
+    let mut min = 0;
+    let mut max = usize::MAX;
+    let mut pattern = ".*";
+    let mut flags = RegexFlags::default();
+    let mut key_type = KeyType::String;
+    let mut value = None;
+    let mut dv = None;
+
+    for k in table.keys() {
+        match k.as_str()
+        {
+            "type" => (),
+
+            "default" => {dv = Some(table[k].clone())},
+
+            "min" => {
+                match &table[k] {
+                    Value::Integer(i) if *i >= 0 => {min = *i as usize;}
+                    _ => {return Err(format!("Map min size must be a positive int but got {:?}", &table[k]))}
+                }
+            },
+
+            "max" => {
+                match &table[k] {
+                    Value::Integer(i) if *i >= 0 => {max = *i as usize;}
+                    _ => {return Err(format!("Map max size must be a positive int but got {:?}", &table[k]))}
+                }
+            },
+
+            "key_regex" => {
+                match &table[k] {
+                    Value::String(re) => {pattern = re;},
+                    _ => return Err(format!("Map key_regex must be a string but got {:?}", &table[k]))
+                }
+            },
+
+            flag if RegexFlags::KEYS.contains(&flag) => flags.set(flag, &table[k])?,
+
+            "key_type" => {key_type = KeyType::from_value(&table[k])?;},
+
+            // parsed once the key regex is known, it is part of the path
+            "value" => {
+                match schema_table(&table[k]) {
+                    Some(t) => {value = Some(t);},
+                    None => return Err(format!("Map value must be a table or a type name but got {:?}", &table[k]))
+                }
+            },
+
+            other_key => ctx.unknown_key(other_key)?
+        }
+    }
+
+    let key = flags.compile(pattern, ctx)?;
+    let value = match value {
+        Some(t) => match ctx.parse_at_no_default(PathSegment::Extra(key.as_str().to_string()), &t) {
+            Ok(schema) => schema,
+            Err(e) => return Err(format!("Invalid map value: {}", e))
+        },
+        None => TomlSchema::Anything
+    };
+    Ok((TomlSchema::Map(Arc::new(MapSchema { key, key_type, value, min, max })), dv))
+}
+
+/* ------------------------------- */
+
+/// `type = ["int", "float"]`, a shorthand for an alternative of schemas that only have a type
+fn parse_type_union(table: &toml::Table, types: &[Value], ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
 {
     let mut options = Vec::new();
     let mut dv = None;
 
+    for (i, t) in types.iter().enumerate() {
+        match t {
+            Value::String(_) => {
+                let opt_table = schema_table(t).expect("strings are schema shorthands");
+                match ctx.parse_at_no_default(PathSegment::Branch(i), &opt_table) {
+                    Ok(schema) => options.push(schema),
+                    Err(e) => return Err(format!("In type list \n {}", e))
+                }
+            },
+            _ => return Err(format!("Types in a type list must be strings but got {:?}", t))
+        }
+    }
+
     for k in table.keys() {
         match k.as_str()
         {
             "type" => (),
 
-            "default" =>{ dv = Some((&table[k]).clone())},
+            "default" => {dv = Some(table[k].clone())},
+
+            other_key => ctx.unknown_key(other_key)?
+        }
+    }
+
+    Ok((TomlSchema::Alternative(Arc::new(AlternativeSchema::new(options))), dv))
+}
+
+/* ------------------------------- */
+
+fn parse_alternative(table: &toml::Table, ctx: &mut ParseContext<'_>) -> Result<(TomlSchema, Option<Value>),String>
+{
+    let mut options = Vec::new();
+    let mut dv = None;
+
+    for k in table.keys() {
+        match k.as_str()
+        {
+            "type" => (),
+
+            "default" =>{ dv = Some(table[k].clone())},
 
             "options" => { match &table[k] {
                 Value::Array(arr) =>
                 {
-                    for opt in arr { match opt {
-                        Value::Table(opt_table) =>
+                    for (i, opt) in arr.iter().enumerate() { match schema_table(opt) {
+                        Some(opt_table) =>
                         {
-                            match TomlSchema::from_table(opt_table) {
-                                Ok((schema, dv)) => {
-                                    if let Some(d) = dv {
-                                        log::warn!("Schema parser got unexpected (ignored) default {:?} in alternative options", d)
-                                    }
-                                    options.push(schema)
-                                },
+                            match ctx.parse_at_no_default(PathSegment::Branch(i), &opt_table) {
+                                Ok(schema) => options.push(schema),
                                 Err(e) => return Err(format!("In alternative option \n {}", e))
                             }
                         },
-                        _ => return Err(format!("Option in alternative must be a table but got {:?}", opt))
+                        None => return Err(format!("Option in alternative must be a table or a type name but got {:?}", opt))
                     }}
                 },
                 _ => return Err(format!("Alternative options must be an array but got {}", &table[k]))
             }}
-            other_key => log::warn!("Schema parser got unexpected (ignored) key '{}' in array config", other_key) 
+            other_key => ctx.unknown_key(other_key)?
         }
     }
 
-    Ok((TomlSchema::Alternative(options), dv))
+    Ok((TomlSchema::Alternative(Arc::new(AlternativeSchema::new(options))), dv))
 }
 
 
@@ -448,7 +1385,7 @@ mod tests {
     fn test_parser_string(table: &toml::Table) {
         if let (schema, Some(def)) = TomlSchema::from_table(table).unwrap() {
             match schema {
-                TomlSchema::String { regex } => {
+                TomlSchema::String { regex, .. } => {
                     match def {
                         Value::String(s) => {assert!(regex.is_match(&s))},
                         _ => panic!("Default value is {:?}", def)
@@ -467,7 +1404,7 @@ mod tests {
         let def = maybe_def.unwrap();
 
         match (schema, def) {
-            (TomlSchema::Integer { min, max }, Value::Integer(i)) => {
+            (TomlSchema::Integer { min, max, .. }, Value::Integer(i)) => {
                 assert!(i >= min &&  i <= max)
             },
             (u,v) => panic!("Incorrect return value {:?} {:?}", u, v)
@@ -478,13 +1415,14 @@ mod tests {
         let def = maybe_def.unwrap();
 
         match (schema, def) {
-            (TomlSchema::Float { min, max, nan_ok }, Value::Float(i)) => {
+            (TomlSchema::Float { min, max, nan_ok, .. }, Value::Float(i)) => {
                 assert!( (nan_ok && i.is_nan()) || (i >= min &&  i <= max))
             },
             (u,v) => panic!("Incorrect return value {:?} {:?}", u, v)
         }
     } 
 
+    #[cfg(feature = "regex")]
     #[test]
     fn parser_string() {
         let table = "
@@ -539,7 +1477,7 @@ mod tests {
         
         let (schema, def) = TomlSchema::from_table(&table).unwrap();
 
-        assert!(matches!(schema, TomlSchema::Bool), "schema is not a boolean, schema = {:?}", schema);
+        assert!(matches!(schema, TomlSchema::Bool { .. }), "schema is not a boolean, schema = {:?}", schema);
         assert!(matches!(def, Some(Value::Boolean(_))), "def is not a boolean, def = {:?}", def);
     }
 
@@ -552,10 +1490,11 @@ mod tests {
 
         let (schema, def) = TomlSchema::from_table(&table).unwrap();
 
-        assert!(matches!(schema, TomlSchema::Date), "schema is not a date, schema = {:?}", schema);
+        assert!(matches!(schema, TomlSchema::Date(_)), "schema is not a date, schema = {:?}", schema);
         assert!(matches!(def, Some(Value::Datetime(_))), "def is not a date, def = {:?}", def);
     }
 
+    #[cfg(feature = "regex")]
     #[test]
     fn parser_table() {
         let table = "
@@ -573,11 +1512,12 @@ mod tests {
 
         assert!(def.is_none());
         match schema {
-            TomlSchema::Table { extras, entries, .. } => {
+            TomlSchema::Table(table_schema) => {
+                let TableSchema { extras, entries, .. } = &*table_schema;
                 assert!(entries.len() == 1);
                 assert!(extras.len() == 1);
 
-                assert!(entries.get("custom_key").is_some(), "entries invalid: {:?}", entries);
+                assert!(entries.contains_key("custom_key"), "entries invalid: {:?}", entries);
             },
             _ => panic!("schema is not a table but {:?}", schema)
         }
@@ -625,4 +1565,354 @@ mod tests {
         } 
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn parser_array_without_child() {
+        let schema: TomlSchema = "type = 'array'\nmin = 1".parse().unwrap();
+        assert!(matches!(&schema, TomlSchema::Array { cond, min: 1, .. } if matches!(**cond, TomlSchema::Anything)));
+
+        schema.check(&Value::Array(vec![Value::Integer(1), Value::String("a".into())])).unwrap();
+        schema.check(&Value::Array(vec![])).unwrap_err();
+
+        "type = 'array'\nchild = 5".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn parser_table_array() {
+        let schema: TomlSchema = "
+            type = 'table_array'
+            min_items = 1
+            unique_by = 'name'
+            name = 'string'
+            port = {type = 'int', min = 1, max = 65535, default = 80}
+            '$type' = {type = 'string', default = 'http'}
+        ".parse().unwrap();
+        let expected: TomlSchema = "
+            type = 'array'
+            min = 1
+            unique_by = 'name'
+            child = {name = 'string', port = {type = 'int', min = 1, max = 65535, default = 80}, '$type' = {type = 'string', default = 'http'}}
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), expected.to_string());
+
+        let doc: toml::Table = "
+            [[listener]]
+            name = 'http'
+            [[listener]]
+            name = 'https'
+            port = 443
+            type = 'tls'
+        ".parse().unwrap();
+        schema.check(&doc["listener"]).unwrap();
+        schema.check(&Value::Array(vec![])).unwrap_err();
+        schema.check(&Value::Array(vec![Value::Integer(1)])).unwrap_err();
+
+        "type = 'table_array'\nmax_items = -1".parse::<TomlSchema>().unwrap_err();
+        "type = 'table_array'\nname = 5".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parser_map() {
+        let schema: TomlSchema = "
+            type = 'map'
+            key_regex = '^[a-z][a-z0-9_-]*$'
+            full_match = true
+            value = {type = ['string', 'int']}
+            max = 2
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), r"map</\A(?:^[a-z][a-z0-9_-]*$)\z/: string | int> [..2]");
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("log = '0.4'\ntoml = 8")).unwrap();
+        match schema.check(&doc("Log = '0.4'")) {
+            Err(SchemaError::KeyMiss { key, .. }) => assert_eq!(key, "Log"),
+            other => panic!("expected a key miss but got {:?}", other)
+        }
+        assert!(matches!(schema.check(&doc("log = true")), Err(SchemaError::AtKey { key, .. }) if key == "log"));
+        assert!(matches!(schema.check(&doc("a = '1'\nb = '2'\nc = '3'")), Err(SchemaError::MapCount { count: 3, .. })));
+        assert!(matches!(schema.check(&Value::Integer(1)), Err(SchemaError::TypeMismatch { .. })));
+
+        let report = schema.validate(&doc("Log = '0.4'\nlog = true\ntoml = '1'"));
+        let paths: Vec<String> = report.errors.iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["", "Log", "log"]);
+
+        let schema: TomlSchema = "type = 'map'".parse().unwrap();
+        assert_eq!(schema.to_string(), "map<anything>");
+        "type = 'map'\nvalue = 5".parse::<TomlSchema>().unwrap_err();
+        "type = 'map'\nkey_regex = '('".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn parser_warnings() {
+        let table = "
+            name = {type = 'string', regx = '^a'}
+            tags = {type = 'array', child = {type = 'string', default = 'x'}}
+            either = {type = 'alternative', options = [{type = 'int', default = 1}, {type = 'bool'}]}
+            extras = [{key = '^x-', schema = {type = 'anything'}, comment = 'extensions'}]
+        ".parse().unwrap();
+
+        let (_, dv, warnings) = TomlSchema::from_table_with_warnings(&table).unwrap();
+        assert!(dv.is_none());
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+
+        let path = |s: &str| s.parse::<SchemaPath>().unwrap();
+        assert!(warnings.contains(&SchemaParseWarning::UnknownKey { path: path("name"), key: "regx".to_string() }));
+        assert!(warnings.contains(&SchemaParseWarning::IgnoredDefault { path: path("tags[]"), value: Value::String("x".into()) }));
+        assert!(warnings.contains(&SchemaParseWarning::IgnoredDefault {
+            path: SchemaPath::from(vec![PathSegment::Key("either".into()), PathSegment::Branch(0)]),
+            value: Value::Integer(1)
+        }));
+        assert!(warnings.contains(&SchemaParseWarning::UnknownKey {
+            path: SchemaPath::from(vec![PathSegment::Extra("^x-".into())]),
+            key: "comment".to_string()
+        }));
+
+        assert_eq!(
+            SchemaParseWarning::UnknownKey { path: path("name"), key: "regx".to_string() }.to_string(),
+            "Schema parser ignored unexpected key 'regx' at name"
+        );
+
+        let clean = "name = {type = 'string', default = 'a'}".parse().unwrap();
+        assert!(TomlSchema::from_table_with_warnings(&clean).unwrap().2.is_empty());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn invalid_defaults() {
+        let table = "
+            port = {type = 'int', min = 1, max = 65535, default = 0}
+            name = {type = 'string', regex = '^[a-z]+$', default = 'Web'}
+            mode = {type = 'exact', value = 'fast', default = 'slow'}
+            ratio = {type = 'float', default = 'half'}
+            url = {type = 'string', regex = '^http', default = '${host}'}
+            server = {type = 'table', default = {}, host = {type = 'string', default = 'a'}}
+            ok = {type = 'int', default = 1}
+        ".parse().unwrap();
+
+        let (_, _, warnings) = TomlSchema::from_table_with_warnings(&table).unwrap();
+        let mut invalid: Vec<String> = warnings.iter().filter_map(|warning| match warning {
+            SchemaParseWarning::InvalidDefault { path, .. } => Some(path.to_string()),
+            _ => None
+        }).collect();
+        invalid.sort();
+        assert_eq!(invalid, ["mode", "name", "port", "ratio"], "{:?}", warnings);
+        assert!(warnings.iter().any(|warning| warning.to_string().starts_with("Schema parser found default 0 at port")));
+
+        let root = "type = 'int'\ndefault = 1".parse().unwrap();
+        let validator = Validator::from_table(&root, &ParseOptions::default(), WarningPolicy::Ignore).unwrap();
+        assert_eq!(validator.warnings(), [SchemaParseWarning::IgnoredDefault { path: SchemaPath::new(), value: Value::Integer(1) }]);
+    }
+
+    #[test]
+    fn parser_strict() {
+        let typo = "name = {type = 'string', regx = '^a'}";
+        TomlSchema::parse_strict(typo).unwrap_err();
+        assert!(typo.parse::<TomlSchema>().is_ok());
+
+        let err = TomlSchema::parse_strict("[server]\nport = {type = 'int', mni = 1}").unwrap_err();
+        assert!(err.contains("Unknown key 'mni' in schema at server.port"), "{}", err);
+        TomlSchema::parse_strict("type = 'table'\nextras = [{key = '.*', schema = {type = 'bool'}, comment = ''}]").unwrap_err();
+
+        // ignored defaults are not errors
+        let schema = TomlSchema::parse_strict("
+            name = {type = 'string', regex = '^a', default = 'a'}
+            tags = {type = 'array', child = {type = 'string', default = 'x'}}
+        ").unwrap();
+        assert!(matches!(schema, TomlSchema::Table(_)));
+    }
+
+    #[test]
+    fn parser_keyword_entries() {
+        for keyword in LATE_KEYWORDS {
+            for entry in ["'string'", "{type = 'int'}", "{name = 'string'}"] {
+                let e = format!("{} = {}", keyword, entry).parse::<TomlSchema>().unwrap_err();
+                assert!(e.contains(&format!("write \"${}\" to declare an entry", keyword)), "{}", e);
+                let schema: TomlSchema = format!("'${}' = {}", keyword, entry).parse().unwrap();
+                let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+                assert!(table.entries.get(keyword).is_some());
+            }
+        }
+
+        // `when` and `require` are entries unless `when` is a condition
+        let schema: TomlSchema = "when = 'string'\nrequire = {type = 'int'}".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert_eq!(table.entries.len(), 2);
+        let e = "when = {key = 'mode', equals = 'file'}\nrequire = 'string'".parse::<TomlSchema>().unwrap_err();
+        assert!(e.contains("write \"$require\" to declare an entry"), "{}", e);
+        "when = {key = 'mode', equals = 'file'}\nrequire = ['path']\n'$require' = 'string'".parse::<TomlSchema>().unwrap();
+    }
+
+    #[test]
+    fn parser_wrapper_entries() {
+        // tables written before these keywords existed keep their entries
+        let schema: TomlSchema = "t = {type = 'table', default_env = 'string'}".parse().unwrap();
+        TomlSchema::parse_strict("t = {type = 'table', default_env = {type = 'string'}}").unwrap();
+        schema.check_str("t = {default_env = 'HOME'}").unwrap();
+        schema.check_str("t = {default_env = 1}").unwrap_err();
+        let schema: TomlSchema = "home = {type = 'string', default_env = 'HOME'}".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert!(matches!(table.entries.get("home"), Some((TomlSchema::Defaulted { .. }, _))), "{:?}", schema);
+        let schema: TomlSchema = "[env]\ndefault_env = 'string'\ndefault_merge = {type = 'bool'}".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert!(matches!(table.entries.get("env"), Some((TomlSchema::Table(_), _))), "{:?}", schema);
+        schema.check_str("env = {default_env = 'HOME', default_merge = true}").unwrap();
+        schema.check_str("env = {default_env = 'HOME', default_merge = 'merge'}").unwrap_err();
+        TomlSchema::parse_strict("[env]\ndefault_env = 'string'\ndefault_merge = {type = 'bool'}").unwrap();
+        let schema: TomlSchema = "list = {type = 'array', child = 'int', default = [1], default_merge = 'merge'}".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert!(matches!(table.entries.get("list"), Some((TomlSchema::Defaulted { merge: true, .. }, _))), "{:?}", schema);
+
+        let schema: TomlSchema = "severity = {type = 'string'}\nlevel = {type = 'int', severity = 'warning'}".parse().unwrap();
+        schema.check_str("severity = 'high'\nlevel = 'a'").unwrap();
+        schema.check_str("severity = 1\nlevel = 1").unwrap_err();
+        TomlSchema::parse_strict("severity = {type = 'string'}").unwrap();
+
+        let schema: TomlSchema = "custom = 'string'".parse().unwrap();
+        schema.check_str("custom = 'a'").unwrap();
+        schema.check_str("custom = true").unwrap_err();
+        TomlSchema::parse_strict("custom = {type = 'bool'}").unwrap();
+
+        let schema: TomlSchema = "extends = 'string'\nbase = {type = 'table', extends = {type = 'int'}}".parse().unwrap();
+        schema.check_str("extends = 'a'\nbase = {extends = 1}").unwrap();
+        schema.check_str("extends = 1\nbase = {}").unwrap_err();
+        TomlSchema::parse_strict("extends = 'string'").unwrap();
+
+        // `x-` keys are extensions unless they hold a schema, `$` makes any of them an entry
+        let schema: TomlSchema = "
+            'x-forwarded-for' = 'string'
+            x-proxy = {host = 'string', port = {type = 'port', default = 80}}
+            x-doc = {summary = 'headers'}
+            '$x-meta' = {}
+        ".parse().unwrap();
+        schema.check_str("x-forwarded-for = 'a'\nx-proxy = {host = 'b'}\nx-meta = {}").unwrap();
+        schema.check_str("x-forwarded-for = 1\nx-proxy = {host = 'b'}\nx-meta = {}").unwrap_err();
+        assert_eq!(schema.extensions().unwrap().keys().collect::<Vec<_>>(), ["x-doc"]);
+    }
+
+    #[test]
+    fn parser_shorthand() {
+        let schema: TomlSchema = "
+            name = 'string'
+            port = {type = 'int', min = 1}
+            tags = {type = 'array', child = 'string'}
+            either = {type = 'alternative', options = ['bool', {type = 'int'}]}
+            extras = [{key = '^x_', schema = 'anything'}]
+        ".parse().unwrap();
+
+        schema.check(&Value::Table("name = 'a'\nport = 1\ntags = ['b']\neither = 2\nx_other = [1]".parse().unwrap())).unwrap();
+        schema.check(&Value::Table("name = 1\nport = 1\ntags = []\neither = true".parse().unwrap())).unwrap_err();
+        schema.check(&Value::Table("name = 'a'\nport = 1\ntags = [1]\neither = true".parse().unwrap())).unwrap_err();
+
+        let err = "port = 'integer'".parse::<TomlSchema>().unwrap_err();
+        assert!(err.contains("integer"), "{}", err);
+        "port = 5".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn parser_type_union() {
+        let schema: TomlSchema = "
+            ratio = {type = ['int', 'float'], default = 1}
+            x = {type = ['string', 'bool']}
+        ".parse().unwrap();
+
+        let TomlSchema::Table(table) = &schema else {panic!("not a table")};
+        assert!(matches!(&table.entries["ratio"], (TomlSchema::Alternative(opts), Some(Value::Integer(1))) if opts.len() == 2));
+
+        schema.check(&Value::Table("ratio = 0.5\nx = true".parse().unwrap())).unwrap();
+        let doc = Value::Table("ratio = 'half'\nx = true".parse().unwrap());
+        let err = format!("{:?}", schema.check(&doc).unwrap_err());
+        assert!(err.contains("Expected int or float but got string"), "{}", err);
+
+        "x = {type = ['int', 1]}".parse::<TomlSchema>().unwrap_err();
+        "x = {type = ['int', 'number']}".parse::<TomlSchema>().unwrap_err();
+        TomlSchema::parse_strict("x = {type = ['int', 'float'], min = 0}").unwrap_err();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parser_regex_flags() {
+        let schema: TomlSchema = "
+            code = {type = 'string', regex = '[a-z]{3}', full_match = true, case_insensitive = true}
+            text = {type = 'string', regex = '^b.c$', multiline = true, dot_matches_newline = true}
+            extras = [{key = 'x-[a-z]+', full_match = true, schema = 'int'}]
+        ".parse().unwrap();
+        let check = |doc: &str| schema.check(&Value::Table(doc.parse().unwrap())).is_ok();
+
+        assert!(check("code = 'AbC'\ntext = \"a\\nb\\nc\""));
+        assert!(!check("code = 'abcd'\ntext = 'b c'"));
+        assert!(!check("code = 'abc'\ntext = \"b\\n\""));
+        assert!(check("code = 'abc'\ntext = 'b-c'\nx-depth = 3"));
+        assert!(!check("code = 'abc'\ntext = 'b-c'\nmy-x-depth = 3"));
+
+        "x = {type = 'string', full_match = 'yes'}".parse::<TomlSchema>().unwrap_err();
+        TomlSchema::parse_strict("x = {type = 'string', full_match = true}").unwrap();
+        TomlSchema::parse_strict("extras = [{key = 'a', schema = 'int', case_insensitive = true}]").unwrap();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parser_regex_limits() {
+        let table: toml::Table = r"name = {type = 'string', regex = '\w{100}\d{100}'}".parse().unwrap();
+        TomlSchema::from_table_with_options(&table, &ParseOptions::default()).unwrap();
+
+        let options = ParseOptions { regex_size_limit: Some(1000), ..ParseOptions::default() };
+        let err = TomlSchema::from_table_with_options(&table, &options).unwrap_err();
+        assert!(err.contains("size limit"), "{}", err);
+
+        let table: toml::Table = r"extras = [{key = '\w{100}', schema = 'int'}]".parse().unwrap();
+        TomlSchema::from_table_with_options(&table, &options).unwrap_err();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parser_regex_interning() {
+        let schema: TomlSchema = r"
+            a = {type = 'string', regex = '^\d+$'}
+            b = {type = 'string', regex = '^\d+$'}
+            c = {type = 'string', regex = '^\d+$', full_match = true}
+            d = {type = 'map', key_regex = '^\d+$', value = {type = 'string', regex = '^\d+$'}}
+        ".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        let entries = &table.entries;
+        let regex = |key: &str| match &entries[key].0 {
+            TomlSchema::String { regex, .. } => regex.clone(),
+            TomlSchema::Map(map) => match &map.value {
+                TomlSchema::String { regex, .. } => regex.clone(),
+                other => panic!("map value is not a string but {:?}", other)
+            },
+            other => panic!("{} is not a string but {:?}", key, other)
+        };
+
+        assert!(regex("a").shares_regex(&regex("b")));
+        assert!(regex("a").shares_regex(&regex("d")));
+        // options are part of the pattern
+        assert!(!regex("a").shares_regex(&regex("c")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn parser_lazy_regex() {
+        let options = ParseOptions { lazy_regex: true, ..Default::default() };
+        let table: toml::Table = r"
+            a = {type = 'string', regex = '^\d+$'}
+            b = {type = 'string', regex = '^\d+$'}
+            bad = {type = 'string', regex = '(', default = 'x'}
+        ".parse().unwrap();
+        let (schema, _, _) = TomlSchema::from_table_with_options(&table, &options).unwrap();
+        let TomlSchema::Table(table_schema) = &schema else {panic!("schema is not a table")};
+        let entries = &table_schema.entries;
+        let regex = |key: &str| match &entries[key].0 {
+            TomlSchema::String { regex, .. } => regex.clone(),
+            other => panic!("{} is not a string but {:?}", key, other)
+        };
+        assert!(!regex("a").is_compiled());
+
+        schema.check_str("a = '12'\nb = '3'").unwrap();
+        assert!(regex("a").is_compiled() && regex("b").is_compiled());
+        schema.check_str("a = '12'\nb = 'x'").unwrap_err();
+
+        // invalid regexes are only found when used, and match nothing
+        schema.check_str("a = '12'\nb = '3'\nbad = 'x'").unwrap_err();
+        TomlSchema::try_from(&table).unwrap_err();
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use crate::*;
+
+
+/// How a document went through a schema, returned by [TomlSchema::coverage]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    /// The keys of the document matched by a table entry, as document paths
+    pub by_entries: Vec<SchemaPath>,
+    /// The keys of the document matched by an extra of a table or by the key regex of a map, with that regex
+    pub by_extras: Vec<(SchemaPath, String)>,
+    /// The sub-schemas that no value of the document went through, as the paths of [TomlSchema::visit]
+    pub unused: Vec<SchemaPath>,
+}
+
+
+/// The state of [TomlSchema::coverage], the locations in the schema and in the document and what was found so far
+struct Tracker {
+    schema_path: SchemaPath,
+    doc_path: SchemaPath,
+    used: HashSet<SchemaPath>,
+    coverage: Coverage,
+}
+
+impl Tracker {
+    /// Records a key of a table matched by `schema` and covers its value, keys reached through [PathSegment::Extra]
+    /// are matched by extras
+    fn key(&mut self, schema: &TomlSchema, key: &str, value: &Value, segment: PathSegment, depth: usize) {
+        self.doc_path.push(PathSegment::Key(key.to_string()));
+        match &segment {
+            PathSegment::Extra(re) => self.coverage.by_extras.push((self.doc_path.clone(), re.clone())),
+            _ => self.coverage.by_entries.push(self.doc_path.clone())
+        }
+        self.schema_path.push(segment);
+        schema.cover(value, depth, self);
+        self.schema_path.pop();
+        self.doc_path.pop();
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Finds which parts of this schema matched the keys of `doc`, this is meant for documents that pass
+    /// [TomlSchema::check], to find dead branches of a schema and keys that slip through broad extras
+    ///
+    /// keys are attributed like in [TomlSchema::check], to their entry or else to the first extra that accepts them,
+    /// values go through the first option of alternatives that they match, values that match no schema are not followed
+    pub fn coverage(&self, doc: &Value) -> Coverage {
+        let mut tracker = Tracker {
+            schema_path: SchemaPath::new(), doc_path: SchemaPath::new(), used: HashSet::new(), coverage: Coverage::default()
+        };
+        self.cover(doc, DEFAULT_MAX_DEPTH, &mut tracker);
+
+        let Tracker { used, mut coverage, .. } = tracker;
+        self.visit(&mut |path, _| {
+            if !used.contains(path) {
+                coverage.unused.push(path.clone());
+            }
+        });
+        coverage
+    }
+
+    fn cover(&self, value: &Value, depth: usize, tracker: &mut Tracker) {
+        let Some(depth) = depth.checked_sub(1) else {return};
+        tracker.used.insert(tracker.schema_path.clone());
+
+        match (self, value) {
+            (TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. }, value) => {
+                schema.cover(value, depth + 1, tracker)
+            },
+            (TomlSchema::Alternative(options), value) => {
+                if let Some(i) = options.iter().position(|opt| opt.check_depth(value, depth).is_ok()) {
+                    tracker.schema_path.push(PathSegment::Branch(i));
+                    options[i].cover(value, depth, tracker);
+                    tracker.schema_path.pop();
+                }
+            },
+            (TomlSchema::Array { cond, .. }, Value::Array(arr)) => {
+                tracker.schema_path.push(PathSegment::AnyIndex);
+                for (i, element) in arr.iter().enumerate() {
+                    tracker.doc_path.push(PathSegment::Index(i));
+                    cond.cover(element, depth, tracker);
+                    tracker.doc_path.pop();
+                }
+                tracker.schema_path.pop();
+            },
+            (TomlSchema::Map(map_schema), Value::Table(table)) => {
+                let MapSchema { key: re, key_type, value: child, .. } = &**map_schema;
+                for (key, value) in table.iter().filter(|(key, _)| re.is_match(key) && key_type.accepts(key)) {
+                    tracker.key(child, key, value, PathSegment::Extra(re.as_str().to_string()), depth);
+                }
+            },
+            (TomlSchema::Table(table_schema), Value::Table(table)) => {
+                let TableSchema { entries, extras, policy, .. } = &**table_schema;
+                for (key, value) in table {
+                    if let Some((schema, _)) = entries.get(key) {
+                        tracker.key(schema, key, value, PathSegment::Key(key.clone()), depth);
+                        continue
+                    }
+                    let extra = policy.candidates(extras, key)
+                        .find(|e| e.key_type.accepts(key) && e.value.check_depth(value, depth).is_ok());
+                    if let Some(TableEntry { key: re, value: schema, .. }) = extra {
+                        tracker.key(schema, key, value, PathSegment::Extra(re.as_str().to_string()), depth);
+                    }
+                }
+            },
+            _ => ()
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage() {
+        let schema: TomlSchema = "
+            name = 'string'
+            legacy = {type = 'bool', default = false}
+            value = {type = ['int', 'string'], default = 0}
+            env = {type = 'map', value = 'string', default = {}}
+            extras = [{key = '^x-', schema = 'int'}, {key = '.*', schema = 'anything'}]
+        ".parse().unwrap();
+        let doc = Value::Table("name = 'a'\nvalue = 'b'\nenv = {HOME = '/'}\nx-a = 1\nother = true".parse().unwrap());
+        schema.check(&doc).unwrap();
+
+        let coverage = schema.coverage(&doc);
+        let paths = |paths: &[&str]| paths.iter().map(|p| p.parse().unwrap()).collect::<Vec<SchemaPath>>();
+
+        assert_eq!(coverage.by_entries, paths(&["env", "name", "value"]));
+        assert_eq!(coverage.by_extras, vec![
+            ("env.HOME".parse().unwrap(), ".*".to_string()),
+            ("other".parse().unwrap(), ".*".to_string()),
+            ("x-a".parse().unwrap(), "^x-".to_string()),
+        ]);
+
+        use PathSegment::*;
+        assert_eq!(coverage.unused, vec![
+            SchemaPath::from(vec![Key("legacy".into())]),
+            vec![Key("value".into()), Branch(0)].into(),
+        ]);
+    }
+}
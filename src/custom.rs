@@ -0,0 +1,295 @@
+use std::any::Any;
+use std::fmt;
+use crate::*;
+
+
+/// The signature of custom validators, the error message is reported in [SchemaError::CustomMiss]
+pub type CustomFn = dyn Fn(&Value) -> Result<(), String> + Send + Sync;
+
+/// The signature of custom validators that need to see more than their value, see [ValidationContext]
+pub type ContextFn = dyn Fn(&Value, &ValidationContext) -> Result<(), String> + Send + Sync;
+
+/// The state given to the validators registered with [CustomValidators::register_with_context], like a handle to
+/// look up installed plugins, see [Validator::state]
+pub type ValidationState = Arc<dyn Any + Send + Sync>;
+
+/// A named native validator, referenced from schemas with `custom = "name"`
+#[derive(Clone)]
+pub struct CustomValidator {
+    name: Arc<str>,
+    func: CustomCheck,
+}
+
+#[derive(Clone)]
+enum CustomCheck {
+    Value(Arc<CustomFn>),
+    Context(Arc<ContextFn>),
+}
+
+/// What a validator registered with [CustomValidators::register_with_context] knows about the value it checks
+///
+/// these validators run once the whole document matched the schema, like the checks of `refers_to`, so the rest of
+/// the document can be used to cross-check the value
+pub struct ValidationContext<'a> {
+    path: &'a SchemaPath,
+    root: &'a Value,
+    state: Option<&'a (dyn Any + Send + Sync)>,
+}
+
+impl<'a> ValidationContext<'a> {
+    /// The location of the value in the document
+    pub fn path(&self) -> &'a SchemaPath {
+        self.path
+    }
+
+    /// The whole document
+    pub fn root(&self) -> &'a Value {
+        self.root
+    }
+
+    /// The state given to the validation, `None` when there is none or it is not a `T`
+    pub fn state<T: Any>(&self) -> Option<&'a T> {
+        self.state?.downcast_ref()
+    }
+}
+
+impl CustomValidator {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this validator needs a [ValidationContext], it then runs after the rest of the validation
+    pub fn needs_context(&self) -> bool {
+        matches!(self.func, CustomCheck::Context(_))
+    }
+
+    /// Checks a value that matched the schema of the validator, validators that need a context pass here and are run
+    /// by [CustomValidator::check_in_context]
+    pub(crate) fn check<'s,'v>(&'s self, val: &'v Value) -> Result<(), SchemaError<'s,'v>> {
+        match &self.func {
+            CustomCheck::Value(func) => func(val).map_err(|message| SchemaError::CustomMiss { val, name: &self.name, message }),
+            CustomCheck::Context(_) => Ok(())
+        }
+    }
+
+    pub(crate) fn check_in_context<'s,'v>(&'s self, val: &'v Value, context: &ValidationContext) -> Result<(), SchemaError<'s,'v>> {
+        match &self.func {
+            CustomCheck::Value(_) => Ok(()),
+            CustomCheck::Context(func) => func(val, context).map_err(|message| SchemaError::CustomMiss { val, name: &self.name, message })
+        }
+    }
+}
+
+impl fmt::Debug for CustomValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CustomValidator({:?})", self.name)
+    }
+}
+
+
+/// The custom validators that schemas may reference, given to the parser in [ParseOptions::validators]
+///
+/// validators run after the schema they are attached to matched, so they only see values of the expected type
+#[derive(Clone, Default)]
+pub struct CustomValidators(HashMap<String, CustomValidator>);
+
+impl CustomValidators {
+    pub fn new() -> Self {
+        CustomValidators::default()
+    }
+
+    /// Adds a validator, a validator registered earlier with the same name is replaced
+    pub fn register(&mut self, name: &str, func: impl Fn(&Value) -> Result<(), String> + Send + Sync + 'static) -> &mut Self {
+        self.0.insert(name.to_string(), CustomValidator { name: name.into(), func: CustomCheck::Value(Arc::new(func)) });
+        self
+    }
+
+    /// Adds a validator that also gets the location of the value, the whole document and the state of the validation,
+    /// it runs once the whole document matched the schema
+    pub fn register_with_context(&mut self, name: &str, func: impl Fn(&Value, &ValidationContext) -> Result<(), String> + Send + Sync + 'static) -> &mut Self {
+        self.0.insert(name.to_string(), CustomValidator { name: name.into(), func: CustomCheck::Context(Arc::new(func)) });
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomValidator> {
+        self.0.get(name)
+    }
+}
+
+impl fmt::Debug for CustomValidators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        f.debug_tuple("CustomValidators").field(&names).finish()
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Whether a custom validator of this schema needs a context, the phase that runs them is skipped otherwise
+    pub(crate) fn has_context_validators(&self) -> bool {
+        self.any_node(&|schema| matches!(schema, TomlSchema::Custom { validator, .. } if validator.needs_context()))
+    }
+
+    /// The errors of the validators that need a context, for a document that already matches this schema
+    fn context_errors<'s,'v>(&'s self, doc: &'v Value, state: Option<&ValidationState>, first_only: bool) -> Vec<(SchemaPath, SchemaError<'s,'v>)> {
+        if !self.has_context_validators() {
+            return Vec::new()
+        }
+        let mut found = Vec::new();
+        // when collecting all errors the document may not match, validators only see values of the expected type
+        let pick = |schema: &'s TomlSchema, value: &'v Value, path: &SchemaPath| match schema {
+            TomlSchema::Custom { schema, validator } if validator.needs_context() && schema.check_depth(value, DEFAULT_MAX_DEPTH).is_ok() => {
+                Some((path.clone(), validator, value))
+            },
+            _ => None
+        };
+        self.collect_matching(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &pick, &mut found);
+
+        let mut errors = Vec::new();
+        for (path, validator, value) in found {
+            let context = ValidationContext { path: &path, root: doc, state: state.map(|state| &**state) };
+            if let Err(e) = validator.check_in_context(value, &context) {
+                errors.push((path, e));
+                if first_only {break}
+            }
+        }
+        errors
+    }
+
+    /// Runs the validators that need a context, the error is nested like the errors of [TomlSchema::check]
+    pub(crate) fn check_context_validators<'s,'v>(&'s self, doc: &'v Value, state: Option<&ValidationState>) -> Result<(), SchemaError<'s,'v>> {
+        match self.context_errors(doc, state, true).into_iter().next() {
+            Some((path, error)) => Err(reference::nest_error(doc, &path, error)),
+            None => Ok(())
+        }
+    }
+
+    /// Reports the errors of every validator that needs a context at its location
+    pub(crate) fn report_context_validators(&self, doc: &Value, state: Option<&ValidationState>, report: &mut ValidationReport) {
+        for (path, error) in self.context_errors(doc, state, false) {
+            report.push(&path, error);
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> ParseOptions {
+        let mut validators = CustomValidators::new();
+        validators
+            .register("even", |v| match v.as_integer() {
+                Some(i) if i % 2 == 0 => Ok(()),
+                _ => Err("not even".to_string())
+            })
+            .register("lowercase", |v| match v.as_str() {
+                Some(s) if s.chars().all(|c| !c.is_uppercase()) => Ok(()),
+                _ => Err("must be lowercase".to_string())
+            })
+            .register("small", |v| match v.as_table() {
+                Some(t) if t.len() <= 2 => Ok(()),
+                _ => Err("too many keys".to_string())
+            });
+        ParseOptions { validators, ..ParseOptions::default() }
+    }
+
+    fn schema(text: &str) -> Result<TomlSchema, String> {
+        TomlSchema::from_table_with_options(&text.parse().unwrap(), &options()).map(|(schema, _, _)| schema)
+    }
+
+    #[test]
+    fn custom_validators() {
+        let schema = schema("
+            workers = {type = 'int', min = 0, custom = 'even', default = 2}
+            tags = {type = 'array', child = {type = 'string', custom = 'lowercase'}}
+        ").unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("workers = 4\ntags = ['a', 'b']")).unwrap();
+
+        let bad = doc("workers = 3\ntags = []");
+        match schema.check(&bad) {
+            Err(SchemaError::AtKey { error, .. }) => {
+                assert!(matches!(&*error, SchemaError::CustomMiss { name: "even", message, .. } if message == "not even"), "{:?}", error)
+            },
+            other => panic!("expected a custom error but got {:?}", other)
+        }
+
+        // the schema is checked before the validator
+        let err = format!("{:?}", schema.check(&doc("workers = -2\ntags = []")).unwrap_err());
+        assert!(err.contains("Int"), "{}", err);
+        schema.check(&doc("workers = 2\ntags = ['A']")).unwrap_err();
+
+        // defaults are filled in before custom validators run
+        let mut completed = doc("tags = ['x']");
+        schema.check_and_complete(&mut completed).unwrap();
+        assert_eq!(completed["workers"].as_integer(), Some(2));
+
+        let err = self::schema("port = {type = 'int', custom = 'is_open'}").unwrap_err();
+        assert!(err.contains("is_open"), "{}", err);
+        "port = {type = 'int', custom = 'even'}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn custom_validator_at_root() {
+        let schema = schema("custom = 'small'\nname = 'string'\nport = {type = 'int', default = 1}").unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        // the root table still accepts the header
+        let ok = doc("'$schema' = 'app'\nname = 'a'");
+        schema.check(&ok).unwrap();
+        assert!(schema.validate(&ok).errors.is_empty(), "{:?}", schema.validate(&ok));
+
+        let bad = doc("'$schema' = 'app'\nname = 'a'\nport = 2");
+        schema.check(&bad).unwrap_err();
+        let report = schema.validate(&bad);
+        assert_eq!(report.errors.len(), 1, "{:?}", report);
+        assert!(report.errors[0].message.contains("too many keys"), "{:?}", report);
+    }
+
+    #[test]
+    fn context_validators() {
+        struct Installed(Vec<&'static str>);
+
+        let mut validators = CustomValidators::new();
+        validators
+            .register_with_context("installed", |v, ctx| {
+                let installed = ctx.state::<Installed>().ok_or("no plugin list")?;
+                match installed.0.contains(&v.as_str().unwrap_or_default()) {
+                    true => Ok(()),
+                    false => Err(format!("plugin at {} is not installed", ctx.path()))
+                }
+            })
+            .register_with_context("known_user", |v, ctx| {
+                let users = ctx.root()["users"].as_array().ok_or("no users")?;
+                if users.contains(v) {Ok(())} else {Err("unknown user".to_string())}
+            });
+        let options = ParseOptions { validators, ..ParseOptions::default() };
+        let (schema, _, _) = TomlSchema::from_table_with_options(&"
+            users = {type = 'array', child = 'string'}
+            owner = {type = 'string', custom = 'known_user'}
+            plugins = {type = 'array', child = {type = 'string', custom = 'installed'}, default = []}
+        ".parse().unwrap(), &options).unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        let validator = Validator::new(schema.clone()).state(Arc::new(Installed(vec!["git", "lsp"])));
+        validator.check(&doc("users = ['ann']\nowner = 'ann'\nplugins = ['git', 'lsp']")).unwrap();
+        let report = validator.clone().fail_fast(false).validate(&doc("users = ['ann']\nowner = 'bob'\nplugins = ['git', 'fmt', 1]"));
+        let messages: Vec<&str> = report.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("plugin at plugins[1] is not installed")), "{:?}", messages);
+
+        // the root document is available without a state
+        schema.check(&doc("users = ['ann']\nowner = 'ann'")).unwrap();
+        let err = format!("{:?}", schema.check(&doc("users = ['ann']\nowner = 'bob'")).unwrap_err());
+        assert!(err.contains("unknown user"), "{}", err);
+        let err = format!("{:?}", schema.check(&doc("users = []\nowner = 'x'\nplugins = ['git']")).unwrap_err());
+        assert!(err.contains("unknown user"), "{}", err);
+    }
+}
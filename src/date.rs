@@ -0,0 +1,178 @@
+#[cfg(feature = "chrono")]
+use std::cmp::Ordering;
+use toml::value::Datetime;
+use crate::*;
+
+
+/// The content of [TomlSchema::Date], it is reference counted so that the schema enum stays small
+///
+/// with the `chrono` feature dates can have bounds, see `compare_dates` for how datetimes of different kinds compare
+#[derive(Debug, Clone, Default)]
+pub struct DateSchema {
+    /// The first date allowed, from `min`
+    #[cfg(feature = "chrono")]
+    pub min: Option<Datetime>,
+    /// The last date allowed, from `max`
+    #[cfg(feature = "chrono")]
+    pub max: Option<Datetime>,
+    /// Whether dates must be offset datetimes like `1979-05-27T07:32:00Z`, from `require_offset`
+    pub require_offset: bool,
+    /// Whether dates must not have an offset, from `local_only`
+    pub local_only: bool,
+}
+
+impl DateSchema {
+    pub(crate) fn check<'s,'v>(&'s self, val: &'v Datetime) -> Result<(), SchemaError<'s,'v>> {
+        if (self.require_offset && val.offset.is_none()) || (self.local_only && val.offset.is_some()) {
+            return Err(SchemaError::DateOffsetMiss { val, required: self.require_offset })
+        }
+        self.check_bounds(val)
+    }
+
+    #[cfg(feature = "chrono")]
+    fn check_bounds<'s,'v>(&'s self, val: &'v Datetime) -> Result<(), SchemaError<'s,'v>> {
+        let miss = || SchemaError::DateMiss { val, min: self.min.as_ref(), max: self.max.as_ref() };
+        if let Some(min) = &self.min {
+            match compare_dates(val, min) {
+                Some(Ordering::Less) => return Err(miss()),
+                None => return Err(SchemaError::DateIncomparable { val, bound: min }),
+                _ => ()
+            }
+        }
+        if let Some(max) = &self.max {
+            match compare_dates(val, max) {
+                Some(Ordering::Greater) => return Err(miss()),
+                None => return Err(SchemaError::DateIncomparable { val, bound: max }),
+                _ => ()
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    fn check_bounds<'s,'v>(&'s self, _val: &'v Datetime) -> Result<(), SchemaError<'s,'v>> {
+        Ok(())
+    }
+}
+
+
+/// Compares two TOML datetimes, `None` when they are not comparable
+/// - two offset datetimes are compared as instants, `10:00:00+02:00` is before `09:00:00Z` on the same day
+/// - when either one is local, their dates and times are compared as written and the offset is ignored
+/// - a date without a time is midnight at the start of that day
+/// - a time without a date can only be compared to another time without a date
+#[cfg(feature = "chrono")]
+pub fn compare_dates(a: &Datetime, b: &Datetime) -> Option<Ordering> {
+    use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+    use toml::value::Offset;
+
+    let time = |dt: &Datetime| match &dt.time {
+        Some(t) => NaiveTime::from_hms_nano_opt(t.hour.into(), t.minute.into(), t.second.into(), t.nanosecond),
+        None => Some(NaiveTime::MIN)
+    };
+    let naive = |dt: &Datetime| -> Option<NaiveDateTime> {
+        let date = dt.date?;
+        Some(NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?.and_time(time(dt)?))
+    };
+    let offset = |dt: &Datetime| match dt.offset? {
+        Offset::Z => FixedOffset::east_opt(0),
+        Offset::Custom { minutes } => FixedOffset::east_opt(i32::from(minutes) * 60)
+    };
+
+    match (a.date, b.date) {
+        (Some(_), Some(_)) => match (offset(a), offset(b)) {
+            (Some(offset_a), Some(offset_b)) => {
+                let a = offset_a.from_local_datetime(&naive(a)?).single()?;
+                let b = offset_b.from_local_datetime(&naive(b)?).single()?;
+                Some(a.cmp(&b))
+            },
+            _ => Some(naive(a)?.cmp(&naive(b)?))
+        },
+        (None, None) => Some(time(a)?.cmp(&time(b)?)),
+        _ => None
+    }
+}
+
+/// The same datetime one day earlier (`days = -1`) or later, `None` without a date
+#[cfg(all(feature = "chrono", feature = "generate"))]
+pub(crate) fn shift_days(dt: &Datetime, days: i64) -> Option<Datetime> {
+    use chrono::{Datelike, NaiveDate, TimeDelta};
+
+    let date = dt.date?;
+    let shifted = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?
+        .checked_add_signed(TimeDelta::try_days(days)?)?;
+    let year = u16::try_from(shifted.year()).ok().filter(|year| *year <= 9999)?;
+    let date = toml::value::Date { year, month: shifted.month() as u8, day: shifted.day() as u8 };
+    Some(Datetime { date: Some(date), ..*dt })
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn date_bounds() {
+        let schema: TomlSchema = "
+            when = {type = 'date', min = 2024-01-01, max = 2024-12-31T23:59:59Z}
+            at = {type = 'date', min = 08:00:00, max = 18:00:00}
+        ".parse().unwrap();
+
+        schema.check_str("when = 2024-06-01\nat = 12:30:00").unwrap();
+        schema.check_str("when = 2024-01-01T00:00:00+01:00\nat = 08:00:00").unwrap();
+        schema.check_str("when = 2024-12-31T22:00:00-05:00\nat = 12:30:00").unwrap_err();
+        match schema.check_str("when = 2023-12-31T23:00:00\nat = 12:30:00") {
+            Err(e) => assert!(e.contains("is not in"), "error is {}", e),
+            Ok(()) => panic!("date before min accepted")
+        }
+        match schema.check_str("when = 2024-06-01\nat = 2024-06-01T12:30:00") {
+            Err(e) => assert!(e.contains("cannot be compared"), "error is {}", e),
+            Ok(()) => panic!("datetime compared to a time")
+        }
+
+        "x = {type = 'date', min = 2024-02-01, max = 2024-01-01}".parse::<TomlSchema>().unwrap_err();
+        "x = {type = 'date', min = '2024-02-01'}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn date_ordering() {
+        let date = |s: &str| s.parse::<Datetime>().unwrap();
+        // offsets are compared as instants
+        assert_eq!(compare_dates(&date("2024-01-01T10:00:00+02:00"), &date("2024-01-01T09:00:00Z")), Some(Ordering::Less));
+        // local datetimes ignore the offset of the other one
+        assert_eq!(compare_dates(&date("2024-01-01T10:00:00"), &date("2024-01-01T09:00:00Z")), Some(Ordering::Greater));
+        assert_eq!(compare_dates(&date("2024-01-01"), &date("2024-01-01T00:00:00")), Some(Ordering::Equal));
+        assert_eq!(compare_dates(&date("10:00:00"), &date("2024-01-01")), None);
+    }
+
+    #[test]
+    fn date_offsets() {
+        let schema: TomlSchema = "
+            stamp = {type = 'date', require_offset = true}
+            day = {type = 'date', local_only = true}
+        ".parse().unwrap();
+
+        schema.check_str("stamp = 1979-05-27T07:32:00Z\nday = 1979-05-27").unwrap();
+        schema.check_str("stamp = 1979-05-27T07:32:00-08:00\nday = 07:32:00").unwrap();
+        match schema.check_str("stamp = 1979-05-27T07:32:00\nday = 1979-05-27") {
+            Err(e) => assert!(e.contains("must have an offset"), "error is {}", e),
+            Ok(()) => panic!("local datetime accepted")
+        }
+        match schema.check_str("stamp = 1979-05-27T07:32:00Z\nday = 1979-05-27T07:32:00Z") {
+            Err(e) => assert!(e.contains("must not have an offset"), "error is {}", e),
+            Ok(()) => panic!("offset datetime accepted")
+        }
+        "x = {type = 'date', require_offset = true, local_only = true}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    fn date_bounds_need_chrono() {
+        let err = "x = {type = 'date', min = 2024-01-01}".parse::<TomlSchema>().unwrap_err();
+        assert!(err.contains("chrono"), "{}", err);
+    }
+}
@@ -0,0 +1,172 @@
+//! How [TomlSchema::check_and_complete] applies defaults, `default_env = "PORT"` fills a missing key from `$PORT` (the
+//! `default` key is used when the variable is not set) and `default_merge = "merge"` adds the missing keys of a table
+//! default to a table of the document
+
+use crate::*;
+
+
+impl TomlSchema {
+    /// The name of the environment variable this schema takes its default from
+    pub fn default_env(&self) -> Option<&str> {
+        match self {
+            TomlSchema::Defaulted { env, .. } => env.as_deref(),
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Graded { schema, .. } => schema.default_env(),
+            _ => None
+        }
+    }
+
+    /// Whether a table default is merged into a table of the document instead of only being used when the key is missing
+    pub fn merges_default(&self) -> bool {
+        match self {
+            TomlSchema::Defaulted { merge, .. } => *merge,
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Graded { schema, .. } => schema.merges_default(),
+            _ => false
+        }
+    }
+
+    /// The value of the environment variable of this schema, if it has one and it is set
+    pub(crate) fn env_value(&self) -> Option<Value> {
+        let raw = std::env::var(self.default_env()?).ok()?;
+        Some(parse_env(self.structure(), raw))
+    }
+}
+
+/// Adds the keys of `default` missing from `table`, tables found in both are merged the same way and other values of the
+/// document are kept as they are, the paths of the added values are pushed to `filled`
+pub(crate) fn merge_default(table: &mut toml::Table, default: &toml::Table, path: &mut SchemaPath, filled: &mut Vec<SchemaPath>) {
+    for (key, value) in default {
+        path.push(PathSegment::Key(key.clone()));
+        match (table.get_mut(key), value) {
+            (None, value) => {
+                table.insert(key.clone(), value.clone());
+                filled.push(path.clone());
+            },
+            (Some(Value::Table(table)), Value::Table(default)) => merge_default(table, default, path, filled),
+            _ => ()
+        }
+        path.pop();
+    }
+}
+
+/// Variables are strings for string schemas, otherwise they are read as TOML values (`8080`, `true`, `[1, 2]`) and
+/// stay strings when they are not valid TOML, checking the completed document then reports the wrong type
+fn parse_env(schema: &TomlSchema, raw: String) -> Value {
+    if matches!(schema, TomlSchema::String { .. }) {
+        return Value::String(raw)
+    }
+    match format!("v = {}", raw).parse::<toml::Table>() {
+        Ok(mut table) if table.len() == 1 => table.remove("v").expect("the only key"),
+        _ => Value::String(raw)
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_defaults() {
+        let schema: TomlSchema = "
+            servers = {type = 'array', child = {host = 'string', port = {type = 'int', default = 80}}}
+            plugins = {type = 'table', extras = [{key = '.*', schema = {enabled = {type = 'bool', default = true}}}]}
+            log = {type = 'alternative', options = ['string', {level = {type = 'string', default = 'info'}, file = 'string'}]}
+        ".parse().unwrap();
+        let mut doc = Value::Table("
+            servers = [{host = 'a'}, {host = 'b', port = 8080}]
+            plugins = {auth = {}, cache = {enabled = false}}
+            log = {file = 'out.log'}
+        ".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["servers"][0]["port"].as_integer(), Some(80));
+        assert_eq!(doc["servers"][1]["port"].as_integer(), Some(8080));
+        assert_eq!(doc["plugins"]["auth"]["enabled"].as_bool(), Some(true));
+        assert_eq!(doc["plugins"]["cache"]["enabled"].as_bool(), Some(false));
+        assert_eq!(doc["log"]["level"].as_str(), Some("info"));
+
+        let mut doc = Value::Table("servers = []
+plugins = {}
+log = 'stderr'".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["log"].as_str(), Some("stderr"));
+    }
+
+    #[test]
+    fn env_defaults() {
+        std::env::set_var("TOML_SCHEMA_TEST_PORT", "9090");
+        std::env::set_var("TOML_SCHEMA_TEST_HOST", "0.0.0.0");
+        std::env::set_var("TOML_SCHEMA_TEST_DEBUG", "yes");
+        std::env::remove_var("TOML_SCHEMA_TEST_UNSET");
+
+        let schema: TomlSchema = "
+            port = {type = 'int', default_env = 'TOML_SCHEMA_TEST_PORT', default = 8080}
+            host = {type = 'string', default_env = 'TOML_SCHEMA_TEST_HOST'}
+            name = {type = 'string', default_env = 'TOML_SCHEMA_TEST_UNSET', default = 'app'}
+            tags = {type = 'array', default_env = 'TOML_SCHEMA_TEST_UNSET', default = []}
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), r#"table { host: string env TOML_SCHEMA_TEST_HOST, name: string env TOML_SCHEMA_TEST_UNSET = "app", port: int env TOML_SCHEMA_TEST_PORT = 8080, tags: array<anything> env TOML_SCHEMA_TEST_UNSET = [] }"#);
+
+        let mut doc = Value::Table(toml::Table::new());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(9090));
+        assert_eq!(doc["host"].as_str(), Some("0.0.0.0"));
+        assert_eq!(doc["name"].as_str(), Some("app"));
+
+        // values in the document win over the environment
+        let mut doc = Value::Table("port = 1\nhost = 'localhost'".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(1));
+
+        // `check` does not read the environment, keys without a default are still required
+        assert!(matches!(schema.check(&Value::Table(toml::Table::new())), Err(SchemaError::MissingKey { key: "host", .. })));
+
+        let bad: TomlSchema = "debug = {type = 'bool', default_env = 'TOML_SCHEMA_TEST_DEBUG'}".parse().unwrap();
+        match bad.check_and_complete(&mut Value::Table(toml::Table::new())) {
+            Err(SchemaError::AtKey { error, .. }) => assert!(matches!(*error, SchemaError::TypeMismatch { .. }), "{:?}", error),
+            other => panic!("expected a type mismatch but got {:?}", other)
+        }
+        let unset: TomlSchema = "debug = {type = 'bool', default_env = 'TOML_SCHEMA_TEST_UNSET'}".parse().unwrap();
+        assert!(matches!(unset.check_and_complete(&mut Value::Table(toml::Table::new())), Err(SchemaError::MissingKey { .. })));
+
+        "port = {type = 'int', default_env = 1}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn merged_defaults() {
+        let schema: TomlSchema = "
+            [server]
+            type = 'table'
+            host = 'string'
+            port = 'int'
+            tls = {enabled = 'bool', cert = {type = 'string', default = ''}}
+            default = {host = 'localhost', port = 8080, tls = {enabled = false}, url = 'http://${server.host}:${server.port}'}
+            default_merge = 'merge'
+            extras = [{key = 'url', schema = 'string'}]
+
+            [limits]
+            type = 'table'
+            cpu = 'int'
+            memory = 'int'
+            default = {cpu = 1, memory = 512}
+        ".parse().unwrap();
+        assert!(schema.to_string().contains(" merged = {"), "{}", schema);
+
+        let mut doc = Value::Table("server = {host = 'example.com', tls = {cert = 'a.pem'}}".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["server"]["host"].as_str(), Some("example.com"));
+        assert_eq!(doc["server"]["port"].as_integer(), Some(8080));
+        assert_eq!(doc["server"]["tls"]["enabled"].as_bool(), Some(false));
+        assert_eq!(doc["server"]["tls"]["cert"].as_str(), Some("a.pem"));
+        // merged strings are interpolated like other defaults
+        assert_eq!(doc["server"]["url"].as_str(), Some("http://example.com:8080"));
+
+        // `replace` is the default, a partial table is not completed
+        let mut doc = Value::Table("limits = {cpu = 2}".parse().unwrap());
+        assert!(matches!(schema.check_and_complete(&mut doc), Err(SchemaError::AtKey { .. })));
+        assert!(doc["limits"].get("memory").is_none());
+
+        "x = {type = 'table', default = {}, default_merge = 'deep'}".parse::<TomlSchema>().unwrap_err();
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+use crate::*;
+
+
+/// Named schemas that other schemas can build on with `extends = "name"`, given to the parser in
+/// [ParseOptions::definitions]
+///
+/// a schema that extends a definition has the keys of the definition and its own keys, its own keys win and the keys
+/// listed in `remove = [..]` are taken out of the definition, definitions may extend other definitions
+#[derive(Clone, Default)]
+pub struct SchemaDefinitions(HashMap<String, toml::Table>);
+
+impl SchemaDefinitions {
+    pub fn new() -> Self {
+        SchemaDefinitions::default()
+    }
+
+    /// Adds a definition, a definition added earlier with the same name is replaced, it is parsed with each schema that
+    /// extends it
+    pub fn define(&mut self, name: &str, schema: toml::Table) -> &mut Self {
+        self.0.insert(name.to_string(), schema);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&toml::Table> {
+        self.0.get(name)
+    }
+
+    /// The table of a schema with an `extends` key, with the keys of the definitions it extends, `is_keyword` tells
+    /// an `extends` keyword from an entry named `extends`
+    pub(crate) fn resolve(&self, table: &toml::Table, is_keyword: &dyn Fn(&Value) -> bool) -> Result<toml::Table, String> {
+        self.resolve_chain(table, is_keyword, &mut Vec::new())
+    }
+
+    /// Same as [SchemaDefinitions::resolve], `chain` holds the definitions being resolved to detect cycles
+    fn resolve_chain(&self, table: &toml::Table, is_keyword: &dyn Fn(&Value) -> bool, chain: &mut Vec<String>) -> Result<toml::Table, String> {
+        let Some(extends) = table.get("extends").filter(|extends| is_keyword(extends)) else {
+            return Ok(table.clone())
+        };
+        let Value::String(name) = extends else {
+            return Err(format!("extends must be the name of a definition but got {:?}", extends))
+        };
+        if chain.contains(name) {
+            return Err(format!("Cycle in extends: {} -> {}", chain.join(" -> "), name))
+        }
+        let Some(base) = self.0.get(name) else {
+            return Err(format!("Unknown definition '{}' in extends", name))
+        };
+
+        chain.push(name.clone());
+        let mut merged = self.resolve_chain(base, is_keyword, chain)?;
+        chain.pop();
+
+        let removed = match table.get("remove") {
+            None => Vec::new(),
+            Some(Value::Array(keys)) => keys.iter().map(|key| match key {
+                Value::String(key) => Ok(key.as_str()),
+                other => Err(format!("remove must be an array of keys but got {:?}", other))
+            }).collect::<Result<Vec<_>, _>>()?,
+            Some(other) => return Err(format!("remove must be an array of keys but got {:?}", other))
+        };
+        for key in removed {
+            if merged.remove(key).is_none() {
+                return Err(format!("Cannot remove '{}', definition '{}' has no such key", key, name))
+            }
+        }
+
+        for (key, value) in table.iter().filter(|(key, _)| !["extends", "remove"].contains(&key.as_str())) {
+            merged.insert(key.clone(), value.clone());
+        }
+        Ok(merged)
+    }
+}
+
+impl fmt::Debug for SchemaDefinitions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        f.debug_tuple("SchemaDefinitions").field(&names).finish()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> ParseOptions {
+        let mut definitions = SchemaDefinitions::new();
+        definitions
+            .define("service", "
+                name = 'string'
+                port = {type = 'int', min = 1, default = 80}
+                debug = {type = 'bool', default = false}
+            ".parse().unwrap())
+            .define("web_service", "
+                extends = 'service'
+                port = {type = 'int', default = 443}
+                root = 'string'
+            ".parse().unwrap());
+        ParseOptions { definitions, ..ParseOptions::default() }
+    }
+
+    fn parse(schema: &str, options: &ParseOptions) -> Result<TomlSchema, String> {
+        TomlSchema::from_table_with_options(&schema.parse().unwrap(), options).map(|(schema, _, _)| schema)
+    }
+
+    #[test]
+    fn extends() {
+        let options = options();
+        let schema = parse("
+            [api]
+            extends = 'service'
+            remove = ['debug']
+            version = 'int'
+            [site]
+            extends = 'web_service'
+        ", &options).unwrap();
+
+        schema.check_str("api = {name = 'a', version = 1}\nsite = {name = 's', root = '/'}").unwrap();
+        // removed keys are unknown
+        schema.check_str("api = {name = 'a', version = 1, debug = true}\nsite = {name = 's', root = '/'}").unwrap_err();
+
+        // the child wins
+        let site = parse("extends = 'web_service'\nroot = {type = 'string', default = '/srv'}", &options).unwrap();
+        let mut doc = Value::Table("name = 's'".parse().unwrap());
+        site.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(443));
+        assert_eq!(doc["root"].as_str(), Some("/srv"));
+        assert_eq!(doc["debug"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn extends_errors() {
+        let mut options = options();
+        let err = parse("x = {extends = 'missing'}", &options).unwrap_err();
+        assert!(err.contains("Unknown definition 'missing'"), "{}", err);
+        let err = parse("x = {extends = 'service', remove = ['other']}", &options).unwrap_err();
+        assert!(err.contains("Cannot remove 'other'"), "{}", err);
+        parse("x = {extends = 1}", &options).unwrap_err();
+
+        options.definitions
+            .define("a", "extends = 'b'".parse().unwrap())
+            .define("b", "extends = 'a'".parse().unwrap());
+        let err = parse("x = {extends = 'a'}", &options).unwrap_err();
+        assert!(err.contains("Cycle in extends: a -> b -> a"), "{}", err);
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::*;
+
+
+impl TomlSchema {
+    /// Renders the schema as an indented outline, one table key per line, this is the same as `format!("{:#}", schema)`
+    pub fn to_pretty_string(&self) -> String {
+        format!("{:#}", self)
+    }
+}
+
+/// Renders a schema as a short outline like `table { name: string /^\w+$/, port: int [1..65535] = 8080 }`,
+/// the alternate form (`{:#}`) puts each table key on its own indented line
+///
+/// table keys are listed in the order of their entries, see [TableEntries]
+impl Display for TomlSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pretty = f.alternate();
+        write_schema(f, self, pretty, 0)
+    }
+}
+
+
+fn write_bounds<T: Display + PartialEq>(f: &mut Formatter<'_>, min: T, max: T, no_min: T, no_max: T) -> fmt::Result {
+    if min == no_min && max == no_max {
+        return Ok(())
+    }
+    f.write_str(" [")?;
+    if min != no_min {write!(f, "{}", min)?;}
+    f.write_str("..")?;
+    if max != no_max {write!(f, "{}", max)?;}
+    f.write_char(']')
+}
+
+#[cfg(feature = "chrono")]
+fn write_date_bounds(f: &mut Formatter<'_>, date: &DateSchema) -> fmt::Result {
+    if date.min.is_none() && date.max.is_none() {
+        return Ok(())
+    }
+    let bound = |b: &Option<Datetime>| b.as_ref().map_or(String::new(), |b| b.to_string());
+    write!(f, " [{}..{}]", bound(&date.min), bound(&date.max))
+}
+
+#[cfg(not(feature = "chrono"))]
+fn write_date_bounds(_f: &mut Formatter<'_>, _date: &DateSchema) -> fmt::Result {
+    Ok(())
+}
+
+fn write_indent(f: &mut Formatter<'_>, level: usize) -> fmt::Result {
+    for _ in 0..level {f.write_str("    ")?;}
+    Ok(())
+}
+
+fn write_schema(f: &mut Formatter<'_>, schema: &TomlSchema, pretty: bool, level: usize) -> fmt::Result {
+    match schema {
+        TomlSchema::String { regex, refers_to, rules } => {
+            match &rules.format {
+                Some(format) => write!(f, "{}", format)?,
+                None => f.write_str("string")?
+            }
+            if regex.as_str() != ".*" {write!(f, " /{}/", regex.as_str())?;}
+            if let Some(not_regex) = &rules.not_regex {write!(f, " not /{}/", not_regex.as_str())?;}
+            if let Some(prefix) = &rules.starts_with {write!(f, " starts with {:?}", prefix)?;}
+            if let Some(suffix) = &rules.ends_with {write!(f, " ends with {:?}", suffix)?;}
+            if let Some(part) = &rules.contains {write!(f, " contains {:?}", part)?;}
+            if let Some(target) = refers_to {write!(f, " -> {}", target)?;}
+            if rules.interpolate {f.write_str(" interpolated")?;}
+            if rules.trim {f.write_str(" trimmed")?;}
+            #[cfg(feature = "unicode")]
+            if let Some(form) = rules.normalize {write!(f, " {}", form)?;}
+            Ok(())
+        },
+        TomlSchema::Integer { min, max, width, coerce } => {
+            f.write_str("int")?;
+            let (no_min, no_max) = match width {
+                Some(width) => {write!(f, " {}", width)?; width.range()},
+                None => (i64::MIN, i64::MAX)
+            };
+            write_bounds(f, *min, *max, no_min, no_max)?;
+            if *coerce {f.write_str(" or string")?;}
+            Ok(())
+        },
+        TomlSchema::Float { min, max, nan_ok, coerce } => {
+            f.write_str("float")?;
+            write_bounds(f, *min, *max, f64::NEG_INFINITY, f64::INFINITY)?;
+            if *nan_ok {f.write_str(" or nan")?;}
+            if *coerce {f.write_str(" or string")?;}
+            Ok(())
+        },
+        TomlSchema::Date(date) => {
+            f.write_str("date")?;
+            if date.require_offset {f.write_str(" with offset")?;}
+            if date.local_only {f.write_str(" local")?;}
+            write_date_bounds(f, date)
+        },
+        TomlSchema::Bool { coerce } => {
+            f.write_str("bool")?;
+            if *coerce {f.write_str(" or string")?;}
+            Ok(())
+        },
+        TomlSchema::Anything => f.write_str("anything"),
+        TomlSchema::Exact { value, epsilon } => {
+            write!(f, "exact {}", value)?;
+            if *epsilon > 0.0 {write!(f, " +- {}", epsilon)?;}
+            Ok(())
+        },
+        TomlSchema::Enum(values) => {
+            f.write_str("one of")?;
+            for (i, value) in values.iter().enumerate() {
+                write!(f, "{}{}", if i == 0 {" "} else {" | "}, value)?;
+            }
+            Ok(())
+        },
+        TomlSchema::Annotated { schema, .. } => write_schema(f, schema, pretty, level),
+        TomlSchema::Defaulted { schema, env, merge } => {
+            write_schema(f, schema, pretty, level)?;
+            if let Some(var) = env {write!(f, " env {}", var)?;}
+            if *merge {f.write_str(" merged")?;}
+            Ok(())
+        },
+        TomlSchema::Plugin(plugin) => {
+            f.write_str(plugin.type_name())?;
+            let options = plugin.node().to_table();
+            if !options.is_empty() {write!(f, " {}", Value::Table(options))?;}
+            Ok(())
+        },
+        TomlSchema::Custom { schema, validator } => {
+            write_schema(f, schema, pretty, level)?;
+            write!(f, " custom({})", validator.name())
+        },
+        TomlSchema::Graded { schema, severity } => {
+            write_schema(f, schema, pretty, level)?;
+            write!(f, " severity {}", severity)
+        },
+        TomlSchema::Alternative(options) => {
+            if options.is_empty() {
+                return f.write_str("alternative()")
+            }
+            for (i, opt) in options.iter().enumerate() {
+                if i > 0 {f.write_str(" | ")?;}
+                // nested alternatives are parenthesized to keep the grouping visible
+                let nested = matches!(opt.unannotated(), TomlSchema::Alternative(_));
+                if nested {f.write_char('(')?;}
+                write_schema(f, opt, pretty, level)?;
+                if nested {f.write_char(')')?;}
+            }
+            Ok(())
+        },
+        TomlSchema::Array { cond, min, max, multiple_of, unique_by, append } => {
+            f.write_str("array<")?;
+            write_schema(f, cond, pretty, level)?;
+            f.write_char('>')?;
+            write_bounds(f, *min, *max, 0, usize::MAX)?;
+            if *multiple_of > 1 {write!(f, " len % {}", multiple_of)?;}
+            if let Some(key) = unique_by {write!(f, " unique by {}", key)?;}
+            if *append {f.write_str(" appended")?;}
+            Ok(())
+        },
+        TomlSchema::Map(map_schema) => {
+            let MapSchema { key, key_type, value, min, max } = &**map_schema;
+            f.write_str("map<")?;
+            match (key.as_str() != ".*", *key_type != KeyType::String) {
+                (true, true) => write!(f, "/{}/ {}: ", key.as_str(), key_type)?,
+                (true, false) => write!(f, "/{}/: ", key.as_str())?,
+                (false, true) => write!(f, "{}: ", key_type)?,
+                (false, false) => ()
+            }
+            write_schema(f, value, pretty, level)?;
+            f.write_char('>')?;
+            write_bounds(f, *min, *max, 0, usize::MAX)
+        },
+        TomlSchema::Table(table_schema) => {
+            let TableSchema { extras, min, max, entries, assertions, requirements, policy } = &**table_schema;
+            f.write_str("table")?;
+            if entries.is_empty() && extras.is_empty() && assertions.is_empty() && requirements.is_empty() {
+                return f.write_str(" {}")
+            }
+            f.write_str(" {")?;
+
+            let mut first = true;
+            let mut separator = |f: &mut Formatter<'_>| -> fmt::Result {
+                if pretty {
+                    f.write_char('\n')?;
+                    write_indent(f, level + 1)
+                } else {
+                    f.write_str(if std::mem::take(&mut first) {" "} else {", "})
+                }
+            };
+
+            for (key, (sub, default)) in entries.iter() {
+                separator(f)?;
+                write!(f, "{}: ", key)?;
+                write_schema(f, sub, pretty, level + 1)?;
+                if let Some(dv) = default {write!(f, " = {}", dv)?;}
+            }
+            for TableEntry { key, key_type, value } in extras.iter() {
+                separator(f)?;
+                write!(f, "/{}/", key.as_str())?;
+                if *key_type != KeyType::String {write!(f, " {}", key_type)?;}
+                f.write_str(": ")?;
+                write_schema(f, value, pretty, level + 1)?;
+            }
+            if *min != 0 || *max != usize::MAX || *policy != ExtrasPolicy::default() {
+                separator(f)?;
+                f.write_str("extras")?;
+                write_bounds(f, *min, *max, 0, usize::MAX)?;
+                if policy.matching != ExtrasMatching::All {write!(f, " {}", policy.matching.as_str())?;}
+                if policy.count_entries {f.write_str(" count_entries")?;}
+                if let Some(case) = policy.key_case {write!(f, " {}_case", case.as_str())?;}
+            }
+            for assertion in assertions.iter() {
+                separator(f)?;
+                write!(f, "assert {}", assertion)?;
+            }
+            for requirement in requirements.iter() {
+                separator(f)?;
+                write!(f, "{}", requirement)?;
+            }
+
+            if pretty {
+                f.write_char('\n')?;
+                write_indent(f, level)?;
+                f.write_char('}')
+            } else {
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn display_compact() {
+        let schema: TomlSchema = r#"
+            name = {type = 'string', regex = '^\w+$'}
+            port = {type = 'int', min = 1, max = 65535, default = 8080}
+        "#.parse().unwrap();
+        assert_eq!(schema.to_string(), r"table { name: string /^\w+$/, port: int [1..65535] = 8080 }");
+
+        let schema: TomlSchema = "
+            type = 'array'
+            min = 1
+            child = {type = 'alternative', options = [{type = 'bool'}, {type = 'float', min = 0.5, nan_ok = true}, {type = 'exact', value = 'x'}]}
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), r#"array<bool | float [0.5..] or nan | exact "x"> [1..]"#);
+
+        let schema: TomlSchema = "
+            type = 'table'
+            max = 3
+            extras = [{key = '^x-', schema = {type = 'anything'}}]
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), "table { /^x-/: anything, extras [..3] }");
+        assert_eq!(TomlSchema::Date(Default::default()).to_string(), "date");
+    }
+
+    #[test]
+    fn display_pretty() {
+        let schema: TomlSchema = "
+            name = {type = 'string'}
+            [server]
+            host = {type = 'string', default = 'localhost'}
+            limits = {type = 'table'}
+        ".parse().unwrap();
+
+        assert_eq!(schema.to_pretty_string(), [
+            "table {",
+            "    name: string",
+            "    server: table {",
+            "        host: string = \"localhost\"",
+            "        limits: table {}",
+            "    }",
+            "}",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,134 @@
+//! The snippets that associate TOML files with a published JSON Schema in editors that use Taplo, like Even Better
+//! TOML for VS Code, see [EditorAssociation]
+
+use std::fmt::Write;
+use crate::*;
+
+
+/// The URL of the JSON Schema of a format and the files it applies to, from which the editor configuration is written
+///
+/// the crate does not convert schemas to JSON Schema, `schema_url` is where the JSON Schema of the format is
+/// published, `include` are glob patterns of the files like `**/service.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorAssociation {
+    pub schema_url: String,
+    pub include: Vec<String>,
+}
+
+impl EditorAssociation {
+    pub fn new(schema_url: &str, include: &[&str]) -> Self {
+        EditorAssociation { schema_url: schema_url.to_string(), include: include.iter().map(|s| s.to_string()).collect() }
+    }
+
+    /// The comment for the first line of a document, `#:schema <url>`, it associates that document only
+    pub fn directive(&self) -> String {
+        format!("#:schema {}", self.schema_url)
+    }
+
+    /// The [SCHEMA_HEADER] key for the top of a document, Taplo reads it like the directive
+    pub fn header(&self) -> String {
+        format!("{} = {}", Value::String(SCHEMA_HEADER.to_string()), Value::String(self.schema_url.clone()))
+    }
+
+    /// The `evenBetterToml.schema.associations` entry of a VS Code `settings.json`, the globs are written as the
+    /// regexes the extension matches against document URIs
+    pub fn vscode_settings(&self) -> String {
+        let mut out = String::from("{\n  \"evenBetterToml.schema.associations\": {\n");
+        for (i, glob) in self.include.iter().enumerate() {
+            let comma = if i + 1 < self.include.len() {","} else {""};
+            let _ = writeln!(out, "    {}: {}{}", json_string(&glob_regex(glob)), json_string(&self.schema_url), comma);
+        }
+        out.push_str("  }\n}\n");
+        out
+    }
+
+    /// A `[[rule]]` of a `.taplo.toml` (or `taplo.toml`) file, the configuration of the Taplo CLI and of the editors
+    /// that use it
+    pub fn taplo_config(&self) -> String {
+        let include = self.include.iter().map(|glob| Value::String(glob.clone())).collect();
+        let schema = toml::Table::from_iter([("path".to_string(), Value::String(self.schema_url.clone()))]);
+        let rule = toml::Table::from_iter([
+            ("include".to_string(), Value::Array(include)),
+            ("schema".to_string(), Value::Table(schema)),
+        ]);
+        let config = toml::Table::from_iter([("rule".to_string(), Value::Array(vec![Value::Table(rule)]))]);
+        toml::to_string(&config).expect("a table of strings is valid TOML")
+    }
+}
+
+/// The regex that matches the paths that a glob matches, `**/` is any number of directories, `*` and `?` do not match
+/// `/`, the match starts after a `/` and ends at the end of the path so that relative globs match full URIs
+fn glob_regex(glob: &str) -> String {
+    let mut re = String::from("(^|/)");
+    let mut rest = glob;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            re.push_str("(.*/)?");
+            rest = after;
+            continue
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            re.push_str(".*");
+            rest = after;
+            continue
+        }
+        match c {
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c if "\\.+()|[]{}^$".contains(c) => {re.push('\\'); re.push(c);},
+            c => re.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    re.push('$');
+    re
+}
+
+/// `s` as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => {let _ = write!(out, "\\u{:04x}", c as u32);},
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_snippets() {
+        let association = EditorAssociation::new("https://example.com/service.json", &["**/service.toml", "conf.d/*.toml"]);
+        assert_eq!(association.directive(), "#:schema https://example.com/service.json");
+        assert_eq!(association.header(), "\"$schema\" = \"https://example.com/service.json\"");
+        assert_eq!(association.vscode_settings(), r#"{
+  "evenBetterToml.schema.associations": {
+    "(^|/)(.*/)?service\\.toml$": "https://example.com/service.json",
+    "(^|/)conf\\.d/[^/]*\\.toml$": "https://example.com/service.json"
+  }
+}
+"#);
+
+        let config: toml::Table = association.taplo_config().parse().unwrap();
+        let rule = &config["rule"][0];
+        assert_eq!(rule["include"].as_array().unwrap().len(), 2);
+        assert_eq!(rule["schema"]["path"].as_str(), Some("https://example.com/service.json"));
+
+        #[cfg(feature = "regex")]
+        {
+            let re = regex::Regex::new(&glob_regex("**/service.toml")).unwrap();
+            assert!(re.is_match("file:///home/a/service.toml") && !re.is_match("file:///home/a/my-service.toml"));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Which schema node validated which value of a document, for finding out why a schema accepted a document, see
+//! [TomlSchema::check_explain]
+
+use std::fmt;
+use crate::*;
+
+
+/// Why a schema node was used for a value, relative to the node of the parent value
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum MatchVia<'s> {
+    /// The schema the check started from
+    Root,
+    /// The entry of the table schema with the key of the value
+    Entry,
+    /// The extra of the table schema whose regex matched the key of the value
+    Extra(&'s str),
+    /// The option of the alternative at this index, the value is the same as the one of the alternative
+    Option(usize),
+    /// The child of an array schema
+    Element,
+    /// The value schema of a map schema
+    MapValue,
+}
+
+impl fmt::Display for MatchVia<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchVia::Root => f.write_str("root"),
+            MatchVia::Entry => f.write_str("entry"),
+            MatchVia::Extra(re) => write!(f, "extra /{}/", re),
+            MatchVia::Option(i) => write!(f, "option {}", i),
+            MatchVia::Element => f.write_str("element"),
+            MatchVia::MapValue => f.write_str("map value"),
+        }
+    }
+}
+
+
+/// The schema node that validated a value of the document, with the nodes of the values inside it, built by
+/// [TomlSchema::check_explain]
+///
+/// an alternative has one child at the same path, the option that matched, wrappers like annotations and custom
+/// validators are not nodes of their own
+#[derive(Debug, Clone)]
+pub struct MatchTrace<'s> {
+    /// The location of the value in the document
+    pub path: SchemaPath,
+    pub schema: &'s TomlSchema,
+    pub via: MatchVia<'s>,
+    pub children: Vec<MatchTrace<'s>>,
+}
+
+impl<'s> MatchTrace<'s> {
+    /// The innermost node of the value at `path`, the option chosen by an alternative rather than the alternative
+    pub fn find(&self, path: &SchemaPath) -> Option<&MatchTrace<'s>> {
+        if !path.starts_with(&self.path) {
+            return None
+        }
+        match self.children.iter().find_map(|child| child.find(path)) {
+            Some(found) => Some(found),
+            None if &self.path == path => Some(self),
+            None => None
+        }
+    }
+
+    fn write(&self, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {"root".to_string()} else {self.path.to_string()};
+        writeln!(f, "{}{}: {} ({})", "  ".repeat(depth), path, SchemaType::from(self.schema.structure()), self.via)?;
+        for child in &self.children {
+            child.write(depth + 1, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MatchTrace<'_> {
+    /// One line per node, `path: type (via)`, the nodes below a node are indented
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(0, f)
+    }
+}
+
+
+impl TomlSchema {
+    /// Same as [TomlSchema::check] but also returns which schema node validated each value of the document, the entry
+    /// or extra of each key of a table and the option chosen by each alternative
+    pub fn check_explain<'s, 'v>(&'s self, data: &'v Value) -> Result<MatchTrace<'s>, SchemaError<'s, 'v>> {
+        self.check(data)?;
+        Ok(self.trace(data, &mut SchemaPath::new(), MatchVia::Root, DEFAULT_MAX_DEPTH))
+    }
+
+    /// The trace of a value that matches this schema, the nodes are chosen like the check chooses them
+    fn trace<'s>(&'s self, value: &Value, path: &mut SchemaPath, via: MatchVia<'s>, depth: usize) -> MatchTrace<'s> {
+        let mut children = Vec::new();
+        let depth = depth.saturating_sub(1);
+
+        match (self.structure(), value) {
+            (TomlSchema::Alternative(options), any) => {
+                if let Some(i) = options.matching(any, depth) {
+                    children.push(options[i].trace(any, path, MatchVia::Option(i), depth));
+                }
+            },
+            (TomlSchema::Array { cond, .. }, Value::Array(arr)) => {
+                for (i, value) in arr.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    children.push(cond.trace(value, path, MatchVia::Element, depth));
+                    path.pop();
+                }
+            },
+            (TomlSchema::Map(map), Value::Table(table)) => {
+                for (key, value) in table {
+                    path.push(PathSegment::Key(key.clone()));
+                    children.push(map.value.trace(value, path, MatchVia::MapValue, depth));
+                    path.pop();
+                }
+            },
+            (TomlSchema::Table(schema), Value::Table(table)) => {
+                let TableSchema { entries, extras, policy, .. } = &**schema;
+                for (key, value) in table {
+                    let found = match entries.get(key) {
+                        Some((schema, _)) => Some((schema, MatchVia::Entry)),
+                        None => policy.candidates(extras, key)
+                            .find(|e| e.key_type.accepts(key) && e.value.check_depth(value, depth).is_ok())
+                            .map(|e| (&e.value, MatchVia::Extra(e.key.as_str())))
+                    };
+                    if let Some((schema, via)) = found {
+                        path.push(PathSegment::Key(key.clone()));
+                        children.push(schema.trace(value, path, via, depth));
+                        path.pop();
+                    }
+                }
+            },
+            _ => ()
+        }
+        MatchTrace { path: path.clone(), schema: self, via, children }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explained() {
+        let schema: TomlSchema = "
+            name = 'string'
+            servers = {type = 'array', child = {type = 'alternative', options = ['string', {host = 'string'}]}}
+            extras = [{key = '^x-', schema = 'anything'}]
+        ".parse().unwrap();
+        let doc = Value::Table("
+            name = 'a'
+            servers = ['a', {host = 'b'}]
+            x-note = 1
+        ".parse().unwrap());
+
+        let trace = schema.check_explain(&doc).unwrap();
+        let path = |s: &str| s.parse::<SchemaPath>().unwrap();
+        assert_eq!(trace.find(&path("x-note")).unwrap().via, MatchVia::Extra("^x-"));
+        assert_eq!(trace.find(&path("servers[0]")).unwrap().via, MatchVia::Option(0));
+        let second = trace.find(&path("servers[1]")).unwrap();
+        assert_eq!(second.via, MatchVia::Option(1));
+        assert_eq!(second.children[0].via, MatchVia::Entry);
+        assert!(trace.find(&path("missing")).is_none());
+
+        assert_eq!(trace.to_string(), "\
+root: table (root)
+  name: string (entry)
+  servers: array (entry)
+    servers[0]: alternative (element)
+      servers[0]: string (option 0)
+    servers[1]: alternative (element)
+      servers[1]: table (option 1)
+        servers[1].host: string (entry)
+  x-note: anything (extra /^x-/)
+");
+        schema.check_explain(&Value::Table("name = 1".parse().unwrap())).unwrap_err();
+    }
+}
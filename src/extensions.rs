@@ -0,0 +1,76 @@
+use crate::*;
+
+
+impl TomlSchema {
+
+    /// The `x-` keys of this schema, they are kept for tools built on top of schemas (documentation generators, editors)
+    /// and do not change what the schema matches
+    pub fn extensions(&self) -> Option<&toml::Table> {
+        match self {
+            TomlSchema::Annotated { extensions, .. } => Some(extensions),
+            _ => None
+        }
+    }
+
+    /// The schema without its extensions and environment default, this is the schema itself when it has none
+    pub fn unannotated(&self) -> &TomlSchema {
+        match self {
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. } => schema.unannotated(),
+            other => other
+        }
+    }
+
+    /// The schema without its extensions, environment default, custom validators and severity, for code that only looks
+    /// at the shape of values
+    pub(crate) fn structure(&self) -> &TomlSchema {
+        match self {
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => schema.structure(),
+            other => other
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_kept() {
+        let schema = TomlSchema::parse_strict("
+            x-title = 'Server config'
+            [port]
+            type = 'int'
+            min = 1
+            default = 80
+            x-doc = {summary = 'the listening port', since = '0.3'}
+        ").unwrap();
+
+        assert_eq!(schema.extensions().unwrap()["x-title"].as_str(), Some("Server config"));
+        let TomlSchema::Table(table) = schema.unannotated() else {panic!("not a table: {:?}", schema)};
+        let entries = &table.entries;
+        assert!(!entries.contains_key("x-title"));
+
+        let (port, default) = &entries["port"];
+        assert_eq!(default, &Some(Value::Integer(80)));
+        assert_eq!(port.extensions().unwrap()["x-doc"]["since"].as_str(), Some("0.3"));
+        assert!(matches!(port.unannotated(), TomlSchema::Integer { min: 1, .. }));
+
+        // extensions do not change what matches
+        schema.check(&Value::Table("port = 8080".parse().unwrap())).unwrap();
+        schema.check(&Value::Table("port = 0".parse().unwrap())).unwrap_err();
+        assert_eq!(schema.to_string(), "table { port: int [1..] = 80 }");
+
+        // visitors see the annotated node once
+        let mut count = 0;
+        schema.visit(&mut |_, s| {count += 1; assert!(s.extensions().is_some());});
+        assert_eq!(count, 2);
+
+        // an escaped key is a table entry
+        let schema: TomlSchema = "'$x-custom' = {type = 'bool'}".parse().unwrap();
+        assert!(schema.extensions().is_none());
+        schema.check(&Value::Table("x-custom = true".parse().unwrap())).unwrap();
+    }
+}
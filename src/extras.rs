@@ -0,0 +1,119 @@
+use crate::*;
+
+
+/// Which extras a key that matches no entry is checked against, from the `extras_match` key of table schemas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtrasMatching {
+    /// Every extra whose key matches is tried in order and the key is accepted by the first one whose schema matches the
+    /// value, errors list all of the extras that were tried, the default
+    #[default]
+    All,
+    /// Only the first extra whose key regex matches is tried, the order of extras decides which schema applies
+    First,
+}
+
+impl ExtrasMatching {
+    /// The name of this policy in the `extras_match` key
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExtrasMatching::All => "all",
+            ExtrasMatching::First => "first",
+        }
+    }
+}
+
+impl TryFrom<&str> for ExtrasMatching {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "all" => Ok(ExtrasMatching::All),
+            "first" => Ok(ExtrasMatching::First),
+            _ => Err(format!("Invalid extras_match {}, expected all or first", value))
+        }
+    }
+}
+
+
+/// How the keys of a table are matched against its extras
+///
+/// the default keeps entries and extras apart, keys with an entry never count towards `min` and `max`, and tries
+/// every matching extra
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExtrasPolicy {
+    /// Keys with an entry also count towards the number of extras when an extra matches their key, from the
+    /// `count_entries` key, their value is still only checked against the entry
+    pub count_entries: bool,
+    pub matching: ExtrasMatching,
+    /// The naming convention of keys that are not entries, from the `key_case` key
+    pub key_case: Option<KeyCase>,
+}
+
+impl ExtrasPolicy {
+    /// The extras that `key` is checked against, in order, the key type is not checked here so that it can be reported
+    pub(crate) fn candidates<'s, 'k>(self, extras: &'s [TableEntry], key: &'k str) -> impl Iterator<Item = &'s TableEntry> + 'k
+    where 's: 'k
+    {
+        let limit = match self.matching {
+            ExtrasMatching::All => usize::MAX,
+            ExtrasMatching::First => 1,
+        };
+        extras.iter().filter(move |e| e.key.is_match(key)).take(limit)
+    }
+
+    /// Whether an extra may accept `key`, by its regex and its key type
+    pub(crate) fn matches_key(self, extras: &[TableEntry], key: &str) -> bool {
+        self.candidates(extras, key).any(|e| e.key_type.accepts(key))
+    }
+
+    /// Whether `key` follows the naming convention of extra keys
+    pub(crate) fn accepts_case(self, key: &str) -> bool {
+        self.key_case.is_none_or(|case| case.accepts(key))
+    }
+
+    /// Whether the key of a fixed entry counts towards the number of extras
+    pub(crate) fn counts_entry(self, extras: &[TableEntry], key: &str) -> bool {
+        self.count_entries && self.matches_key(extras, key)
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extras_policies() {
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+        let extras = "extras = [{key = '^x-', schema = 'int'}, {key = '.*', schema = 'string'}]";
+
+        let all: TomlSchema = extras.parse().unwrap();
+        all.check(&doc("x-port = 'http'\nname = 'a'")).unwrap();
+
+        // the first extra decides, the catch-all is never tried for `x-` keys
+        let first: TomlSchema = format!("{}\nextras_match = 'first'", extras).parse().unwrap();
+        first.check(&doc("x-port = 8080\nname = 'a'")).unwrap();
+        match first.check(&doc("x-port = 'http'")) {
+            Err(SchemaError::TableMiss { key: "x-port", errors, .. }) => assert_eq!(errors.len(), 1),
+            other => panic!("expected a table miss but got {:?}", other)
+        }
+        assert_eq!(all.validate(&doc("x-port = true")).errors.len(), 1);
+
+        let counted: TomlSchema = "
+            name = {type = 'string', default = ''}
+            extras = [{key = '^[a-z]+$', schema = 'anything'}]
+            count_entries = true
+            max = 2
+        ".parse().unwrap();
+        counted.check(&doc("name = 'a'\nother = 1")).unwrap();
+        counted.check(&doc("other = 1\nmore = 2")).unwrap();
+        assert!(matches!(counted.check(&doc("name = 'a'\nother = 1\nmore = 2")), Err(SchemaError::TableCount { count: 3, .. })));
+        assert_eq!(counted.validate(&doc("name = 'a'\nother = 1\nmore = 2")).errors.len(), 1);
+        assert_eq!(counted.to_string(), "table { name: string = \"\", /^[a-z]+$/: anything, extras [..2] count_entries }");
+        assert_eq!(first.to_string(), "table { /^x-/: int, /.*/: string, extras first }");
+
+        "extras_match = 'best'".parse::<TomlSchema>().unwrap_err();
+        "count_entries = 'yes'".parse::<TomlSchema>().unwrap_err();
+    }
+}
@@ -0,0 +1,635 @@
+//! String types with a syntax of their own, `type = "glob"` is a string schema whose strings must also be well-formed
+//! glob patterns, `type = "cron"` cron expressions, `socket_addr` and `cidr` network addresses and `base64` and `hex`
+//! encoded bytes and `language_tag` and `country_code` locales, the same formats are set on string schemas by
+//! `format = "hex"`, the errors say what is wrong and where
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+
+/// The syntax that the strings of a string schema follow, set by string types like `glob`
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum StringFormat {
+    /// A glob pattern like `src/**/*.rs`, with `*`, `?`, `**`, `[a-z]` classes and `{a,b}` alternations, with a `base`
+    /// the pattern is relative to that directory and must not be absolute or go up out of it with `..`
+    Glob { base: Option<String> },
+    /// A cron expression like `*/15 9-17 * * MON-FRI`, five fields (minute, hour, day of month, month, day of week), six
+    /// with seconds first, or a macro like `@daily`
+    #[cfg(feature = "cron")]
+    Cron,
+    /// An IP address and a port like `127.0.0.1:8080` or `[::1]:8080`
+    SocketAddr,
+    /// An IP network like `10.0.0.0/8` or `fd00::/8`, the bits after the prefix must be zero
+    Cidr,
+    /// Bytes in base64, with the standard or the URL-safe alphabet and with or without padding, the number of decoded
+    /// bytes must be in `[min_bytes, max_bytes]`
+    Base64 { min_bytes: usize, max_bytes: usize },
+    /// Bytes in hexadecimal, two digits of either case per byte, the number of decoded bytes must be in
+    /// `[min_bytes, max_bytes]`
+    Hex { min_bytes: usize, max_bytes: usize },
+    /// A BCP 47 language tag like `en`, `pt-BR` or `zh-Hant-TW`, only the syntax is checked, not that the subtags are
+    /// registered
+    LanguageTag,
+    /// An assigned ISO 3166-1 alpha-2 country code like `FR`, in upper case
+    CountryCode,
+}
+
+impl StringFormat {
+    /// The format of the string type `name`, without its options
+    pub(crate) fn from_type(name: &str) -> Option<StringFormat> {
+        match name {
+            "glob" => Some(StringFormat::Glob { base: None }),
+            #[cfg(feature = "cron")]
+            "cron" => Some(StringFormat::Cron),
+            "socket_addr" => Some(StringFormat::SocketAddr),
+            "cidr" => Some(StringFormat::Cidr),
+            "base64" => Some(StringFormat::Base64 { min_bytes: 0, max_bytes: usize::MAX }),
+            "hex" => Some(StringFormat::Hex { min_bytes: 0, max_bytes: usize::MAX }),
+            "language_tag" => Some(StringFormat::LanguageTag),
+            "country_code" => Some(StringFormat::CountryCode),
+            _ => None
+        }
+    }
+
+    /// The name of the string type of this format
+    pub fn name(&self) -> &'static str {
+        match self {
+            StringFormat::Glob { .. } => "glob",
+            #[cfg(feature = "cron")]
+            StringFormat::Cron => "cron",
+            StringFormat::SocketAddr => "socket_addr",
+            StringFormat::Cidr => "cidr",
+            StringFormat::Base64 { .. } => "base64",
+            StringFormat::Hex { .. } => "hex",
+            StringFormat::LanguageTag => "language_tag",
+            StringFormat::CountryCode => "country_code",
+        }
+    }
+
+    /// Checks that `s` follows this format, the error is the reason it does not
+    pub fn check(&self, s: &str) -> Result<(), String> {
+        match self {
+            StringFormat::Glob { base } => check_glob(s, base.as_deref()),
+            #[cfg(feature = "cron")]
+            StringFormat::Cron => check_cron(s),
+            StringFormat::SocketAddr => check_socket_addr(s),
+            StringFormat::Cidr => check_cidr(s),
+            StringFormat::Base64 { min_bytes, max_bytes } => check_byte_count(base64_len(s)?, *min_bytes, *max_bytes),
+            StringFormat::Hex { min_bytes, max_bytes } => check_byte_count(hex_len(s)?, *min_bytes, *max_bytes),
+            StringFormat::LanguageTag => check_language_tag(s),
+            StringFormat::CountryCode => check_country_code(s),
+        }
+    }
+
+    /// The bounds on the number of decoded bytes of the encoded formats
+    pub(crate) fn byte_bounds(&mut self) -> Option<(&mut usize, &mut usize)> {
+        match self {
+            StringFormat::Base64 { min_bytes, max_bytes } | StringFormat::Hex { min_bytes, max_bytes } => Some((min_bytes, max_bytes)),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for StringFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringFormat::Glob { base: Some(base) } => write!(f, "glob in {:?}", base),
+            StringFormat::Base64 { min_bytes, max_bytes } | StringFormat::Hex { min_bytes, max_bytes }
+                if (*min_bytes, *max_bytes) != (0, usize::MAX) => {
+                match (min_bytes, max_bytes) {
+                    (min, max) if min == max => write!(f, "{} of {} bytes", self.name(), min),
+                    (min, &usize::MAX) => write!(f, "{} of at least {} bytes", self.name(), min),
+                    (min, max) => write!(f, "{} of {} to {} bytes", self.name(), min, max),
+                }
+            },
+            other => f.write_str(other.name())
+        }
+    }
+}
+
+
+/// Checks the syntax of a glob pattern, positions in the errors are byte offsets
+fn check_glob(s: &str, base: Option<&str>) -> Result<(), String> {
+    if s.is_empty() {
+        return Err("the pattern is empty".to_string())
+    }
+    let bytes = s.as_bytes();
+    let mut open_braces = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => {
+                if i + 1 == bytes.len() {
+                    return Err("the pattern ends with an unfinished escape".to_string())
+                }
+                i += 1;
+            },
+            b'[' => i = class_end(s, i)?,
+            b'{' => open_braces.push(i),
+            b'}' if open_braces.pop().is_none() => return Err(format!("'}}' at {} closes no alternation", i)),
+            b'*' if bytes.get(i + 1) == Some(&b'*') => {
+                let start = i;
+                while bytes.get(i + 1) == Some(&b'*') {i += 1;}
+                let whole = (start == 0 || bytes[start - 1] == b'/') && (i + 1 == bytes.len() || bytes[i + 1] == b'/');
+                if !whole || i - start > 1 {
+                    return Err(format!("'**' at {} must be a whole path component", start))
+                }
+            },
+            _ => ()
+        }
+        i += 1;
+    }
+    if let Some(start) = open_braces.first() {
+        return Err(format!("the alternation at {} is not closed", start))
+    }
+
+    if let Some(base) = base {
+        if s.starts_with('/') || s.as_bytes().get(1) == Some(&b':') {
+            return Err(format!("the pattern is absolute but must be relative to {}", base))
+        }
+        if s.split('/').any(|component| component == "..") {
+            return Err(format!("the pattern goes up out of {} with '..'", base))
+        }
+    }
+    Ok(())
+}
+
+/// The index of the `]` that closes the class opened at `start`, a `]` right after `[`, `[!` or `[^` is part of the
+/// class
+fn class_end(s: &str, start: usize) -> Result<usize, String> {
+    let mut chars = s[start + 1..].char_indices().map(|(i, c)| (start + 1 + i, c)).peekable();
+    chars.next_if(|(_, c)| *c == '!' || *c == '^');
+    let mut first = true;
+    let mut previous = None;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ']' if !first => return Ok(i),
+            '-' if !first && previous.is_some() && chars.peek().is_some_and(|(_, next)| *next != ']') => {
+                let (_, to) = chars.next().expect("peeked");
+                let from = previous.take().expect("checked");
+                if from > to {
+                    return Err(format!("the range {}-{} at {} is reversed", from, to, start))
+                }
+                first = false;
+                continue
+            },
+            c => previous = Some(c)
+        }
+        first = false;
+    }
+    Err(format!("the class at {} is not closed", start))
+}
+
+
+/// Checks an IP address with a port, IPv6 addresses are in brackets
+fn check_socket_addr(s: &str) -> Result<(), String> {
+    if s.parse::<SocketAddr>().is_ok() {
+        return Ok(())
+    }
+    let (host, port) = match s.rsplit_once(':') {
+        Some((host, port)) if !host.ends_with(':') && !port.contains(']') => (host, port),
+        _ => return Err("expected an IP address and a port like 127.0.0.1:8080 or [::1]:8080".to_string())
+    };
+    if port.parse::<u16>().is_err() {
+        return Err(format!("the port {:?} is not a number from 0 to 65535", port))
+    }
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => Err(format!("the IPv6 address must be in brackets, like [{}]:{}", host, port)),
+        _ => Err(format!("{:?} is not an IP address, host names are not allowed", host))
+    }
+}
+
+/// Checks an IP network, an address and a prefix length
+fn check_cidr(s: &str) -> Result<(), String> {
+    let Some((addr, prefix)) = s.split_once('/') else {
+        return Err("expected an address and a prefix length like 10.0.0.0/8".to_string())
+    };
+    let addr: IpAddr = addr.parse().map_err(|_| format!("{:?} is not an IP address", addr))?;
+    let bits = if addr.is_ipv4() {32} else {128};
+    let prefix = match prefix.parse::<u32>() {
+        Ok(prefix) if prefix <= bits => prefix,
+        _ => return Err(format!("the prefix length {:?} is not a number from 0 to {}", prefix, bits))
+    };
+    let value = match addr {
+        IpAddr::V4(v4) => u32::from(v4) as u128,
+        IpAddr::V6(v6) => u128::from(v6),
+    };
+    // the low bits of the address after the prefix, IPv4 addresses are in the low 32 bits
+    let host_mask = u128::MAX.checked_shr(128 - bits + prefix).unwrap_or(0);
+    if value & host_mask != 0 {
+        let network = match addr {
+            IpAddr::V4(_) => IpAddr::from(std::net::Ipv4Addr::from((value & !host_mask) as u32)),
+            IpAddr::V6(_) => IpAddr::from(std::net::Ipv6Addr::from(value & !host_mask)),
+        };
+        return Err(format!("the address has bits set after the prefix, the network is {}/{}", network, prefix))
+    }
+    Ok(())
+}
+
+
+/// The number of bytes encoded by a base64 string
+fn base64_len(s: &str) -> Result<usize, String> {
+    let data = s.trim_end_matches('=');
+    let padding = s.len() - data.len();
+    if let Some((i, c)) = data.char_indices().find(|(_, c)| !(c.is_ascii_alphanumeric() || "+/-_".contains(*c))) {
+        return Err(format!("{:?} at {} is not a base64 character", c, i))
+    }
+    if data.contains(['+', '/']) && data.contains(['-', '_']) {
+        return Err("the standard (+/) and URL-safe (-_) alphabets are mixed".to_string())
+    }
+    if data.len() % 4 == 1 || (padding > 0 && !s.len().is_multiple_of(4)) || padding > 2 {
+        return Err(format!("the length {} is not a valid base64 length", s.len()))
+    }
+    Ok(data.len() * 3 / 4)
+}
+
+/// The number of bytes encoded by a hex string
+fn hex_len(s: &str) -> Result<usize, String> {
+    if let Some((i, c)) = s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(format!("{:?} at {} is not a hex digit", c, i))
+    }
+    if !s.len().is_multiple_of(2) {
+        return Err(format!("the length {} is odd, each byte is two digits", s.len()))
+    }
+    Ok(s.len() / 2)
+}
+
+fn check_byte_count(count: usize, min: usize, max: usize) -> Result<(), String> {
+    match (count < min, count > max) {
+        (true, _) if min == max => Err(format!("it decodes to {} bytes but must be {} bytes", count, min)),
+        (true, _) => Err(format!("it decodes to {} bytes but must be at least {}", count, min)),
+        (_, true) => Err(format!("it decodes to {} bytes but must be at most {}", count, max)),
+        _ => Ok(())
+    }
+}
+
+
+/// The tags of BCP 47 that do not follow its grammar, kept for compatibility
+const IRREGULAR_TAGS: [&str; 17] = [
+    "en-GB-oed", "i-ami", "i-bnn", "i-default", "i-enochian", "i-hak", "i-klingon", "i-lux", "i-mingo", "i-navajo", "i-pwn",
+    "i-tao", "i-tay", "i-tsu", "sgn-BE-FR", "sgn-BE-NL", "sgn-CH-DE",
+];
+
+/// Checks the syntax of a language tag, a language with optional extended languages, script, region, variants,
+/// extensions and private use subtags, in this order
+fn check_language_tag(s: &str) -> Result<(), String> {
+    if IRREGULAR_TAGS.iter().any(|tag| tag.eq_ignore_ascii_case(s)) {
+        return Ok(())
+    }
+    if s.contains('_') {
+        return Err("subtags are separated by '-', not '_'".to_string())
+    }
+    let subtags: Vec<&str> = s.split('-').collect();
+    if let Some(bad) = subtags.iter().find(|t| t.is_empty() || t.len() > 8 || !t.bytes().all(|b| b.is_ascii_alphanumeric())) {
+        return Err(format!("{:?} is not a subtag, subtags are 1 to 8 letters or digits", bad))
+    }
+    let alpha = |t: &str, len: std::ops::RangeInclusive<usize>| len.contains(&t.len()) && t.bytes().all(|b| b.is_ascii_alphabetic());
+    let digits = |t: &str, len: usize| t.len() == len && t.bytes().all(|b| b.is_ascii_digit());
+    let private_use = |rest: &[&str]| match rest.is_empty() {
+        true => Err("the private use section after 'x' has no subtags".to_string()),
+        false => Ok(())
+    };
+
+    let language = subtags[0];
+    if language.eq_ignore_ascii_case("x") {
+        return private_use(&subtags[1..])
+    }
+    if !alpha(language, 2..=8) {
+        return Err(format!("the language {:?} must be 2 to 8 letters", language))
+    }
+    let mut i = 1;
+    if language.len() <= 3 {
+        let extlangs = subtags[i..].iter().take(3).take_while(|t| alpha(t, 3..=3)).count();
+        i += extlangs;
+    }
+    // script, then region
+    if subtags.get(i).is_some_and(|t| alpha(t, 4..=4)) {i += 1;}
+    if subtags.get(i).is_some_and(|t| alpha(t, 2..=2) || digits(t, 3)) {i += 1;}
+
+    let mut variants: Vec<&str> = Vec::new();
+    while let Some(t) = subtags.get(i).filter(|t| t.len() >= 5 || (t.len() == 4 && t.as_bytes()[0].is_ascii_digit())) {
+        if variants.iter().any(|v| v.eq_ignore_ascii_case(t)) {
+            return Err(format!("the variant {:?} is repeated", t))
+        }
+        variants.push(t);
+        i += 1;
+    }
+
+    let mut singletons: Vec<&str> = Vec::new();
+    while let Some(singleton) = subtags.get(i).filter(|t| t.len() == 1 && !t.eq_ignore_ascii_case("x")) {
+        if singletons.iter().any(|s| s.eq_ignore_ascii_case(singleton)) {
+            return Err(format!("the extension {:?} is repeated", singleton))
+        }
+        singletons.push(singleton);
+        i += 1;
+        let count = subtags[i..].iter().take_while(|t| t.len() >= 2).count();
+        if count == 0 {
+            return Err(format!("the extension {:?} has no subtags", singleton))
+        }
+        i += count;
+    }
+
+    match subtags.get(i) {
+        None => Ok(()),
+        Some(t) if t.eq_ignore_ascii_case("x") => private_use(&subtags[i + 1..]),
+        Some(t) => Err(format!("the subtag {:?} is not valid at position {}", t, i + 1))
+    }
+}
+
+/// The assigned ISO 3166-1 alpha-2 codes
+const COUNTRY_CODES: &str = "\
+    AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI BJ BL BM BN BO BQ BR BS BT BV BW BY BZ \
+    CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ DE DJ DK DM DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR \
+    GA GB GD GE GF GG GH GI GL GM GN GP GQ GR GS GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP \
+    KE KG KH KI KM KN KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ MR MS MT \
+    MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM PN PR PS PT PW PY QA RE RO RS RU RW \
+    SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG \
+    UM US UY UZ VA VC VE VG VI VN VU WF WS YE YT ZA ZM ZW";
+
+fn check_country_code(s: &str) -> Result<(), String> {
+    let assigned = |code: &str| code.len() == 2 && COUNTRY_CODES.split(' ').any(|c| c == code);
+    if assigned(s) {
+        Ok(())
+    } else if assigned(&s.to_ascii_uppercase()) {
+        Err(format!("country codes are in upper case, {}", s.to_ascii_uppercase()))
+    } else {
+        Err(format!("{:?} is not an assigned ISO 3166-1 alpha-2 code", s))
+    }
+}
+
+
+/// A field of a cron expression, its name, its range and the names of its values from the minimum
+#[cfg(feature = "cron")]
+struct CronField {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+}
+
+#[cfg(feature = "cron")]
+const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+#[cfg(feature = "cron")]
+const DAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// The fields of a cron expression with seconds, the expressions with five fields start at the minute
+#[cfg(feature = "cron")]
+const CRON_FIELDS: [CronField; 6] = [
+    CronField { name: "second", min: 0, max: 59, names: &[] },
+    CronField { name: "minute", min: 0, max: 59, names: &[] },
+    CronField { name: "hour", min: 0, max: 23, names: &[] },
+    CronField { name: "day of month", min: 1, max: 31, names: &[] },
+    CronField { name: "month", min: 1, max: 12, names: &MONTHS },
+    // 7 is sunday too
+    CronField { name: "day of week", min: 0, max: 7, names: &DAYS },
+];
+
+/// The macros that stand for a whole expression
+#[cfg(feature = "cron")]
+const CRON_MACROS: [&str; 7] = ["@yearly", "@annually", "@monthly", "@weekly", "@daily", "@midnight", "@hourly"];
+
+/// Checks a cron expression, each field is `*`, a value, a range `a-b` or a list of them, with an optional step `/n`,
+/// the day fields can also be `?`
+#[cfg(feature = "cron")]
+fn check_cron(s: &str) -> Result<(), String> {
+    if s.starts_with('@') {
+        return match CRON_MACROS.contains(&s) {
+            true => Ok(()),
+            false => Err(format!("unknown macro {}, expected one of {:?}", s, CRON_MACROS))
+        }
+    }
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let fields = match parts.len() {
+        5 => &CRON_FIELDS[1..],
+        6 => &CRON_FIELDS[..],
+        n => return Err(format!("expected 5 fields (minute hour day-of-month month day-of-week) or 6 with seconds but got {}", n))
+    };
+    for (part, field) in parts.iter().zip(fields) {
+        check_cron_field(part, field).map_err(|e| format!("the {} field {:?} {}", field.name, part, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cron")]
+fn check_cron_field(part: &str, field: &CronField) -> Result<(), String> {
+    if part == "?" && field.name.starts_with("day") {
+        return Ok(())
+    }
+    for item in part.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None)
+        };
+        if let Some(step) = step {
+            match step.parse::<u32>() {
+                Ok(n) if n > 0 => (),
+                _ => return Err(format!("has the step {:?}, it must be a positive number", step))
+            }
+        }
+        if range == "*" {
+            continue
+        }
+        let (from, to) = match range.split_once('-') {
+            Some((from, to)) => (cron_value(from, field)?, Some(cron_value(to, field)?)),
+            None => (cron_value(range, field)?, None)
+        };
+        if let Some(to) = to.filter(|to| *to < from) {
+            return Err(format!("has the reversed range {}-{}", from, to))
+        }
+    }
+    Ok(())
+}
+
+/// A value of a field, a number or a name like `MON`
+#[cfg(feature = "cron")]
+fn cron_value(text: &str, field: &CronField) -> Result<u32, String> {
+    if let Some(i) = field.names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+        return Ok(field.min + i as u32)
+    }
+    match text.parse::<u32>() {
+        Ok(n) if (field.min..=field.max).contains(&n) => Ok(n),
+        Ok(n) => Err(format!("has {}, out of the range {}-{}", n, field.min, field.max)),
+        Err(_) if text.is_empty() => Err("has an empty value".to_string()),
+        Err(_) => Err(format!("has {:?}, it is not a number{}", text, match field.names.is_empty() {
+            true => String::new(),
+            false => format!(" or one of {}", field.names.join(", "))
+        }))
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_syntax() {
+        let glob = StringFormat::Glob { base: None };
+        for valid in ["src/**/*.rs", "**", "*.{rs,toml}", "[]a]?", "[!a-z0-9]*", "a\\*b", "{a,{b,c}}/**/x", "/etc/*.conf", "../x"] {
+            glob.check(valid).unwrap_or_else(|e| panic!("{} is rejected: {}", valid, e));
+        }
+        for (invalid, reason) in [
+            ("", "empty"), ("a\\", "unfinished escape"), ("[a-z", "class at 0 is not closed"), ("x[z-a]", "range z-a at 1 is reversed"),
+            ("{a,b", "alternation at 0"), ("a}", "'}' at 1"), ("src/a**/x", "'**' at 5"), ("***", "'**' at 0"),
+        ] {
+            let e = glob.check(invalid).unwrap_err();
+            assert!(e.contains(reason), "{}: {}", invalid, e);
+        }
+
+        let based = StringFormat::Glob { base: Some("src".to_string()) };
+        based.check("**/*.rs").unwrap();
+        assert!(based.check("/etc/*").unwrap_err().contains("absolute"));
+        assert!(based.check("a/../../x").unwrap_err().contains("goes up out of src"));
+    }
+
+    #[test]
+    fn glob_schema() {
+        let schema: crate::TomlSchema = "
+            include = {type = 'array', child = 'glob'}
+            sources = {type = 'glob', base = 'src', ends_with = '.rs'}
+        ".parse().unwrap();
+        schema.check_str("include = ['src/**/*.rs', '*.toml']\nsources = '**/*.rs'").unwrap();
+        let e = schema.check_str("include = ['src/[a-']\nsources = 'x.rs'").unwrap_err();
+        assert!(e.contains("is not a valid glob: the class at 4 is not closed"), "{}", e);
+        schema.check_str("include = []\nsources = '../x.rs'").unwrap_err();
+        schema.check_str("include = []\nsources = 'x.txt'").unwrap_err();
+
+        "x = {type = 'string', base = 'src'}".parse::<crate::TomlSchema>().unwrap();
+        crate::TomlSchema::parse_strict("x = {type = 'string', base = 'src'}").unwrap_err();
+        crate::TomlSchema::parse_strict("x = {type = 'glob', base = 'src'}").unwrap();
+    }
+
+    #[test]
+    fn network_addresses() {
+        for valid in ["127.0.0.1:8080", "0.0.0.0:0", "[::1]:443", "[fe80::1%3]:22"] {
+            StringFormat::SocketAddr.check(valid).unwrap_or_else(|e| panic!("{} is rejected: {}", valid, e));
+        }
+        for (invalid, reason) in [
+            ("127.0.0.1", "expected an IP address and a port"), ("127.0.0.1:70000", "the port \"70000\""),
+            ("localhost:80", "host names are not allowed"), ("::1:80", "must be in brackets, like [::1]:80"),
+        ] {
+            let e = StringFormat::SocketAddr.check(invalid).unwrap_err();
+            assert!(e.contains(reason), "{}: {}", invalid, e);
+        }
+
+        for valid in ["10.0.0.0/8", "0.0.0.0/0", "192.168.1.7/32", "fd00::/8", "::/0", "::1/128"] {
+            StringFormat::Cidr.check(valid).unwrap_or_else(|e| panic!("{} is rejected: {}", valid, e));
+        }
+        for (invalid, reason) in [
+            ("10.0.0.0", "expected an address and a prefix"), ("10.0.0.0/33", "from 0 to 32"), ("10.0.0/8", "not an IP address"),
+            ("192.168.1.7/24", "the network is 192.168.1.0/24"), ("fd00::1/8", "the network is fd00::/8"),
+        ] {
+            let e = StringFormat::Cidr.check(invalid).unwrap_err();
+            assert!(e.contains(reason), "{}: {}", invalid, e);
+        }
+
+        let schema: crate::TomlSchema = "
+            listen = 'socket_addr'
+            allow = {type = 'array', child = 'cidr'}
+            port = {type = 'port', allow_privileged = false, default = 8080}
+            admin_port = 'port'
+        ".parse().unwrap();
+        schema.check_str("listen = '0.0.0.0:80'\nallow = ['10.0.0.0/8']\nport = 8443\nadmin_port = 22").unwrap();
+        assert!(schema.check_str("listen = '0.0.0.0:80'\nallow = []\nport = 80\nadmin_port = 1").unwrap_err().contains("[1024,65535]"));
+        assert!(schema.check_str("listen = '0.0.0.0:80'\nallow = []\nport = 8080\nadmin_port = 65536").unwrap_err().contains("[0,65535]"));
+        crate::TomlSchema::parse_strict("x = {type = 'port', allow_privileged = false}").unwrap();
+        crate::TomlSchema::parse_strict("x = {type = 'int', allow_privileged = false}").unwrap_err();
+    }
+
+    #[test]
+    fn encoded_bytes() {
+        assert_eq!(base64_len("aGVsbG8="), Ok(5));
+        assert_eq!(base64_len("aGVsbG8"), Ok(5));
+        assert_eq!(base64_len("-_-_"), Ok(3));
+        assert_eq!(base64_len(""), Ok(0));
+        assert!(base64_len("aGVsbG8*").unwrap_err().contains("'*' at 7"));
+        assert!(base64_len("ab+_").unwrap_err().contains("mixed"));
+        assert!(base64_len("abcde").unwrap_err().contains("length 5"));
+        assert!(base64_len("abc==").unwrap_err().contains("length 5"));
+        assert_eq!(hex_len("00ffAB"), Ok(3));
+        assert!(hex_len("0g").unwrap_err().contains("'g' at 1"));
+        assert!(hex_len("abc").unwrap_err().contains("odd"));
+
+        let schema: crate::TomlSchema = "
+            key = {type = 'string', format = 'base64', min_bytes = 32, max_bytes = 32}
+            digest = {type = 'hex', min_bytes = 20}
+            salt = {type = 'string', format = 'hex', default = ''}
+        ".parse().unwrap();
+        let key = "A".repeat(43) + "=";
+        schema.check_str(&format!("key = '{}'\ndigest = '{}'", key, "ab".repeat(32))).unwrap();
+        let e = schema.check_str(&format!("key = 'AAAA'\ndigest = '{}'", "ab".repeat(20))).unwrap_err();
+        assert!(e.contains("is not a valid base64 of 32 bytes: it decodes to 3 bytes but must be 32 bytes"), "{}", e);
+        let e = schema.check_str(&format!("key = '{}'\ndigest = 'abab'", key)).unwrap_err();
+        assert!(e.contains("it decodes to 2 bytes but must be at least 20"), "{}", e);
+
+        for (invalid, error) in [
+            ("x = {type = 'string', format = 'yaml'}", "Unknown string format \"yaml\""),
+            ("x = {type = 'glob', format = 'hex'}", "format cannot be used with type glob"),
+            ("x = {type = 'hex', min_bytes = 4, max_bytes = 2}", "min_bytes 4 is larger than max_bytes 2"),
+            ("x = {type = 'hex', min_bytes = -1}", "min_bytes must be a positive int"),
+        ] {
+            let e = invalid.parse::<crate::TomlSchema>().unwrap_err();
+            assert!(e.contains(error), "{}", e);
+        }
+        crate::TomlSchema::parse_strict("x = {type = 'string', min_bytes = 4}").unwrap_err();
+        crate::TomlSchema::parse_strict("x = {type = 'string', format = 'glob', base = 'src'}").unwrap();
+    }
+
+    #[test]
+    fn locales() {
+        assert_eq!(COUNTRY_CODES.split(' ').count(), 249);
+        for valid in [
+            "en", "pt-BR", "zh-Hant-TW", "es-419", "zh-yue-HK", "sl-rozaj-biske", "de-CH-1901", "en-US-u-ca-gregory-x-mine",
+            "x-private", "i-klingon", "EN-gb", "tlh",
+        ] {
+            StringFormat::LanguageTag.check(valid).unwrap_or_else(|e| panic!("{} is rejected: {}", valid, e));
+        }
+        for (invalid, reason) in [
+            ("en_US", "not '_'"), ("", "\"\" is not a subtag"), ("e", "language \"e\""), ("en--US", "\"\" is not a subtag"),
+            ("de-DE-1901-1901", "variant \"1901\" is repeated"), ("en-u", "extension \"u\" has no subtags"),
+            ("en-a-bbb-a-ccc", "extension \"a\" is repeated"), ("en-x", "private use"), ("en-US-GB", "\"GB\" is not valid at position 3"),
+            ("english-language-tag", "\"tag\" is not valid at position 3"),
+        ] {
+            let e = StringFormat::LanguageTag.check(invalid).unwrap_err();
+            assert!(e.contains(reason), "{}: {}", invalid, e);
+        }
+
+        StringFormat::CountryCode.check("FR").unwrap();
+        assert!(StringFormat::CountryCode.check("fr").unwrap_err().contains("upper case, FR"));
+        assert!(StringFormat::CountryCode.check("UK").unwrap_err().contains("not an assigned"));
+        assert!(StringFormat::CountryCode.check("FRA").unwrap_err().contains("not an assigned"));
+
+        let schema: crate::TomlSchema = "
+            locale = {type = 'language_tag', default = 'en'}
+            countries = {type = 'array', child = 'country_code'}
+        ".parse().unwrap();
+        schema.check_str("locale = 'fr-CA'\ncountries = ['FR', 'CA']").unwrap();
+        let e = schema.check_str("countries = ['FR', 'EU']").unwrap_err();
+        assert!(e.contains("is not a valid country_code"), "{}", e);
+    }
+
+    #[cfg(feature = "cron")]
+    #[test]
+    fn cron_syntax() {
+        let cron = StringFormat::Cron;
+        for valid in ["* * * * *", "*/15 9-17 * * MON-FRI", "0 0 1,15 * ?", "30 0 0 1 jan-mar/2 7", "@daily", "0 12 * DEC sun"] {
+            cron.check(valid).unwrap_or_else(|e| panic!("{} is rejected: {}", valid, e));
+        }
+        for (invalid, reason) in [
+            ("* * * *", "expected 5 fields"), ("60 * * * *", "the minute field \"60\" has 60, out of the range 0-59"),
+            ("0 0 0 * *", "day of month field \"0\""), ("0 0 * 13 *", "month field"), ("0 0 * * FRI-MON", "reversed range 5-1"),
+            ("*/0 * * * *", "the step \"0\""), ("0 0 * * monday", "not a number or one of SUN"), ("1, * * * *", "empty value"),
+            ("0 ? * * *", "the hour field \"?\""), ("@often", "unknown macro @often"),
+        ] {
+            let e = cron.check(invalid).unwrap_err();
+            assert!(e.contains(reason), "{}: {}", invalid, e);
+        }
+
+        let schema: crate::TomlSchema = "schedule = 'cron'".parse().unwrap();
+        let e = schema.check_str("schedule = '0 25 * * *'").unwrap_err();
+        assert!(e.contains("is not a valid cron: the hour field \"25\""), "{}", e);
+    }
+}
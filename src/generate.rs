@@ -0,0 +1,805 @@
+//! Random generation of values that match a schema, enabled with the `generate` feature
+//!
+//! This is meant for property-based testing of code that consumes validated configs, every generated value
+//! passes [TomlSchema::check], bounds are respected, strings are generated from the regular expressions and
+//! tables get all their required keys, some optional ones and as many extras as allowed (up to a few)
+//!
+//! [TomlSchema::generate_invalid] does the opposite and generates near misses, values that break a single
+//! constraint of the schema
+//!
+//! ```ignore
+//! let mut rng = toml_schema::generate::XorShiftRng::new(42);
+//! let value = schema.generate(&mut rng).unwrap();
+//! schema.check(&value).unwrap();
+//! ```
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+use toml::value::Datetime;
+use crate::*;
+
+
+/// The source of randomness used by [TomlSchema::generate], implement it to plug in any random generator
+pub trait Rng {
+    /// A uniformly distributed random number
+    fn next_u64(&mut self) -> u64;
+
+    /// A uniformly distributed number in `[min, max]`
+    fn in_range(&mut self, min: i64, max: i64) -> i64 {
+        if min >= max {
+            return min
+        }
+        let span = (max as i128 - min as i128 + 1) as u128;
+        (min as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+
+    /// A uniformly distributed float in `[0, 1)`
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `true` with probability `1 / n`
+    fn one_in(&mut self, n: u64) -> bool {
+        self.next_u64().is_multiple_of(n.max(1))
+    }
+}
+
+/// A small, fast and deterministic random generator (xorshift64*), good enough for tests
+#[derive(Debug, Clone)]
+pub struct XorShiftRng(u64);
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> Self {
+        // the state must never be zero
+        XorShiftRng((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+    }
+}
+
+impl Rng for XorShiftRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+
+/// Unbounded repetitions and counts are limited to this many items above their minimum
+const MAX_EXTRA_ITEMS: usize = 4;
+
+/// Generated strings are checked against their regex and generated again this many times at most
+const MAX_ATTEMPTS: usize = 32;
+
+/// Floats without bounds are generated in `[-BIG_FLOAT, BIG_FLOAT]`
+const BIG_FLOAT: f64 = 1e6;
+
+
+impl TomlSchema {
+
+    /// Generates a random value that matches this schema
+    ///
+    /// this fails only for schemas that no value can match (empty bounds or alternatives, unsatisfiable regexes) or that
+    /// are nested more than [DEFAULT_MAX_DEPTH] levels
+    pub fn generate(&self, rng: &mut impl Rng) -> Result<Value, String> {
+        self.generate_depth(rng, DEFAULT_MAX_DEPTH)
+    }
+
+    pub(crate) fn generate_depth(&self, rng: &mut impl Rng, depth: usize) -> Result<Value, String> {
+        let Some(depth) = depth.checked_sub(1) else {
+            return Err("Maximum nesting depth exceeded while generating a value".to_string())
+        };
+
+        match self {
+            TomlSchema::String { refers_to: Some(target), .. } => Err(format!("Cannot generate a reference to {}", target)),
+            TomlSchema::String { regex, rules, .. } if rules.is_default() => generate_string(regex, rng).map(Value::String),
+            TomlSchema::String { regex, rules, .. } => {
+                let accepts = |s: &str| regex.is_match(s) && rules.check(s).is_ok();
+                // literals are unlikely to come out of the regex alone
+                generate_matching(regex.as_str(), accepts, rng)
+                    .or_else(|_| generate_matching(regex.as_str(), |s| accepts(&rules.decorate(s)), rng).map(|s| rules.decorate(&s)))
+                    .map(Value::String)
+            },
+            TomlSchema::Integer { min, max, .. } => {
+                if min > max {
+                    return Err(format!("Cannot generate an int in [{}, {}]", min, max))
+                }
+                Ok(Value::Integer(generate_int(*min, *max, rng)))
+            },
+            TomlSchema::Float { min, max, nan_ok, .. } => {
+                if *nan_ok && rng.one_in(16) {
+                    return Ok(Value::Float(f64::NAN))
+                }
+                generate_float(*min, *max, rng).map(Value::Float)
+            },
+            TomlSchema::Bool { .. } => Ok(Value::Boolean(rng.one_in(2))),
+            TomlSchema::Date(date) => generate_date_in(date, rng).map(Value::Datetime),
+            TomlSchema::Exact { value, .. } => Ok(value.clone()),
+            TomlSchema::Enum(values) if values.is_empty() => Err("Cannot generate a value of an empty enum".to_string()),
+            TomlSchema::Enum(values) => Ok(values[rng.in_range(0, values.len() as i64 - 1) as usize].clone()),
+            TomlSchema::Anything => Ok(generate_scalar(rng)),
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => schema.generate_depth(rng, depth + 1),
+            TomlSchema::Plugin(plugin) => Err(format!("Cannot generate values of the user-defined type {}", plugin.type_name())),
+            TomlSchema::Custom { schema, validator } => {
+                for _ in 0..MAX_ATTEMPTS {
+                    let value = schema.generate_depth(rng, depth + 1)?;
+                    if validator.check(&value).is_ok() {
+                        return Ok(value)
+                    }
+                }
+                Err(format!("Could not generate a value accepted by the custom validator '{}'", validator.name()))
+            },
+            TomlSchema::Alternative(options) => {
+                // start at a random option and try the others if it cannot be generated
+                let start = rng.in_range(0, options.len() as i64 - 1) as usize;
+                let mut last_error = "Cannot generate a value for an empty alternative".to_string();
+                for i in 0..options.len() {
+                    match options[(start + i) % options.len()].generate_depth(rng, depth) {
+                        Ok(v) => return Ok(v),
+                        Err(e) => last_error = e
+                    }
+                }
+                Err(last_error)
+            },
+            TomlSchema::Array { cond, min, max, multiple_of, unique_by, .. } => {
+                // the length is a number of steps of `multiple_of` elements
+                let step = *multiple_of as usize;
+                let (min_steps, max_steps) = (min.div_ceil(step), max / step);
+                if min_steps > max_steps {
+                    return Err(format!("Cannot generate an array with [{}, {}] elements and a multiple of {}", min, max, multiple_of))
+                }
+                let len = generate_count(min_steps, max_steps, rng) * step;
+                let mut arr = Vec::with_capacity(len);
+                let mut attempts = 0;
+                while arr.len() < len {
+                    attempts += 1;
+                    if attempts > (len + 1) * MAX_ATTEMPTS {
+                        return Err(format!("Could not generate {} elements with distinct {}", len, unique_by.as_deref().unwrap_or_default()))
+                    }
+                    arr.push(cond.generate_depth(rng, depth)?);
+                    // elements that repeat a unique value are generated again
+                    if unique_by.as_deref().is_some_and(|key| Self::find_duplicate(key, &arr).is_some()) {
+                        arr.pop();
+                    }
+                }
+                Ok(Value::Array(arr))
+            },
+            TomlSchema::Map(map_schema) => {
+                let MapSchema { key, key_type, value, min, max } = &**map_schema;
+                let count = generate_count(*min, *max, rng);
+                let mut table = toml::Table::new();
+                let mut attempts = 0;
+                while table.len() < count {
+                    attempts += 1;
+                    if attempts > (count + 1) * MAX_ATTEMPTS {
+                        return Err(format!("Could not generate {} distinct keys", count))
+                    }
+                    let key = generate_key(key, key_type, rng)?;
+                    if !table.contains_key(&key) {
+                        table.insert(key, value.generate_depth(rng, depth)?);
+                    }
+                }
+                Ok(Value::Table(table))
+            },
+            TomlSchema::Table(table_schema) => {
+                let TableSchema { extras, min, max, entries, assertions, requirements, policy } = &**table_schema;
+                let mut generate_table = || -> Result<toml::Table, String> {
+                    let mut table = toml::Table::new();
+
+                    for (key, (schema, default)) in entries.iter() {
+                        // optional keys are left out half of the time
+                        if default.is_some() && rng.one_in(2) {
+                            continue;
+                        }
+                        table.insert(key.clone(), schema.generate_depth(rng, depth)?);
+                    }
+
+                    if extras.is_empty() && *min > 0 {
+                        return Err(format!("Cannot generate {} extra keys without extras", min))
+                    }
+                    let counted = table.keys().filter(|k| policy.counts_entry(extras, k)).count();
+                    if counted > *max {
+                        return Err(format!("Cannot generate a table where {} entries count as extras with max = {}", counted, max))
+                    }
+                    let count = if extras.is_empty() {0} else {generate_count(min.saturating_sub(counted), max - counted, rng)};
+                    let mut added = 0;
+                    let mut attempts = 0;
+                    while added < count {
+                        attempts += 1;
+                        if attempts > count * MAX_ATTEMPTS {
+                            return Err(format!("Could not generate {} distinct extra keys", count))
+                        }
+                        let extra = &extras[rng.in_range(0, extras.len() as i64 - 1) as usize];
+                        let Ok(key) = generate_key(&extra.key, &extra.key_type, rng) else {continue};
+                        // keys of fixed entries are always checked against the entry, not the extras, and the key
+                        // must not be taken by an earlier extra with first-match
+                        if entries.contains_key(&key) || table.contains_key(&key) || !policy.accepts_case(&key) ||
+                            !policy.candidates(extras, &key).any(|e| std::ptr::eq(e, extra)) {
+                            continue;
+                        }
+                        table.insert(key, extra.value.generate_depth(rng, depth)?);
+                        added += 1;
+                    }
+                    Ok(table)
+                };
+
+                // assertions and requirements are not solved, tables are generated until one passes them
+                for _ in 0..MAX_ATTEMPTS {
+                    let table = generate_table()?;
+                    if assertions.iter().all(|a| a.holds(&table, entries).is_ok()) &&
+                        requirements.iter().all(|r| r.missing(&table, entries).is_none()) {
+                        return Ok(Value::Table(table))
+                    }
+                }
+                Err("Could not generate a table that passes its assertions and requirements".to_string())
+            }
+        }
+    }
+}
+
+
+/// A count in `[min, max]` that is at most [MAX_EXTRA_ITEMS] above `min`
+pub(crate) fn generate_count(min: usize, max: usize, rng: &mut impl Rng) -> usize {
+    let upper = max.min(min.saturating_add(MAX_EXTRA_ITEMS));
+    rng.in_range(0, (upper - min) as i64) as usize + min
+}
+
+/// Bounds are generated more often than other values since they are where bugs hide
+fn generate_int(min: i64, max: i64, rng: &mut impl Rng) -> i64 {
+    match rng.next_u64() % 8 {
+        0 => min,
+        1 => max,
+        // prefer small values when the range is huge
+        2 | 3 => {
+            let low = min.max(-1000).min(max);
+            rng.in_range(low, max.min(1000).max(low))
+        },
+        _ => rng.in_range(min, max)
+    }
+}
+
+fn generate_float(min: f64, max: f64, rng: &mut impl Rng) -> Result<f64, String> {
+    if min.is_nan() || max.is_nan() || min > max {
+        return Err(format!("Cannot generate a float in [{}, {}]", min, max))
+    }
+    let low = if min.is_finite() {min} else {max.min(0.0) - BIG_FLOAT};
+    let high = if max.is_finite() {max} else {low.max(0.0) + BIG_FLOAT};
+    let value = match rng.next_u64() % 8 {
+        0 if min.is_finite() => min,
+        1 if max.is_finite() => max,
+        _ => low + (high - low) * rng.unit()
+    };
+    Ok(value.clamp(low, high))
+}
+
+fn generate_date(rng: &mut impl Rng) -> Datetime {
+    let text = match rng.next_u64() % 4 {
+        0 => format!("{:04}-{:02}-{:02}", rng.in_range(1970, 2100), rng.in_range(1, 12), rng.in_range(1, 28)),
+        1 => format!("{:02}:{:02}:{:02}", rng.in_range(0, 23), rng.in_range(0, 59), rng.in_range(0, 59)),
+        2 => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            rng.in_range(1970, 2100), rng.in_range(1, 12), rng.in_range(1, 28),
+            rng.in_range(0, 23), rng.in_range(0, 59), rng.in_range(0, 59)
+        ),
+        _ => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            rng.in_range(1970, 2100), rng.in_range(1, 12), rng.in_range(1, 28),
+            rng.in_range(0, 23), rng.in_range(0, 59), rng.in_range(0, 59)
+        )
+    };
+    text.parse().expect("generated datetimes are well formed")
+}
+
+/// A random date accepted by `date`, or one of its bounds when random dates are not
+fn generate_date_in(date: &DateSchema, rng: &mut impl Rng) -> Result<Datetime, String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate = generate_date(rng);
+        if date.check(&candidate).is_ok() {
+            return Ok(candidate)
+        }
+    }
+    #[cfg(feature = "chrono")]
+    if let Some(bound) = [date.min, date.max].into_iter().flatten().find(|bound| date.check(bound).is_ok()) {
+        return Ok(bound)
+    }
+    Err("Could not generate a date in the bounds".to_string())
+}
+
+pub(crate) fn generate_scalar(rng: &mut impl Rng) -> Value {
+    match rng.next_u64() % 4 {
+        0 => Value::Integer(rng.in_range(-1000, 1000)),
+        1 => Value::Boolean(rng.one_in(2)),
+        2 => Value::Float(rng.unit() * 100.0),
+        _ => Value::String(generate_word(rng))
+    }
+}
+
+fn generate_word(rng: &mut impl Rng) -> String {
+    let len = rng.in_range(1, 8);
+    (0..len).map(|_| (b'a' + rng.in_range(0, 25) as u8) as char).collect()
+}
+
+
+/// Generates a key that contains a match of `regex` and has the type `key_type`, typed keys are generated from their
+/// type and kept when they match the regex
+fn generate_key(pattern: &Pattern, key_type: &KeyType, rng: &mut impl Rng) -> Result<String, String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let key = match key_type {
+            KeyType::String => return generate_string(pattern, rng),
+            KeyType::Integer { min, max } if min > max => break,
+            KeyType::Integer { min, max } => generate_int(*min, *max, rng).to_string(),
+            KeyType::Date => generate_date(rng).to_string(),
+            KeyType::Semver => format!("{}.{}.{}", rng.in_range(0, 20), rng.in_range(0, 20), rng.in_range(0, 20)),
+        };
+        if pattern.is_match(&key) {
+            return Ok(key)
+        }
+    }
+    Err(format!("Could not generate a {} key matching /{}/", key_type, pattern.as_str()))
+}
+
+/// Generates a string that contains a match of `regex`
+pub(crate) fn generate_string(regex: &Pattern, rng: &mut impl Rng) -> Result<String, String> {
+    generate_matching(regex.as_str(), |s| regex.is_match(s), rng)
+}
+
+/// Generates a string accepted by `is_match` from the regex `text` that it implements
+fn generate_matching(text: &str, is_match: impl Fn(&str) -> bool, rng: &mut impl Rng) -> Result<String, String> {
+    let hir = regex_syntax::Parser::new().parse(text).map_err(|e| e.to_string())?;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let mut out = String::new();
+        if generate_hir(&hir, rng, &mut out) && is_match(&out) {
+            return Ok(out)
+        }
+    }
+    Err(format!("Could not generate a string matching /{}/", text))
+}
+
+/// Appends a match of `hir` to `out`, returns `false` if the expression cannot match anything
+fn generate_hir(hir: &Hir, rng: &mut impl Rng, out: &mut String) -> bool {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => true,
+        HirKind::Literal(lit) => {
+            out.push_str(&String::from_utf8_lossy(&lit.0));
+            true
+        },
+        HirKind::Class(Class::Unicode(class)) => {
+            let ranges: Vec<(u32, u32)> = class.ranges().iter().map(|r| (r.start() as u32, r.end() as u32)).collect();
+            match pick_char(&ranges, rng) {
+                Some(c) => {out.push(c); true},
+                None => false
+            }
+        },
+        HirKind::Class(Class::Bytes(class)) => {
+            // only ASCII bytes can be part of a string
+            let ranges: Vec<(u32, u32)> = class.ranges().iter()
+                .filter(|r| r.start() < 0x80)
+                .map(|r| (r.start() as u32, r.end().min(0x7F) as u32))
+                .collect();
+            match pick_char(&ranges, rng) {
+                Some(c) => {out.push(c); true},
+                None => false
+            }
+        },
+        HirKind::Repetition(rep) => {
+            let min = rep.min as usize;
+            let max = rep.max.map_or(usize::MAX, |m| m as usize);
+            (0..generate_count(min, max, rng)).all(|_| generate_hir(&rep.sub, rng, out))
+        },
+        HirKind::Capture(cap) => generate_hir(&cap.sub, rng, out),
+        HirKind::Concat(parts) => parts.iter().all(|p| generate_hir(p, rng, out)),
+        HirKind::Alternation(options) => {
+            let i = rng.in_range(0, options.len() as i64 - 1) as usize;
+            generate_hir(&options[i], rng, out)
+        }
+    }
+}
+
+/// Picks a character from a set of ranges, printable ASCII characters are preferred when the set contains some
+fn pick_char(ranges: &[(u32, u32)], rng: &mut impl Rng) -> Option<char> {
+    let printable: Vec<(u32, u32)> = ranges.iter()
+        .filter(|(s, e)| *s <= 0x7E && *e >= 0x20)
+        .map(|(s, e)| ((*s).max(0x20), (*e).min(0x7E)))
+        .collect();
+    let ranges = if !printable.is_empty() && !rng.one_in(8) {&printable[..]} else {ranges};
+
+    let total: u64 = ranges.iter().map(|(s, e)| (e - s + 1) as u64).sum();
+    if total == 0 {
+        return None
+    }
+    let mut n = rng.next_u64() % total;
+    for (s, e) in ranges {
+        let size = (e - s + 1) as u64;
+        if n < size {
+            // surrogates are not characters, fall back to the start of the range
+            return char::from_u32(s + n as u32).or(char::from_u32(*s))
+        }
+        n -= size;
+    }
+    None
+}
+
+
+
+impl TomlSchema {
+
+    /// Generates a value that does not match this schema but is close to one that does, a single constraint is
+    /// violated (a bound, the regex, the type, one array element, one table entry or the number of extras)
+    ///
+    /// this is meant to exercise the error handling of applications and to test that schemas are as tight as intended,
+    /// it fails for schemas that accept every value like `anything`
+    pub fn generate_invalid(&self, rng: &mut impl Rng) -> Result<Value, String> {
+        for _ in 0..MAX_ATTEMPTS {
+            // a near miss of a nested schema may still be accepted by the whole schema (through an alternative or
+            // an extra), only values that really fail are returned
+            if let Some(value) = self.near_miss(rng, DEFAULT_MAX_DEPTH) {
+                if self.check(&value).is_err() {
+                    return Ok(value)
+                }
+            }
+        }
+        Err(format!("Could not generate a value that does not match {}", self))
+    }
+
+    fn near_miss(&self, rng: &mut impl Rng, depth: usize) -> Option<Value> {
+        let depth = depth.checked_sub(1)?;
+
+        let value = match self {
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } => return schema.near_miss(rng, depth + 1),
+            // the misses of a lower severity are accepted
+            TomlSchema::Graded { schema, severity: Severity::Error } => return schema.near_miss(rng, depth + 1),
+            TomlSchema::Graded { .. } => None,
+            TomlSchema::Integer { min, max, .. } => match rng.next_u64() % 3 {
+                0 if *min > i64::MIN => Some(Value::Integer(min - 1)),
+                1 if *max < i64::MAX => Some(Value::Integer(max + 1)),
+                _ => None
+            },
+            TomlSchema::Float { min, max, nan_ok, .. } => match rng.next_u64() % 4 {
+                0 if min.is_finite() => Some(Value::Float(min.next_down())),
+                1 if max.is_finite() => Some(Value::Float(max.next_up())),
+                2 if !nan_ok => Some(Value::Float(f64::NAN)),
+                _ => None
+            },
+            TomlSchema::Date(date) if (date.require_offset || date.local_only) && rng.one_in(2) => {
+                let mut other = generate_date(rng);
+                other.offset = match date.require_offset {
+                    true => None,
+                    false => Some(toml::value::Offset::Z)
+                };
+                // a time alone cannot have an offset
+                if other.date.is_some() && other.time.is_some() {Some(Value::Datetime(other))} else {None}
+            },
+            #[cfg(feature = "chrono")]
+            TomlSchema::Date(date) => match rng.one_in(2) {
+                false => date.min.and_then(|min| crate::date::shift_days(&min, -1)).map(Value::Datetime),
+                true => date.max.and_then(|max| crate::date::shift_days(&max, 1)).map(Value::Datetime)
+            },
+            TomlSchema::String { regex, .. } => match rng.one_in(4) {
+                false => mutate_string(regex, rng).map(Value::String),
+                true => None
+            },
+            TomlSchema::Exact { value: Value::Float(f), epsilon } if *epsilon > 0.0 && f.is_finite() => {
+                Some(Value::Float(f + epsilon * 2.0))
+            },
+            TomlSchema::Exact { value, epsilon } => match rng.one_in(2) {
+                false => near_value(value, rng).filter(|v| !values_match(value, v, *epsilon)),
+                true => None
+            },
+            TomlSchema::Enum(values) if !values.is_empty() && !rng.one_in(2) => {
+                near_value(&values[rng.in_range(0, values.len() as i64 - 1) as usize], rng)
+                    .filter(|v| !values.iter().any(|value| values_match(value, v, 0.0)))
+            },
+            TomlSchema::Alternative(options) if !options.is_empty() => {
+                options[rng.in_range(0, options.len() as i64 - 1) as usize].near_miss(rng, depth)
+            },
+            TomlSchema::Array { cond, min, max, multiple_of, unique_by, .. } => match rng.next_u64() % 6 {
+                0 if *min > 0 => {
+                    (0..min - 1).map(|_| cond.generate_depth(rng, depth).ok()).collect::<Option<Vec<_>>>().map(Value::Array)
+                },
+                1 if *max < MAX_GENERATED_LEN => {
+                    (0..=*max).map(|_| cond.generate_depth(rng, depth).ok()).collect::<Option<Vec<_>>>().map(Value::Array)
+                },
+                2 => {
+                    // a valid array with one invalid element
+                    let Ok(Value::Array(mut arr)) = self.generate_depth(rng, depth + 1) else {return None};
+                    if arr.is_empty() {
+                        if *max == 0 {return None}
+                        arr.push(Value::Boolean(false));
+                    }
+                    let i = rng.in_range(0, arr.len() as i64 - 1) as usize;
+                    arr[i] = cond.near_miss(rng, depth)?;
+                    Some(Value::Array(arr))
+                },
+                5 if *multiple_of > 1 => {
+                    // a valid array with one more element
+                    let Ok(Value::Array(mut arr)) = self.generate_depth(rng, depth + 1) else {return None};
+                    arr.push(cond.generate_depth(rng, depth).ok()?);
+                    Some(Value::Array(arr))
+                },
+                4 if unique_by.is_some() && *max >= 2 => {
+                    // a valid array with one element repeated
+                    let Ok(Value::Array(mut arr)) = self.generate_depth(rng, depth + 1) else {return None};
+                    let key = unique_by.as_deref()?;
+                    let i = arr.iter().position(|v| v.get(key).is_some())?;
+                    let repeated = arr[i].clone();
+                    match arr.len() < *max {
+                        true => arr.push(repeated),
+                        false => arr[if i == 0 {1} else {0}] = repeated
+                    }
+                    Some(Value::Array(arr))
+                },
+                _ => None
+            },
+            TomlSchema::Map(map_schema) => {
+                let MapSchema { key, key_type, value, min, max } = &**map_schema;
+                let Ok(Value::Table(mut table)) = self.generate_depth(rng, depth + 1) else {return None};
+                match rng.next_u64() % 4 {
+                    0 if !table.is_empty() => {
+                        // one value is invalid
+                        let k = table.keys().nth(rng.in_range(0, table.len() as i64 - 1) as usize)?.clone();
+                        table.insert(k, value.near_miss(rng, depth)?);
+                    },
+                    1 => {
+                        // a key that does not match
+                        let k = format!("unknown_{}", generate_word(rng));
+                        if key.is_match(&k) && key_type.accepts(&k) {return None}
+                        table.insert(k, value.generate_depth(rng, depth).ok()?);
+                    },
+                    2 if *min > 0 => {
+                        // one key too few
+                        let extra: Vec<String> = table.keys().skip(min - 1).cloned().collect();
+                        for k in extra {table.remove(&k);}
+                    },
+                    3 if *max < MAX_GENERATED_LEN => {
+                        // one key too many
+                        for _ in 0..(max + 1) * MAX_ATTEMPTS {
+                            if table.len() > *max {break}
+                            let Ok(k) = generate_key(key, key_type, rng) else {continue};
+                            table.insert(k, value.generate_depth(rng, depth).ok()?);
+                        }
+                        if table.len() <= *max {return None}
+                    },
+                    _ => return wrong_type(self, rng)
+                }
+                Some(Value::Table(table))
+            },
+            TomlSchema::Table(table_schema) => {
+                let TableSchema { extras, min, max, entries, policy, .. } = &**table_schema;
+                let Ok(Value::Table(mut table)) = self.generate_depth(rng, depth + 1) else {return None};
+                let extra_keys: Vec<String> = table.keys().filter(|k| !entries.contains_key(k)).cloned().collect();
+                let counted = table.keys().filter(|k| policy.counts_entry(extras, k)).count();
+
+                match rng.next_u64() % 4 {
+                    0 if !entries.is_empty() => {
+                        // one entry gets an invalid value
+                        let (key, (schema, _)) = entries.iter().nth(rng.in_range(0, entries.len() as i64 - 1) as usize)?;
+                        table.insert(key.clone(), schema.near_miss(rng, depth)?);
+                    },
+                    1 => {
+                        // a key that is neither an entry nor matched by an extra
+                        let key = format!("unknown_{}", generate_word(rng));
+                        if entries.contains_key(&key) || policy.matches_key(extras, &key) {
+                            return None
+                        }
+                        table.insert(key, generate_scalar(rng));
+                    },
+                    2 if *min > counted => {
+                        // one extra too few
+                        for key in extra_keys.iter().skip(min - 1 - counted) {
+                            table.remove(key);
+                        }
+                    },
+                    3 if !extras.is_empty() && *max < MAX_GENERATED_LEN => {
+                        // one extra too many
+                        let mut count = extra_keys.len() + counted;
+                        for _ in 0..(max + 1) * MAX_ATTEMPTS {
+                            if count > *max {break}
+                            let extra = &extras[rng.in_range(0, extras.len() as i64 - 1) as usize];
+                            let Ok(key) = generate_key(&extra.key, &extra.key_type, rng) else {continue};
+                            if entries.contains_key(&key) || table.contains_key(&key) || !policy.accepts_case(&key) ||
+                                !policy.candidates(extras, &key).any(|e| std::ptr::eq(e, extra)) {continue}
+                            table.insert(key, extra.value.generate_depth(rng, depth).ok()?);
+                            count += 1;
+                        }
+                        if count <= *max {return None}
+                    },
+                    _ => return wrong_type(self, rng)
+                }
+                Some(Value::Table(table))
+            },
+            _ => None
+        };
+        value.or_else(|| wrong_type(self, rng))
+    }
+}
+
+/// Arrays and extras above this count are never generated to violate a maximum
+const MAX_GENERATED_LEN: usize = 64;
+
+/// A value of a type the schema does not accept, if there is one
+fn wrong_type(schema: &TomlSchema, rng: &mut impl Rng) -> Option<Value> {
+    let candidates = [
+        Value::Integer(rng.in_range(-1000, 1000)),
+        Value::Float(rng.unit() * 100.0),
+        Value::Boolean(rng.one_in(2)),
+        Value::String(generate_word(rng)),
+        Value::Datetime(generate_date(rng)),
+        Value::Array(Vec::new()),
+        Value::Table(toml::Table::new()),
+    ];
+    let wrong: Vec<Value> = candidates.into_iter().filter(|v| !accepts_type(schema, v)).collect();
+    if wrong.is_empty() {
+        return None
+    }
+    Some(wrong[rng.in_range(0, wrong.len() as i64 - 1) as usize].clone())
+}
+
+fn accepts_type(schema: &TomlSchema, value: &Value) -> bool {
+    match schema {
+        TomlSchema::Alternative(options) => options.iter().any(|opt| accepts_type(opt, value)),
+        TomlSchema::Exact { value: expected, .. } => SchemaType::from(expected) == SchemaType::from(value),
+        TomlSchema::Enum(values) => values.iter().any(|expected| SchemaType::from(expected) == SchemaType::from(value)),
+        TomlSchema::Anything => true,
+        TomlSchema::Map(_) => value.is_table(),
+        TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => accepts_type(schema, value),
+        // the type of values accepted by plugins is not known, only the values they reject are surely wrong
+        TomlSchema::Plugin(plugin) => plugin.check(value).is_ok(),
+        _ => SchemaType::from(schema) == SchemaType::from(value)
+    }
+}
+
+/// A value of the same type that is slightly different
+fn near_value(value: &Value, rng: &mut impl Rng) -> Option<Value> {
+    match value {
+        Value::String(s) => Some(Value::String(format!("{}{}", s, generate_word(rng)))),
+        Value::Integer(i) => Some(Value::Integer(i.wrapping_add(if rng.one_in(2) {1} else {-1}))),
+        Value::Float(f) => Some(Value::Float(if f.is_nan() {0.0} else {f.next_up()})),
+        Value::Boolean(b) => Some(Value::Boolean(!b)),
+        Value::Datetime(_) => Some(Value::Datetime(generate_date(rng))),
+        Value::Array(arr) if !arr.is_empty() => {
+            let mut arr = arr.clone();
+            let i = rng.in_range(0, arr.len() as i64 - 1) as usize;
+            arr[i] = near_value(&arr[i], rng)?;
+            Some(Value::Array(arr))
+        },
+        Value::Array(_) => Some(Value::Array(vec![generate_scalar(rng)])),
+        Value::Table(table) => {
+            let mut table = table.clone();
+            table.insert(format!("unknown_{}", generate_word(rng)), generate_scalar(rng));
+            Some(Value::Table(table))
+        }
+    }
+}
+
+/// A string that does not match `regex`, made with a single edit to one that does
+fn mutate_string(regex: &Pattern, rng: &mut impl Rng) -> Option<String> {
+    let base = generate_string(regex, rng).unwrap_or_default();
+
+    for _ in 0..MAX_ATTEMPTS {
+        let mut chars: Vec<char> = base.chars().collect();
+        let pos = rng.in_range(0, chars.len() as i64) as usize;
+        let c = (rng.in_range(0x20, 0x7E) as u8) as char;
+        match rng.next_u64() % 4 {
+            0 if pos < chars.len() => {chars.remove(pos);},
+            1 if pos < chars.len() => chars[pos] = c,
+            2 => chars.clear(),
+            _ => chars.insert(pos, c)
+        }
+        let candidate: String = chars.into_iter().collect();
+        if !regex.is_match(&candidate) {
+            return Some(candidate)
+        }
+    }
+    None
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_valid() {
+        let schema = std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse::<TomlSchema>().unwrap();
+        let mut rng = XorShiftRng::new(7);
+
+        for _ in 0..200 {
+            let value = schema.generate(&mut rng).unwrap();
+            if let Err(e) = schema.check(&value) {
+                panic!("generated {} which does not match: {:?}", value, e);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_bounds_and_regexes() {
+        let schema: TomlSchema = r#"
+            small = {type = 'int', min = -3, max = 3}
+            big = {type = 'int', min = 9223372036854775000}
+            negative = {type = 'int', min = -5000, max = -2000}
+            ratio = {type = 'float', min = 0.0, max = 1.0, nan_ok = true}
+            low = {type = 'float', max = -1e300}
+            name = {type = 'string', regex = '^[a-z]{3,5}(-[0-9]+)?$'}
+            email = {type = 'string', regex = '(?i)^\w+@example\.(com|org)$'}
+            image = {type = 'string', regex = '^[a-z_.-]*$', starts_with = 'app-', contains = '_v', ends_with = '.png', not_regex = 'zz'}
+            when = {type = 'date'}
+            stamp = {type = 'date', require_offset = true}
+            day = {type = 'date', local_only = true}
+            tags = {type = 'array', min = 2, max = 3, child = {type = 'exact', value = 'x'}}
+            pairs = {type = 'array', child = 'int', len_multiple_of = 2, max = 7}
+            listeners = {type = 'array', max = 5, unique_by = 'port', child = {port = {type = 'int', min = 1, max = 8}}}
+            env = {type = 'map', key_regex = '^[A-Z]{2,4}$', value = {type = 'int', min = 0}, min = 1, max = 4}
+            ports = {type = 'map', key_type = {type = 'int', min = 1, max = 9}, value = 'bool'}
+            versions = {extras = [{key = '^1\.', key_type = 'semver', schema = 'date'}]}
+            ordered = {extras = [{key = '^x', schema = 'int'}, {key = '.*', schema = 'bool'}], extras_match = 'first', max = 4}
+            cased = {extras = [{key = '^[a-z_]{1,8}$', schema = 'int'}], key_case = 'snake', max = 3}
+            counted = {a = {type = 'int', default = 0}, extras = [{key = '^[a-z]$', schema = 'bool'}], count_entries = true, min = 1, max = 2}
+            either = {type = 'alternative', options = [{type = 'bool'}, {type = 'table', extras = [{key = '^k[0-9]$', schema = {type = 'anything'}}], min = 1, max = 3}]}
+        "#.parse().unwrap();
+        let mut rng = XorShiftRng::new(1);
+
+        for _ in 0..500 {
+            let value = schema.generate(&mut rng).unwrap();
+            if let Err(e) = schema.check(&value) {
+                panic!("generated {} which does not match: {:?}", value, e);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_impossible() {
+        let mut rng = XorShiftRng::new(3);
+
+        TomlSchema::Integer { min: 5, max: 0, width: None, coerce: false }.generate(&mut rng).unwrap_err();
+        TomlSchema::Alternative(Arc::new(AlternativeSchema::new(Vec::new()))).generate(&mut rng).unwrap_err();
+        TomlSchema::String { regex: Regex::new("[^\\s\\S]").unwrap().into(), refers_to: None, rules: Default::default() }.generate(&mut rng).unwrap_err();
+
+        let schema: TomlSchema = "type = 'table'\nmin = 1".parse().unwrap();
+        schema.generate(&mut rng).unwrap_err();
+    }
+
+    #[test]
+    fn generate_deterministic() {
+        let schema = std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse::<TomlSchema>().unwrap();
+        let a = schema.generate(&mut XorShiftRng::new(99)).unwrap();
+        let b = schema.generate(&mut XorShiftRng::new(99)).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn generate_invalid() {
+        let schema = std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse::<TomlSchema>().unwrap();
+        let mut rng = XorShiftRng::new(5);
+        for _ in 0..200 {
+            let value = schema.generate_invalid(&mut rng).unwrap();
+            assert!(schema.check(&value).is_err(), "generated {} which matches", value);
+        }
+
+        // near misses stay close to the bounds
+        let schema: TomlSchema = "type = 'int'\nmin = 1\nmax = 3".parse().unwrap();
+        for _ in 0..50 {
+            match schema.generate_invalid(&mut rng).unwrap() {
+                Value::Integer(i) => assert!(i == 0 || i == 4, "{} is not a near miss", i),
+                other => assert!(!other.is_integer())
+            }
+        }
+
+        let schema: TomlSchema = "type = 'string'\nregex = '^[a-z]+$'".parse().unwrap();
+        for _ in 0..50 {
+            schema.check(&schema.generate_invalid(&mut rng).unwrap()).unwrap_err();
+        }
+
+        let schema: TomlSchema = "type = 'map'\nkey_regex = '^[a-z]+$'\nvalue = 'bool'\nmin = 1\nmax = 2".parse().unwrap();
+        for _ in 0..50 {
+            schema.check(&schema.generate_invalid(&mut rng).unwrap()).unwrap_err();
+        }
+
+        let schema: TomlSchema = "type = 'array'\nmin = 2\nmax = 2\nunique_by = 'id'\nchild = {id = 'int'}".parse().unwrap();
+        for _ in 0..50 {
+            schema.check(&schema.generate_invalid(&mut rng).unwrap()).unwrap_err();
+        }
+
+        TomlSchema::Anything.generate_invalid(&mut rng).unwrap_err();
+    }
+}
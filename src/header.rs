@@ -0,0 +1,122 @@
+//! Documents that name their schema, `"$schema" = "service.schema.toml"` at the top of a document is how editors find
+//! the schema of a file, [validate_auto] loads it and checks the document
+
+use std::path::Path;
+use crate::*;
+
+
+/// The key of the header that names the schema of a document
+pub const SCHEMA_HEADER: &str = "$schema";
+
+/// Whether the key of the root table is a schema header, table schemas accept it there without an entry or an extra
+/// for it
+pub(crate) fn is_header(key: &str, value: &Value) -> bool {
+    key == SCHEMA_HEADER && value.is_str()
+}
+
+/// The schema named by the `$schema` key of a document, `None` when the document has no header
+pub fn schema_header(doc: &Value) -> Option<&str> {
+    doc.get(SCHEMA_HEADER)?.as_str()
+}
+
+/// Reads the TOML file at `path`, loads the schema named by its `$schema` header with the default [ParseOptions] and
+/// checks the file with [TomlSchema::check_and_complete], returns the completed document
+///
+/// see [validate_auto_with_options] for how the schema is found
+pub fn validate_auto(path: impl AsRef<Path>) -> Result<Value, String> {
+    validate_auto_with_options(path, &ParseOptions::default())
+}
+
+/// Same as [validate_auto] with non-default [ParseOptions], the header is the name of a schema of
+/// [ParseOptions::registry], a URL resolved like `{type = "reference", url = ".."}` (this requires the `remote`
+/// feature) or the path of a schema file relative to the directory of the document
+///
+/// errors (IO errors, TOML syntax errors, a missing header, schema errors) are returned as strings, prefixed with the
+/// path of the document
+pub fn validate_auto_with_options(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Value, String> {
+    let path = path.as_ref();
+    let prefix = |e: String| format!("{}: {}", path.display(), e);
+
+    let text = std::fs::read_to_string(path).map_err(|e| prefix(e.to_string()))?;
+    let mut doc = Value::Table(text.parse::<toml::Table>().map_err(|e| prefix(e.to_string()))?);
+    let Some(name) = schema_header(&doc) else {
+        return Err(prefix(format!("No {} header to find the schema", SCHEMA_HEADER)))
+    };
+    let schema = load_schema(name, path.parent().unwrap_or(Path::new("")), options).map_err(prefix)?;
+    schema.check_and_complete(&mut doc).map_err(|e| prefix(format!("{:?}", e)))?;
+    Ok(doc)
+}
+
+/// The schema named by a header of a document in `dir`
+fn load_schema(name: &str, dir: &Path, options: &ParseOptions) -> Result<Arc<TomlSchema>, String> {
+    if let Some(schema) = options.registry.get(name) {
+        return Ok(schema)
+    }
+    if name.contains("://") {
+        #[cfg(feature = "remote")]
+        return options.remote.resolve(name, options).map(Arc::new);
+        #[cfg(not(feature = "remote"))]
+        return Err(format!("Schema {} is a URL, this requires the `remote` feature", name));
+    }
+
+    let file = dir.join(name);
+    let text = std::fs::read_to_string(&file).map_err(|e| format!("Cannot read schema {}: {}", file.display(), e))?;
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| format!("Invalid schema {}: {}", file.display(), e))?;
+    let (schema, _, warnings) = TomlSchema::from_table_with_options(&table, options)
+        .map_err(|e| format!("Invalid schema {}: {}", file.display(), e))?;
+    for warning in warnings {
+        log::warn!("{}: {}", file.display(), warning);
+    }
+    Ok(Arc::new(schema))
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_headers() {
+        let dir = std::env::temp_dir().join(format!("toml_schema_header_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("service.schema.toml"), "name = 'string'\nport = {type = 'int', default = 80}").unwrap();
+        std::fs::write(dir.join("good.toml"), "'$schema' = 'service.schema.toml'\nname = 'a'").unwrap();
+        std::fs::write(dir.join("bad.toml"), "'$schema' = 'service.schema.toml'\nname = 1").unwrap();
+        std::fs::write(dir.join("named.toml"), "'$schema' = 'service'\nname = 'b'\nport = 1").unwrap();
+        std::fs::write(dir.join("none.toml"), "name = 'a'").unwrap();
+
+        let registry = SchemaRegistry::new();
+        registry.register("service", "name = 'string'\nport = 'int'".parse().unwrap());
+        let options = ParseOptions { registry, ..ParseOptions::default() };
+        let good = validate_auto(dir.join("good.toml"));
+        let bad = validate_auto(dir.join("bad.toml"));
+        let named = validate_auto_with_options(dir.join("named.toml"), &options);
+        let none = validate_auto(dir.join("none.toml"));
+        let unregistered = validate_auto(dir.join("named.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let good = good.unwrap();
+        assert_eq!(schema_header(&good), Some("service.schema.toml"));
+        assert_eq!(good["port"].as_integer(), Some(80));
+        assert!(bad.unwrap_err().contains("bad.toml: "));
+        named.unwrap();
+        assert!(none.unwrap_err().contains("No $schema header"));
+        assert!(unregistered.unwrap_err().contains("Cannot read schema"));
+
+        // ordinary schemas accept the header, only as a string
+        let schema: TomlSchema = "name = 'string'".parse().unwrap();
+        schema.check_str("'$schema' = 'x.toml'\nname = 'a'").unwrap();
+        schema.check_str("'$schema' = 1\nname = 'a'").unwrap_err();
+        assert!(schema.validate(&Value::Table("'$schema' = 'x.toml'\nname = 'a'".parse().unwrap())).is_ok());
+
+        // nested tables do not
+        let schema: TomlSchema = "server = {type = 'table', name = 'string'}".parse().unwrap();
+        let nested = "server = {'$schema' = 'x.toml', name = 'a'}";
+        schema.check_str(nested).unwrap_err();
+        assert!(!schema.validate(&Value::Table(nested.parse().unwrap())).is_ok());
+        let mut doc = Value::Table(nested.parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap_err();
+    }
+}
@@ -0,0 +1,266 @@
+//! Placeholders in strings resolved by [TomlSchema::check_and_complete], `${server.host}` is replaced by the value at
+//! this path of the document and `${ENV:HOME}` by an environment variable, `$${` is a literal `${`
+//!
+//! strings of filled defaults are always interpolated, strings of the document only when their schema has
+//! `interpolate = true`, placeholders may point to other interpolated strings as long as they do not form a cycle
+
+use std::collections::HashSet;
+use crate::*;
+
+
+/// Resolves the interpolated strings of a document, each one at most once
+struct Resolver<'d> {
+    doc: &'d Value,
+    targets: HashSet<SchemaPath>,
+    resolved: HashMap<SchemaPath, String>,
+    /// the strings being resolved, a placeholder that points to one of them is a cycle
+    stack: Vec<SchemaPath>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, path: &SchemaPath) -> Result<String, (SchemaPath, SchemaError<'static, 'static>)> {
+        if let Some(s) = self.resolved.get(path) {
+            return Ok(s.clone())
+        }
+        let Some(Value::String(raw)) = path.value_in(self.doc) else {
+            unreachable!("interpolated strings are collected from the document")
+        };
+
+        self.stack.push(path.clone());
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw.as_str();
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("$${") {
+                out.push_str("${");
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("${") {
+                let Some(end) = after.find('}') else {
+                    return Err(self.error(after, "the placeholder is not closed"))
+                };
+                let placeholder = &after[..end];
+                out.push_str(&self.placeholder(placeholder)?);
+                rest = &after[end + 1..];
+            } else {
+                out.push('$');
+                rest = &rest[1..];
+            }
+        }
+        out.push_str(rest);
+        self.stack.pop();
+
+        self.resolved.insert(path.clone(), out.clone());
+        Ok(out)
+    }
+
+    fn placeholder(&mut self, placeholder: &str) -> Result<String, (SchemaPath, SchemaError<'static, 'static>)> {
+        if let Some(var) = placeholder.strip_prefix("ENV:") {
+            return std::env::var(var).map_err(|_| self.error(placeholder, "the environment variable is not set"))
+        }
+
+        let target: SchemaPath = placeholder.parse().map_err(|e: String| self.error(placeholder, &e))?;
+        if self.stack.contains(&target) {
+            return Err(self.error(placeholder, "the placeholders form a cycle"))
+        }
+        match target.value_in(self.doc) {
+            Some(Value::String(_)) if self.targets.contains(&target) => self.resolve(&target),
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(Value::Table(_) | Value::Array(_)) => Err(self.error(placeholder, "only strings, numbers, bools and dates can be interpolated")),
+            Some(value) => Ok(value.to_string()),
+            None => Err(self.error(placeholder, "there is no value at this path"))
+        }
+    }
+
+    /// The error of the string being resolved
+    fn error(&self, placeholder: &str, reason: &str) -> (SchemaPath, SchemaError<'static, 'static>) {
+        let path = self.stack.first().cloned().unwrap_or_default();
+        (path, SchemaError::InterpolationMiss { placeholder: placeholder.to_string(), reason: reason.to_string() })
+    }
+}
+
+/// Whether a string of `value` has a placeholder, the result of interpolating it depends on the document
+pub(crate) fn has_placeholders(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.contains("${"),
+        Value::Array(arr) => arr.iter().any(has_placeholders),
+        Value::Table(table) => table.values().any(has_placeholders),
+        _ => false
+    }
+}
+
+/// The paths of all strings in `value`
+fn collect_strings(value: &Value, path: &mut SchemaPath, out: &mut Vec<SchemaPath>) {
+    match value {
+        Value::String(_) => out.push(path.clone()),
+        Value::Array(arr) => for (i, value) in arr.iter().enumerate() {
+            path.push(PathSegment::Index(i));
+            collect_strings(value, path, out);
+            path.pop();
+        },
+        Value::Table(table) => for (key, value) in table {
+            path.push(PathSegment::Key(key.clone()));
+            collect_strings(value, path, out);
+            path.pop();
+        },
+        _ => ()
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Finds the strings of `value` checked by a string schema whose rules are accepted by `keep`
+    pub(crate) fn collect_string_rules<'s>(&'s self, value: &Value, path: &mut SchemaPath, depth: usize, keep: &impl Fn(&StringRules) -> bool,
+        out: &mut Vec<(SchemaPath, &'s StringRules)>)
+    {
+        let pick = |schema: &'s TomlSchema, value: &Value| match (schema, value) {
+            (TomlSchema::String { rules, .. }, Value::String(_)) if keep(rules) => Some(&**rules),
+            _ => None
+        };
+        self.collect_leaves(value, path, depth, &pick, out)
+    }
+
+    /// Finds the values of `value` for which `pick` returns something given the schema that checks them, values are not
+    /// checked yet so alternatives use the first option that matches or else the first option of the same type
+    pub(crate) fn collect_leaves<'s, T>(&'s self, value: &Value, path: &mut SchemaPath, depth: usize,
+        pick: &impl Fn(&'s TomlSchema, &Value) -> Option<T>, out: &mut Vec<(SchemaPath, T)>)
+    {
+        let Some(depth) = depth.checked_sub(1) else {return};
+        if let Some(found) = pick(self, value) {
+            out.push((path.clone(), found));
+            return
+        }
+
+        match (self, value) {
+            (TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. }, value) => {
+                schema.collect_leaves(value, path, depth, pick, out);
+            },
+            (TomlSchema::Alternative(options), value) => {
+                let option = options.matching(value, depth).map(|i| &options[i])
+                    .or_else(|| options.iter().find(|opt| SchemaType::from(*opt) == SchemaType::from(value)));
+                if let Some(option) = option {
+                    option.collect_leaves(value, path, depth, pick, out);
+                }
+            },
+            (TomlSchema::Array { cond, .. }, Value::Array(arr)) => {
+                for (i, value) in arr.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    cond.collect_leaves(value, path, depth, pick, out);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Map(map_schema), Value::Table(table)) => {
+                let MapSchema { value: child, .. } = &**map_schema;
+                for (key, value) in table {
+                    path.push(PathSegment::Key(key.clone()));
+                    child.collect_leaves(value, path, depth, pick, out);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Table(table_schema), Value::Table(table)) => {
+                let TableSchema { entries, extras, policy, .. } = &**table_schema;
+                for (key, value) in table {
+                    let schema = match entries.get(key) {
+                        Some((schema, _)) => Some(schema),
+                        None => policy.candidates(extras, key)
+                            .find(|e| e.key_type.accepts(key) && e.value.check_depth(value, depth).is_ok())
+                            .map(|e| &e.value)
+                    };
+                    if let Some(schema) = schema {
+                        path.push(PathSegment::Key(key.clone()));
+                        schema.collect_leaves(value, path, depth, pick, out);
+                        path.pop();
+                    }
+                }
+            },
+            _ => ()
+        }
+    }
+
+    /// Replaces the placeholders of the strings of `doc` that were filled from defaults (at `filled`) or that have
+    /// `interpolate`, the error is located at the string that could not be interpolated
+    pub(crate) fn interpolate(&self, doc: &mut Value, filled: &[SchemaPath]) -> Result<(), (SchemaPath, SchemaError<'static, 'static>)> {
+        let mut found = Vec::new();
+        self.collect_string_rules(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &|rules| rules.interpolate, &mut found);
+        let mut targets: Vec<SchemaPath> = found.into_iter().map(|(path, _)| path).collect();
+        for path in filled {
+            if let Some(value) = path.value_in(doc) {
+                collect_strings(value, &mut path.clone(), &mut targets);
+            }
+        }
+        if targets.is_empty() {
+            return Ok(())
+        }
+
+        let mut resolver = Resolver { doc, targets: targets.iter().cloned().collect(), resolved: HashMap::new(), stack: Vec::new() };
+        for path in &targets {
+            resolver.resolve(path)?;
+        }
+        let resolved = resolver.resolved;
+
+        for (path, s) in resolved {
+            if let Some(value) = path.value_in_mut(doc) {
+                *value = Value::String(s);
+            }
+        }
+        Ok(())
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        std::env::set_var("TOML_SCHEMA_TEST_USER", "alice");
+        std::env::remove_var("TOML_SCHEMA_TEST_UNSET");
+
+        let schema: TomlSchema = "
+            host = {type = 'string', default = 'localhost'}
+            port = {type = 'int', default = 8080}
+            url = {type = 'string', regex = '^http://', default = 'http://${host}:${port}/'}
+            home = {type = 'string', default = '/home/${ENV:TOML_SCHEMA_TEST_USER}'}
+            price = {type = 'string', default = '$${literal} $5'}
+            log = {type = 'string', interpolate = true, default = ''}
+            raw = {type = 'string', default = ''}
+        ".parse().unwrap();
+        assert_eq!(schema.to_string(), r#"table { home: string = "/home/${ENV:TOML_SCHEMA_TEST_USER}", host: string = "localhost", log: string interpolated = "", port: int = 8080, price: string = "$${literal} $5", raw: string = "", url: string /^http:/// = "http://${host}:${port}/" }"#);
+
+        let mut doc = Value::Table("host = 'example.com'\nlog = '${home}/log'\nraw = '${host}'".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["url"].as_str(), Some("http://example.com:8080/"));
+        assert_eq!(doc["home"].as_str(), Some("/home/alice"));
+        assert_eq!(doc["price"].as_str(), Some("${literal} $5"));
+        // strings of the document only with `interpolate`, they can use interpolated defaults
+        assert_eq!(doc["log"].as_str(), Some("/home/alice/log"));
+        assert_eq!(doc["raw"].as_str(), Some("${host}"));
+
+        for (bad, placeholder) in [
+            ("log = '${a}'\na = '${log}'", "a"),
+            ("log = '${log}'", "log"),
+            ("log = '${missing}'", "missing"),
+            ("log = '${ENV:TOML_SCHEMA_TEST_UNSET}'", "ENV:TOML_SCHEMA_TEST_UNSET"),
+            ("log = '${open'", "open"),
+        ] {
+            let schema: TomlSchema = "
+                log = {type = 'string', interpolate = true}
+                a = {type = 'string', interpolate = true, default = ''}
+            ".parse().unwrap();
+            match schema.check_and_complete(&mut Value::Table(bad.parse().unwrap())) {
+                Err(SchemaError::AtKey { error, .. }) => match *error {
+                    SchemaError::InterpolationMiss { placeholder: p, .. } => assert_eq!(p, placeholder, "{}", bad),
+                    other => panic!("expected an interpolation error for {} but got {:?}", bad, other)
+                },
+                other => panic!("expected an interpolation error for {} but got {:?}", bad, other)
+            }
+        }
+
+        // `check` does not interpolate
+        schema.check(&Value::Table("log = '${missing}'".parse().unwrap())).unwrap();
+    }
+}
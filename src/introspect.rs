@@ -0,0 +1,114 @@
+//! Read-only queries on compiled schemas, for tools that need to know what a schema accepts (documentation, editors,
+//! form generators) without matching on [TomlSchema], whose variants change between versions
+
+use crate::*;
+
+
+/// What one schema node accepts, see [TomlSchema::info], the fields that do not apply to the type of the node are empty
+///
+/// wrappers (extensions, custom validators, defaults from the environment, severities) are looked through, new fields
+/// may be added so the struct cannot be built outside of the crate
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SchemaInfo<'s> {
+    pub schema_type: SchemaType,
+    /// The bounds of an int
+    pub int_range: Option<(i64, i64)>,
+    /// The bounds of a float
+    pub float_range: Option<(f64, f64)>,
+    /// The bounds on the length of an array, the number of entries of a map or the number of extras of a table
+    pub count: Option<(usize, usize)>,
+    /// The regex of a string or of the keys of a map
+    pub regex: Option<&'s str>,
+    /// The schema of the elements of an array or of the values of a map
+    pub child: Option<&'s TomlSchema>,
+    /// The options of an alternative
+    pub options: &'s [TomlSchema],
+    /// The values an exact schema accepts
+    pub values: Vec<&'s Value>,
+    /// The keys of a table without a default, in the order of the schema
+    pub required_keys: Vec<&'s str>,
+    /// The keys of a table with a default
+    pub optional_keys: Vec<&'s str>,
+}
+
+impl TomlSchema {
+    /// The details of this schema node, see [SchemaInfo]
+    pub fn info(&self) -> SchemaInfo<'_> {
+        let schema = self.structure();
+        let mut info = SchemaInfo {
+            schema_type: SchemaType::from(schema), int_range: None, float_range: None, count: None, regex: None,
+            child: None, options: &[], values: Vec::new(), required_keys: Vec::new(), optional_keys: Vec::new()
+        };
+        match schema {
+            TomlSchema::Integer { min, max, .. } => info.int_range = Some((*min, *max)),
+            TomlSchema::Float { min, max, .. } => info.float_range = Some((*min, *max)),
+            TomlSchema::String { regex, .. } => info.regex = Some(regex.as_str()),
+            TomlSchema::Array { cond, min, max, .. } => {
+                info.count = Some((*min, *max));
+                info.child = Some(cond);
+            },
+            TomlSchema::Map(map) => {
+                info.count = Some((map.min, map.max));
+                info.regex = Some(map.key.as_str());
+                info.child = Some(&map.value);
+            },
+            TomlSchema::Table(table) => {
+                info.count = Some((table.min, table.max));
+                for (key, (_, default)) in table.entries.iter() {
+                    match default {
+                        None => info.required_keys.push(key),
+                        Some(_) => info.optional_keys.push(key)
+                    }
+                }
+            },
+            TomlSchema::Alternative(options) => info.options = options.options(),
+            TomlSchema::Exact { value, .. } => info.values.push(value),
+            TomlSchema::Enum(values) => info.values.extend(values.iter()),
+            _ => ()
+        }
+        info
+    }
+
+    /// The keys of a table schema that must be in the documents, empty for other schemas
+    pub fn required_keys(&self) -> Vec<&str> {
+        self.info().required_keys
+    }
+
+    /// The keys of a table schema that have a default, empty for other schemas
+    pub fn optional_keys(&self) -> Vec<&str> {
+        self.info().optional_keys
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_info() {
+        let schema: TomlSchema = "
+            name = {type = 'string', regex = '^[a-z]+$', x-doc = 'the name'}
+            port = {type = 'int', min = 1, max = 65535, default = 80}
+            hosts = {type = 'array', child = 'string', max = 3}
+            mode = {type = 'exact', values = ['fast', 'slow'], default = 'fast'}
+            either = {type = 'alternative', options = ['int', 'bool']}
+        ".parse().unwrap();
+
+        assert_eq!(schema.required_keys(), ["either", "hosts", "name"]);
+        assert_eq!(schema.optional_keys(), ["mode", "port"]);
+
+        let field = |key: &str| schema.resolve_path(&key.parse().unwrap())[0].0;
+        assert_eq!(field("name").info().regex, Some("^[a-z]+$"));
+        assert_eq!(field("port").info().int_range, Some((1, 65535)));
+        let hosts = field("hosts").info();
+        assert_eq!(hosts.count, Some((0, 3)));
+        assert_eq!(hosts.child.map(SchemaType::from), Some(SchemaType::String));
+        assert_eq!(field("mode").info().values.len(), 2);
+        assert_eq!(field("either").info().options.len(), 2);
+        assert!(field("port").required_keys().is_empty());
+    }
+}
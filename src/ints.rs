@@ -0,0 +1,78 @@
+//! Fixed-width ints, `bits = 8` and `allow_negative = false` limit an int schema to the values of a `u8`, see [IntWidth]
+
+use std::fmt;
+
+
+/// The width of the ints of a schema, from `bits` and `allow_negative`, errors for values out of its range say which
+/// width they do not fit in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntWidth {
+    pub bits: u8,
+    pub signed: bool,
+}
+
+impl IntWidth {
+    pub(crate) const BITS: [i64; 4] = [8, 16, 32, 64];
+
+    /// The smallest and largest values of this width, unsigned 64-bit ints stop at [i64::MAX] like all TOML ints
+    pub fn range(self) -> (i64, i64) {
+        match (self.bits, self.signed) {
+            (64, true) => (i64::MIN, i64::MAX),
+            (64, false) => (0, i64::MAX),
+            (bits, true) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+            (bits, false) => (0, (1 << bits) - 1),
+        }
+    }
+
+    pub fn contains(self, i: i64) -> bool {
+        let (min, max) = self.range();
+        i >= min && i <= max
+    }
+}
+
+impl fmt::Display for IntWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.signed {"i"} else {"u"}, self.bits)
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn int_widths() {
+        assert_eq!(IntWidth { bits: 8, signed: false }.range(), (0, 255));
+        assert_eq!(IntWidth { bits: 16, signed: true }.range(), (i16::MIN as i64, i16::MAX as i64));
+        assert_eq!(IntWidth { bits: 32, signed: false }.range(), (0, u32::MAX as i64));
+
+        let schema: TomlSchema = "
+            flags = {type = 'int', bits = 8, allow_negative = false}
+            offset = {type = 'int', bits = 16, min = -10}
+            count = {type = 'int', allow_negative = false}
+        ".parse().unwrap();
+        schema.check_str("flags = 255\noffset = 32767\ncount = 0").unwrap();
+        let e = schema.check_str("flags = 256\noffset = 0\ncount = 0").unwrap_err();
+        assert!(e.contains("Int 256 does not fit in a u8 [0,255]"), "{}", e);
+        let e = schema.check_str("flags = 0\noffset = -11\ncount = 0").unwrap_err();
+        assert!(e.contains("Int -11 does not match [-10,32767]"), "{}", e);
+        let e = schema.check_str("flags = 0\noffset = 0\ncount = -1").unwrap_err();
+        assert!(e.contains("Int -1 does not match [0,"), "{}", e);
+        assert!(schema.to_string().contains("int u8"), "{}", schema);
+
+        for (invalid, error) in [
+            ("x = {type = 'int', bits = 12}", "bits must be 8, 16, 32 or 64 but got 12"),
+            ("x = {type = 'int', allow_negative = 'no'}", "allow_negative must be a boolean"),
+            ("x = {type = 'int', bits = 8, min = 300}", "Int min 300 does not fit in a i8"),
+            ("x = {type = 'int', bits = 16, allow_negative = false, max = -1}", "Int max -1 does not fit in a u16"),
+            ("x = {type = 'int', min = 5, max = 4}", "Int min 5 is larger than max 4"),
+            ("x = {type = 'port', allow_privileged = false, max = 80}", "Int min 1024 is larger than max 80"),
+        ] {
+            let e = invalid.parse::<TomlSchema>().unwrap_err();
+            assert!(e.contains(error), "{}", e);
+        }
+    }
+}
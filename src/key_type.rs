@@ -0,0 +1,229 @@
+use std::fmt;
+use std::str::FromStr;
+use toml::value::Datetime;
+use crate::*;
+
+
+/// What the keys of a `map` or of a table extra must parse as, from the `key_type` key
+///
+/// it is written as a type name (`key_type = "int"`) or as a table with bounds for ints
+/// (`key_type = {type = "int", min = 1, max = 65535}`), keys are always strings in TOML so this is checked on top
+/// of the key regex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyType {
+    /// Any key, the default
+    #[default]
+    String,
+    /// A decimal integer in `[min, max]`
+    Integer{min: i64, max: i64},
+    /// A TOML date, time or date-time
+    Date,
+    /// A semantic version like `1.0.0-alpha+build`
+    Semver,
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyType::String => f.write_str("string"),
+            KeyType::Integer { min: i64::MIN, max: i64::MAX } => f.write_str("int"),
+            KeyType::Integer { min, max } => write!(f, "int [{}..{}]", min, max),
+            KeyType::Date => f.write_str("date"),
+            KeyType::Semver => f.write_str("semver"),
+        }
+    }
+}
+
+impl KeyType {
+    /// Parses the value of a `key_type` key
+    pub(crate) fn from_value(value: &Value) -> Result<KeyType, String> {
+        let (name, options) = match value {
+            Value::String(s) => (s.as_str(), None),
+            Value::Table(t) => match t.get("type") {
+                Some(Value::String(s)) => (s.as_str(), Some(t)),
+                other => return Err(format!("key_type must have a type name but got {:?}", other))
+            },
+            _ => return Err(format!("key_type must be a type name or a table but got {:?}", value))
+        };
+
+        let mut key_type = match name {
+            "string" => KeyType::String,
+            "int" => KeyType::Integer { min: i64::MIN, max: i64::MAX },
+            "date" => KeyType::Date,
+            "semver" => KeyType::Semver,
+            _ => return Err(format!("Invalid key type {}", name))
+        };
+        for (k, v) in options.into_iter().flatten() {
+            match (&mut key_type, k.as_str(), v) {
+                (_, "type", _) => (),
+                (KeyType::Integer { min, .. }, "min", Value::Integer(i)) => *min = *i,
+                (KeyType::Integer { max, .. }, "max", Value::Integer(i)) => *max = *i,
+                _ => return Err(format!("Invalid option {} = {} for {} keys", k, v, name))
+            }
+        }
+        Ok(key_type)
+    }
+
+    /// Whether `key` parses as this type
+    pub fn accepts(&self, key: &str) -> bool {
+        match self {
+            KeyType::String => true,
+            // `i64::from_str` also accepts a leading '+', keys are plain digits
+            KeyType::Integer { min, max } => {
+                !key.starts_with('+') && key.parse::<i64>().is_ok_and(|i| i >= *min && i <= *max)
+            },
+            KeyType::Date => Datetime::from_str(key).is_ok(),
+            KeyType::Semver => is_semver(key),
+        }
+    }
+}
+
+/// A naming convention for the keys of a table that are not entries, from the `key_case` key of table schemas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    /// `lower_case_with_underscores`
+    Snake,
+    /// `lower-case-with-dashes`
+    Kebab,
+    /// `lowerCamelCase`
+    Camel,
+}
+
+impl KeyCase {
+    /// The name of this convention in the `key_case` key
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyCase::Snake => "snake",
+            KeyCase::Kebab => "kebab",
+            KeyCase::Camel => "camel",
+        }
+    }
+
+    /// Whether `key` follows this convention, keys start with a lowercase letter and digits are allowed after it
+    pub fn accepts(&self, key: &str) -> bool {
+        let separated = |sep: char| key.split(sep).all(|word| !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()));
+        key.starts_with(|c: char| c.is_ascii_lowercase()) && match self {
+            KeyCase::Snake => separated('_'),
+            KeyCase::Kebab => separated('-'),
+            KeyCase::Camel => key.bytes().all(|b| b.is_ascii_alphanumeric()),
+        }
+    }
+}
+
+impl TryFrom<&str> for KeyCase {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "snake" => Ok(KeyCase::Snake),
+            "kebab" => Ok(KeyCase::Kebab),
+            "camel" => Ok(KeyCase::Camel),
+            _ => Err(format!("Invalid key_case {}, expected snake, kebab or camel", value))
+        }
+    }
+}
+
+/// Checks the `major.minor.patch[-pre][+build]` syntax of semantic versions 2.0
+fn is_semver(s: &str) -> bool {
+    let (rest, build) = match s.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (s, None)
+    };
+    let (core, pre) = match rest.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (rest, None)
+    };
+
+    let numeric = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()) && (p == "0" || !p.starts_with('0'));
+    let identifier = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3 && parts.iter().all(|p| numeric(p))
+        && pre.is_none_or(|pre| pre.split('.').all(|p| identifier(p) && (!p.bytes().all(|b| b.is_ascii_digit()) || numeric(p))))
+        && build.is_none_or(|build| build.split('.').all(identifier))
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_types() {
+        let table = |s: &str| Value::Table(s.parse().unwrap());
+        let ports = KeyType::from_value(&table("type = 'int'\nmin = 1\nmax = 65535")).unwrap();
+        assert!(ports.accepts("8080"));
+        assert!(!ports.accepts("80800") && !ports.accepts("0") && !ports.accepts("+80") && !ports.accepts("http"));
+        assert_eq!(ports.to_string(), "int [1..65535]");
+
+        let dates = KeyType::from_value(&Value::String("date".into())).unwrap();
+        assert!(dates.accepts("2024-01-31") && dates.accepts("2024-01-31T10:00:00Z") && !dates.accepts("2024-13-01"));
+
+        let versions = KeyType::from_value(&Value::String("semver".into())).unwrap();
+        for ok in ["0.1.0", "1.0.0-alpha.1", "1.0.0+build.5", "1.0.0-rc-1+001"] {
+            assert!(versions.accepts(ok), "{}", ok);
+        }
+        for bad in ["1.0", "01.0.0", "1.0.0-", "1.0.0-01", "1.0.0+", "v1.0.0"] {
+            assert!(!versions.accepts(bad), "{}", bad);
+        }
+
+        KeyType::from_value(&Value::String("float".into())).unwrap_err();
+        KeyType::from_value(&table("type = 'date'\nmin = 1")).unwrap_err();
+    }
+
+    #[test]
+    fn key_cases() {
+        for (case, ok, bad) in [
+            (KeyCase::Snake, ["log_level", "v2", "a_1"], ["LogLevel", "log-level", "_log", "log__level", "log_"]),
+            (KeyCase::Kebab, ["log-level", "v2", "a-1"], ["log_level", "Log-level", "-log", "log--level", "log-"]),
+            (KeyCase::Camel, ["logLevel", "v2", "httpURL"], ["LogLevel", "log_level", "log-level", "2fa", ""]),
+        ] {
+            for key in ok {assert!(case.accepts(key), "{} {}", key, case.as_str());}
+            for key in bad {assert!(!case.accepts(key), "{} {}", key, case.as_str());}
+        }
+
+        let schema: TomlSchema = "
+            name = {type = 'string', default = ''}
+            extras = [{key = '.*', schema = 'anything'}]
+            key_case = 'kebab'
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        // entries are named by the schema and are not checked
+        schema.check(&doc("name = 'a'\nlog-level = 1")).unwrap();
+        assert_eq!(schema.check(&doc("log_level = 1")), Err(SchemaError::KeyCaseMiss { key: "log_level", expected: KeyCase::Kebab }));
+        assert_eq!(schema.validate(&doc("logLevel = 1\nLOG = 2\nok-key = 3")).errors.len(), 2);
+        assert_eq!(schema.to_string(), "table { name: string = \"\", /.*/: anything, extras kebab_case }");
+        "key_case = 'pascal'".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn typed_keys() {
+        let schema: TomlSchema = "
+            [listeners]
+            type = 'map'
+            key_type = {type = 'int', min = 1, max = 65535}
+            value = {protocol = 'string'}
+
+            [releases]
+            extras = [{key = '.*', key_type = 'semver', schema = 'date'}]
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("
+            listeners.8080.protocol = 'http'
+            listeners.443.protocol = 'https'
+            releases.'1.0.0' = 2024-01-01
+        ")).unwrap();
+
+        match schema.check(&doc("listeners.80800.protocol = 'http'")) {
+            Err(SchemaError::AtKey { error, .. }) => {
+                assert!(matches!(*error, SchemaError::KeyTypeMiss { key: "80800", .. }), "{:?}", error);
+            },
+            other => panic!("expected a key type error but got {:?}", other)
+        }
+        schema.check(&doc("releases.latest = 2024-01-01")).unwrap_err();
+        assert_eq!(schema.validate(&doc("listeners.http = {protocol = 'http'}\nreleases.latest = 2024-01-01")).errors.len(), 2);
+    }
+}
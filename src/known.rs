@@ -0,0 +1,158 @@
+//! Ready-made schemas for well-known TOML formats, enabled with the `schemas` feature
+//!
+//! Each format has a `*_SOURCE` constant containing the schema as TOML text and a loader function that
+//! returns the compiled schema, the schema is only compiled once and shared by all callers
+//!
+//! ```ignore
+//! let manifest = std::fs::read_to_string("Cargo.toml").unwrap().parse().unwrap();
+//! toml_schema::known::cargo().check(&manifest).unwrap();
+//! ```
+
+use std::sync::OnceLock;
+use crate::*;
+
+
+/// The source of the schema for `Cargo.toml` package manifests
+pub const CARGO_SOURCE: &str = include_str!("../schemas/cargo.toml");
+
+/// The source of the schema for `pyproject.toml` files
+pub const PYPROJECT_SOURCE: &str = include_str!("../schemas/pyproject.toml");
+
+/// The source of the schema for `rustfmt.toml` files
+pub const RUSTFMT_SOURCE: &str = include_str!("../schemas/rustfmt.toml");
+
+/// The source of the schema for `rust-toolchain.toml` files
+pub const RUST_TOOLCHAIN_SOURCE: &str = include_str!("../schemas/rust_toolchain.toml");
+
+
+/// Compiles a bundled schema, they are tested so this should never fail
+fn load(cell: &'static OnceLock<TomlSchema>, source: &str) -> &'static TomlSchema {
+    cell.get_or_init(|| {
+        let table = source.parse::<toml::Table>().expect("bundled schema is not valid TOML");
+        TomlSchema::try_from(table).expect("bundled schema is not a valid schema")
+    })
+}
+
+/// The schema for `Cargo.toml` package manifests (virtual workspace manifests are not supported)
+pub fn cargo() -> &'static TomlSchema {
+    static CELL: OnceLock<TomlSchema> = OnceLock::new();
+    load(&CELL, CARGO_SOURCE)
+}
+
+/// The schema for `pyproject.toml` files, the `[tool]` table accepts anything
+pub fn pyproject() -> &'static TomlSchema {
+    static CELL: OnceLock<TomlSchema> = OnceLock::new();
+    load(&CELL, PYPROJECT_SOURCE)
+}
+
+/// The schema for `rustfmt.toml` files, stable options are fully checked and unstable ones only by type
+pub fn rustfmt() -> &'static TomlSchema {
+    static CELL: OnceLock<TomlSchema> = OnceLock::new();
+    load(&CELL, RUSTFMT_SOURCE)
+}
+
+/// The schema for `rust-toolchain.toml` files
+pub fn rust_toolchain() -> &'static TomlSchema {
+    static CELL: OnceLock<TomlSchema> = OnceLock::new();
+    load(&CELL, RUST_TOOLCHAIN_SOURCE)
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(s: &str) -> Value {
+        Value::Table(s.parse().unwrap())
+    }
+
+    #[test]
+    fn known_cargo() {
+        let manifest = doc(&std::fs::read_to_string("Cargo.toml").unwrap());
+        cargo().check(&manifest).unwrap();
+
+        let mut inherited = doc("
+            [package]
+            name = 'member'
+            version.workspace = true
+            edition.workspace = true
+            authors = ['someone']
+            [features]
+            default = ['std']
+            std = []
+            [[bin]]
+            name = 'tool'
+            path = 'src/main.rs'
+            [build-dependencies]
+            cc = '1.0'
+        ");
+        cargo().check_and_complete(&mut inherited).unwrap();
+
+        cargo().check(&doc("
+            [package]
+            name = 'bad name'
+        ")).unwrap_err();
+    }
+
+    #[test]
+    fn known_pyproject() {
+        let mut project = doc("
+            [build-system]
+            requires = ['setuptools>=61']
+            build-backend = 'setuptools.build_meta'
+
+            [project]
+            name = 'my-package'
+            version = '0.1.0'
+            readme = 'README.md'
+            requires-python = '>=3.8'
+            authors = [{name = 'A. Person', email = 'a@example.com'}]
+            dependencies = ['requests>=2']
+            license = {text = 'MIT'}
+
+            [project.optional-dependencies]
+            test = ['pytest']
+
+            [tool.black]
+            line-length = 88
+        ");
+        pyproject().check_and_complete(&mut project).unwrap();
+
+        pyproject().check(&doc("
+            [project]
+            name = 'my-package'
+            dependencies = 'requests'
+        ")).unwrap_err();
+    }
+
+    #[test]
+    fn known_rustfmt() {
+        let mut config = doc("
+            edition = '2021'
+            max_width = 120
+            imports_granularity = 'Crate'
+        ");
+        rustfmt().check_and_complete(&mut config).unwrap();
+
+        rustfmt().check(&doc("max_widht = 120")).unwrap_err();
+        rustfmt().check(&doc("newline_style = 'Mac'")).unwrap_err();
+    }
+
+    #[test]
+    fn known_rust_toolchain() {
+        let mut config = doc("
+            [toolchain]
+            channel = 'nightly-2024-01-01'
+            components = ['rustfmt', 'clippy']
+        ");
+        rust_toolchain().check_and_complete(&mut config).unwrap();
+
+        rust_toolchain().check(&doc("
+            [toolchain]
+            channel = 'nightly'
+            unknown = true
+        ")).unwrap_err();
+    }
+}
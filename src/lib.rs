@@ -3,7 +3,7 @@
 //! This crates aims to provide something similar to [JSON schemas](https://json-schema.org/understanding-json-schema/about) for TOML
 //! - Schemas are written in TOML
 //! - All TOML types are currently supported
-//! - References (and recursive schemas) are not yet supported
+//! - References to registered schemas are supported, recursive schemas are not yet
 //! 
 //! This crate is very much new and a lot of functionnalities are not fully tested
 //! 
@@ -18,58 +18,246 @@
 //! - `date` : a date
 //! - `array` : an array of values that all match a specific schema
 //! - `table` : a TOML table with specific keys
+//! - `table_array` : an array of tables, a shorthand for `array` with a `table` child
+//! - `map` : a TOML table where all keys match a regex and all values match one schema
 //! - `alternative` : an OR operation on sub-patterns
+//! - `anything` : any TOML value
+//! - `exact` : exactly one TOML value
+//! - `reference` : a schema registered by name, a shorthand for a copy of that schema
 //! 
 //! If the parser expects a schema but finds not `type` key, it will assume the type is `table`, but if the type is none of
 //! the above, parsing will fail
 //! 
+//! Where a schema is expected, a type name alone is a shorthand for a schema with no other keys, `port = "int"` is the
+//! same as `port = {type = "int"}` and `child = "string"` the same as `child = {type = "string"}`
+//! 
+//! `type` may also be a list of type names, `{type = ["int", "float"]}` is an `alternative` of these types and only
+//! accepts a `default` key
+//! 
+//! A schema with `extends = "name"` has the keys of a schema registered under that name in `ParseOptions::definitions`
+//! with its own keys on top, they replace the keys of the definition with the same name and `remove = ["key", ..]` takes
+//! keys out of the definition, `{extends = "service", port = {type = "int", default = 443}, remove = ["debug"]}`
+//! 
 //! For each type of schema there, are other keys that are either required of optional to give more details
 //! about the schema
 //! 
 //! A `default` key may also be provided when the schema is the value of a key in a `table` schema
-//! to make that key optional, `default` will be ignored in other positions
+//! to make that key optional, `default` will be ignored in other positions, [TomlSchema::check_and_complete] fills the
+//! missing keys of every table of the document, including the tables in arrays, maps, values matched by `extras` and the
+//! option of an `alternative` that matched the value
+//! 
+//! `default_env = "PORT"` in the same positions fills a missing key from the environment variable `PORT` in
+//! [TomlSchema::check_and_complete], it is read as a TOML value (`8080`, `true`, `[1, 2]`) unless the schema is a `string`,
+//! `default` is used when the variable is not set, [TomlSchema::check] does not read the environment
+//! 
+//! `default_merge` (default = `"replace"`) decides what happens to a table default when the document has the key,
+//! with `"merge"` the keys of the default that are missing from the document table are added, nested tables are merged
+//! the same way and values of the document always win
+//! 
+//! Any extra keys will be ignored (except in `table`), with a warning, schemas parsed with [TomlSchema::parse_strict] or
+//! `TomlSchema::from_table_strict` reject them instead to catch misspelled options
+//! 
+//! The format of schema files is itself a schema, [meta_schema] (its source is `META_SCHEMA_SOURCE`), strict parsing
+//! checks each node of a schema against it, so a wrong value like `min = "1"` or `merge = "appnd"` is reported with its
+//! location, editors and CI can also check schema files with it directly, it does not know the types of plugins
+//! 
+//! Defaults are checked while parsing, a default that its schema rejects (wrong type, out of bounds, regex miss) is a
+//! [SchemaParseWarning::InvalidDefault] and a default where it is never used (array children, alternative options,
+//! extras, the root of a [Validator]) is a [SchemaParseWarning::IgnoredDefault], defaults with placeholders are not checked
 //! 
-//! Any extra keys will be ignored (except in `table`
+//! Keys starting with `x-` are extensions, they are allowed in any schema and kept as metadata for other tools
+//! (see [TomlSchema::extensions]), they never change what the schema matches
+//! 
+//! A `severity` key (default = `"error"`) in any schema can lower the errors of the schema and of the values below it to
+//! `"warning"` or `"info"`, [TomlSchema::check] accepts such values and [TomlSchema::validate] reports them with their
+//! severity ([ReportedError::severity]) without failing the report, to announce a stricter schema before enforcing it
+//! 
+//! A `custom` key in any schema names a native validator, it runs on values that matched the rest of the schema,
+//! validators are registered in a [CustomValidators] given to the parser with `ParseOptions`, validators registered with
+//! `register_with_context` also get a [ValidationContext] with the location of the value, the whole document and a state
+//! given to the `Validator`, like a list of installed plugins to cross-check names against
+//! 
+//! Whole new types can also be added, a `PluginType` registered in `ParseOptions::plugins` parses its own keys and
+//! checks values, schemas then use it like a built-in type (`color = {type = "color", alpha = true}`)
 //! 
 //! ### string
 //! - `regex` (optional, default = `/.*/`) : a regular expression that must be found in the string, if you want to match the whole string,
-//! use '^' and '$'
+//!   use '^' and '$' or `full_match`
+//! - `not_regex` (optional) : a regular expression that must not be found in the string, `not_regex = '\s'` rejects whitespace
+//! - `starts_with`, `ends_with`, `contains` (optional) : literal text that the string must start with, end with or contain,
+//!   they are simpler than a regex and each fails with its own error
+//! - `full_match` (optional, default = `false`) : if this is true, the regex must match the whole string instead of a part of it
+//! - `case_insensitive`, `multiline`, `dot_matches_newline` (optional, default = `false`) : flags of the regex, like `(?i)`, `(?m)`
+//!   and `(?s)`, these options apply to `regex` and `not_regex`
+//! - `refers_to` (optional) : a path in the document like `profiles.*` or `listeners[].name`, the string must be one of the keys
+//!   matched by a final `*` or one of the strings at the path, references are checked once the rest of the document matches
+//! - `interpolate` (optional, default = `false`) : placeholders in the string are replaced by [TomlSchema::check_and_complete]
+//! - `trim` (optional, default = `false`) : surrounding whitespace is ignored by the checks and removed by
+//!   [TomlSchema::check_and_complete]
+//! - `normalize` (optional) : `"nfc"`, `"nfd"`, `"nfkc"` or `"nfkd"`, the string is checked in this unicode normal form and
+//!   converted to it by [TomlSchema::check_and_complete], this requires the `unicode` feature
+//! - `format` (optional) : the name of one of the string types below, like `"hex"`, `type = "string", format = "hex"` is the
+//!   same as `type = "hex"`
+//! 
+//! Strings of defaults filled by [TomlSchema::check_and_complete], and strings with `interpolate`, may contain placeholders,
+//! `${server.host}` is replaced by the value at this path of the document (a string, number, bool or date) and
+//! `${ENV:HOME}` by an environment variable, `$${` is a literal `${`, placeholders can point to other interpolated strings
+//! but not form a cycle, failures are [SchemaError::InterpolationMiss] errors
+//! 
+//! ### glob
+//! A string that must be a well-formed glob pattern like `src/**/*.rs`, with `*`, `?`, `**`, `[a-z]` classes and `{a,b}`
+//! alternations, unclosed classes and alternations, reversed ranges and `**` inside a path component fail with a
+//! [SchemaError::FormatMiss] that says where, it takes the keys of `string` and
+//! - `base` (optional) : the directory the pattern is relative to, absolute patterns and patterns that go up out of it
+//!   with `..` are rejected
+//! 
+//! ### cron
+//! A string that must be a cron expression like `*/15 9-17 * * MON-FRI`, with five fields (minute, hour, day of month, month,
+//! day of week), six with seconds first, or a macro like `@daily`, each field is `*`, a value, a range or a list of them with
+//! an optional step `/n`, months and days of the week can be names, errors name the field that is invalid, it takes the keys
+//! of `string`, this requires the `cron` feature
+//! 
+//! ### socket_addr, cidr
+//! Strings that must be network addresses, `socket_addr` an IP address and a port like `127.0.0.1:8080` or `[::1]:8080`
+//! (host names are rejected) and `cidr` an IP network like `10.0.0.0/8` whose address has no bits set after the prefix,
+//! they take the keys of `string`
+//! 
+//! ### base64, hex
+//! Strings that must be encoded bytes, `base64` with the standard or the URL-safe alphabet and with or without padding, `hex`
+//! with two digits per byte, they take the keys of `string` and
+//! - `min_bytes`, `max_bytes` (optional) : bounds on the number of decoded bytes, `min_bytes = 32, max_bytes = 32` for a
+//!   256-bit key
+//! 
+//! ### language_tag, country_code
+//! Strings that must be locales, `language_tag` a BCP 47 language tag like `pt-BR` or `zh-Hant-TW` (only its syntax is
+//! checked, not that its subtags are registered) and `country_code` an assigned ISO 3166-1 alpha-2 code in upper case like
+//! `FR`, they take the keys of `string`
 //! 
 //! ### int
 //! - `min` (optional, default = [i64::MIN]) : the minmimum value allowed
 //! - `max` (optional, default = [i64::MAX]) : the maximum value allowed
+//! - `allow_negative` (optional, default = `true`) : if this is false, negative values are rejected
+//! - `bits` (optional) : `8`, `16`, `32` or `64`, the value must fit in an int of this width, signed unless `allow_negative`
+//!   is false, values that do not fit fail with [SchemaError::IntWidthMiss], like `Int 256 does not fit in a u8 [0,255]`, an explicit
+//!   `min` or `max` that does not fit is a schema error
+//! - `coerce` (optional, default = `false`) : if this is true, strings like `"8080"` are accepted when they are ints that
+//!   match, [TomlSchema::check_and_complete] rewrites them to ints, other strings fail with [SchemaError::CoerceMiss], the
+//!   string is an optional `+` or `-` and digits, without spaces or `_`
+//! 
+//! ### port
+//! An int from 0 to 65535, it takes the keys of `int` and
+//! - `allow_privileged` (optional, default = `true`) : if this is false, the ports below 1024 are rejected
 //! 
 //! ### float
 //! - `min` (optional, default = [f64::NEG_INFINITY]) : the minmimum value allowed
 //! - `max` (optional, default = [f64::INFINITY]) : the maximum value allowed
 //! - `nan_ok` (optional, default = `false`) : if this is true, [f64::NAN] is accepted
+//! - `coerce` (optional, default = `false`) : strings like `"0.5"` are accepted and rewritten to floats, like ints, the
+//!   string is an optional `+` or `-` and digits with an optional `.` (`"5."` and `".5"` are accepted) and exponent like
+//!   `"1e-3"`, or `inf`, `infinity` and `nan` in any case, without spaces or `_`
 //! 
 //! ### bool
+//! - `coerce` (optional, default = `false`) : if this is true, the strings `"true"`, `"yes"`, `"on"`, `"false"`, `"no"` and
+//!   `"off"` in any case and without spaces are accepted, [TomlSchema::check_and_complete] rewrites them to bools
 //! 
 //! ### date
+//! - `min`, `max` (optional) : the first and last dates allowed, like `min = 2024-01-01`, this requires the `chrono` feature
+//! - `require_offset` (optional, default = `false`) : only offset datetimes like `1979-05-27T07:32:00Z` are accepted, so that
+//!   timestamps are unambiguous
+//! - `local_only` (optional, default = `false`) : datetimes, dates and times must not have an offset
+//! 
+//! Two datetimes with an offset are compared as instants, when either one is local their dates and times are compared as
+//! written, a date alone is midnight at the start of the day and a time alone can only be compared to another time alone,
+//! values that cannot be compared to a bound fail with [SchemaError::DateIncomparable]
 //! 
 //! ### array
-//! - `child` (required) : a schema that all elements of this array must match
+//! - `child` (optional, default = `anything`) : a schema that all elements of this array must match
 //! - `min` (optional, default = `0`) : the minimum number of elements
 //! - `max` (optional, default = [usize::MAX]) : the maximum number of elements
+//! - `len` (optional) : the exact number of elements, instead of `min` and `max`
+//! - `len_multiple_of` (optional, default = `1`) : the number of elements must be a multiple of this, e.g. for pairs of
+//!   coordinates
+//! - `unique_by` (optional) : a key that must have a different value in every element, elements that are not tables or do
+//!   not have the key are ignored
+//! - `merge` (optional, default = `"replace"`) : with `"append"` the arrays of all layers are concatenated by [TomlSchema::merge]
+//! 
+//! ### table_array
+//! - `min_items`, `max_items`, `unique_by` and `merge` (optional) : the `min`, `max`, `unique_by` and `merge` keys of the array
+//! 
+//! All other keys are the `table` schema of the elements, `{type = "table_array", name = "string"}` is the same as
+//! `{type = "array", child = {name = "string"}}`, this is the usual shape of `[[name]]` arrays
 //! 
 //! ### table
 //! - `extras` (optional, default = `[]`) : an array of tables with a `key` and `schema` key that defines regex-based key-value pairs
 //! - `extras[n].key` (required) : a regular expression that must be found in the key 
 //! - `extras[n].schema` (required) : a schema that must be matched by the value
+//! - `extras[n].full_match`, `extras[n].case_insensitive`, ... (optional) : the same regex options as in `string`
+//! - `extras[n].key_type` (optional, default = `"string"`) : what the key must parse as, like in `map`
 //! - `min` (optional, default = `0`) : the minimum number of extra keys
-//! - `max` (optional, default = `0`) : the maximum number of extra keys
+//! - `max` (optional, default = [usize::MAX]) : the maximum number of extra keys, keys that match no entry are still rejected
+//!   when there are no `extras`
+//! - `assert` (optional, default = `[]`) : an array of [Assertion] tables like `{kind = "lte", left = "min", right = "max"}`
+//!   that compare the values of two keys, `kind` is one of `eq`, `ne`, `lt`, `lte`, `gt` or `gte`, missing keys take their
+//!   default value and assertions on keys that have no value are skipped
+//! - `extras_match` (optional, default = `"all"`) : with `"all"` a key is accepted by any extra whose key and schema match,
+//!   with `"first"` only the first extra whose key regex matches is tried, like a `match` on the key
+//! - `count_entries` (optional, default = `false`) : keys with an entry also count towards `min` and `max` when an extra
+//!   matches them, their value is still only checked against the entry
+//! - `key_case` (optional) : `"snake"`, `"kebab"` or `"camel"`, keys that are not entries must follow this naming convention,
+//!   offenders fail with [SchemaError::KeyCaseMiss]
+//! - `when` and `require` (optional) : `when = {key = "mode", equals = "file"}` with `require = ["path"]` makes keys required
+//!   when another key has a value, `when` can also be an array of conditions that each have their own `require` key
 //! 
-//! All other keys must be schemas, they defined a table key (optional if `default` is provided in this schema) that must match
-//! the schema, a '$' is stripped from the beginning of the key if it exists to allow escaping schema keywords, if you want a key
+//! All other keys must be schemas, they defined a table key (optional if `default` is provided in this schema, missing keys
+//! without a default fail with [SchemaError::MissingKey]) that must match the schema, a '$' is stripped from the beginning of the key if it exists to allow escaping schema keywords, if you want a key
 //! that starts with '$', start your key with "$$" etc...
 //! 
-//! All keys in the TOML table beeing matched are matched against entries before extra keys, this means that if a key matches an
-//! entry and an extra, it will not count towards the number of extra keys, this means that you may want to make extra key 
-//! regular expressions mutually excusive with the table entries
+//! Keywords added after tables could have entries with their name are told apart from entries by their value:
+//! `custom`, `severity`, `extends`, `default_env`, `default_merge` and the keys that start with `x-` are entries when their
+//! value is a type name or a table (a table of schemas for `x-` keys, `extends` always extends a definition with this name),
+//! `assert`, `extras_match`, `count_entries` and `key_case` are always keywords and their value cannot look like a schema,
+//! `when` and `require` are only keywords when `when` is a condition, in every case `"$require" = ...` declares an entry
+//! 
+//! All keys in the TOML table beeing matched are matched against entries before extra keys, a key that matches an entry is
+//! never checked against the extras and only counts towards the number of extra keys with `count_entries`, extras are tried
+//! in order according to `extras_match`
+//! 
+//! ### map
+//! - `key_regex` (optional, default = `/.*/`) : a regular expression that must be found in every key
+//! - `full_match`, `case_insensitive`, ... (optional) : the same regex options as in `string`
+//! - `key_type` (optional, default = `"string"`) : `"int"`, `"date"` or `"semver"` to require keys that parse as these types,
+//!   ints can have bounds with `{type = "int", min = 1, max = 65535}`
+//! - `value` (optional, default = `anything`) : a schema that all values must match
+//! - `min` (optional, default = `0`) : the minimum number of keys
+//! - `max` (optional, default = [usize::MAX]) : the maximum number of keys
+//! 
+//! This is simpler than a `table` with a single extra, errors name the key that does not match instead of counting extras
 //! 
 //! ### alternative
 //! - `options` (required) : an array of schemas, a TOML value matches if any of them match
+//!
+//! The first option that matches is the one that counts, [TomlSchema::matched_option] tells which one it is so that
+//! applications can branch on the kind of value they got without checking it again
+//!
+//! ### anything
+//!
+//! ### exact
+//! - `value` (required) : the only value that matches, tables and arrays are compared recursively
+//! - `values` (instead of `value`) : a non-empty array of the values that match, `{type = "exact", values = ["fast", "slow"]}`
+//! - `epsilon` (optional, default = `0.0`) : with `value`, floats (also in arrays and tables) may differ from the expected
+//!   ones by this much, NaN always matches NaN, see [values_match]
+//! 
+//! [TomlSchema::normalize] simplifies the alternatives of a schema, nested alternatives are flattened, options shadowed by
+//! an `anything` are removed and adjacent `exact` options become a single one with `values`
+//! 
+//! ### reference
+//! - `schema` (required unless `url` is given) : the name of a schema of the [SchemaRegistry] given to the parser in [ParseOptions::registry]
+//! - `url` : the URL of a schema, fetched by the `SchemaFetcher` of `RemoteSchemas` in `ParseOptions::remote`, with a cache
+//!   directory and an offline mode, `file://` URLs are read without a fetcher, this requires the `remote` feature
+//! 
+//! The registered schema is copied when the reference is parsed, it must be registered first, [SchemaRegistry::register_toml]
+//! parses and registers a schema that may refer to the schemas already registered, references are copies so a
+//! schema cannot refer to itself, a cycle of references is a parse error that lists the chain of references
 //! 
 //! ## Examples
 //! 
@@ -87,47 +275,376 @@
 //! ```
 //! - you may find a basic schema for Cargo.toml files on github at "test_files/test_schema.toml"
 //! 
+//! ## Untrusted input
+//! 
+//! Schemas can be built with `ParseOptions` that limit the size of compiled regexes, and documents can be checked with
+//! `TomlSchema::check_with_max_depth` and `TomlSchema::check_with_budget` to bound the nesting depth and the time spent
+//! 
+//! ## Validation options
+//! 
+//! A `Validator` wraps a schema with the options of the validation (fail-fast or collect-all, maximum number of errors,
+//! depth, time budget, reference checks, memoization of repeated subtrees and what to do with the warnings of the schema
+//! parser) and always returns a `ValidationReport`
+//! 
+//! Existing documents can adopt a schema before they are fixed with suppressions, `Suppression::new("int-range", "legacy.*")`
+//! given to [Validator::suppress] hides the errors with this message code (see [MESSAGE_CODES]) under the matching paths,
+//! `*` matches any key and `[]` any element, [Suppression::from_value] reads a list of them kept with the documents
+//! 
+//! Documents too large to be kept in memory, or split in several files, can be validated one top-level entry at a time
+//! with [TomlSchema::entry_validator]
+//! 
+//! ## Error output
+//! 
+//! The `Debug` form of an error repeats every nested cause on one line, [SchemaError::compact] gives one `path: message`
+//! line per error for logs and [SchemaError::verbose] an indented tree of the causes, like the miss of each option of an
+//! alternative, both exist on [ValidationReport]
+//! 
+//! When a schema accepts a document it should not, [TomlSchema::check_explain] returns a [MatchTrace], the tree of the
+//! schema nodes that validated each value with the entry or extra used for each key and the option chosen by each
+//! alternative, its `Display` form has one indented `path: type (via)` line per node
+//! 
+//! ## Translated errors
+//! 
+//! Every error has a language-neutral `ErrorMessage`, a code like `int-range` with named arguments, that a
+//! `MessageCatalog` of templates (`int-range = "{value} n'est pas dans [{min}, {max}]"`) renders with [SchemaError::localized],
+//! errors without a template keep their English message
+//! 
+//! ## Introspection
+//! 
+//! [TomlSchema::info] describes a compiled schema node without matching on [TomlSchema] (type, bounds, regex, child schema
+//! of arrays and maps, options of alternatives, accepted exact values), [TomlSchema::required_keys] and
+//! [TomlSchema::optional_keys] list the keys of a table, [TomlSchema::resolve_path] finds the schema of a location
+//! 
+//! Locations are [SchemaPath]s everywhere (errors, reports, suppressions, visits, queries, completions and match traces),
+//! written `server.listeners[0].port` with `[]` for any element, `/regex/` for an extra and `|n` for an option of an
+//! alternative, the text form is read back by `FromStr` into the same path
+//! 
+//! [TomlSchema::stats] counts the nodes of each type, the deepest nesting, the regexes and roughly the memory of a schema,
+//! to find what makes a large generated schema slow
+//! 
+//! [TomlSchema], [SchemaType], [SchemaError] and [SchemaParseWarning] are `#[non_exhaustive]` so new kinds of schemas can
+//! be added in minor versions, matches on them need a `_` arm, schemas are built in code with constructors like
+//! [TomlSchema::table], [TomlSchema::int] or [TomlSchema::array] instead of the variants
+//! 
+//! ## Bundled schemas
+//! 
+//! With the `schemas` feature, the `known` module provides ready-made schemas for `Cargo.toml`, `pyproject.toml`,
+//! `rustfmt.toml` and `rust-toolchain.toml`, for example `toml_schema::known::cargo()`
+//! 
+//! ## Schema headers
+//! 
+//! A document can name its schema with a `"$schema" = "service.schema.toml"` key, like JSON documents do for editors,
+//! [validate_auto] reads a file, loads its schema and checks it, the header is the name of a schema of
+//! [ParseOptions::registry], a URL (with the `remote` feature) or a schema file next to the document, table schemas
+//! accept a string `$schema` key at the root of the document without declaring it
+//! 
+//! For editors that use Taplo (like Even Better TOML for VS Code), [EditorAssociation] writes the `#:schema` directive, the
+//! VS Code settings and the `.taplo.toml` rule that point the files of a format at its published JSON Schema
+//! 
+//! ## Layered configuration
+//! 
+//! [TomlSchema::merge] overlays documents (system, user, project...) in order, tables are merged key by key and other values of
+//! later layers replace earlier ones, except arrays with `merge = "append"`, the result is then checked and completed
+//! 
+//! Drop-in directories like `conf.d` are loaded with [ConfigDir], their `*.toml` files are merged in the order of their names
+//! the same way and each error names the file that set the wrong value
+//! 
+//! ## Versions and migrations
+//! 
+//! When a format changes, `Migrations::from_table` reads the steps from each version to the next (`rename`, `move`, `convert`
+//! and `split`) and [Migrations::migrate] upgrades old documents before they are checked against the latest schema
+//! 
+//! [VersionedSchema] holds the schemas of several versions, documents declare theirs with `schema_version = 2` (the key
+//! can be changed) and are checked against the schema of that version, unknown versions are reported with the supported ones
+//! 
+//! ## Schema caching
+//! 
+//! With the `cache` feature, a compiled schema can be stored as a compact binary blob with `TomlSchema::to_bytes`
+//! and loaded with `TomlSchema::from_bytes`, this skips parsing the schema TOML on every run
+//! 
+//! ## Value generation
+//! 
+//! With the `generate` feature, `TomlSchema::generate` creates random values that match a schema, for property-based
+//! testing of the code that reads them, and `TomlSchema::generate_invalid` creates near misses that break a single
+//! constraint, to exercise error handling and check that a schema is as tight as intended
+//! 
+//! ## Minimal builds
+//! 
+//! The regex engine is the `regex` feature, enabled by default, with `default-features = false` the crate does not depend
+//! on it and is much smaller, string schemas then only support literal patterns (`abc`, `^abc`, `abc$`, `^abc$` and
+//! `.*`), `starts_with`, `ends_with`, `contains`, lengths and `exact` values, a schema with any other regex fails to parse
+//! with an error that names the missing feature
+//! 
+//! ## Key order
+//! 
+//! Errors, filled defaults and the outline of a schema follow the order of its table entries, so the output does not
+//! change between runs, schemas parsed from TOML list their keys alphabetically and `TableEntries` built by hand keep
+//! the order they are given in
+//! 
+//! ## Schema tests
+//! 
+//! A schema `name.toml` can ship test vectors in `name.tests.toml`, an array `tests` of tables with a `value` and
+//! whether it is `valid`, `vectors::run_schema_tests` runs them and the `test_vectors` example does the same from the
+//! command line
+//! 
+//! ## Compile-time checked schemas
+//! 
+//! The companion `toml_schema_macros` crate provides `include_schema!("schema.toml")`, which checks a schema file
+//! while compiling so that invalid schemas are reported by `cargo build`, and `toml_schema!{ ... }` which does the
+//! same for a schema written in Rust code, as a TOML string or as inline table pairs (`port = {type = "int"}, ...`)
+//! 
 //! 
 //! ## Planned additions
-//! - `reference` : a link to another schema (or the schema itself)
-//! - `anything` : a schema that matches anything
-//! - `exact` : a schema that matches only one value
+//! - `wasm` : a `wasm-bindgen` layer with `compileSchema` and `validate` for editors that run in the browser, and a
+//!   `wasm32-unknown-unknown` build in CI, it will wrap the string entry points `str::parse`, [TomlSchema::check_str] and [TomlSchema::complete_str]
+//! - `rayon` : a feature that checks the elements of large arrays, the entries of wide tables and batches of files in parallel
+//! - recursive schemas : a `reference` to the schema itself, references are copies for now so a cycle is a parse error
+//! - `no_std` : checking with `alloc` only, for tools that validate configuration on embedded devices, this needs a TOML
+//!   value type that does not depend on `std` since the whole API is built on [toml::Value]
 
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::sync::Arc;
 use toml::Value;
+use toml::value::Datetime;
+#[cfg(feature = "regex")]
 use regex::Regex;
 
+mod alternative;
+mod assertion;
+mod build;
+mod coerce;
+mod completion;
+mod confdir;
 mod constructor;
+mod coverage;
+mod custom;
+mod date;
+mod definitions;
+mod defaults;
+mod display;
+mod editor;
+mod explain;
+mod extensions;
+mod extras;
+mod formats;
+mod header;
+mod interpolate;
+mod introspect;
+mod ints;
+mod key_type;
+mod memo;
+mod messages;
+mod merge;
+mod meta;
+mod migration;
+mod normalize;
 mod parse_toml;
+mod path;
+mod pattern;
+use pattern::LazyRegex;
+mod plugin;
+mod query;
+mod reference;
+mod registry;
+mod render;
+mod report;
 mod schema_type;
+mod severity;
+mod stats;
+mod stream;
+mod strings;
+mod suppress;
+mod table;
+mod validated;
+mod validator;
+mod versioned;
+mod visit;
+pub mod vectors;
+
+#[cfg(feature = "schemas")]
+pub mod known;
+
+#[cfg(feature = "cache")]
+mod cache;
+
+#[cfg(feature = "generate")]
+pub mod generate;
+
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "remote")]
+pub use remote::{SchemaFetcher, FileFetcher, RemoteSchemas};
+
 
 /// An enum that represents the a kind of schema, used mostly in errors
 pub use schema_type::SchemaType;
 
+/// How bad the errors of a schema are, set with `severity`
+pub use severity::Severity;
+
+/// What a schema node accepts, see [TomlSchema::info]
+pub use introspect::SchemaInfo;
+
+/// The node counts and size of a schema, see [TomlSchema::stats]
+pub use stats::SchemaStats;
+
+/// The schema of schema files, checked by strict parsing
+pub use meta::{meta_schema, META_SCHEMA_SOURCE};
+
+/// The editor configuration for the JSON Schema of a format, see [EditorAssociation]
+pub use editor::EditorAssociation;
+
+/// Documents that name their schema with a `$schema` header
+pub use header::{validate_auto, validate_auto_with_options, schema_header, SCHEMA_HEADER};
+
+/// The result of [TomlSchema::validate], with every error of a document
+pub use report::{ValidationReport, ReportedError};
+
+/// A suggestion for editors, returned by [TomlSchema::completions_at]
+pub use completion::Completion;
+
+/// Which parts of a schema a document went through, returned by [TomlSchema::coverage]
+pub use coverage::Coverage;
+
+/// Which schema node validated each value of a document, returned by [TomlSchema::check_explain]
+pub use explain::{MatchTrace, MatchVia};
+
+/// A document that matched a schema with typed getters, returned by [TomlSchema::checked]
+pub use validated::{ValidatedDoc, FromValue};
+
+/// A harmless problem found while parsing a schema, returned by [TomlSchema::from_table_with_warnings]
+pub use constructor::SchemaParseWarning;
+
+/// Options for building schemas, used by [TomlSchema::from_table_with_options]
+pub use constructor::ParseOptions;
+
+/// Native validators referenced from schemas with `custom = "name"`
+pub use custom::{CustomValidator, CustomValidators, CustomFn, ContextFn, ValidationContext, ValidationState};
+
+/// Named schemas that other schemas extend with `extends = "name"`
+pub use definitions::SchemaDefinitions;
+
+/// Comparisons between the values of two keys of a table and conditionally required keys, from the `assert` and
+/// `when` keys of table schemas
+pub use assertion::{Assertion, AssertionKind, Requirement};
+
+/// User-defined schema types, registered with [SchemaPlugins]
+pub use plugin::{PluginType, PluginNode, PluginSchema, SchemaPlugins};
+
+/// What the keys of maps and table extras must parse as
+pub use key_type::{KeyType, KeyCase};
+
+/// How the keys of a table are matched against its extras
+pub use extras::{ExtrasPolicy, ExtrasMatching};
+
+/// A location in a schema or document, used by [TomlSchema::visit] and [TomlSchema::resolve_path]
+pub use path::{SchemaPath, PathSegment};
+
+/// The contents of table and map schemas
+pub use table::{TableSchema, MapSchema, TableEntries};
+
+/// The regexes of strings, table extras and map keys, compared as strings when they are literals
+pub use pattern::Pattern;
+
+/// The comparison of `exact` schemas
+pub use parse_toml::values_match;
+
+/// The options of alternative schemas
+pub use alternative::AlternativeSchema;
+
+/// The content of date schemas
+pub use date::DateSchema;
+
+/// The ordering of date bounds
+#[cfg(feature = "chrono")]
+pub use date::compare_dates;
+
+/// The options of string schemas
+pub use strings::StringRules;
+
+/// The fixed widths of ints
+pub use ints::IntWidth;
+
+/// The unicode normalization forms of `normalize`
+#[cfg(feature = "unicode")]
+pub use strings::NormalForm;
+
+/// The syntax of string types like `glob`
+pub use formats::StringFormat;
+
+/// The validation of a document one entry at a time
+pub use stream::EntryValidator;
+
+/// Error messages in other languages
+pub use messages::{ErrorMessage, MessageArg, MessageCatalog, Localized, MESSAGE_CODES};
+
+/// Compiled schemas by name, see [SchemaRegistry]
+pub use registry::SchemaRegistry;
+
+/// A schema with the options of the validation, see [Validator]
+pub use validator::{Validator, WarningPolicy};
+
+/// Errors hidden by a [Validator], see [Validator::suppress]
+pub use suppress::Suppression;
+
+/// Configurations split in the files of a drop-in directory like `conf.d`
+pub use confdir::{ConfigDir, DirError};
+
+/// Transformations that upgrade documents written for older versions of a schema
+pub use migration::{Migrations, Migration, MigrationStep};
+
+/// Schemas for several versions of a format, selected by a version key of documents
+pub use versioned::{VersionedSchema, VersionError, DEFAULT_VERSION_KEY};
+
 
 /// A component of a [TomlSchema], only useful to construct a schema by hand
 #[derive(Debug, Clone)]
 pub struct TableEntry {
-    pub key: Regex,
+    pub key: Pattern,
+    pub key_type: KeyType,
     pub value: TomlSchema
 }
 
 
-/// The main type of the crate, it can be constructed from a [toml::Table] object or by hand, the main constructor
-/// for this type is [TomlSchema::try_from]
+/// The main type of the crate, it can be constructed from a [toml::Table] object or by hand with constructors like
+/// [TomlSchema::table], the main constructor for this type is [TomlSchema::try_from]
+/// 
+/// Sub-schemas are reference counted, so cloning a schema is cheap and a compiled schema can be shared between
+/// threads (it is `Send + Sync`)
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum TomlSchema {
-    Alternative(Vec<TomlSchema>),
-    String{regex: Regex},
-    Integer{min: i64, max: i64},
-    Date,
-    Bool,
-    Float{min: f64, max: f64, nan_ok: bool},
-    Table{extras: Vec<TableEntry>, min: usize, max: usize, entries: HashMap<String, (TomlSchema, Option<Value>)>},
-    Array{cond: Box<TomlSchema>, min: usize, max: usize},
+    Alternative(Arc<AlternativeSchema>),
+    String{regex: Pattern, refers_to: Option<Arc<str>>, rules: Arc<StringRules>},
+    /// An int in `[min, max]`, the bounds are within the range of `width` when there is one, with `coerce` the strings
+    /// that are ints are accepted too
+    Integer{min: i64, max: i64, width: Option<IntWidth>, coerce: bool},
+    Date(Arc<DateSchema>),
+    /// A bool, with `coerce` the strings `"true"`, `"yes"`, `"on"`, `"false"`, `"no"` and `"off"` are accepted too
+    Bool{coerce: bool},
+    Float{min: f64, max: f64, nan_ok: bool, coerce: bool},
+    Table(Arc<TableSchema>),
+    /// An array, its length must be in `[min, max]` and a multiple of `multiple_of`
+    Array{cond: Arc<TomlSchema>, min: usize, max: usize, multiple_of: u32, unique_by: Option<Arc<str>>, append: bool},
+    Map(Arc<MapSchema>),
     Anything,
-    Exact(Value)
+    /// Exactly one value, floats may differ from it by `epsilon`, see [values_match]
+    Exact{value: Value, epsilon: f64},
+    /// One of several exact values, from `{type = "exact", values = [..]}` or merged by [TomlSchema::normalize]
+    Enum(Arc<[Value]>),
+    /// A schema with `x-` extension keys, they do not change what the schema matches
+    Annotated{schema: Arc<TomlSchema>, extensions: Arc<toml::Table>},
+    /// A schema with `default_env = "VAR"` or `default_merge = "merge"`, they change how [TomlSchema::check_and_complete]
+    /// fills the key
+    Defaulted{schema: Arc<TomlSchema>, env: Option<Arc<str>>, merge: bool},
+    /// A schema with a custom validator (`custom = "name"`) that runs once the schema matched
+    Custom{schema: Arc<TomlSchema>, validator: CustomValidator},
+    /// A schema with `severity = "warning"` or `"info"`, [TomlSchema::check] accepts the values it rejects and
+    /// [TomlSchema::validate] reports them with this severity
+    Graded{schema: Arc<TomlSchema>, severity: Severity},
+    /// A schema of a user-defined type, see [PluginType]
+    Plugin(PluginSchema)
 }
 
 
@@ -139,29 +656,99 @@ impl TryFrom<toml::Table> for TomlSchema {
     /// note: default values are not checked against the schema
     fn try_from(table: toml::Table) -> Result<Self,String>
     {
-        let (schema, dv) = TomlSchema::from_table(&table)?;
+        TomlSchema::try_from(&table)
+    }
+}
+
+impl TryFrom<&toml::Table> for TomlSchema {
+    type Error = String;
+
+    /// Same as the `TryFrom<toml::Table>` constructor for a borrowed table
+    fn try_from(table: &toml::Table) -> Result<Self,String>
+    {
+        let (schema, dv) = TomlSchema::from_table(table)?;
 
-        if dv.is_some() {log::warn!("Tried to construct a TOML Schema with a default value in the root")}
+        if let Some(value) = dv {
+            log::warn!("{}", SchemaParseWarning::IgnoredDefault { path: SchemaPath::new(), value })
+        }
         
         Ok(schema)
     }
 }
 
+impl TryFrom<toml::Value> for TomlSchema {
+    type Error = String;
+
+    /// Same as the `TryFrom<toml::Table>` constructor, values that are not tables are rejected
+    fn try_from(value: toml::Value) -> Result<Self,String>
+    {
+        match value {
+            Value::Table(table) => TomlSchema::try_from(&table),
+            other => Err(format!("A schema must be a table but got {} {}", SchemaType::from(&other), other))
+        }
+    }
+}
+
+impl std::str::FromStr for TomlSchema {
+    type Err = String;
+
+    /// Parses TOML text and builds a schema from it, this is the entry point for bindings that only deal in strings
+    fn from_str(s: &str) -> Result<Self, String>
+    {
+        let table = s.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        TomlSchema::try_from(table)
+    }
+}
+
+
+/// The maximum number of nested schemas [TomlSchema::check] goes through before failing with [SchemaError::DepthExceeded]
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
 
 /// The error type returned by [TomlSchema::check], it cannot outlive the [TomlSchema] or the [toml::Table] it comes from
 #[derive(Clone, PartialEq)]
+#[non_exhaustive]
 pub enum SchemaError<'s, 'v> {
     TypeMismatch{expected: SchemaType, got: SchemaType},
     RegexMiss{string: &'v str, re: &'s str},
+    NotRegexMatch{string: &'v str, re: &'s str},
+    PrefixMiss{string: &'v str, prefix: &'s str},
+    SuffixMiss{string: &'v str, suffix: &'s str},
+    ContainsMiss{string: &'v str, part: &'s str},
+    FormatMiss{string: &'v str, format: &'s StringFormat, reason: String},
     FloatMiss{val: f64, min: f64, max: f64, nan_ok: bool},
     IntMiss{val: i64, min: i64, max: i64},
+    IntWidthMiss{val: i64, width: IntWidth},
+    CoerceMiss{string: &'v str, expected: SchemaType},
     ArrayCount{count: usize, min: usize, max: usize},
+    ArrayStep{count: usize, multiple_of: usize},
     ArrayMiss{value: &'v Value, error: Box<SchemaError<'s,'v>>},
     TableMiss{key: &'v str, value: &'v Value, errors: Vec<SchemaError<'s,'v>>},
     AtKey{key: &'v String, error: Box<SchemaError<'s,'v>>},
     InTableElement{val: &'v Value, error: Box<SchemaError<'s,'v>>},
     TableCount{count: usize, min: usize, max: usize},
     AlternativeMiss{val: &'v Value, errors: Vec<SchemaError<'s,'v>>},
+    ExactMiss{val: &'v Value, expected: &'s Value},
+    FloatDeltaMiss{val: f64, expected: f64, delta: f64, epsilon: f64},
+    EnumMiss{val: &'v Value, expected: &'s [Value]},
+    DateMiss{val: &'v Datetime, min: Option<&'s Datetime>, max: Option<&'s Datetime>},
+    DateIncomparable{val: &'v Datetime, bound: &'s Datetime},
+    DateOffsetMiss{val: &'v Datetime, required: bool},
+    CustomMiss{val: &'v Value, name: &'s str, message: String},
+    PluginMiss{val: &'v Value, type_name: &'s str, message: String},
+    AssertionFailed{assertion: &'s Assertion, left: Value, right: Value},
+    DuplicateValue{key: &'s str, value: &'v Value, first: usize, second: usize},
+    DanglingReference{value: &'v str, target: &'s str},
+    MissingRequired{key: &'s str, requirement: &'s Requirement},
+    MissingKey{key: &'s str, expected: SchemaType},
+    DuplicateKey{key: &'v str},
+    InterpolationMiss{placeholder: String, reason: String},
+    KeyMiss{key: &'v str, re: &'s str},
+    KeyTypeMiss{key: &'v str, expected: &'s KeyType},
+    KeyCaseMiss{key: &'v str, expected: KeyCase},
+    MapCount{count: usize, min: usize, max: usize},
+    DepthExceeded,
+    BudgetExceeded,
 }
 
 
@@ -169,17 +756,71 @@ pub enum SchemaError<'s, 'v> {
 impl<'s,'v> std::fmt::Debug for SchemaError<'s,'v> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::TypeMismatch{expected, got} => write!(f, "Expected {:?} but got {:?}", expected, got),
+            Self::TypeMismatch{expected, got} => write!(f, "Expected {} but got {}", expected, got),
             Self::RegexMiss{string, re} => write!(f, "Regex {:?} does not match {:?}", re, string),
+            Self::NotRegexMatch{string, re} => write!(f, "Forbidden regex {:?} matches {:?}", re, string),
+            Self::PrefixMiss{string, prefix} => write!(f, "String {:?} does not start with {:?}", string, prefix),
+            Self::SuffixMiss{string, suffix} => write!(f, "String {:?} does not end with {:?}", string, suffix),
+            Self::ContainsMiss{string, part} => write!(f, "String {:?} does not contain {:?}", string, part),
+            Self::FormatMiss{string, format, reason} => write!(f, "String {:?} is not a valid {}: {}", string, format, reason),
             Self::FloatMiss { val, min, max, nan_ok } => write!(f, "Float {:?} does not match [{:?},{:?}] (nan:{:?})", val,min,max,nan_ok),
             Self::IntMiss { val, min, max } => write!(f, "Int {:?} does not match [{:?},{:?}]",val,min,max),
+            Self::IntWidthMiss { val, width } => {
+                let (min, max) = width.range();
+                write!(f, "Int {:?} does not fit in a {} [{:?},{:?}]", val, width, min, max)
+            },
+            Self::CoerceMiss { string, expected } => write!(f, "String {:?} cannot be read as {}", string, expected),
             Self::ArrayCount { count, min, max } => write!(f, "Array count {:?} does not match [{:?},{:?}]",count,min,max),
+            Self::ArrayStep { count, multiple_of } => write!(f, "Array count {} is not a multiple of {}", count, multiple_of),
             Self::ArrayMiss { value, error } => write!(f, "Child of Array {:?} does not match because {:?}", value, error),
             Self::TableMiss { key, value, errors } => write!(f, "No match for (key = {:?}, value = {:?}), error list : {:?}", key, value, errors),
             Self::AtKey { key, error } => write!(f, "At key '{:?}', got ({:?})", key, error),
             Self::InTableElement {val, error} => write!(f, "In Array (child {:?}), got ({:?})", val, error),
             Self::TableCount { count, min, max } => write!(f, "Table extra count {:?} does not match [{:?},{:?}]",count,min,max),
-            Self::AlternativeMiss { val, errors } => write!(f, "No Alternative matched for {:?}, error list : {:?}", val, errors)
+            Self::AlternativeMiss { errors, .. } if !errors.is_empty() && errors.iter().all(|e| matches!(e, Self::TypeMismatch { .. })) => {
+                // only the types were wrong, this is the common case of type lists
+                let mut expected: Vec<SchemaType> = Vec::new();
+                for e in errors {
+                    if let Self::TypeMismatch { expected: t, .. } = e {
+                        if !expected.contains(t) {expected.push(*t);}
+                    }
+                }
+                let Self::TypeMismatch { got, .. } = &errors[0] else {unreachable!()};
+                let names: Vec<&str> = expected.iter().map(|t| t.as_str()).collect();
+                let (last, rest) = names.split_last().expect("errors is not empty");
+                match rest.is_empty() {
+                    true => write!(f, "Expected {} but got {}", last, got),
+                    false => write!(f, "Expected {} or {} but got {}", rest.join(", "), last, got)
+                }
+            },
+            Self::AlternativeMiss { val, errors } => write!(f, "No Alternative matched for {:?}, error list : {:?}", val, errors),
+            Self::ExactMiss { val, expected } => write!(f, "Value {:?} is not exactly {:?}", val, expected),
+            Self::FloatDeltaMiss { val, expected, delta, epsilon } => {
+                write!(f, "Float {} differs from {} by {} which is more than {}", val, expected, delta, epsilon)
+            },
+            Self::EnumMiss { val, expected } => write!(f, "Value {:?} is not one of {:?}", val, expected),
+            Self::DateMiss { val, min, max } => {
+                let bound = |b: &Option<&Datetime>| b.map_or(String::new(), |b| b.to_string());
+                write!(f, "Date {} is not in [{}..{}]", val, bound(min), bound(max))
+            },
+            Self::DateIncomparable { val, bound } => write!(f, "Date {} cannot be compared to the bound {}", val, bound),
+            Self::DateOffsetMiss { val, required: true } => write!(f, "Date {} must have an offset", val),
+            Self::DateOffsetMiss { val, required: false } => write!(f, "Date {} must not have an offset", val),
+            Self::CustomMiss { val, name, message } => write!(f, "Custom validator '{}' rejected {:?}: {}", name, val, message),
+            Self::PluginMiss { val, type_name, message } => write!(f, "Value {:?} does not match {}: {}", val, type_name, message),
+            Self::AssertionFailed { assertion, left, right } => write!(f, "Assertion {} failed with {} and {}", assertion, left, right),
+            Self::DuplicateValue { key, value, first, second } => write!(f, "Elements {} and {} have the same {} {}", first, second, key, value),
+            Self::DanglingReference { value, target } => write!(f, "Value {:?} is not one of {} in the document", value, target),
+            Self::MissingRequired { key, requirement } => write!(f, "Key {} is required when {} = {}", key, requirement.key, requirement.equals),
+            Self::InterpolationMiss { placeholder, reason } => write!(f, "Cannot interpolate ${{{}}}, {}", placeholder, reason),
+            Self::MissingKey { key, expected } => write!(f, "Missing key {} of type {}", key, expected),
+            Self::DuplicateKey { key } => write!(f, "Key {:?} is given more than once", key),
+            Self::KeyMiss { key, re } => write!(f, "Key {:?} does not match regex {:?}", key, re),
+            Self::KeyTypeMiss { key, expected } => write!(f, "Key {:?} is not a {}", key, expected),
+            Self::KeyCaseMiss { key, expected } => write!(f, "Key {:?} is not {} case", key, expected.as_str()),
+            Self::MapCount { count, min, max } => write!(f, "Map has {:?} keys but expected [{:?},{:?}]", count, min, max),
+            Self::DepthExceeded => write!(f, "Maximum nesting depth exceeded"),
+            Self::BudgetExceeded => write!(f, "Validation time budget exceeded")
         }
     }
 }
@@ -201,6 +842,7 @@ mod test {
         })
     }
 
+    #[cfg(feature = "regex")]
     #[test]
     fn parse_test() {
         init_test();
@@ -218,6 +860,7 @@ mod test {
         ).unwrap();
     }
 
+    #[cfg(feature = "regex")]
     #[test]
     fn parse_fail_test() {
         init_test();
@@ -233,4 +876,294 @@ mod test {
             &test_file.parse().unwrap()
         ).unwrap_err();
     }
+
+    #[test]
+    fn str_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            name = {type = 'string'}
+            port = {type = 'int', min = 1, max = 65535, default = 8080}
+        ".parse().unwrap();
+
+        schema.check_str("name = 'server'\nport = 80").unwrap();
+        schema.check_str("name = 'server'\nport = 0").unwrap_err();
+        schema.check_str("name = ").unwrap_err();
+
+        let completed = schema.complete_str("name = 'server'").unwrap();
+        assert!(completed.contains("port = 8080"), "completed document is {}", completed);
+
+        "type = 'nope'".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn check_overloads_test() {
+        init_test();
+
+        let schema: TomlSchema = "name = {type = 'string'}".parse().unwrap();
+        assert!(schema.check_table(&"name = 'a'".parse().unwrap()).is_ok());
+        let report = schema.check_table(&"name = 1\nother = 'b'".parse().unwrap());
+        let paths: Vec<String> = report.errors.iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["name", "other"]);
+
+        let docs: Vec<Value> = ["name = 'a'", "name = 1", "other = 'b'"].iter().map(|s| s.parse().unwrap()).collect();
+        let results = schema.check_many(&docs);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(SchemaError::AtKey { .. })));
+        assert!(results[2].is_err());
+        assert!(schema.check_many([]).is_empty());
+    }
+
+    #[test]
+    fn try_from_test() {
+        init_test();
+
+        let table: toml::Table = "name = {type = 'string'}".parse().unwrap();
+        let schema = TomlSchema::try_from(&table).unwrap();
+        schema.check_str("name = 'server'").unwrap();
+
+        let schema = TomlSchema::try_from(Value::Table(table)).unwrap();
+        schema.check_str("name = 1").unwrap_err();
+
+        let err = TomlSchema::try_from(Value::String("string".into())).unwrap_err();
+        assert!(err.contains("must be a table"), "{}", err);
+    }
+
+    #[test]
+    fn exact_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            version = {type = 'exact', value = 2}
+            mode = {type = 'alternative', options = [{type = 'exact', value = 'fast'}, {type = 'exact', value = 'safe'}]}
+        ".parse().unwrap();
+
+        schema.check_str("version = 2\nmode = 'safe'").unwrap();
+        schema.check_str("version = 2\nmode = 'slow'").unwrap_err();
+        match schema.check_str("version = 3\nmode = 'fast'") {
+            Err(e) => assert!(e.contains("is not exactly"), "error is {}", e),
+            Ok(()) => panic!("exact value accepted a different value")
+        }
+    }
+
+    #[test]
+    fn exact_float_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            ratio = {type = 'exact', value = 0.3, epsilon = 1e-9}
+            missing = {type = 'exact', value = nan}
+            point = {type = 'exact', value = [1.0, 2.0], epsilon = 0.5}
+        ".parse().unwrap();
+
+        schema.check_str("ratio = 0.30000000000000004\nmissing = nan\npoint = [1.2, 1.9]").unwrap();
+        match schema.check_str("ratio = 0.31\nmissing = nan\npoint = [1.0, 2.0]") {
+            Err(e) => assert!(e.contains("differs from 0.3 by"), "error is {}", e),
+            Ok(()) => panic!("float out of epsilon accepted")
+        }
+        schema.check_str("ratio = 0.3\nmissing = 1.0\npoint = [1.0, 2.0]").unwrap_err();
+        schema.check_str("ratio = 0.3\nmissing = nan\npoint = [1.0, 3.0]").unwrap_err();
+
+        assert!(values_match(&Value::Float(0.0), &Value::Float(-0.0), 0.0));
+        assert!(values_match(&Value::Float(f64::INFINITY), &Value::Float(f64::INFINITY), 0.1));
+        "x = {type = 'exact', values = [1.0], epsilon = 0.1}".parse::<TomlSchema>().unwrap_err();
+        "x = {type = 'exact', value = 1.0, epsilon = -0.1}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn not_regex_test() {
+        init_test();
+
+        let schema: TomlSchema = r"
+            name = {type = 'string', regex = '^\w+$', not_regex = '^_'}
+            label = {type = 'string', not_regex = 'TODO', case_insensitive = true}
+        ".parse().unwrap();
+
+        schema.check_str("name = 'public'\nlabel = 'done'").unwrap();
+        schema.check_str("name = 'has space'\nlabel = 'done'").unwrap_err();
+        match schema.check_str("name = '_private'\nlabel = 'done'") {
+            Err(e) => assert!(e.contains("Forbidden regex"), "error is {}", e),
+            Ok(()) => panic!("string matching not_regex accepted")
+        }
+        // regex options apply to both patterns
+        schema.check_str("name = 'a'\nlabel = 'todo: later'").unwrap_err();
+    }
+
+    #[test]
+    fn string_literals_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            image = {type = 'string', starts_with = 'app-', ends_with = '.png', contains = '_v'}
+        ".parse().unwrap();
+
+        schema.check_str("image = 'app-logo_v2.png'").unwrap();
+        for (doc, message) in [
+            ("image = 'logo_v2.png'", "does not start with \"app-\""),
+            ("image = 'app-logo_v2.jpg'", "does not end with \".png\""),
+            ("image = 'app-logo.png'", "does not contain \"_v\""),
+        ] {
+            match schema.check_str(doc) {
+                Err(e) => assert!(e.contains(message), "error is {}", e),
+                Ok(()) => panic!("{} accepted", doc)
+            }
+        }
+        // literals are not regexes
+        "x = {type = 'string', starts_with = 1}".parse::<TomlSchema>().unwrap_err();
+        "x = {type = 'string', contains = '('}".parse::<TomlSchema>().unwrap().check_str("x = 'f(x)'").unwrap();
+    }
+
+    #[test]
+    fn required_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            name = 'string'
+            port = {type = 'int', default = 8080}
+            [extra]
+            extras = [{key = '.*', schema = 'int'}]
+            default = {}
+        ".parse().unwrap();
+
+        match schema.check_str("port = 80") {
+            Err(e) => assert_eq!(e, "Missing key name of type string"),
+            Ok(()) => panic!("a key without a default was not required")
+        }
+        let mut doc = Value::Table("port = 80".parse().unwrap());
+        assert_eq!(schema.check_and_complete(&mut doc), Err(SchemaError::MissingKey { key: "name", expected: SchemaType::String }));
+        assert_eq!(schema.validate(&doc).errors.len(), 1);
+
+        // extras are not limited by default
+        let many: String = (0..20).map(|i| format!("extra.k{} = {}\n", i, i)).collect();
+        schema.check_str(&format!("name = 'a'\n{}", many)).unwrap();
+    }
+
+    #[test]
+    fn depth_test() {
+        init_test();
+
+        let schema: TomlSchema = "
+            type = 'array'
+            child = {type = 'array', child = {type = 'array', child = {type = 'int'}}}
+        ".parse().unwrap();
+        let doc: Value = Value::Array(vec![Value::Array(vec![Value::Array(vec![Value::Integer(1)])])]);
+
+        schema.check(&doc).unwrap();
+        schema.check_with_max_depth(&doc, 4).unwrap();
+        assert!(matches!(schema.check_with_max_depth(&doc, 3), Err(SchemaError::ArrayMiss { .. })));
+        assert!(matches!(schema.check_with_max_depth(&doc, 0), Err(SchemaError::DepthExceeded)));
+
+        // a deep document against an equally deep schema
+        let mut deep = Value::Integer(0);
+        let mut deep_schema = TomlSchema::Integer { min: i64::MIN, max: i64::MAX, width: None, coerce: false };
+        for _ in 0..2 * DEFAULT_MAX_DEPTH {
+            deep = Value::Array(vec![deep]);
+            deep_schema = TomlSchema::Array { cond: Arc::new(deep_schema), min: 0, max: usize::MAX, multiple_of: 1, unique_by: None, append: false };
+        }
+        deep_schema.check_with_max_depth(&deep, usize::MAX).unwrap();
+
+        let mut err = deep_schema.check(&deep).unwrap_err();
+        while let SchemaError::ArrayMiss { error, .. } = err {
+            err = *error;
+        }
+        assert!(matches!(err, SchemaError::DepthExceeded), "innermost error is {:?}", err);
+    }
+
+    #[test]
+    fn budget_test() {
+        use std::time::Duration;
+
+        let schema: TomlSchema = "type = 'array'\nchild = {type = 'table', extras = [{key = '.*', schema = 'int'}]}".parse().unwrap();
+        let doc = Value::Array(vec![Value::Table("a = 1\nb = 2".parse().unwrap()); 1000]);
+
+        schema.check_with_budget(&doc, Duration::from_secs(60)).unwrap();
+        let err = schema.check_with_budget(&doc, Duration::ZERO).unwrap_err();
+        assert!(matches!(err, SchemaError::BudgetExceeded), "{:?}", err);
+
+        // the budget does not outlive the call
+        schema.check(&doc).unwrap();
+    }
+
+    #[test]
+    fn unique_test() {
+        let schema: TomlSchema = "
+            type = 'array'
+            unique_by = 'name'
+            child = {type = 'table', extras = [{key = '.*', schema = 'anything'}]}
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        let ok = doc("a = [{name = 'http', port = 80}, {name = 'https', port = 80}, {port = 1}, {port = 2}]");
+        schema.check(&ok["a"]).unwrap();
+
+        let dup = doc("a = [{name = 'http'}, {name = 'https'}, {name = 'http'}, {name = 'https'}]");
+        match schema.check(&dup["a"]) {
+            Err(SchemaError::DuplicateValue { key, value, first, second }) => {
+                assert_eq!((key, first, second), ("name", 0, 2));
+                assert_eq!(value, &Value::String("http".into()));
+            },
+            other => panic!("expected a duplicate but got {:?}", other)
+        }
+        assert_eq!(schema.validate(&dup["a"]).errors.len(), 1);
+        assert_eq!(schema.to_string(), "array<table { /.*/: anything }> unique by name");
+
+        "type = 'array'\nunique_by = 1".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[test]
+    fn array_len_test() {
+        let schema: TomlSchema = "
+            rgb = {type = 'array', child = 'int', len = 3}
+            points = {type = 'array', child = 'float', len_multiple_of = 2}
+        ".parse().unwrap();
+
+        schema.check_str("rgb = [1, 2, 3]\npoints = [0.0, 1.0, 2.0, 3.0]").unwrap();
+        schema.check_str("rgb = [1, 2]\npoints = []").unwrap_err();
+        match schema.check_str("rgb = [1, 2, 3]\npoints = [0.0, 1.0, 2.0]") {
+            Err(e) => assert!(e.contains("is not a multiple of 2"), "error is {}", e),
+            Ok(()) => panic!("odd number of coordinates accepted")
+        }
+        let validator = Validator::new(schema).fail_fast(false);
+        let doc = Value::Table("rgb = [1, 2, 3]\npoints = [0.0]".parse().unwrap());
+        assert_eq!(validator.validate(&doc).errors.len(), 1);
+
+        "x = {type = 'array', len = 2, min = 1}".parse::<TomlSchema>().unwrap_err();
+        "x = {type = 'array', len_multiple_of = 0}".parse::<TomlSchema>().unwrap_err();
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn send_sync_test() {
+        init_test();
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TomlSchema>();
+        assert_send_sync::<TableEntry>();
+        assert_send_sync::<SchemaType>();
+
+        let schema: TomlSchema = "
+            [items]
+            type = 'array'
+            child = {type = 'table', name = {type = 'string', regex = '^item[0-9]+$'}}
+        ".parse().unwrap();
+
+        // sharing a schema between threads does not copy it
+        let shared = schema.clone();
+        match (&schema, &shared) {
+            (TomlSchema::Table(a), TomlSchema::Table(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => panic!("schema is not a table but {:?}", schema)
+        }
+
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let schema = &schema;
+                scope.spawn(move || {
+                    schema.check_str(&format!("items = [{{name = 'item{}'}}]", i)).unwrap();
+                    schema.check_str("items = [{name = 'nope'}]").unwrap_err();
+                });
+            }
+        });
+    }
 }
\ No newline at end of file
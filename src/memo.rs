@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::*;
+
+
+/// The number of memos active on all threads, validations without memoization skip the thread-local lookup when none is
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The subtrees already validated by the memoized validation running on this thread, see [Validator::memoize]
+    static MEMO: RefCell<Option<Memo>> = const { RefCell::new(None) };
+}
+
+/// The schema nodes and values that passed, a node is identified by its address which is stable while the schema is
+/// borrowed by the validation, a value by a 128 bits hash with random keys so that documents cannot craft collisions
+struct Memo {
+    valid: HashSet<MemoKey>,
+    /// the fingerprints of the tables and arrays already hashed by their address, which is stable while the document
+    /// is borrowed by the validation, a value is hashed from the fingerprints of its children so each one is hashed once
+    fingerprints: HashMap<usize, u128>,
+    keys: (RandomState, RandomState),
+}
+
+impl Memo {
+    fn fingerprint(&mut self, value: &Value) -> u128 {
+        let address = value as *const Value as usize;
+        if let Some(fingerprint) = self.fingerprints.get(&address) {
+            return *fingerprint
+        }
+        let mut state = PairHasher(self.keys.0.build_hasher(), self.keys.1.build_hasher());
+        std::mem::discriminant(value).hash(&mut state);
+        match value {
+            Value::String(s) => s.hash(&mut state),
+            Value::Integer(i) => i.hash(&mut state),
+            Value::Float(f) => f.to_bits().hash(&mut state),
+            Value::Boolean(b) => b.hash(&mut state),
+            Value::Datetime(dt) => dt.to_string().hash(&mut state),
+            Value::Array(arr) => {
+                arr.len().hash(&mut state);
+                arr.iter().for_each(|v| self.fingerprint(v).hash(&mut state));
+            },
+            Value::Table(table) => {
+                table.len().hash(&mut state);
+                for (k, v) in table {
+                    k.hash(&mut state);
+                    self.fingerprint(v).hash(&mut state);
+                }
+            }
+        }
+        let fingerprint = (u128::from(state.0.finish()) << 64) | u128::from(state.1.finish());
+        if matches!(value, Value::Table(_) | Value::Array(_)) {
+            self.fingerprints.insert(address, fingerprint);
+        }
+        fingerprint
+    }
+}
+
+/// Feeds the same bytes to two hashers with different keys, tables are hashed in their order of iteration
+struct PairHasher(DefaultHasher, DefaultHasher);
+
+impl Hasher for PairHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+        self.1.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+/// A schema node, the remaining depth and the fingerprint of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct MemoKey(usize, usize, u128);
+
+/// Memoizes the validations of this thread until it is dropped, a memo that is already active is kept
+pub(crate) struct MemoGuard(bool);
+
+impl MemoGuard {
+    pub(crate) fn start() -> Self {
+        MEMO.with(|memo| {
+            let mut memo = memo.borrow_mut();
+            if memo.is_some() {
+                return MemoGuard(false)
+            }
+            *memo = Some(Memo { valid: HashSet::new(), fingerprints: HashMap::new(), keys: (RandomState::new(), RandomState::new()) });
+            ACTIVE.fetch_add(1, Ordering::Relaxed);
+            MemoGuard(true)
+        })
+    }
+}
+
+impl Drop for MemoGuard {
+    fn drop(&mut self) {
+        if self.0 {
+            MEMO.with(|memo| memo.borrow_mut().take());
+            ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+
+/// The key of `schema` checking `value`, only tables and arrays checked by a table, map, array or alternative are
+/// memoized since smaller values are cheaper to check than to hash, `None` when no memo is active
+pub(crate) fn memo_key(schema: &TomlSchema, value: &Value, depth: usize) -> Option<MemoKey> {
+    let composite = matches!(schema, TomlSchema::Table(_) | TomlSchema::Map(_) | TomlSchema::Array { .. } | TomlSchema::Alternative(_))
+        && matches!(value, Value::Table(_) | Value::Array(_));
+    if !composite || ACTIVE.load(Ordering::Relaxed) == 0 {
+        return None
+    }
+    MEMO.with(|memo| {
+        let fingerprint = memo.borrow_mut().as_mut()?.fingerprint(value);
+        Some(MemoKey(schema as *const TomlSchema as usize, depth, fingerprint))
+    })
+}
+
+/// Whether the value of `key` already passed
+pub(crate) fn is_valid(key: MemoKey) -> bool {
+    MEMO.with(|memo| memo.borrow().as_ref().is_some_and(|memo| memo.valid.contains(&key)))
+}
+
+pub(crate) fn remember(key: MemoKey) {
+    MEMO.with(|memo| {
+        if let Some(memo) = memo.borrow_mut().as_mut() {
+            memo.valid.insert(key);
+        }
+    })
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memoized_validation() {
+        let schema: TomlSchema = "
+            [hosts]
+            type = 'array'
+            child = {name = {type = 'string', regex = '^[a-z]+$'}, port = {type = 'int', min = 1}}
+        ".parse().unwrap();
+        let host = "{name = 'web', port = 80}";
+        let doc = |hosts: &[&str]| Value::Table(format!("hosts = [{}]", hosts.join(", ")).parse().unwrap());
+        let validator = Validator::new(schema).memoize(true);
+
+        validator.check(&doc(&[host; 50])).unwrap();
+        validator.check(&doc(&[host, host, "{name = 'web', port = 0}"])).unwrap_err();
+        // the memo does not outlive a validation
+        assert!(memo_key(validator.schema(), &doc(&[host]), 1).is_none());
+
+        // the documents are kept alive like during a validation, their values are remembered by address
+        let _guard = MemoGuard::start();
+        let (table, same, other) = (doc(&[host]), doc(&[host]), doc(&[host, host]));
+        let key = memo_key(validator.schema(), &table, 1).unwrap();
+        // the root, the array and the host are hashed once
+        assert_eq!(MEMO.with(|memo| memo.borrow().as_ref().unwrap().fingerprints.len()), 3);
+        assert_eq!(memo_key(validator.schema(), &table, 1), Some(key));
+        assert_eq!(memo_key(validator.schema(), &same, 1), Some(key));
+        assert_ne!(memo_key(validator.schema(), &other, 1), Some(key));
+        assert!(!is_valid(key));
+        remember(key);
+        assert!(is_valid(key));
+    }
+}
@@ -0,0 +1,92 @@
+//! Layered configurations, [TomlSchema::merge] overlays documents (system, user, project...) using the schema to
+//! decide how values combine
+
+use crate::*;
+
+
+impl TomlSchema {
+
+    /// Overlays `layers` in order, later layers win, then checks and completes the result like
+    /// [TomlSchema::check_and_complete]
+    ///
+    /// tables are merged key by key, arrays are replaced unless their schema has `merge = "append"` and other values
+    /// are replaced, keys that no schema describes are merged as if they were `anything`, errors are returned as
+    /// strings since they would borrow the merged document
+    pub fn merge(&self, layers: &[Value]) -> Result<Value, String> {
+        let mut merged = Value::Table(toml::Table::new());
+        for layer in layers {
+            merge_value(self, &mut merged, layer);
+        }
+        self.check_and_complete(&mut merged).map_err(|e| format!("{:?}", e))?;
+        Ok(merged)
+    }
+
+    /// Whether the arrays matched by this schema (or one of its options) are appended to each other when merged
+    pub(crate) fn appends(&self) -> bool {
+        self.flatten_alternatives().iter().any(|schema| matches!(schema, TomlSchema::Array { append: true, .. }))
+    }
+}
+
+
+/// Overlays `layer` on `base`, `schema` governs both values
+pub(crate) fn merge_value(schema: &TomlSchema, base: &mut Value, layer: &Value) {
+    match (base, layer) {
+        (Value::Table(base), Value::Table(layer)) => {
+            for (key, value) in layer {
+                let Some(present) = base.get_mut(key) else {
+                    base.insert(key.clone(), value.clone());
+                    continue
+                };
+                let path = SchemaPath::new().join(PathSegment::Key(key.clone()));
+                match schema.resolve_path(&path).first() {
+                    Some((child, _)) => merge_value(child, present, value),
+                    None => merge_value(&TomlSchema::Anything, present, value)
+                }
+            }
+        },
+        (Value::Array(base), Value::Array(layer)) if schema.appends() => base.extend(layer.iter().cloned()),
+        (base, layer) => *base = layer.clone()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let schema: TomlSchema = "
+            name = 'string'
+            plugins = {type = 'array', child = 'string', merge = 'append', default = []}
+            paths = {type = 'array', child = 'string', default = []}
+
+            [server]
+            host = {type = 'string', default = 'localhost'}
+            port = {type = 'int', default = 80}
+            extras = [{key = '^x-', schema = {type = 'table', extras = [{key = '.*', schema = 'anything'}]}}]
+        ".parse().unwrap();
+
+        let layers: Vec<Value> = [
+            "name = 'system'\nplugins = ['a']\npaths = ['/usr']\nserver = {port = 8080, x-tls = {cert = 'a.pem'}}",
+            "plugins = ['b']\npaths = ['~']\nserver = {x-tls = {key = 'a.key'}}",
+            "name = 'project'\nserver = {host = 'example.com'}",
+        ].iter().map(|s| Value::Table(s.parse().unwrap())).collect();
+
+        let merged = schema.merge(&layers).unwrap();
+        assert_eq!(merged["name"].as_str(), Some("project"));
+        assert_eq!(merged["plugins"], Value::Array(vec!["a".into(), "b".into()]));
+        assert_eq!(merged["paths"], Value::Array(vec!["~".into()]));
+        assert_eq!(merged["server"]["host"].as_str(), Some("example.com"));
+        assert_eq!(merged["server"]["port"].as_integer(), Some(8080));
+        assert_eq!(merged["server"]["x-tls"]["cert"].as_str(), Some("a.pem"));
+        assert_eq!(merged["server"]["x-tls"]["key"].as_str(), Some("a.key"));
+
+        // the merged document is checked, `name` is required
+        schema.merge(&layers[1..2]).unwrap_err();
+
+        "x = {type = 'array', merge = 'prepend'}".parse::<TomlSchema>().unwrap_err();
+    }
+}
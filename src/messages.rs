@@ -0,0 +1,339 @@
+//! Translated error messages, a [SchemaError] is described by an [ErrorMessage] with a code and named arguments that
+//! a [MessageCatalog] renders with its own templates
+
+use std::fmt;
+use crate::*;
+
+
+/// A language-neutral description of an error, the code names the kind of error and the arguments fill the
+/// placeholders of the templates of a [MessageCatalog], see [MESSAGE_CODES] for the arguments of each code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorMessage {
+    pub code: &'static str,
+    pub args: Vec<(&'static str, MessageArg)>,
+    /// The English message, used when a catalog has no template for the code
+    pub default: String,
+}
+
+/// An argument of an [ErrorMessage], values are written in TOML syntax
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageArg {
+    Text(String),
+    /// The errors that caused this one, like the errors of each option of an alternative
+    Errors(Vec<ErrorMessage>),
+}
+
+/// The codes of error messages with the names of their arguments
+pub const MESSAGE_CODES: &[(&str, &[&str])] = &[
+    ("type-mismatch", &["expected", "got"]),
+    ("regex-miss", &["string", "regex"]),
+    ("forbidden-regex", &["string", "regex"]),
+    ("prefix-miss", &["string", "prefix"]),
+    ("suffix-miss", &["string", "suffix"]),
+    ("contains-miss", &["string", "part"]),
+    ("format-miss", &["string", "format", "reason"]),
+    ("float-range", &["value", "min", "max"]),
+    ("int-range", &["value", "min", "max"]),
+    ("int-width", &["value", "width", "min", "max"]),
+    ("coerce-miss", &["string", "expected"]),
+    ("array-count", &["count", "min", "max"]),
+    ("array-step", &["count", "multiple_of"]),
+    ("array-element", &["value", "error"]),
+    ("table-extra", &["key", "value", "errors"]),
+    ("at-key", &["key", "error"]),
+    ("table-element", &["value", "error"]),
+    ("table-count", &["count", "min", "max"]),
+    ("alternative-type", &["expected", "got"]),
+    ("alternative-miss", &["value", "errors"]),
+    ("exact-miss", &["value", "expected"]),
+    ("float-delta", &["value", "expected", "delta", "epsilon"]),
+    ("enum-miss", &["value", "expected"]),
+    ("date-range", &["value", "min", "max"]),
+    ("date-incomparable", &["value", "bound"]),
+    ("date-offset-required", &["value"]),
+    ("date-offset-forbidden", &["value"]),
+    ("custom", &["value", "name", "message"]),
+    ("plugin", &["value", "type", "message"]),
+    ("assertion", &["assertion", "left", "right"]),
+    ("duplicate-value", &["key", "value", "first", "second"]),
+    ("dangling-reference", &["value", "target"]),
+    ("missing-required", &["key", "when_key", "when_value"]),
+    ("interpolation", &["placeholder", "reason"]),
+    ("missing-key", &["key", "expected"]),
+    ("duplicate-key", &["key"]),
+    ("key-regex", &["key", "regex"]),
+    ("key-type", &["key", "expected"]),
+    ("key-case", &["key", "expected"]),
+    ("map-count", &["count", "min", "max"]),
+    ("depth-exceeded", &[]),
+    ("budget-exceeded", &[]),
+];
+
+
+/// Message templates in one language, keyed by the codes of [MESSAGE_CODES], `{name}` in a template is replaced by the
+/// argument `name` and `{{`, `}}` are literal braces, errors without a template keep their English message
+///
+/// catalogs are usually written in TOML, one `code = "template"` per line
+#[derive(Debug, Clone, Default)]
+pub struct MessageCatalog {
+    templates: HashMap<&'static str, String>,
+}
+
+impl MessageCatalog {
+    pub fn new() -> Self {
+        MessageCatalog::default()
+    }
+
+    /// Reads the templates of a table like `type-mismatch = "{got} au lieu de {expected}"`
+    pub fn from_table(table: &toml::Table) -> Result<Self, String> {
+        let mut catalog = MessageCatalog::new();
+        for (code, template) in table {
+            let Value::String(template) = template else {
+                return Err(format!("The template of {} must be a string but got {}", code, template))
+            };
+            catalog.insert(code, template)?;
+        }
+        Ok(catalog)
+    }
+
+    /// Adds the template of `code`, fails when the code is unknown or the template has a placeholder that is not an
+    /// argument of the code
+    pub fn insert(&mut self, code: &str, template: &str) -> Result<&mut Self, String> {
+        let Some((code, names)) = MESSAGE_CODES.iter().find(|(c, _)| *c == code) else {
+            return Err(format!("Unknown error code {:?}", code))
+        };
+        for piece in parse_template(template)? {
+            if let Piece::Arg(name) = piece {
+                if !names.contains(&name) {
+                    return Err(format!("Unknown placeholder {{{}}} in the template of {}, expected one of {:?}", name, code, names))
+                }
+            }
+        }
+        self.templates.insert(code, template.to_string());
+        Ok(self)
+    }
+
+    /// The message in the language of this catalog, the causes of an error are rendered the same way and separated by `; `
+    pub fn render(&self, message: &ErrorMessage) -> String {
+        let Some(template) = self.templates.get(message.code) else {
+            return message.default.clone()
+        };
+        let mut out = String::new();
+        // templates are checked when they are inserted
+        for piece in parse_template(template).unwrap_or_default() {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Arg(name) => match message.args.iter().find(|(n, _)| *n == name) {
+                    Some((_, MessageArg::Text(text))) => out.push_str(text),
+                    Some((_, MessageArg::Errors(errors))) => {
+                        out.push_str(&errors.iter().map(|e| self.render(e)).collect::<Vec<_>>().join("; "))
+                    },
+                    None => ()
+                }
+            }
+        }
+        out
+    }
+}
+
+
+enum Piece<'t> {
+    Text(&'t str),
+    Arg(&'t str),
+}
+
+fn parse_template(template: &str) -> Result<Vec<Piece<'_>>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        if i > 0 {
+            pieces.push(Piece::Text(&rest[..i]));
+        }
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+        if let Some(after) = rest.strip_prefix(brace) {
+            pieces.push(Piece::Text(brace));
+            rest = after;
+            continue
+        }
+        match (brace, rest.find('}')) {
+            ("{", Some(end)) => {
+                pieces.push(Piece::Arg(&rest[..end]));
+                rest = &rest[end + 1..];
+            },
+            _ => return Err(format!("Unbalanced brace in template {:?}", template))
+        }
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    Ok(pieces)
+}
+
+
+/// An error rendered with a [MessageCatalog], see [SchemaError::localized]
+pub struct Localized<'c> {
+    message: ErrorMessage,
+    catalog: &'c MessageCatalog,
+}
+
+impl fmt::Display for Localized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.catalog.render(&self.message))
+    }
+}
+
+
+impl SchemaError<'_, '_> {
+    /// The language-neutral description of this error
+    pub fn message(&self) -> ErrorMessage {
+        let text = |s: &dyn fmt::Display| MessageArg::Text(s.to_string());
+        let errors = |errors: &[SchemaError]| MessageArg::Errors(errors.iter().map(SchemaError::message).collect());
+        let bound = |b: &Option<&Datetime>| MessageArg::Text(b.map_or(String::new(), |b| b.to_string()));
+
+        let (code, args): (&'static str, Vec<(&'static str, MessageArg)>) = match self {
+            Self::TypeMismatch { expected, got } => ("type-mismatch", vec![("expected", text(expected)), ("got", text(got))]),
+            Self::RegexMiss { string, re } => ("regex-miss", vec![("string", text(string)), ("regex", text(re))]),
+            Self::NotRegexMatch { string, re } => ("forbidden-regex", vec![("string", text(string)), ("regex", text(re))]),
+            Self::PrefixMiss { string, prefix } => ("prefix-miss", vec![("string", text(string)), ("prefix", text(prefix))]),
+            Self::SuffixMiss { string, suffix } => ("suffix-miss", vec![("string", text(string)), ("suffix", text(suffix))]),
+            Self::ContainsMiss { string, part } => ("contains-miss", vec![("string", text(string)), ("part", text(part))]),
+            Self::FormatMiss { string, format, reason } => {
+                ("format-miss", vec![("string", text(string)), ("format", text(format)), ("reason", text(reason))])
+            },
+            Self::FloatMiss { val, min, max, .. } => ("float-range", vec![("value", text(val)), ("min", text(min)), ("max", text(max))]),
+            Self::IntMiss { val, min, max } => ("int-range", vec![("value", text(val)), ("min", text(min)), ("max", text(max))]),
+            Self::IntWidthMiss { val, width } => {
+                let (min, max) = width.range();
+                ("int-width", vec![("value", text(val)), ("width", text(width)), ("min", text(&min)), ("max", text(&max))])
+            },
+            Self::CoerceMiss { string, expected } => ("coerce-miss", vec![("string", text(string)), ("expected", text(expected))]),
+            Self::ArrayCount { count, min, max } => ("array-count", vec![("count", text(count)), ("min", text(min)), ("max", text(max))]),
+            Self::ArrayStep { count, multiple_of } => ("array-step", vec![("count", text(count)), ("multiple_of", text(multiple_of))]),
+            Self::ArrayMiss { value, error } => ("array-element", vec![("value", text(value)), ("error", errors(std::slice::from_ref(&**error)))]),
+            Self::TableMiss { key, value, errors: causes } => {
+                ("table-extra", vec![("key", text(key)), ("value", text(value)), ("errors", errors(causes))])
+            },
+            Self::AtKey { key, error } => ("at-key", vec![("key", text(key)), ("error", errors(std::slice::from_ref(&**error)))]),
+            Self::InTableElement { val, error } => ("table-element", vec![("value", text(val)), ("error", errors(std::slice::from_ref(&**error)))]),
+            Self::TableCount { count, min, max } => ("table-count", vec![("count", text(count)), ("min", text(min)), ("max", text(max))]),
+            Self::AlternativeMiss { errors: causes, .. } if !causes.is_empty() && causes.iter().all(|e| matches!(e, Self::TypeMismatch { .. })) => {
+                let mut expected: Vec<&str> = Vec::new();
+                for e in causes {
+                    if let Self::TypeMismatch { expected: t, .. } = e {
+                        if !expected.contains(&t.as_str()) {expected.push(t.as_str());}
+                    }
+                }
+                let Self::TypeMismatch { got, .. } = &causes[0] else {unreachable!()};
+                ("alternative-type", vec![("expected", MessageArg::Text(expected.join(", "))), ("got", text(got))])
+            },
+            Self::AlternativeMiss { val, errors: causes } => ("alternative-miss", vec![("value", text(val)), ("errors", errors(causes))]),
+            Self::ExactMiss { val, expected } => ("exact-miss", vec![("value", text(val)), ("expected", text(expected))]),
+            Self::FloatDeltaMiss { val, expected, delta, epsilon } => {
+                ("float-delta", vec![("value", text(val)), ("expected", text(expected)), ("delta", text(delta)), ("epsilon", text(epsilon))])
+            },
+            Self::EnumMiss { val, expected } => {
+                let values: Vec<String> = expected.iter().map(|v| v.to_string()).collect();
+                ("enum-miss", vec![("value", text(val)), ("expected", MessageArg::Text(values.join(", ")))])
+            },
+            Self::DateMiss { val, min, max } => ("date-range", vec![("value", text(val)), ("min", bound(min)), ("max", bound(max))]),
+            Self::DateIncomparable { val, bound } => ("date-incomparable", vec![("value", text(val)), ("bound", text(bound))]),
+            Self::DateOffsetMiss { val, required: true } => ("date-offset-required", vec![("value", text(val))]),
+            Self::DateOffsetMiss { val, required: false } => ("date-offset-forbidden", vec![("value", text(val))]),
+            Self::CustomMiss { val, name, message } => ("custom", vec![("value", text(val)), ("name", text(name)), ("message", text(message))]),
+            Self::PluginMiss { val, type_name, message } => {
+                ("plugin", vec![("value", text(val)), ("type", text(type_name)), ("message", text(message))])
+            },
+            Self::AssertionFailed { assertion, left, right } => {
+                ("assertion", vec![("assertion", text(assertion)), ("left", text(left)), ("right", text(right))])
+            },
+            Self::DuplicateValue { key, value, first, second } => {
+                ("duplicate-value", vec![("key", text(key)), ("value", text(value)), ("first", text(first)), ("second", text(second))])
+            },
+            Self::DanglingReference { value, target } => ("dangling-reference", vec![("value", text(value)), ("target", text(target))]),
+            Self::MissingRequired { key, requirement } => {
+                ("missing-required", vec![("key", text(key)), ("when_key", text(&requirement.key)), ("when_value", text(&requirement.equals))])
+            },
+            Self::InterpolationMiss { placeholder, reason } => ("interpolation", vec![("placeholder", text(placeholder)), ("reason", text(reason))]),
+            Self::MissingKey { key, expected } => ("missing-key", vec![("key", text(key)), ("expected", text(expected))]),
+            Self::DuplicateKey { key } => ("duplicate-key", vec![("key", text(key))]),
+            Self::KeyMiss { key, re } => ("key-regex", vec![("key", text(key)), ("regex", text(re))]),
+            Self::KeyTypeMiss { key, expected } => ("key-type", vec![("key", text(key)), ("expected", text(expected))]),
+            Self::KeyCaseMiss { key, expected } => ("key-case", vec![("key", text(key)), ("expected", text(&expected.as_str()))]),
+            Self::MapCount { count, min, max } => ("map-count", vec![("count", text(count)), ("min", text(min)), ("max", text(max))]),
+            Self::DepthExceeded => ("depth-exceeded", Vec::new()),
+            Self::BudgetExceeded => ("budget-exceeded", Vec::new()),
+        };
+        ErrorMessage { code, args, default: format!("{:?}", self) }
+    }
+
+    /// This error in the language of `catalog`, its [Display](fmt::Display) form is the translated message
+    pub fn localized<'c>(&self, catalog: &'c MessageCatalog) -> Localized<'c> {
+        Localized { message: self.message(), catalog }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn french() -> MessageCatalog {
+        MessageCatalog::from_table(&"
+            type-mismatch = '{got} trouvé au lieu de {expected}'
+            int-range = \"{value} n'est pas dans [{min}, {max}]\"
+            at-key = 'clé {key} : {error}'
+            alternative-type = 'attendu {expected}, trouvé {got}'
+            missing-key = 'clé {{{key}}} manquante'
+        ".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn translated_messages() {
+        let schema: TomlSchema = "
+            port = {type = 'int', min = 1}
+            name = {type = 'alternative', options = ['string', 'int']}
+            tags = {type = 'array', child = 'string'}
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+        let catalog = french();
+
+        let value = doc("port = 0\nname = 'a'\ntags = []");
+        let error = schema.check(&value).unwrap_err();
+        assert_eq!(error.message().code, "at-key");
+        assert_eq!(error.localized(&catalog).to_string(), "clé port : 0 n'est pas dans [1, 9223372036854775807]");
+
+        let value = doc("port = 1\nname = true\ntags = []");
+        let error = schema.check(&value).unwrap_err();
+        assert_eq!(error.localized(&catalog).to_string(), "clé name : attendu string, int, trouvé bool");
+
+        let value = doc("name = 'a'\ntags = []");
+        let error = schema.check(&value).unwrap_err();
+        assert_eq!(error.localized(&catalog).to_string(), "clé {port} manquante");
+
+        // without a template the English message is kept
+        let value = doc("port = 1\nname = 'a'\ntags = [1]");
+        let error = schema.check(&value).unwrap_err();
+        assert_eq!(error.localized(&catalog).to_string().as_str(), format!("clé tags : {:?}", match &error {
+            SchemaError::AtKey { error, .. } => &**error,
+            other => other
+        }));
+    }
+
+    #[test]
+    fn catalog_errors() {
+        let mut catalog = MessageCatalog::new();
+        catalog.insert("no-such-code", "x").unwrap_err();
+        catalog.insert("int-range", "{val}").unwrap_err();
+        catalog.insert("int-range", "{value").unwrap_err();
+        catalog.insert("int-range", "{value} }").unwrap_err();
+        catalog.insert("int-range", "{value} }}").unwrap();
+        MessageCatalog::from_table(&"int-range = 1".parse().unwrap()).unwrap_err();
+
+        for (code, _) in MESSAGE_CODES {
+            assert_eq!(MESSAGE_CODES.iter().filter(|(c, _)| c == code).count(), 1, "{}", code);
+        }
+    }
+}
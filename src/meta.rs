@@ -0,0 +1,87 @@
+//! The schema of schema files, it pins down the keys of each type of schema node and the values they take, see
+//! [meta_schema]
+
+use std::sync::OnceLock;
+use crate::*;
+
+
+/// The source of [meta_schema], the schema of schema files
+pub const META_SCHEMA_SOURCE: &str = include_str!("../schemas/meta.toml");
+
+/// The schema that schema files match, an alternative with one option for each type of schema node
+///
+/// sub-schemas are only checked to be a type name or a table, each of them matches this schema on its own, and
+/// user-defined types of [ParseOptions::plugins] are not known to it, strict parsing ([ParseOptions::strict]) checks
+/// every node of a schema against it
+pub fn meta_schema() -> &'static TomlSchema {
+    static CELL: OnceLock<TomlSchema> = OnceLock::new();
+    CELL.get_or_init(|| META_SCHEMA_SOURCE.parse().expect("the meta schema is a valid schema"))
+}
+
+/// Checks the node at `path` of a schema being parsed against the option of [meta_schema] for its type, nodes of
+/// types that the meta schema does not know (plugins, misspelled types) are left to the parser
+pub(crate) fn check_node(table: &toml::Table, path: &SchemaPath) -> Result<(), String> {
+    let TomlSchema::Alternative(options) = meta_schema() else {
+        unreachable!("the meta schema is an alternative")
+    };
+    let type_value = table.get("type").cloned().unwrap_or_else(|| Value::String("table".to_string()));
+    let option = options.iter().find(|option| match option.structure() {
+        TomlSchema::Table(node) => node.entries.get("type").is_some_and(|(schema, _)| schema.check(&type_value).is_ok()),
+        _ => false
+    });
+    let Some(option) = option else {
+        return Ok(())
+    };
+    let node = Value::Table(table.clone());
+    let at = match path.is_empty() {
+        true => String::new(),
+        false => format!(" at {}", path)
+    };
+    option.check(&node).map_err(|e| match e {
+        // the same error as an unknown key found by the parser
+        SchemaError::TableMiss { key, .. } => format!("Unknown key '{}' in schema{}", key, at),
+        e => format!("Schema{} does not match the schema format: {}", at, e.compact())
+    })
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn schema_files() {
+        for source in [
+            META_SCHEMA_SOURCE,
+            include_str!("../test_files/test_schema.toml"),
+            include_str!("../schemas/cargo.toml"),
+            include_str!("../schemas/pyproject.toml"),
+            include_str!("../schemas/rustfmt.toml"),
+            include_str!("../schemas/rust_toolchain.toml"),
+        ] {
+            let table: toml::Table = source.parse().unwrap();
+            if let Err(e) = meta_schema().check(&Value::Table(table.clone())) {
+                panic!("{}", e.verbose());
+            }
+            TomlSchema::from_table_strict(&table).unwrap();
+        }
+    }
+
+    #[test]
+    fn strict_format_errors() {
+        for (schema, error) in [
+            ("port = {type = 'int', min = '1'}", "Schema at port does not match the schema format: min: Expected int but got string"),
+            ("tags = {type = 'array', merge = 'appnd'}", "Schema at tags does not match the schema format: merge: Value String(\"appnd\")"),
+            ("[server]\nextras = [{key = 'a', schema = 'int', key_typ = 'int'}]", "No extra matched key_typ"),
+            ("type = 'table'\nkey_case = 'upper'", "Schema does not match the schema format: key_case"),
+        ] {
+            let e = TomlSchema::parse_strict(schema).unwrap_err();
+            assert!(e.contains(error), "{}", e);
+        }
+        // user-defined types are left to their plugin
+        assert!(TomlSchema::parse_strict("x = {type = 'widget', x = 1}").unwrap_err().contains("Invalid schema type widget"));
+    }
+}
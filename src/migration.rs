@@ -0,0 +1,325 @@
+use crate::*;
+
+
+/// One transformation of a [Migration], steps whose source is missing from the document are skipped
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationStep {
+    /// `{rename = "server.addr", to = "address"}`, changes the last key of the path and keeps the value in its table
+    Rename{path: SchemaPath, to: String},
+    /// `{move = "port", to = "server.port"}`, missing tables on the way to the destination are created
+    Move{from: SchemaPath, to: SchemaPath},
+    /// `{convert = "port", to = "int"}`, between strings, ints, floats and bools, `"8080"` becomes `8080`
+    Convert{path: SchemaPath, to: SchemaType},
+    /// `{split = "addr", separator = ":", into = ["host", "port"]}`, splits a string into exactly as many strings as
+    /// there are destinations, the original key is removed
+    Split{path: SchemaPath, separator: String, into: Vec<SchemaPath>},
+}
+
+/// The steps that upgrade a document from `version - 1` to `version`, one element of the `migrations` array
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub version: u64,
+    pub steps: Vec<MigrationStep>,
+}
+
+/// The migrations between the versions of a schema, read from a table like
+///
+/// ```toml
+/// [[migrations]]
+/// version = 2
+/// steps = [{rename = "addr", to = "address"}, {move = "port", to = "server.port"}]
+/// ```
+///
+/// [Migrations::migrate] upgrades old documents before they are checked against the latest schema
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Migrations {
+    migrations: Vec<Migration>,
+}
+
+
+impl MigrationStep {
+    /// Parses one element of the `steps` array of a migration
+    pub(crate) fn from_value(value: &Value) -> Result<MigrationStep, String> {
+        let Value::Table(table) = value else {
+            return Err(format!("Migration steps must be tables but got {:?}", value))
+        };
+        let string = |key: &str| match table.get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            other => Err(format!("Migration step {} must be a string but got {:?}", key, other))
+        };
+        let path = |key: &str| parse_path(&string(key)?);
+
+        let (step, keys): (MigrationStep, &[&str]) = if table.contains_key("rename") {
+            (MigrationStep::Rename { path: path("rename")?, to: string("to")? }, &["rename", "to"])
+        } else if table.contains_key("move") {
+            (MigrationStep::Move { from: path("move")?, to: path("to")? }, &["move", "to"])
+        } else if table.contains_key("convert") {
+            let to = SchemaType::try_from(string("to")?.as_str())?;
+            if !matches!(to, SchemaType::String | SchemaType::Integer | SchemaType::Float | SchemaType::Bool) {
+                return Err(format!("Values can only be converted to string, int, float or bool but got {}", to))
+            }
+            (MigrationStep::Convert { path: path("convert")?, to }, &["convert", "to"])
+        } else if table.contains_key("split") {
+            let Some(Value::Array(into)) = table.get("into") else {
+                return Err(format!("Migration step into must be an array of paths but got {:?}", table.get("into")))
+            };
+            let into = into.iter().map(|p| match p {
+                Value::String(s) => parse_path(s),
+                other => Err(format!("Migration step into must be an array of paths but got {:?}", other))
+            }).collect::<Result<Vec<_>, _>>()?;
+            (MigrationStep::Split { path: path("split")?, separator: string("separator")?, into }, &["split", "separator", "into"])
+        } else {
+            return Err(format!("Migration steps must have a rename, move, convert or split key but got {:?}", table))
+        };
+
+        if let Some(key) = table.keys().find(|k| !keys.contains(&k.as_str())) {
+            return Err(format!("Unknown key '{}' in migration step", key))
+        }
+        Ok(step)
+    }
+
+    /// Applies this step to `doc`
+    pub fn apply(&self, doc: &mut Value) -> Result<(), String> {
+        match self {
+            MigrationStep::Rename { path, to } => {
+                let mut target = path.clone();
+                target.pop();
+                target.push(PathSegment::Key(to.clone()));
+                move_value(doc, path, &target)
+            },
+            MigrationStep::Move { from, to } => move_value(doc, from, to),
+            MigrationStep::Convert { path, to } => {
+                let Some(value) = path.value_in_mut(doc) else {return Ok(())};
+                *value = convert(value, *to).ok_or_else(|| format!("Cannot convert {} at {} to {}", value, path, to))?;
+                Ok(())
+            },
+            MigrationStep::Split { path, separator, into } => {
+                let Some(value) = path.value_in(doc) else {return Ok(())};
+                let Value::String(s) = value else {
+                    return Err(format!("Cannot split {} at {}, it is not a string", value, path))
+                };
+                let parts: Vec<String> = s.split(separator.as_str()).map(String::from).collect();
+                if parts.len() != into.len() {
+                    return Err(format!("Splitting {:?} at {} gives {} parts instead of {}", s, path, parts.len(), into.len()))
+                }
+                remove_value(doc, path);
+                for (target, part) in into.iter().zip(parts) {
+                    insert_value(doc, target, Value::String(part))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Migration {
+    /// Parses one element of the `migrations` array
+    pub(crate) fn from_value(value: &Value) -> Result<Migration, String> {
+        let Value::Table(table) = value else {
+            return Err(format!("Migrations must be tables but got {:?}", value))
+        };
+        if let Some(key) = table.keys().find(|k| !["version", "steps"].contains(&k.as_str())) {
+            return Err(format!("Unknown key '{}' in migration", key))
+        }
+        let version = match table.get("version") {
+            Some(Value::Integer(v)) if *v > 0 => *v as u64,
+            other => return Err(format!("Migration version must be a positive int but got {:?}", other))
+        };
+        let steps = match table.get("steps") {
+            Some(Value::Array(steps)) => steps.iter().map(MigrationStep::from_value).collect::<Result<_, _>>()?,
+            None => Vec::new(),
+            Some(other) => return Err(format!("Migration steps must be an array but got {:?}", other))
+        };
+        Ok(Migration { version, steps })
+    }
+}
+
+impl Migrations {
+    /// Reads the `migrations` array of `table`, other keys are ignored so migrations can live next to other metadata
+    pub fn from_table(table: &toml::Table) -> Result<Migrations, String> {
+        let mut migrations = match table.get("migrations") {
+            Some(Value::Array(arr)) => arr.iter().map(Migration::from_value).collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+            Some(other) => return Err(format!("migrations must be an array of tables but got {:?}", other))
+        };
+        migrations.sort_by_key(|m| m.version);
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].version == pair[1].version) {
+            return Err(format!("Version {} has more than one migration", pair[0].version))
+        }
+        Ok(Migrations { migrations })
+    }
+
+    /// The migrations, sorted by version
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// The version documents are upgraded to, `None` when there are no migrations
+    pub fn latest(&self) -> Option<u64> {
+        self.migrations.last().map(|m| m.version)
+    }
+
+    /// Upgrades a document written for `from_version` by applying the migrations of every later version in order,
+    /// returns the version of the upgraded document
+    ///
+    /// a missing version in between fails before the document is changed, a failing step leaves the document
+    /// partially migrated
+    pub fn migrate(&self, doc: &mut Value, from_version: u64) -> Result<u64, String> {
+        let pending: Vec<&Migration> = self.migrations.iter().filter(|m| m.version > from_version).collect();
+        for (expected, migration) in (from_version + 1..).zip(&pending) {
+            if migration.version != expected {
+                return Err(format!("There is no migration from version {} to {}", expected - 1, expected))
+            }
+        }
+        let mut version = from_version;
+        for migration in pending {
+            for step in &migration.steps {
+                step.apply(doc).map_err(|e| format!("Migration to version {} failed: {}", migration.version, e))?;
+            }
+            version = migration.version;
+        }
+        Ok(version)
+    }
+}
+
+
+/// Parses a path of a migration step, it must end with a key and only contain keys and indices
+fn parse_path(s: &str) -> Result<SchemaPath, String> {
+    let path: SchemaPath = s.parse()?;
+    if !matches!(path.segments().last(), Some(PathSegment::Key(_))) {
+        return Err(format!("Migration path {:?} must end with a key", s))
+    }
+    if path.segments().iter().any(|seg| !matches!(seg, PathSegment::Key(_) | PathSegment::Index(_))) {
+        return Err(format!("Migration path {:?} can only contain keys and indices", s))
+    }
+    Ok(path)
+}
+
+/// Removes the value at `path`, it must end with a key
+fn remove_value(doc: &mut Value, path: &SchemaPath) -> Option<Value> {
+    let (Some(PathSegment::Key(key)), parent) = (path.segments().last(), &path.segments()[..path.len() - 1]) else {
+        return None
+    };
+    SchemaPath::from(parent.to_vec()).value_in_mut(doc)?.as_table_mut()?.remove(key)
+}
+
+/// Inserts a value at `path`, missing tables are created and existing values are never replaced
+fn insert_value(doc: &mut Value, path: &SchemaPath, value: Value) -> Result<(), String> {
+    let mut current = doc;
+    for (i, segment) in path.segments().iter().enumerate() {
+        let last = i + 1 == path.len();
+        current = match (segment, current) {
+            (PathSegment::Key(key), Value::Table(table)) => {
+                if last {
+                    if table.contains_key(key) {
+                        return Err(format!("Cannot write to {}, it already has a value", path))
+                    }
+                    table.insert(key.clone(), value);
+                    return Ok(())
+                }
+                table.entry(key.clone()).or_insert_with(|| Value::Table(toml::Table::new()))
+            },
+            (PathSegment::Index(i), Value::Array(arr)) if !last && *i < arr.len() => &mut arr[*i],
+            _ => return Err(format!("Cannot write to {}, a parent is not a table", path))
+        };
+    }
+    Err(format!("Cannot write to {}", path))
+}
+
+fn move_value(doc: &mut Value, from: &SchemaPath, to: &SchemaPath) -> Result<(), String> {
+    if from == to || from.value_in(doc).is_none() {
+        return Ok(())
+    }
+    if to.value_in(doc).is_some() {
+        return Err(format!("Cannot move {} to {}, it already has a value", from, to))
+    }
+    let value = remove_value(doc, from).expect("the value was found");
+    insert_value(doc, to, value)
+}
+
+/// The value converted to a scalar type, `None` when it does not represent a value of this type
+fn convert(value: &Value, to: SchemaType) -> Option<Value> {
+    match (value, to) {
+        (Value::String(_), SchemaType::String) | (Value::Integer(_), SchemaType::Integer) |
+        (Value::Float(_), SchemaType::Float) | (Value::Boolean(_), SchemaType::Bool) => Some(value.clone()),
+        (Value::String(s), SchemaType::Integer) => s.trim().parse().ok().map(Value::Integer),
+        (Value::String(s), SchemaType::Float) => s.trim().parse().ok().map(Value::Float),
+        (Value::String(s), SchemaType::Bool) => s.trim().parse().ok().map(Value::Boolean),
+        (Value::Array(_) | Value::Table(_), SchemaType::String) => None,
+        (other, SchemaType::String) => Some(Value::String(other.to_string())),
+        (Value::Integer(i), SchemaType::Float) => Some(Value::Float(*i as f64)),
+        (Value::Float(f), SchemaType::Integer) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Some(Value::Integer(*f as i64)),
+        (Value::Integer(i), SchemaType::Bool) if *i == 0 || *i == 1 => Some(Value::Boolean(*i == 1)),
+        _ => None
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrations() -> Migrations {
+        Migrations::from_table(&"
+            [[migrations]]
+            version = 3
+            steps = [
+                {split = 'server.address', separator = ':', into = ['server.host', 'server.port']},
+                {convert = 'server.port', to = 'int'},
+            ]
+
+            [[migrations]]
+            version = 2
+            steps = [
+                {rename = 'addr', to = 'address'},
+                {move = 'address', to = 'server.address'},
+                {convert = 'debug', to = 'bool'},
+            ]
+        ".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrate() {
+        let migrations = migrations();
+        assert_eq!(migrations.latest(), Some(3));
+
+        let mut doc = Value::Table("addr = 'example.com:8080'\ndebug = 'true'\nname = 'x'".parse().unwrap());
+        assert_eq!(migrations.migrate(&mut doc, 1), Ok(3));
+        assert_eq!(doc, Value::Table("
+            debug = true
+            name = 'x'
+            server = {host = 'example.com', port = 8080}
+        ".parse().unwrap()));
+
+        // documents of the latest version are untouched, missing sources are skipped
+        assert_eq!(migrations.migrate(&mut doc, 3), Ok(3));
+        let mut doc = Value::Table("name = 'x'".parse().unwrap());
+        assert_eq!(migrations.migrate(&mut doc, 1), Ok(3));
+
+        let mut doc = Value::Table("server = {address = 'no-port'}".parse().unwrap());
+        migrations.migrate(&mut doc, 2).unwrap_err();
+        let mut doc = Value::Table("addr = 'a:b'\nserver = {address = 'c:d'}".parse().unwrap());
+        migrations.migrate(&mut doc, 1).unwrap_err();
+        let mut doc = Value::Table("server = {address = 'a:b'}".parse().unwrap());
+        migrations.migrate(&mut doc, 2).unwrap_err();
+    }
+
+    #[test]
+    fn invalid_migrations() {
+        let parse = |s: &str| Migrations::from_table(&s.parse().unwrap());
+
+        parse("[[migrations]]\nversion = 2\n[[migrations]]\nversion = 2").unwrap_err();
+        parse("[[migrations]]\nversion = 0").unwrap_err();
+        parse("[[migrations]]\nversion = 2\nsteps = [{convert = 'a', to = 'table'}]").unwrap_err();
+        parse("[[migrations]]\nversion = 2\nsteps = [{rename = 'a', to = 'b', extra = 1}]").unwrap_err();
+        parse("[[migrations]]\nversion = 2\nsteps = [{move = 'a[]', to = 'b'}]").unwrap_err();
+        parse("[[migrations]]\nversion = 2\nsteps = [{delete = 'a'}]").unwrap_err();
+
+        // version 3 cannot be reached from 1 without a migration to 2
+        let gap = parse("[[migrations]]\nversion = 3").unwrap();
+        gap.migrate(&mut Value::Table(toml::Table::new()), 1).unwrap_err();
+        assert_eq!(gap.migrate(&mut Value::Table(toml::Table::new()), 2), Ok(3));
+    }
+}
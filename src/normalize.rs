@@ -0,0 +1,159 @@
+use crate::*;
+
+
+impl TomlSchema {
+
+    /// A simpler schema that matches the same values, every alternative in the schema is rewritten so that
+    /// - alternatives nested in an alternative are replaced by their options
+    /// - the options after an `anything` are removed, the first matching option is the one that counts
+    /// - adjacent `exact` options without `epsilon` are merged into one [TomlSchema::Enum]
+    /// - an alternative with a single option is replaced by that option
+    ///
+    /// errors may be worded differently, a merged enum reports one [SchemaError::EnumMiss] instead of one miss per value
+    pub fn normalize(&self) -> TomlSchema {
+        match self {
+            TomlSchema::Alternative(options) => normalize_options(options),
+            TomlSchema::Table(table_schema) => {
+                let entries = table_schema.entries.iter()
+                    .map(|(k, (schema, default))| (k.clone(), (schema.normalize(), default.clone())))
+                    .collect();
+                let extras = table_schema.extras.iter()
+                    .map(|extra| TableEntry { value: extra.value.normalize(), ..extra.clone() })
+                    .collect();
+                TomlSchema::Table(Arc::new(TableSchema { entries, extras, ..(**table_schema).clone() }))
+            },
+            TomlSchema::Map(map) => TomlSchema::Map(Arc::new(MapSchema { value: map.value.normalize(), ..(**map).clone() })),
+            TomlSchema::Array { cond, min, max, multiple_of, unique_by, append } => TomlSchema::Array {
+                cond: Arc::new(cond.normalize()), min: *min, max: *max, multiple_of: *multiple_of, unique_by: unique_by.clone(),
+                append: *append
+            },
+            TomlSchema::Annotated { schema, extensions } => TomlSchema::Annotated {
+                schema: Arc::new(schema.normalize()), extensions: extensions.clone()
+            },
+            TomlSchema::Defaulted { schema, env, merge } => TomlSchema::Defaulted {
+                schema: Arc::new(schema.normalize()), env: env.clone(), merge: *merge
+            },
+            TomlSchema::Custom { schema, validator } => TomlSchema::Custom {
+                schema: Arc::new(schema.normalize()), validator: validator.clone()
+            },
+            TomlSchema::Graded { schema, severity } => TomlSchema::Graded {
+                schema: Arc::new(schema.normalize()), severity: *severity
+            },
+            other => other.clone()
+        }
+    }
+}
+
+
+/// The normalized form of an alternative of `options`
+fn normalize_options(options: &[TomlSchema]) -> TomlSchema {
+    let mut flat = Vec::new();
+    flatten(options, &mut flat);
+
+    // options after an `anything` are never reached
+    if let Some(i) = flat.iter().position(|option| matches!(option, TomlSchema::Anything)) {
+        flat.truncate(i + 1);
+    }
+
+    let mut merged: Vec<TomlSchema> = Vec::with_capacity(flat.len());
+    let mut values: Vec<Value> = Vec::new();
+    for option in flat {
+        match option {
+            TomlSchema::Exact { value, epsilon: 0.0 } => push_unique(&mut values, value),
+            TomlSchema::Enum(options) => options.iter().for_each(|value| push_unique(&mut values, value.clone())),
+            other => {
+                flush_values(&mut values, &mut merged);
+                merged.push(other);
+            }
+        }
+    }
+    flush_values(&mut values, &mut merged);
+
+    match merged.len() {
+        1 => merged.pop().unwrap(),
+        _ => TomlSchema::Alternative(Arc::new(AlternativeSchema::new(merged)))
+    }
+}
+
+/// The normalized options of an alternative, with the options of nested alternatives in their place
+fn flatten(options: &[TomlSchema], out: &mut Vec<TomlSchema>) {
+    for option in options {
+        match option {
+            TomlSchema::Alternative(nested) => flatten(nested, out),
+            other => out.push(other.normalize())
+        }
+    }
+}
+
+fn push_unique(values: &mut Vec<Value>, value: Value) {
+    if !values.iter().any(|v| values_match(v, &value, 0.0)) {
+        values.push(value);
+    }
+}
+
+/// Ends a run of exact values, a single value stays an `exact` schema
+fn flush_values(values: &mut Vec<Value>, out: &mut Vec<TomlSchema>) {
+    match values.len() {
+        0 => (),
+        1 => out.push(TomlSchema::Exact { value: values.pop().unwrap(), epsilon: 0.0 }),
+        _ => out.push(TomlSchema::Enum(std::mem::take(values).into()))
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_alternatives() {
+        let schema: TomlSchema = "
+            [level]
+            type = 'alternative'
+            options = [
+                {type = 'alternative', options = [{type = 'exact', value = 1}, {type = 'exact', value = 2}]},
+                {type = 'exact', values = [2, 3]},
+                'string',
+                'anything',
+                'int',
+            ]
+            [port]
+            type = 'alternative'
+            options = [{type = 'alternative', options = [{type = 'int', min = 1}]}]
+            [list]
+            type = 'array'
+            child = {type = 'alternative', options = ['bool']}
+        ".parse().unwrap();
+        let normalized = schema.normalize();
+        let TomlSchema::Table(table) = &normalized else {panic!("not a table: {:?}", normalized)};
+
+        let TomlSchema::Alternative(options) = &table.entries["level"].0 else {panic!("{:?}", table.entries["level"])};
+        assert_eq!(options.len(), 3);
+        let TomlSchema::Enum(values) = &options[0] else {panic!("{:?}", options[0])};
+        assert_eq!(&values[..], [Value::Integer(1), Value::Integer(2), Value::Integer(3)]);
+        assert!(matches!(options[1], TomlSchema::String { .. }));
+        assert!(matches!(options[2], TomlSchema::Anything));
+
+        assert!(matches!(table.entries["port"].0, TomlSchema::Integer { min: 1, .. }));
+        let TomlSchema::Array { cond, .. } = &table.entries["list"].0 else {panic!("{:?}", table.entries["list"])};
+        assert!(matches!(**cond, TomlSchema::Bool { .. }));
+
+        for doc in ["level = 3\nport = 1\nlist = []", "level = 1.5\nport = 2\nlist = [true]", "level = 0\nport = 0\nlist = []"] {
+            let doc = Value::Table(doc.parse().unwrap());
+            assert_eq!(schema.check(&doc).is_ok(), normalized.check(&doc).is_ok(), "{}", doc);
+        }
+    }
+
+    #[test]
+    fn exact_values() {
+        let schema: TomlSchema = "mode = {type = 'exact', values = ['fast', 'slow']}".parse().unwrap();
+        schema.check_str("mode = 'slow'").unwrap();
+        let err = schema.check_str("mode = 'medium'").unwrap_err();
+        assert!(err.contains("is not one of"), "{}", err);
+
+        "mode = {type = 'exact', values = []}".parse::<TomlSchema>().unwrap_err();
+        "mode = {type = 'exact', value = 1, values = [1]}".parse::<TomlSchema>().unwrap_err();
+    }
+}
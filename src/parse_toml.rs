@@ -1,18 +1,77 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
 use toml::Table;
 
 use crate::*;
 
 
+thread_local! {
+    /// The end of the time budget of the validation running on this thread, see [TomlSchema::check_with_budget]
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// The deadline of the validation running on this thread, if it has one
+pub(crate) fn current_deadline() -> Option<Instant> {
+    DEADLINE.with(|d| d.get())
+}
+
+/// Sets the deadline of this thread until it is dropped, nested budgets can only make the deadline earlier
+pub(crate) struct DeadlineGuard(Option<Instant>);
+
+impl DeadlineGuard {
+    pub(crate) fn set(deadline: Option<Instant>) -> Self {
+        let previous = current_deadline();
+        let deadline = match (previous, deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b)
+        };
+        DEADLINE.with(|d| d.set(deadline));
+        DeadlineGuard(previous)
+    }
+}
+
+impl Drop for DeadlineGuard {
+    fn drop(&mut self) {
+        DEADLINE.with(|d| d.set(self.0));
+    }
+}
+
+
+/// The entries without a default that are not in `table`, in the order of the entries so that the first one is always
+/// the same
+pub(crate) fn missing_keys<'s, 't>(entries: &'s TableEntries, table: &'t Table) -> impl Iterator<Item = (&'s str, &'s TomlSchema)> + use<'s, 't> {
+    entries.iter()
+        .filter(|(key, (_, default))| default.is_none() && !table.contains_key(key.as_str()))
+        .map(|(key, (schema, _))| (key.as_str(), schema))
+}
+
+
+/// Whether `val` is the exact value `expected`, tables and arrays are compared recursively and floats are equal when
+/// they differ by at most `epsilon` or are both NaN, with an `epsilon` of `0.0` `-0.0` is equal to `0.0`
+pub fn values_match(expected: &Value, val: &Value, epsilon: f64) -> bool {
+    match (expected, val) {
+        (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= epsilon,
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_match(a, b, epsilon)),
+        (Value::Table(a), Value::Table(b)) => {
+            a.len() == b.len() && a.iter().all(|(k, a)| b.get(k).is_some_and(|b| values_match(a, b, epsilon)))
+        },
+        (a, b) => a == b
+    }
+}
+
+
 impl TomlSchema {
 
     /// An internal function for matching table entries
-    fn find_extra_match<'s,'v>(extras: &'s Vec<TableEntry>, key: &'v String, value: &'v Value) -> Result<(), Vec<SchemaError<'s,'v>>>
+    pub(crate) fn find_extra_match<'s,'v>(extras: &'s [TableEntry], policy: ExtrasPolicy, key: &'v str, value: &'v Value, depth: usize) -> Result<(), Vec<SchemaError<'s,'v>>>
     {
         let mut errors = Vec::new();
         
-        for TableEntry { key: ex_key, value: ex_schema } in extras.iter() {
-            if ex_key.is_match(key) {
-                match ex_schema.check(value) {
+        for TableEntry { key_type, value: ex_schema, .. } in policy.candidates(extras, key) {
+            if !key_type.accepts(key) {
+                errors.push(SchemaError::KeyTypeMiss { key, expected: key_type });
+            } else {
+                match ex_schema.check_depth(value, depth) {
                     Ok(()) => {
                         return Ok(())
                     },
@@ -20,74 +79,134 @@ impl TomlSchema {
                 }
             }
         }
-        return Err(errors)
+        Err(errors)
     }
 
 
-    fn check_string<'s,'v>(regex: &'s Regex, s: &'v String) -> Result<(), SchemaError<'s,'v>> {
-        if regex.is_match(s) {Ok(())} 
+    fn check_string<'s,'v>(regex: &'s Pattern, rules: &'s StringRules, s: &'v String) -> Result<(), SchemaError<'s,'v>> {
+        if regex.is_match(&rules.canonical(s)) {rules.check(s)} 
         else {Err(SchemaError::RegexMiss{string: s, re: regex.as_str()})}
     }
 
-    fn check_int(i : i64, min: i64, max: i64) -> Result<(), SchemaError<'static, 'static>> {
+    fn check_int(i : i64, min: i64, max: i64, width: Option<IntWidth>) -> Result<(), SchemaError<'static, 'static>> {
         if i >= min && i <= max {Ok(())}
-        else {Err(SchemaError::IntMiss { val: i, min: min, max: max })}
+        else if let Some(width) = width.filter(|width| !width.contains(i)) {Err(SchemaError::IntWidthMiss { val: i, width })}
+        else {Err(SchemaError::IntMiss { val: i, min, max })}
     }
 
     fn check_float(f : f64, min: f64, max: f64, nan_ok: bool) -> Result<(), SchemaError<'static, 'static>> {
         if (nan_ok && f.is_nan()) || (f >= min && f <= max) {Ok(())}
-        else {Err(SchemaError::FloatMiss { val: f, min: min, max: max, nan_ok: nan_ok })}
+        else {Err(SchemaError::FloatMiss { val: f, min, max, nan_ok })}
     }
 
-    fn check_alt<'s,'v>(options: &'s Vec<TomlSchema>, val: &'v Value) -> Result<(), SchemaError<'s,'v>> {
-        let mut errors = Vec::with_capacity(options.len());
-        for schema in options {
-            match schema.check(val) {
+    fn check_alt<'s,'v>(options: &'s AlternativeSchema, val: &'v Value, depth: usize) -> Result<(), SchemaError<'s,'v>> {
+        if options.matches_anything() {
+            return Self::OK
+        }
+        // errors are only kept for the options that were checked and failed, nothing is allocated when the first option
+        // of the type of the value matches
+        let mut failed = Vec::new();
+        for (i, schema) in options.iter().enumerate() {
+            // an option of another type would fail right away, unless the depth is already exhausted
+            if depth > 0 && !options.may_accept(i, val) {
+                continue
+            }
+            match schema.check_depth(val, depth) {
                 Ok(()) => {return Ok(());},
-                Err(e) => {errors.push(e);}
+                Err(e) => {failed.push((i, e));}
             }
         }
+        let mut failed = failed.into_iter().peekable();
+        let errors = options.iter().enumerate().map(|(i, schema)| match failed.next_if(|(j, _)| *j == i) {
+            Some((_, e)) => e,
+            None => SchemaError::TypeMismatch { expected: schema.into(), got: val.into() }
+        }).collect();
         Err(SchemaError::AlternativeMiss {val, errors})
     }
 
+    fn check_exact<'s,'v>(expected: &'s Value, epsilon: f64, val: &'v Value) -> Result<(), SchemaError<'s,'v>> {
+        match (expected, val) {
+            _ if values_match(expected, val, epsilon) => Ok(()),
+            (Value::Float(expected), Value::Float(val)) => {
+                Err(SchemaError::FloatDeltaMiss { val: *val, expected: *expected, delta: (val - expected).abs(), epsilon })
+            },
+            _ => Err(SchemaError::ExactMiss { val, expected })
+        }
+    }
+
+    fn check_enum<'s,'v>(expected: &'s [Value], val: &'v Value) -> Result<(), SchemaError<'s,'v>> {
+        if expected.iter().any(|expected| values_match(expected, val, 0.0)) {Ok(())}
+        else {Err(SchemaError::EnumMiss { val, expected })}
+    }
+
     const OK: Result<(), SchemaError<'static,'static>> = Ok(());
 
-    fn check_array<'s,'v>(child: &'s TomlSchema, min: usize, max: usize, arr: &'v Vec<Value>) -> Result<(), SchemaError<'s,'v>> {
+    fn check_array<'s,'v>(
+        child: &'s TomlSchema, min: usize, max: usize, multiple_of: u32,
+        unique_by: Option<&'s str>, arr: &'v [Value], depth: usize
+    ) -> Result<(), SchemaError<'s,'v>> {
         if arr.len() < min || arr.len() > max {
-            return Err(SchemaError::ArrayCount { count: arr.len(), min: min, max: max })
+            return Err(SchemaError::ArrayCount { count: arr.len(), min, max })
         }
+        if !arr.len().is_multiple_of(multiple_of as usize) {
+            return Err(SchemaError::ArrayStep { count: arr.len(), multiple_of: multiple_of as usize })
+        }
+        Self::check_elements(child, arr, depth)?;
+        match unique_by.and_then(|key| Some((key, Self::find_duplicate(key, arr)?))) {
+            Some((key, (first, second))) => Err(SchemaError::DuplicateValue { key, value: &arr[second][key], first, second }),
+            None => Ok(())
+        }
+    }
+
+    fn check_elements<'s,'v>(child: &'s TomlSchema, arr: &'v [Value], depth: usize) -> Result<(), SchemaError<'s,'v>> {
         for val in arr.iter() {
-            if let Err(e) = child.check(val) {
+            if let Err(e) = child.check_depth(val, depth) {
                 return Err(SchemaError::ArrayMiss{ value: val , error: Box::new(e) })
             }
         }
         Ok(())
     }
 
-    fn check_table<'s,'v>(
-        entries: &'s HashMap<String, (TomlSchema, Option<Value>)>, 
-        extras: &'s Vec<TableEntry>, min: usize, max: usize, 
-        table: &'v Table
-    ) -> Result<(), SchemaError<'s,'v>> {
+    /// The indices of the first two elements that have the same value at `key`, elements that are not tables
+    /// or do not have the key are never duplicates
+    pub(crate) fn find_duplicate(key: &str, arr: &[Value]) -> Option<(usize, usize)> {
+        // values are not hashable, their TOML representation is used instead
+        let mut seen = HashMap::new();
+        for (i, value) in arr.iter().enumerate() {
+            let Some(value) = value.as_table().and_then(|t| t.get(key)) else {continue};
+            if let Some(first) = seen.insert(value.to_string(), i) {
+                return Some((first, i))
+            }
+        }
+        None
+    }
+
+    fn check_table_keys<'s,'v>(schema: &'s TableSchema, table: &'v Table, depth: usize, root: bool) -> Result<(), SchemaError<'s,'v>> {
+        let TableSchema { entries, extras, min, max, policy, .. } = schema;
+        let (min, max, policy) = (*min, *max, *policy);
 
         let mut found_extras = 0;
-        let mut req_entries = HashSet::with_capacity(entries.len());
-        
-        //consider all entries required
-        for (k,_) in entries.iter() {req_entries.insert(k);}
+        // the keys are not collected, counting the required ones is enough to know that none is missing
+        let mut found_required = 0;
 
         for (key,value) in table {
             match entries.get(key) {
                 // first try to match an explicit entry
-                Some((schema, _)) => {
-                    if let Err(e) = schema.check(value) {
+                Some((schema, default)) => {
+                    if let Err(e) = schema.check_depth(value, depth) {
                         return Err(SchemaError::AtKey { key, error: Box::new(e)})
                     }
-                    req_entries.remove(key);
+                    if default.is_none() {found_required += 1;}
+                    if policy.counts_entry(extras, key) {found_extras += 1;}
                 },
+                // the header that names the schema of the document, only at its root
+                None if root && header::is_header(key, value) => (),
                 // then one of the regex-based extras
                 None => {
-                    match Self::find_extra_match(extras, key, value) {
+                    if !policy.accepts_case(key) {
+                        return Err(SchemaError::KeyCaseMiss { key, expected: policy.key_case.expect("only keys with a case are rejected") })
+                    }
+                    match Self::find_extra_match(extras, policy, key, value, depth) {
                         Ok(()) => {found_extras += 1;}
                         Err(errs) => {
                             return Err(SchemaError::TableMiss { key, value, errors: errs })
@@ -96,62 +215,298 @@ impl TomlSchema {
                 }
             }
         }
-        if found_extras >= min && found_extras <= max {Ok(())} 
-        else {Err(SchemaError::TableCount { count: found_extras, min, max })}
+        if found_extras < min || found_extras > max {
+            return Err(SchemaError::TableCount { count: found_extras, min, max })
+        }
+        if found_required == entries.required_len() {
+            return Self::OK
+        }
+        match missing_keys(entries, table).next() {
+            Some((key, schema)) => Err(SchemaError::MissingKey { key, expected: schema.into() }),
+            None => Self::OK
+        }
+    }
+
+    fn check_map<'s,'v>(map: &'s MapSchema, table: &'v Table, depth: usize) -> Result<(), SchemaError<'s,'v>> {
+        let MapSchema { key: key_re, key_type, value: child, min, max } = map;
+        let (min, max) = (*min, *max);
+        if table.len() < min || table.len() > max {
+            return Err(SchemaError::MapCount { count: table.len(), min, max })
+        }
+        for (key, value) in table {
+            if !key_re.is_match(key) {
+                return Err(SchemaError::KeyMiss { key, re: key_re.as_str() })
+            }
+            if !key_type.accepts(key) {
+                return Err(SchemaError::KeyTypeMiss { key, expected: key_type })
+            }
+            if let Err(e) = child.check_depth(value, depth) {
+                return Err(SchemaError::AtKey { key, error: Box::new(e) })
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the assertions and requirements between the keys of a table
+    fn check_siblings<'s,'v>(schema: &'s TableSchema, table: &'v Table) -> Result<(), SchemaError<'s,'v>> {
+        let TableSchema { entries, assertions, requirements, .. } = schema;
+        for assertion in assertions {
+            if let Err((left, right)) = assertion.holds(table, entries) {
+                return Err(SchemaError::AssertionFailed { assertion, left, right })
+            }
+        }
+        for requirement in requirements {
+            if let Some(key) = requirement.missing(table, entries) {
+                return Err(SchemaError::MissingRequired { key, requirement })
+            }
+        }
+        Self::OK
     }
 
     /// This checks that a toml value matches a schema, without modifying/copying, the returned error
     /// cannot outlive the passed toml value or self since it contains references to them
+    /// 
+    /// nesting is limited to [DEFAULT_MAX_DEPTH] levels, see [TomlSchema::check_with_max_depth]
     pub fn check<'s,'v>(&'s self, data: &'v toml::Value) -> Result<(), SchemaError<'s,'v>> {
-        match (self, data) {
-            (TomlSchema::String {regex}, Value::String(s)) =>            {Self::check_string(regex, s)},
-            (TomlSchema::Integer { min, max }, Value::Integer(i)) =>     {Self::check_int(*i, *min, *max)}
-            (TomlSchema::Float { min, max, nan_ok }, Value::Float(f)) => {Self::check_float(*f, *min, *max, *nan_ok)}
-            (TomlSchema::Date , Value::Datetime(_)) =>                   {Self::OK},
-            (TomlSchema::Bool, Value::Boolean(_)) =>                     {Self::OK},
-            (TomlSchema::Alternative(opts), any) =>                      {Self::check_alt(opts, any)},
-            (TomlSchema::Array { cond, min, max }, Value::Array(arr)) => {Self::check_array(cond, *min, *max, arr)},
+        self.check_root(data, DEFAULT_MAX_DEPTH)?;
+        self.check_references(data)?;
+        self.check_context_validators(data, None)
+    }
+
+    /// Same as [TomlSchema::check] but fails with [SchemaError::DepthExceeded] if checking needs to go through
+    /// more than `max_depth` nested schemas, this protects against stack overflows on maliciously deep documents
+    pub fn check_with_max_depth<'s,'v>(&'s self, data: &'v toml::Value, max_depth: usize) -> Result<(), SchemaError<'s,'v>> {
+        self.check_root(data, max_depth)?;
+        self.check_references(data)?;
+        self.check_context_validators(data, None)
+    }
+
+    /// Same as [TomlSchema::check] but fails with [SchemaError::BudgetExceeded] if checking takes longer than `budget`,
+    /// with [TomlSchema::check_with_max_depth] this bounds the resources used to validate untrusted documents
+    ///
+    /// the budget is checked before entering each sub-schema, a single value is always checked in linear time
+    pub fn check_with_budget<'s,'v>(&'s self, data: &'v toml::Value, budget: Duration) -> Result<(), SchemaError<'s,'v>> {
+        let _guard = DeadlineGuard::set(Instant::now().checked_add(budget));
+        self.check_root(data, DEFAULT_MAX_DEPTH)?;
+        self.check_references(data)?;
+        self.check_context_validators(data, None)
+    }
+
+    /// Same as [TomlSchema::check_depth] for the root of a document, the only table that accepts a `$schema` header
+    pub(crate) fn check_root<'s,'v>(&'s self, data: &'v toml::Value, depth: usize) -> Result<(), SchemaError<'s,'v>> {
+        self.check_node(data, depth, true)
+    }
+
+    /// The recursive checker, `depth` is the number of nested schemas that may still be entered
+    pub(crate) fn check_depth<'s,'v>(&'s self, data: &'v toml::Value, depth: usize) -> Result<(), SchemaError<'s,'v>> {
+        self.check_node(data, depth, false)
+    }
+
+    fn check_node<'s,'v>(&'s self, data: &'v toml::Value, depth: usize, root: bool) -> Result<(), SchemaError<'s,'v>> {
+        let Some(depth) = depth.checked_sub(1) else {
+            return Err(SchemaError::DepthExceeded)
+        };
+        // scalars take constant time (or linear time for regexes), the budget is only looked at on tables and arrays
+        let composite = matches!(data, Value::Table(_) | Value::Array(_));
+        if composite && current_deadline().is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(SchemaError::BudgetExceeded)
+        }
+        let memo_key = memo::memo_key(self, data, depth);
+        if memo_key.is_some_and(memo::is_valid) {
+            return Self::OK
+        }
+
+        let result = match (self, data) {
+            (TomlSchema::String {regex, rules, ..}, Value::String(s)) => {Self::check_string(regex, rules, s)},
+            (TomlSchema::Integer { min, max, width, .. }, Value::Integer(i)) => {Self::check_int(*i, *min, *max, *width)}
+            (TomlSchema::Integer { min, max, width, coerce: true }, Value::String(s)) => match coerce::parse_int(s) {
+                Some(i) => Self::check_int(i, *min, *max, *width),
+                None => Err(SchemaError::CoerceMiss { string: s, expected: SchemaType::Integer })
+            },
+            (TomlSchema::Float { min, max, nan_ok, .. }, Value::Float(f)) => {Self::check_float(*f, *min, *max, *nan_ok)}
+            (TomlSchema::Float { min, max, nan_ok, coerce: true }, Value::String(s)) => match coerce::parse_float(s) {
+                Some(f) => Self::check_float(f, *min, *max, *nan_ok),
+                None => Err(SchemaError::CoerceMiss { string: s, expected: SchemaType::Float })
+            },
+            (TomlSchema::Date(date), Value::Datetime(d)) =>              {date.check(d)},
+            (TomlSchema::Bool { .. }, Value::Boolean(_)) =>              {Self::OK},
+            (TomlSchema::Bool { coerce: true }, Value::String(s)) => match coerce::parse_bool(s) {
+                Some(_) => Self::OK,
+                None => Err(SchemaError::CoerceMiss { string: s, expected: SchemaType::Bool })
+            },
+            (TomlSchema::Alternative(opts), any) =>                      {Self::check_alt(opts, any, depth)},
+            (TomlSchema::Array { cond, min, max, multiple_of, unique_by, .. }, Value::Array(arr)) => {
+                Self::check_array(cond, *min, *max, *multiple_of, unique_by.as_deref(), arr, depth)
+            },
+            (TomlSchema::Map(map), Value::Table(t)) => {
+                Self::check_map(map, t, depth)
+            },
             (TomlSchema::Anything, _) =>                                 {Self::OK},
+            (TomlSchema::Exact{value, epsilon}, any) =>                  {Self::check_exact(value, *epsilon, any)},
+            (TomlSchema::Enum(expected), any) =>                         {Self::check_enum(expected, any)},
+            (TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. }, any) => {schema.check_node(any, depth + 1, root)},
+            (TomlSchema::Custom { schema, validator }, any) => {
+                schema.check_node(any, depth + 1, root)?;
+                validator.check(any)
+            },
+            (TomlSchema::Graded { schema, severity: Severity::Error }, any) => {schema.check_node(any, depth + 1, root)},
+            (TomlSchema::Graded { .. }, _) =>                            {Self::OK},
+            (TomlSchema::Plugin(plugin), any) =>                         {plugin.check(any)},
             
-            (TomlSchema::Table { entries, extras, min, max }, Value::Table(table)) => {
-                Self::check_table(entries, extras, *min, *max, table)
+            (TomlSchema::Table(schema), Value::Table(table)) => {
+                Self::check_table_keys(schema, table, depth, root)?;
+                Self::check_siblings(schema, table)
             }
             
             (s,v) => Err(SchemaError::TypeMismatch{expected: s.into(), got: v.into()})
+        };
+        if let (Some(key), Ok(())) = (memo_key, &result) {
+            memo::remember(key);
         }
+        result
     }
 
 
 
-    /// Check that the data matches the schema and fills in the default values as needed
+    /// Check that the data matches the schema and fills in the default values as needed, placeholders like
+    /// `${server.host}` in strings of the defaults are then replaced, see [`interpolate`](crate#string)
     /// 
     /// important note: default values are checked against the schema and validation will fail if
     /// a default value does not match the schema, this means that [TomlSchema::check_and_complete] can fail
     /// even when [TomlSchema::check] passes
     pub fn check_and_complete<'s, 'v>(&'s self, data: &'v mut toml::Value) -> Result<(),SchemaError<'s,'v>>
     {
-        match (self, data) {
-            (TomlSchema::String {regex}, Value::String(s)) =>            {Self::check_string(regex, s)},
-            (TomlSchema::Integer { min, max }, Value::Integer(i)) =>     {Self::check_int(*i, *min, *max)},
-            (TomlSchema::Float { min, max, nan_ok }, Value::Float(f)) => {Self::check_float(*f, *min, *max, *nan_ok)},
-            (TomlSchema::Date , Value::Datetime(_)) =>                   {Self::OK},
-            (TomlSchema::Bool, Value::Boolean(_)) =>                     {Self::OK},
-            (TomlSchema::Alternative(opts), any) =>                      {Self::check_alt(opts, any)},
-            (TomlSchema::Array { cond, min, max }, Value::Array(arr)) => {Self::check_array(cond, *min, *max, arr)},
-            (TomlSchema::Anything, _) =>                                 {Self::OK},
+        let completed = self.complete(data);
+
+        // the document is only read from here on, errors can borrow it
+        let data: &'v Value = data;
+        if let Err((path, error)) = completed {
+            return Err(reference::nest_error(data, &path, error))
+        }
+        self.check_root(data, DEFAULT_MAX_DEPTH)?;
+        self.check_references(data)?;
+        self.check_context_validators(data, None)
+    }
+
+    /// Fills the defaults of `data`, replaces the placeholders of the filled strings, rewrites the strings with `trim`
+    /// or `normalize` and the strings read by schemas with `coerce`, without checking the result, fails with the location of a placeholder that cannot be replaced
+    pub(crate) fn complete(&self, data: &mut Value) -> Result<(), (SchemaPath, SchemaError<'static, 'static>)> {
+        let mut filled = Vec::new();
+        self.fill_defaults(data, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &mut filled);
+        self.interpolate(data, &filled)?;
+        self.canonicalize(data);
+        self.coerce(data);
+        Ok(())
+    }
 
-            (TomlSchema::Table { entries, extras, min, max }, Value::Table(table)) => {
+    /// Inserts the defaults (or environment values) of the missing keys of the tables of `data` and merges table defaults
+    /// with `default_merge`, the paths of the inserted values are added to `filled`
+    ///
+    /// values matched by extras, array elements, map values and inserted defaults are completed with their own schema,
+    /// an alternative completes the value with the first option that matches it before completion, like the check does
+    fn fill_defaults(&self, data: &mut toml::Value, path: &mut SchemaPath, depth: usize, filled: &mut Vec<SchemaPath>)
+    {
+        let Some(depth) = depth.checked_sub(1) else {return};
 
-                //add default values as needed
-                for (key, (_,def_val)) in entries.iter() {
-                    if let (Some(dv), None) = (def_val, table.get(key)) {
-                        table.insert(key.clone(), dv.clone());
+        match (self, data) {
+            (TomlSchema::Annotated { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Graded { schema, .. }, any) => {
+                schema.fill_defaults(any, path, depth, filled)
+            },
+            (TomlSchema::Alternative(options), any) => {
+                if let Some(i) = options.matching(any, depth) {
+                    options[i].fill_defaults(any, path, depth, filled);
+                }
+            },
+            (TomlSchema::Array { cond, .. }, Value::Array(arr)) => {
+                for (i, value) in arr.iter_mut().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    cond.fill_defaults(value, path, depth, filled);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Map(map), Value::Table(table)) => {
+                for (key, value) in table.iter_mut() {
+                    path.push(PathSegment::Key(key.clone()));
+                    map.value.fill_defaults(value, path, depth, filled);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Table(schema), Value::Table(table)) => {
+                let TableSchema { entries, extras, policy, .. } = &**schema;
+                //add default values as needed, the environment first
+                for (key, (schema, def_val)) in entries.iter() {
+                    match (table.get_mut(key), def_val) {
+                        (None, _) => {
+                            if let Some(value) = schema.env_value().or_else(|| def_val.clone()) {
+                                table.insert(key.clone(), value);
+                                filled.push(path.join(PathSegment::Key(key.clone())));
+                            }
+                        },
+                        (Some(Value::Table(present)), Some(Value::Table(default))) if schema.merges_default() => {
+                            path.push(PathSegment::Key(key.clone()));
+                            defaults::merge_default(present, default, path, filled);
+                            path.pop();
+                        },
+                        _ => ()
                     }
                 }
-                Self::check_table(entries, extras, *min, *max, table)
-            }
-            
-            (s,v) => Err(SchemaError::TypeMismatch{expected: s.into(), got: (&*v).into()})
+                // then the values below, through the entry or the extra that matches them
+                for (key, value) in table.iter_mut() {
+                    let schema = match entries.get(key) {
+                        Some((schema, _)) => Some(schema),
+                        None => policy.candidates(extras, key)
+                            .find(|e| e.key_type.accepts(key) && e.value.check_depth(value, depth).is_ok())
+                            .map(|e| &e.value)
+                    };
+                    if let Some(schema) = schema {
+                        path.push(PathSegment::Key(key.clone()));
+                        schema.fill_defaults(value, path, depth, filled);
+                        path.pop();
+                    }
+                }
+            },
+            _ => ()
+        }
+    }
+
+
+
+    /// Same as [TomlSchema::validate] for a table that is not in a [toml::Value], errors are reported with their path
+    ///
+    /// the table is only borrowed, unless the root of the schema is not a table schema or the schema has references
+    /// or validators with a context, these look at the whole document so they check a copy of the table
+    pub fn check_table(&self, table: &Table) -> ValidationReport
+    {
+        match self.unannotated() {
+            TomlSchema::Table(table_schema) if !self.has_references() && !self.has_context_validators() => {
+                let mut report = ValidationReport::default();
+                Self::validate_table(table_schema, table, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH - 1, &mut report);
+                report
+            },
+            _ => self.validate(&Value::Table(table.clone()))
         }
     }
-}
\ No newline at end of file
+
+    /// Checks many documents with this schema, the results are in the same order as `docs`
+    pub fn check_many<'s, 'v>(&'s self, docs: impl IntoIterator<Item = &'v Value>) -> Vec<Result<(), SchemaError<'s, 'v>>>
+    {
+        docs.into_iter().map(|doc| self.check(doc)).collect()
+    }
+
+    /// Same as [TomlSchema::check] but takes the document as TOML text, errors (including TOML syntax errors)
+    /// are returned as strings so they do not borrow the document
+    pub fn check_str(&self, doc: &str) -> Result<(), String>
+    {
+        let value = Value::Table(doc.parse::<Table>().map_err(|e| e.to_string())?);
+        self.check(&value).map_err(|e| format!("{:?}", e))
+    }
+
+    /// Same as [TomlSchema::check_and_complete] but takes the document as TOML text and returns the
+    /// completed document as TOML text
+    pub fn complete_str(&self, doc: &str) -> Result<String, String>
+    {
+        let mut value = Value::Table(doc.parse::<Table>().map_err(|e| e.to_string())?);
+        self.check_and_complete(&mut value).map_err(|e| format!("{:?}", e))?;
+        toml::to_string(&value).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,198 @@
+/// One step in a [SchemaPath]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A fixed key of a table
+    Key(String),
+    /// A specific element of an array
+    Index(usize),
+    /// Any element of an array, used for the child schema of an array
+    AnyIndex,
+    /// A table key matched by an extra, the string is the regular expression of the extra
+    Extra(String),
+    /// An option of an alternative, by position
+    Branch(usize),
+}
+
+/// A location in a schema or in a document, as a list of [PathSegment]s starting from the root
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SchemaPath(Vec<PathSegment>);
+
+impl SchemaPath {
+    /// The path of the root
+    pub fn new() -> Self {
+        SchemaPath(Vec::new())
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether this is the path of the root
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.0.push(segment)
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.0.pop()
+    }
+
+    /// Whether `prefix` is this path or one of its ancestors
+    pub fn starts_with(&self, prefix: &SchemaPath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// A new path with one more segment
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    /// The value at this path of a document, only keys and indices can be followed
+    pub(crate) fn value_in<'v>(&self, doc: &'v toml::Value) -> Option<&'v toml::Value> {
+        self.0.iter().try_fold(doc, |value, segment| match segment {
+            PathSegment::Key(k) => value.as_table()?.get(k),
+            PathSegment::Index(i) => value.as_array()?.get(*i),
+            _ => None
+        })
+    }
+
+    /// Same as [SchemaPath::value_in] for a mutable document
+    pub(crate) fn value_in_mut<'v>(&self, doc: &'v mut toml::Value) -> Option<&'v mut toml::Value> {
+        self.0.iter().try_fold(doc, |value, segment| match segment {
+            PathSegment::Key(k) => value.as_table_mut()?.get_mut(k),
+            PathSegment::Index(i) => value.as_array_mut()?.get_mut(*i),
+            _ => None
+        })
+    }
+}
+
+impl From<Vec<PathSegment>> for SchemaPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        SchemaPath(segments)
+    }
+}
+
+/// Writes the path in the syntax of [SchemaPath::from_str](std::str::FromStr), keys that are not bare TOML keys are quoted,
+/// the segments that only appear in schema paths are written `/regex/` for extras (a `/` of the regex is written `\/`)
+/// and `|n` for alternative options
+impl std::fmt::Display for SchemaPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) => {
+                    if i > 0 {f.write_str(".")?;}
+                    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                    if bare {f.write_str(key)?;} else {write!(f, "{:?}", key)?;}
+                },
+                PathSegment::Extra(re) => {
+                    if i > 0 {f.write_str(".")?;}
+                    write!(f, "/{}/", re.replace('/', "\\/"))?;
+                },
+                PathSegment::Index(n) => write!(f, "[{}]", n)?,
+                PathSegment::AnyIndex => f.write_str("[]")?,
+                PathSegment::Branch(n) => write!(f, "|{}", n)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a document path like `server.listeners[0].port`, keys are separated by dots, indices are written in brackets,
+/// `[]` means any element and keys may be quoted like in TOML (`servers."eu.west".port`)
+///
+/// the `/regex/` and `|n` segments of schema paths are also read so that every path written by
+/// [Display](std::fmt::Display) is read back as the same path, `plugins./^x-/|1`
+impl std::str::FromStr for SchemaPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut path = SchemaPath::new();
+        let mut chars = s.chars().peekable();
+        let mut expect_key = true;
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                '[' => {
+                    chars.next();
+                    let index: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    let index = index.trim();
+                    if index.is_empty() {
+                        path.push(PathSegment::AnyIndex);
+                    } else {
+                        let i = index.parse().map_err(|_| format!("Invalid array index '{}' in path {:?}", index, s))?;
+                        path.push(PathSegment::Index(i));
+                    }
+                    expect_key = false;
+                },
+                '|' if !expect_key || path.is_empty() => {
+                    chars.next();
+                    let mut index = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        index.push(c);
+                    }
+                    let i = index.parse().map_err(|_| format!("Invalid option index '{}' in path {:?}", index, s))?;
+                    path.push(PathSegment::Branch(i));
+                    expect_key = false;
+                },
+                '/' if expect_key => {
+                    chars.next();
+                    let mut re = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('/') => break,
+                            Some('\\') if chars.peek() == Some(&'/') => re.push(chars.next().expect("peeked")),
+                            Some(other) => re.push(other),
+                            None => return Err(format!("Unterminated regex in path {:?}", s))
+                        }
+                    }
+                    path.push(PathSegment::Extra(re));
+                    expect_key = false;
+                },
+                '.' if !expect_key => {
+                    chars.next();
+                    expect_key = true;
+                },
+                '"' | '\'' if expect_key => {
+                    chars.next();
+                    let mut key = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(q) if q == c => break,
+                            Some('\\') if c == '"' => key.push(chars.next().ok_or_else(|| format!("Unterminated key in path {:?}", s))?),
+                            Some(other) => key.push(other),
+                            None => return Err(format!("Unterminated key in path {:?}", s))
+                        }
+                    }
+                    path.push(PathSegment::Key(key));
+                    expect_key = false;
+                },
+                _ if expect_key => {
+                    let mut key = String::new();
+                    while let Some(c) = chars.next_if(|c| !matches!(c, '.' | '[' | ']' | '"' | '\'' | '|')) {
+                        key.push(c);
+                    }
+                    if key.is_empty() {
+                        return Err(format!("Empty key in path {:?}", s))
+                    }
+                    path.push(PathSegment::Key(key));
+                    expect_key = false;
+                },
+                _ => return Err(format!("Unexpected '{}' in path {:?}", c, s))
+            }
+        }
+
+        if expect_key && !path.is_empty() {
+            return Err(format!("Path {:?} ends with a '.'", s))
+        }
+        Ok(path)
+    }
+}
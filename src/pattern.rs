@@ -0,0 +1,288 @@
+#[cfg(feature = "regex")]
+use std::sync::OnceLock;
+use crate::*;
+
+
+/// The regular expression of a string schema, of a table extra or of the keys of a map, patterns without regex
+/// metacharacters are compared as strings instead of being compiled
+///
+/// `abc` is found in strings with a substring search, `^abc`, `abc$` and `^abc$` (or `abc` with `full_match`) compare
+/// the start, the end or the whole string and `.*` matches every string, patterns with flags like `case_insensitive`
+/// are always compiled
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: Arc<str>,
+    matcher: Matcher,
+}
+
+/// How a pattern is compared, literals are a range of the text of the pattern
+#[derive(Debug, Clone)]
+enum Matcher {
+    Contains(Span),
+    Prefix(Span),
+    Suffix(Span),
+    Equals(Span),
+    Regex(Arc<LazyRegex>),
+}
+
+/// The bytes `start..end` of a string
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: u32,
+    end: u32,
+}
+
+impl Span {
+    /// The span of `part` in `text`, `part` must be a substring of `text`
+    fn of(text: &str, part: &str) -> Span {
+        let start = part.as_ptr() as usize - text.as_ptr() as usize;
+        Span { start: start as u32, end: (start + part.len()) as u32 }
+    }
+}
+
+/// A regex that is compiled when it is created or the first time it is used, see `ParseOptions::lazy_regex`
+#[cfg(feature = "regex")]
+#[derive(Debug)]
+pub(crate) struct LazyRegex {
+    compiled: OnceLock<Option<Regex>>,
+    size_limit: Option<usize>,
+    dfa_size_limit: Option<usize>,
+}
+
+/// Without the `regex` feature no regex can be created, patterns that are not literals are rejected when parsing
+#[cfg(not(feature = "regex"))]
+#[derive(Debug)]
+pub(crate) enum LazyRegex {}
+
+#[cfg(feature = "regex")]
+impl LazyRegex {
+    /// A regex compiled now, with the size limits of `options`
+    pub(crate) fn compiled(text: &str, options: &ParseOptions) -> Result<LazyRegex, String> {
+        let regex = build_regex(text, options.regex_size_limit, options.regex_dfa_size_limit)?;
+        Ok(LazyRegex { compiled: OnceLock::from(Some(regex)), size_limit: None, dfa_size_limit: None })
+    }
+
+    /// A regex compiled the first time it is used, with the size limits of `options`
+    pub(crate) fn deferred(_text: &str, options: &ParseOptions) -> Result<LazyRegex, String> {
+        Ok(LazyRegex { compiled: OnceLock::new(), size_limit: options.regex_size_limit, dfa_size_limit: options.regex_dfa_size_limit })
+    }
+
+    /// Whether the regex of `text` is found in `s`
+    fn is_match(&self, text: &str, s: &str) -> bool {
+        self.get(text).is_some_and(|re| re.is_match(s))
+    }
+
+    fn is_compiled(&self) -> bool {
+        self.compiled.get().is_some()
+    }
+
+    /// The regex of `text`, compiled now if it was not yet, a regex that does not compile logs an error and is `None`
+    fn get(&self, text: &str) -> Option<&Regex> {
+        self.compiled.get_or_init(|| match build_regex(text, self.size_limit, self.dfa_size_limit) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                log::error!("Invalid regex /{}/ matches nothing: {}", text, e);
+                None
+            }
+        }).as_ref()
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+impl LazyRegex {
+    pub(crate) fn compiled(text: &str, _options: &ParseOptions) -> Result<LazyRegex, String> {
+        Err(format!("Regex {:?} needs the `regex` feature, without it only literal patterns like `abc`, `^abc`, `abc$`, `^abc$` and `.*` are supported", text))
+    }
+
+    pub(crate) fn deferred(text: &str, options: &ParseOptions) -> Result<LazyRegex, String> {
+        LazyRegex::compiled(text, options)
+    }
+
+    fn is_match(&self, _text: &str, _s: &str) -> bool {
+        match *self {}
+    }
+
+    fn is_compiled(&self) -> bool {
+        match *self {}
+    }
+}
+
+/// Compiles a regex with optional size limits, see [regex::RegexBuilder::size_limit]
+#[cfg(feature = "regex")]
+fn build_regex(text: &str, size_limit: Option<usize>, dfa_size_limit: Option<usize>) -> Result<Regex, String> {
+    let mut builder = regex::RegexBuilder::new(text);
+    if let Some(limit) = size_limit {builder.size_limit(limit);}
+    if let Some(limit) = dfa_size_limit {builder.dfa_size_limit(limit);}
+    builder.build().map_err(|e| e.to_string())
+}
+
+
+impl Pattern {
+    /// Compiles `text`, unless it is a literal
+    pub fn new(text: &str) -> Result<Pattern, String> {
+        Pattern::with_compiler(text, |text| LazyRegex::compiled(text, &ParseOptions::default()).map(Arc::new))
+    }
+
+    /// Same as [Pattern::new] with a custom function to compile regexes, it is not called for literals
+    pub(crate) fn with_compiler<E>(text: &str, compile: impl FnOnce(&str) -> Result<Arc<LazyRegex>, E>) -> Result<Pattern, E> {
+        let matcher = match literal_matcher(text) {
+            Some(matcher) => matcher,
+            None => Matcher::Regex(compile(text)?)
+        };
+        Ok(Pattern { text: text.into(), matcher })
+    }
+
+    /// Whether the pattern is found in `s`
+    pub fn is_match(&self, s: &str) -> bool {
+        let lit = |span: &Span| &self.text[span.start as usize..span.end as usize];
+        match &self.matcher {
+            Matcher::Contains(span) => s.contains(lit(span)),
+            Matcher::Prefix(span) => s.starts_with(lit(span)),
+            Matcher::Suffix(span) => s.ends_with(lit(span)),
+            Matcher::Equals(span) => s == lit(span),
+            Matcher::Regex(re) => re.is_match(&self.text, s),
+        }
+    }
+
+    /// The text of the pattern, with the flags and anchors of its options
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the pattern is compared as a string, without a regex
+    pub fn is_literal(&self) -> bool {
+        !matches!(self.matcher, Matcher::Regex(_))
+    }
+
+    /// Whether the regex of the pattern was compiled, literals are never compiled
+    pub fn is_compiled(&self) -> bool {
+        matches!(&self.matcher, Matcher::Regex(re) if re.is_compiled())
+    }
+
+    /// The address of the compiled regex, patterns with the same regex share it, `None` for literals
+    pub(crate) fn regex_id(&self) -> Option<usize> {
+        match &self.matcher {
+            Matcher::Regex(re) => Some(Arc::as_ptr(re) as usize),
+            _ => None
+        }
+    }
+
+    /// Whether both patterns use the same regex
+    #[cfg(all(test, feature = "regex"))]
+    pub(crate) fn shares_regex(&self, other: &Pattern) -> bool {
+        matches!((&self.matcher, &other.matcher), (Matcher::Regex(a), Matcher::Regex(b)) if Arc::ptr_eq(a, b))
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<Regex> for Pattern {
+    /// A pattern that uses an already compiled regex, even if it is a literal
+    fn from(regex: Regex) -> Self {
+        let text = regex.as_str().into();
+        let regex = LazyRegex { compiled: OnceLock::from(Some(regex)), size_limit: None, dfa_size_limit: None };
+        Pattern { text, matcher: Matcher::Regex(Arc::new(regex)) }
+    }
+}
+
+
+/// Whether `s` matches itself as a regex, it has no metacharacters
+fn is_literal(s: &str) -> bool {
+    !s.contains(['\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$'])
+}
+
+/// The string comparison that matches the same strings as the regex `text`, if there is one, the `\A(?:..)\z` anchors
+/// of `full_match` are recognized
+fn literal_matcher(text: &str) -> Option<Matcher> {
+    if text == ".*" {
+        return Some(Matcher::Contains(Span { start: 0, end: 0 }))
+    }
+    if let Some(inner) = text.strip_prefix("\\A(?:").and_then(|t| t.strip_suffix(")\\z")) {
+        return is_literal(inner).then(|| Matcher::Equals(Span::of(text, inner)))
+    }
+    let (start, rest) = match text.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, text)
+    };
+    let (end, rest) = match rest.strip_suffix('$') {
+        Some(rest) => (true, rest),
+        None => (false, rest)
+    };
+    if !is_literal(rest) {
+        return None
+    }
+    let span = Span::of(text, rest);
+    Some(match (start, end) {
+        (false, false) => Matcher::Contains(span),
+        (true, false) => Matcher::Prefix(span),
+        (false, true) => Matcher::Suffix(span),
+        (true, true) => Matcher::Equals(span),
+    })
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns() {
+        let cases = [
+            ("abc", "xabcx", true), ("abc", "ab", false),
+            ("^x-", "x-a", true), ("^x-", "ax-", false),
+            ("_id$", "user_id", true), ("_id$", "id_", false),
+            ("^name$", "name", true), ("^name$", "names", false),
+            ("\\A(?:name)\\z", "name", true), ("\\A(?:name)\\z", "a name", false),
+            (".*", "", true), ("", "a", true), ("^$", "", true), ("^$", "a", false),
+        ];
+        for (text, key, expected) in cases {
+            let pattern = Pattern::new(text).unwrap();
+            assert!(pattern.is_literal(), "{} is not a literal", text);
+            assert_eq!(pattern.is_match(key), expected, "{} on {}", text, key);
+            #[cfg(feature = "regex")]
+            assert_eq!(Regex::new(text).unwrap().is_match(key), expected, "the regex {} disagrees on {}", text, key);
+            assert_eq!(pattern.as_str(), text);
+        }
+
+        #[cfg(feature = "regex")]
+        for text in ["a.c", "^a+$", "(?i)abc", "\\$", "a|b", "\\A(?:a|b)\\z", "^[a-z]"] {
+            assert!(!Pattern::new(text).unwrap().is_literal(), "{} is a literal", text);
+        }
+        Pattern::new("(").unwrap_err();
+    }
+
+    #[test]
+    #[cfg(not(feature = "regex"))]
+    fn without_regex() {
+        let schema: TomlSchema = "
+            name = {type = 'string', regex = '^app-', min_len = 5}
+            kind = {type = 'exact', values = ['a', 'b']}
+            extras = [{key = '^x-', schema = 'int'}]
+        ".parse().unwrap();
+        schema.check_str("name = 'app-one'\nkind = 'a'\nx-a = 1").unwrap();
+        schema.check_str("name = 'one'\nkind = 'a'").unwrap_err();
+
+        let e = "name = {type = 'string', regex = '^[a-z]+$'}".parse::<TomlSchema>().unwrap_err();
+        assert!(e.contains("needs the `regex` feature"), "error is {}", e);
+        let e = "extras = [{key = 'a|b', schema = 'int'}]".parse::<TomlSchema>().unwrap_err();
+        assert!(e.contains("needs the `regex` feature"), "error is {}", e);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn literal_schema_keys() {
+        let schema: TomlSchema = "
+            extras = [{key = '^x-', schema = 'int'}, {key = 'name', schema = 'string', full_match = true}]
+            env = {type = 'map', key_regex = '^[A-Z]+$', value = 'string', default = {}}
+        ".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert!(table.extras.iter().all(|e| e.key.is_literal()));
+        assert!(matches!(&table.entries["env"].0, TomlSchema::Map(map) if !map.key.is_literal()));
+
+        schema.check_str("x-a = 1\nname = 'n'\nenv = {HOME = '/'}").unwrap();
+        schema.check_str("a-x = 1").unwrap_err();
+        schema.check_str("names = 'n'").unwrap_err();
+        schema.check_str("env = {home = '/'}").unwrap_err();
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+use crate::*;
+
+
+/// A user-defined schema type, registered in [SchemaPlugins] under the name used in the `type` key of schemas
+///
+/// names of the built-in types cannot be used, they always refer to the built-in type
+pub trait PluginType: Send + Sync {
+    /// Builds a schema of this type from its table, `type`, `default` and the generic keys (`x-` extensions, `custom`)
+    /// are already removed
+    fn parse(&self, table: &toml::Table) -> Result<Arc<dyn PluginNode>, String>;
+}
+
+/// A schema of a user-defined type, built by [PluginType::parse]
+pub trait PluginNode: Send + Sync + fmt::Debug {
+    /// Checks a value, the error message is reported in [SchemaError::PluginMiss]
+    fn check(&self, value: &Value) -> Result<(), String>;
+
+    /// The schema keys that [PluginType::parse] turns back into this node, they are used to serialize and display the schema
+    fn to_table(&self) -> toml::Table;
+}
+
+
+/// A compiled schema of a plugin type, the content of [TomlSchema::Plugin]
+#[derive(Clone, Debug)]
+pub struct PluginSchema {
+    type_name: Arc<str>,
+    node: Arc<dyn PluginNode>,
+}
+
+impl PluginSchema {
+    /// The name of the type in schemas
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    pub fn node(&self) -> &dyn PluginNode {
+        &*self.node
+    }
+
+    pub(crate) fn check<'s,'v>(&'s self, val: &'v Value) -> Result<(), SchemaError<'s,'v>> {
+        self.node.check(val).map_err(|message| SchemaError::PluginMiss { val, type_name: &self.type_name, message })
+    }
+}
+
+
+/// The user-defined schema types known to the parser, given to it in [ParseOptions::plugins]
+#[derive(Clone, Default)]
+pub struct SchemaPlugins(HashMap<String, Arc<dyn PluginType>>);
+
+impl SchemaPlugins {
+    pub fn new() -> Self {
+        SchemaPlugins::default()
+    }
+
+    /// Adds a type, fails if `name` is the name of a built-in type, a type registered earlier with the same name is replaced
+    pub fn register(&mut self, name: &str, plugin: impl PluginType + 'static) -> Result<&mut Self, String> {
+        if constructor::is_builtin_type(name) {
+            return Err(format!("'{}' is a built-in schema type", name))
+        }
+        self.0.insert(name.to_string(), Arc::new(plugin));
+        Ok(self)
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Builds a schema of the type `name`, returns `None` if no such type is registered
+    pub(crate) fn parse(&self, name: &str, table: &toml::Table) -> Option<Result<PluginSchema, String>> {
+        let plugin = self.0.get(name)?;
+        Some(plugin.parse(table).map(|node| PluginSchema { type_name: name.into(), node }))
+    }
+}
+
+impl fmt::Debug for SchemaPlugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        f.debug_tuple("SchemaPlugins").field(&names).finish()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{type = "color", alpha = true}`, a `#rrggbb` or `#rrggbbaa` string
+    struct Color;
+
+    #[derive(Debug)]
+    struct ColorNode {
+        alpha: bool
+    }
+
+    impl PluginType for Color {
+        fn parse(&self, table: &toml::Table) -> Result<Arc<dyn PluginNode>, String> {
+            let alpha = match table.get("alpha") {
+                None => false,
+                Some(Value::Boolean(b)) => *b,
+                Some(other) => return Err(format!("alpha must be a boolean but got {}", other))
+            };
+            if let Some(key) = table.keys().find(|k| *k != "alpha") {
+                return Err(format!("unknown color option {}", key))
+            }
+            Ok(Arc::new(ColorNode { alpha }))
+        }
+    }
+
+    impl PluginNode for ColorNode {
+        fn check(&self, value: &Value) -> Result<(), String> {
+            let Some(s) = value.as_str() else {return Err("colors are strings".to_string())};
+            let digits = s.strip_prefix('#').ok_or("colors start with '#'")?;
+            let len_ok = digits.len() == 6 || (self.alpha && digits.len() == 8);
+            match len_ok && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                true => Ok(()),
+                false => Err(format!("{} is not a color", s))
+            }
+        }
+
+        fn to_table(&self) -> toml::Table {
+            match self.alpha {
+                true => toml::Table::from_iter([("alpha".to_string(), Value::Boolean(true))]),
+                false => toml::Table::new()
+            }
+        }
+    }
+
+    fn options() -> ParseOptions {
+        let mut plugins = SchemaPlugins::new();
+        plugins.register("color", Color).unwrap();
+        ParseOptions { plugins, ..ParseOptions::default() }
+    }
+
+    #[test]
+    fn plugin_types() {
+        let table = "
+            fg = {type = 'color', default = '#000000'}
+            bg = {type = 'color', alpha = true, x-doc = 'background'}
+            palette = {type = 'array', child = 'color'}
+        ".parse().unwrap();
+        let (schema, _, _) = TomlSchema::from_table_with_options(&table, &options()).unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("fg = '#ffaa00'\nbg = '#ffaa0080'\npalette = ['#123456']")).unwrap();
+        match schema.check(&doc("fg = '#ffaa0080'\nbg = '#000000'\npalette = []")) {
+            Err(SchemaError::AtKey { error, .. }) => {
+                assert!(matches!(*error, SchemaError::PluginMiss { type_name: "color", .. }), "{:?}", error)
+            },
+            other => panic!("expected a color error but got {:?}", other)
+        }
+        schema.check(&doc("fg = '#000000'\nbg = '#000000'\npalette = [1]")).unwrap_err();
+
+        assert_eq!(SchemaType::from(schema.at_path("fg").unwrap()[0].0), SchemaType::Plugin);
+        assert_eq!(schema.to_string(), "table { bg: color { alpha = true }, fg: color = \"#000000\", palette: array<color> }");
+
+        // unknown types and options are errors
+        "c = {type = 'color'}".parse::<TomlSchema>().unwrap_err();
+        TomlSchema::from_table_with_options(&"c = {type = 'color', beta = 1}".parse().unwrap(), &options()).unwrap_err();
+        SchemaPlugins::new().register("int", Color).unwrap_err();
+        // `plugin` names the kind of these types, not one of them
+        SchemaPlugins::new().register("plugin", Color).unwrap_err();
+        let e = TomlSchema::from_table_with_options(&"c = {type = 'plugin'}".parse().unwrap(), &options()).unwrap_err();
+        assert!(e.contains("registered plugin"), "{}", e);
+    }
+}
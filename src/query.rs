@@ -0,0 +1,261 @@
+use crate::*;
+
+
+impl TomlSchema {
+
+    /// Finds the sub-schemas governing a location in documents, the path uses the syntax of [SchemaPath]'s `FromStr`
+    /// (`server.listeners[0].port`)
+    ///
+    /// see [TomlSchema::resolve_path]
+    pub fn at_path(&self, path: &str) -> Result<Vec<(&TomlSchema, Option<&Value>)>, String> {
+        Ok(self.resolve_path(&path.parse()?))
+    }
+
+    /// Finds the sub-schemas governing a location, with the default value when the location is a table entry that has one
+    ///
+    /// keys resolve through table entries first and through every extra whose regex matches the key otherwise, indices
+    /// resolve to the child of arrays, alternatives are looked through so all options that can contain the location
+    /// are returned, an empty result means that no value is allowed at this location
+    ///
+    /// paths produced by [TomlSchema::visit] ([PathSegment::Extra], [PathSegment::Branch]) are also accepted
+    pub fn resolve_path(&self, path: &SchemaPath) -> Vec<(&TomlSchema, Option<&Value>)> {
+        let mut current: Vec<(&TomlSchema, Option<&Value>)> = vec![(self, None)];
+
+        for segment in path.segments() {
+            let mut next = Vec::new();
+
+            for (schema, _) in current {
+                // branches select an option explicitly, other segments look through alternatives
+                if let (PathSegment::Branch(i), TomlSchema::Alternative(options)) = (segment, schema.structure()) {
+                    next.extend(options.get(*i).map(|opt| (opt, None)));
+                    continue;
+                }
+                for schema in schema.flatten_alternatives() {
+                    resolve_segment(schema, segment, &mut next);
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// Checks only the value at `path` of a document against the schemas of this location, the rest of the document is
+    /// not checked, errors are nested in [SchemaError::AtKey] and [SchemaError::ArrayMiss] like in [TomlSchema::check]
+    ///
+    /// a missing value fails with [SchemaError::MissingKey] when its key is required, a value at a location that no schema
+    /// describes fails with the error of its parent, references (`refers_to`) are not checked since their targets are
+    /// elsewhere in the document, only keys and indices of `path` are followed
+    pub fn check_at<'s,'v>(&'s self, doc: &'v Value, path: &SchemaPath) -> Result<(), SchemaError<'s,'v>> {
+        let Some(value) = path.value_in(doc) else {
+            return self.check_missing(doc, path)
+        };
+        let candidates = self.resolve_path(path);
+        if candidates.is_empty() {
+            let mut parent = path.clone();
+            return match parent.pop() {
+                Some(_) => self.check_at(doc, &parent),
+                None => Ok(())
+            }
+        }
+
+        let mut errors = Vec::with_capacity(candidates.len());
+        for (schema, _) in candidates {
+            match schema.check_depth(value, DEFAULT_MAX_DEPTH) {
+                Ok(()) => return Ok(()),
+                Err(e) => errors.push(e)
+            }
+        }
+        let error = match errors.len() {
+            1 => errors.pop().expect("one error"),
+            _ => SchemaError::AlternativeMiss { val: value, errors }
+        };
+        Err(reference::nest_error(doc, path, error))
+    }
+
+    /// The error of [TomlSchema::check_at] when there is no value at `path`, missing parents are checked first
+    fn check_missing<'s,'v>(&'s self, doc: &'v Value, path: &SchemaPath) -> Result<(), SchemaError<'s,'v>> {
+        let mut parent = path.clone();
+        let Some(PathSegment::Key(key)) = parent.pop() else {return Ok(())};
+        if parent.value_in(doc).is_none() {
+            return self.check_at(doc, &parent)
+        }
+        for (schema, _) in self.resolve_path(&parent) {
+            for schema in schema.flatten_alternatives() {
+                let TomlSchema::Table(table) = schema else {continue};
+                if let Some((key, (schema, None))) = table.entries.get_key_value(&key) {
+                    let error = SchemaError::MissingKey { key, expected: schema.into() };
+                    return Err(reference::nest_error(doc, &parent, error))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// This schema if it is not an alternative, or all the non-alternative options it contains, annotations and
+    /// custom validators are looked through
+    pub(crate) fn flatten_alternatives(&self) -> Vec<&TomlSchema> {
+        match self.structure() {
+            TomlSchema::Alternative(options) => options.iter().flat_map(|opt| opt.flatten_alternatives()).collect(),
+            other => vec![other]
+        }
+    }
+}
+
+
+fn resolve_segment<'s>(schema: &'s TomlSchema, segment: &PathSegment, out: &mut Vec<(&'s TomlSchema, Option<&'s Value>)>) {
+    match (schema, segment) {
+        (TomlSchema::Anything, PathSegment::Key(_) | PathSegment::Index(_) | PathSegment::AnyIndex) => {
+            out.push((schema, None))
+        },
+        (TomlSchema::Table(table_schema), PathSegment::Key(key)) => {
+            let TableSchema { entries, extras, policy, .. } = &**table_schema;
+            match entries.get(key) {
+                Some((sub, default)) => out.push((sub, default.as_ref())),
+                None => {
+                    out.extend(policy.candidates(extras, key).filter(|e| e.key_type.accepts(key)).map(|e| (&e.value, None)))
+                }
+            }
+        },
+        (TomlSchema::Table(table_schema), PathSegment::Extra(re)) => {
+            out.extend(table_schema.extras.iter().filter(|e| e.key.as_str() == re).map(|e| (&e.value, None)))
+        },
+        (TomlSchema::Map(map), PathSegment::Key(key)) if map.key.is_match(key) && map.key_type.accepts(key) => {
+            out.push((&map.value, None))
+        },
+        (TomlSchema::Map(map), PathSegment::Extra(re)) if map.key.as_str() == re => {
+            out.push((&map.value, None))
+        },
+        (TomlSchema::Array { cond, .. }, PathSegment::Index(_) | PathSegment::AnyIndex) => {
+            out.push((cond, None))
+        },
+        _ => ()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            [server]
+            host = {type = 'string', default = 'localhost'}
+            extras = [{key = '^x-', schema = {type = 'int'}}]
+
+            [server.listeners]
+            type = 'array'
+            child = {type = 'table', port = {type = 'int', min = 1, default = 80}}
+
+            [server.mode]
+            type = 'alternative'
+            options = [{type = 'bool'}, {type = 'table', name = {type = 'string'}}]
+
+            [plugins]
+            type = 'anything'
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn path_syntax() {
+        use PathSegment::*;
+
+        let path: SchemaPath = "server.listeners[0].port".parse().unwrap();
+        assert_eq!(path, vec![Key("server".into()), Key("listeners".into()), Index(0), Key("port".into())].into());
+
+        let path: SchemaPath = "a.\"b.c\"[].'d'[12]".parse().unwrap();
+        assert_eq!(path, vec![Key("a".into()), Key("b.c".into()), AnyIndex, Key("d".into()), Index(12)].into());
+
+        assert_eq!("".parse::<SchemaPath>().unwrap(), SchemaPath::new());
+        "a.".parse::<SchemaPath>().unwrap_err();
+        "a..b".parse::<SchemaPath>().unwrap_err();
+        "a[x]".parse::<SchemaPath>().unwrap_err();
+        "a\"b\"".parse::<SchemaPath>().unwrap_err();
+
+        assert_eq!(path.to_string(), "a.\"b.c\"[].d[12]");
+        assert_eq!(path.to_string().parse::<SchemaPath>().unwrap(), path);
+        let visited = SchemaPath::from(vec![Key("t".into()), Extra("^x-".into()), Branch(1), Extra("a/b".into()), AnyIndex]);
+        assert_eq!(visited.to_string(), "t./^x-/|1./a\\/b/[]");
+        assert_eq!(visited.to_string().parse::<SchemaPath>().unwrap(), visited);
+        assert_eq!("|0.a".parse::<SchemaPath>().unwrap(), vec![Branch(0), Key("a".into())].into());
+        "a|".parse::<SchemaPath>().unwrap_err();
+        "a./x".parse::<SchemaPath>().unwrap_err();
+    }
+
+    #[test]
+    fn at_path() {
+        let schema = schema();
+
+        let found = schema.at_path("server.listeners[0].port").unwrap();
+        assert!(matches!(found[..], [(TomlSchema::Integer { min: 1, .. }, Some(Value::Integer(80)))]), "{:?}", found);
+
+        let found = schema.at_path("server.host").unwrap();
+        assert!(matches!(found[..], [(TomlSchema::String { .. }, Some(Value::String(_)))]), "{:?}", found);
+
+        let found = schema.at_path("server.x-timeout").unwrap();
+        assert!(matches!(found[..], [(TomlSchema::Integer { .. }, None)]), "{:?}", found);
+
+        let found = schema.at_path("server.mode.name").unwrap();
+        assert!(matches!(found[..], [(TomlSchema::String { .. }, None)]), "{:?}", found);
+
+        let found = schema.at_path("plugins.anything[3].goes").unwrap();
+        assert!(matches!(found[..], [(TomlSchema::Anything, None)]), "{:?}", found);
+
+        let found = schema.at_path("").unwrap();
+        assert_eq!(found.len(), 1);
+
+        assert!(schema.at_path("server.unknown").unwrap().is_empty());
+        assert!(schema.at_path("server.host[0]").unwrap().is_empty());
+        schema.at_path("server..host").unwrap_err();
+    }
+
+    #[test]
+    fn check_at() {
+        let schema = schema();
+        let doc = Value::Table("
+            plugins = 1
+            [server]
+            host = 5
+            listeners = [{port = 8080}, {port = 0}]
+            mode = {name = 'fast'}
+            unknown = true
+        ".parse().unwrap());
+        let at = |path: &str| schema.check_at(&doc, &path.parse().unwrap());
+
+        at("server.listeners[0]").unwrap();
+        at("server.mode").unwrap();
+        at("plugins").unwrap();
+        assert!(matches!(at("server.listeners[1].port"), Err(SchemaError::AtKey { error, .. }) if matches!(*error, SchemaError::AtKey { .. })));
+        assert!(matches!(at("server.listeners"), Err(SchemaError::AtKey { .. })));
+        assert!(matches!(at("server.host"), Err(SchemaError::AtKey { .. })));
+        at("").unwrap_err();
+        // values that no schema describes are errors of their parent
+        assert!(matches!(at("server.unknown"), Err(SchemaError::AtKey { key, .. }) if key == "server"));
+
+        // missing values are only errors when they are required
+        at("server.listeners[5]").unwrap();
+        at("server.listeners[0].missing").unwrap();
+        let doc = Value::Table("server = {listeners = [{}]}\nplugins = []".parse().unwrap());
+        schema.check_at(&doc, &"server.listeners[0].port".parse().unwrap()).unwrap();
+        assert!(matches!(schema.check_at(&doc, &"plugins".parse().unwrap()), Ok(())));
+
+        let required: TomlSchema = "[server]\nport = 'int'".parse().unwrap();
+        let missing = required.check_at(&doc, &"server.port".parse().unwrap()).unwrap_err();
+        assert!(matches!(missing, SchemaError::AtKey { error, .. } if matches!(*error, SchemaError::MissingKey { key: "port", .. })));
+    }
+
+    #[test]
+    fn resolve_visited_paths() {
+        let schema = schema();
+
+        let mut count = 0;
+        schema.visit(&mut |path, sub| {
+            let found = schema.resolve_path(path);
+            assert!(found.iter().any(|(s, _)| std::ptr::eq(*s, sub)), "{:?} does not resolve to itself", path);
+            count += 1;
+        });
+        assert!(count > 5);
+    }
+}
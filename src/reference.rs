@@ -0,0 +1,246 @@
+//! The second validation phase, string schemas with `refers_to` must match a key or a value found elsewhere in the
+//! document, which is only known once the whole document is available
+
+use crate::*;
+
+
+/// A string value found by the first phase that must be resolved in the document
+struct Reference<'s, 'v> {
+    path: SchemaPath,
+    value: &'v str,
+    target: &'s str,
+}
+
+/// Parses a `refers_to` target, the last segment is `*` to refer to keys of a table, otherwise the value must be equal
+/// to one of the strings at the target
+pub(crate) fn parse_target(target: &str) -> Result<SchemaPath, String> {
+    let path: SchemaPath = target.parse()?;
+    if path.is_empty() {
+        return Err("refers_to must not be empty".to_string())
+    }
+    Ok(path)
+}
+
+/// The strings that a reference to `target` may be equal to, `*` in the target matches any key of a table
+fn candidates<'v>(value: &'v Value, target: &[PathSegment], out: &mut Vec<&'v str>) {
+    let Some((segment, rest)) = target.split_first() else {
+        if let Value::String(s) = value {out.push(s);}
+        return
+    };
+    match (segment, value) {
+        (PathSegment::Key(k), Value::Table(table)) if k == "*" => {
+            for (key, value) in table {
+                match rest.is_empty() {
+                    true => out.push(key),
+                    false => candidates(value, rest, out)
+                }
+            }
+        },
+        (PathSegment::Key(k), Value::Table(table)) => {
+            if let Some(value) = table.get(k) {candidates(value, rest, out);}
+        },
+        (PathSegment::Index(i), Value::Array(arr)) => {
+            if let Some(value) = arr.get(*i) {candidates(value, rest, out);}
+        },
+        (PathSegment::AnyIndex, Value::Array(arr)) => {
+            for value in arr {candidates(value, rest, out);}
+        },
+        _ => ()
+    }
+}
+
+/// Nests an error found at `path` of `doc` in [SchemaError::AtKey] and [SchemaError::ArrayMiss] like the errors of
+/// [TomlSchema::check], the path must only have keys and indices that exist in the document
+pub(crate) fn nest_error<'s, 'v>(doc: &'v Value, path: &SchemaPath, mut error: SchemaError<'s, 'v>) -> SchemaError<'s, 'v> {
+    // the values along the path are needed to build the nested errors
+    let mut values = vec![doc];
+    for segment in path.segments() {
+        let parent = values[values.len() - 1];
+        values.push(match segment {
+            PathSegment::Key(k) => &parent[k.as_str()],
+            PathSegment::Index(i) => &parent[*i],
+            _ => unreachable!("errors are only found at keys and indices")
+        });
+    }
+
+    for (segment, (parent, value)) in path.segments().iter().zip(values.iter().zip(&values[1..])).rev() {
+        error = match segment {
+            PathSegment::Key(k) => {
+                let (key, _) = parent.as_table().and_then(|t| t.get_key_value(k.as_str())).expect("key found above");
+                SchemaError::AtKey { key, error: Box::new(error) }
+            },
+            _ => SchemaError::ArrayMiss { value, error: Box::new(error) }
+        };
+    }
+    error
+}
+
+
+impl TomlSchema {
+
+    /// Whether a string schema of this schema has `refers_to`, the second phase is skipped otherwise
+    pub(crate) fn has_references(&self) -> bool {
+        self.any_node(&|schema| matches!(schema, TomlSchema::String { refers_to: Some(_), .. }))
+    }
+
+    /// Finds the values of `value` whose schema is picked by `pick`, the value must already match the schema so
+    /// alternatives and extras are resolved to the first schema they match, `pick` sees every schema on the way
+    /// including the wrappers of [TomlSchema::Custom] and [TomlSchema::Annotated]
+    pub(crate) fn collect_matching<'s, 'v, T>(&'s self, value: &'v Value, path: &mut SchemaPath, depth: usize,
+        pick: &impl Fn(&'s TomlSchema, &'v Value, &SchemaPath) -> Option<T>, out: &mut Vec<T>)
+    {
+        let Some(depth) = depth.checked_sub(1) else {return};
+        out.extend(pick(self, value, path));
+
+        match (self, value) {
+            (TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. }, value) => {
+                schema.collect_matching(value, path, depth, pick, out);
+            },
+            // the value may not match a schema with a lower severity, the values below it are not looked at
+            (TomlSchema::Graded { schema, severity: Severity::Error }, value) => {
+                schema.collect_matching(value, path, depth, pick, out);
+            },
+            (TomlSchema::Alternative(options), value) => {
+                if let Some(option) = options.iter().find(|opt| opt.check_depth(value, depth).is_ok()) {
+                    option.collect_matching(value, path, depth, pick, out);
+                }
+            },
+            (TomlSchema::Array { cond, .. }, Value::Array(arr)) => {
+                for (i, value) in arr.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    cond.collect_matching(value, path, depth, pick, out);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Map(map_schema), Value::Table(table)) => {
+                let MapSchema { value: child, .. } = &**map_schema;
+                for (key, value) in table {
+                    path.push(PathSegment::Key(key.clone()));
+                    child.collect_matching(value, path, depth, pick, out);
+                    path.pop();
+                }
+            },
+            (TomlSchema::Table(table_schema), Value::Table(table)) => {
+                let TableSchema { entries, extras, policy, .. } = &**table_schema;
+                for (key, value) in table {
+                    let schema = match entries.get(key) {
+                        Some((schema, _)) => Some(schema),
+                        None => policy.candidates(extras, key)
+                            .find(|e| e.key_type.accepts(key) && e.value.check_depth(value, depth).is_ok())
+                            .map(|e| &e.value)
+                    };
+                    if let Some(schema) = schema {
+                        path.push(PathSegment::Key(key.clone()));
+                        schema.collect_matching(value, path, depth, pick, out);
+                        path.pop();
+                    }
+                }
+            },
+            _ => ()
+        }
+    }
+
+    /// The references of `doc` that do not resolve, with their location
+    fn dangling_references<'s, 'v>(&'s self, doc: &'v Value) -> Vec<Reference<'s, 'v>> {
+        if !self.has_references() {
+            return Vec::new()
+        }
+        let mut references = Vec::new();
+        let pick = |schema: &'s TomlSchema, value: &'v Value, path: &SchemaPath| match (schema, value) {
+            (TomlSchema::String { refers_to: Some(target), .. }, Value::String(s)) => Some(Reference { path: path.clone(), value: s, target }),
+            _ => None
+        };
+        self.collect_matching(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &pick, &mut references);
+
+        references.retain(|reference| {
+            // targets are checked when the schema is parsed
+            let Ok(target) = parse_target(reference.target) else {return true};
+            let mut found = Vec::new();
+            candidates(doc, target.segments(), &mut found);
+            !found.contains(&reference.value)
+        });
+        references
+    }
+
+    /// Checks the references of a document that already matches this schema, the error is nested in
+    /// [SchemaError::AtKey] and [SchemaError::ArrayMiss] like the errors of [TomlSchema::check]
+    pub(crate) fn check_references<'s, 'v>(&'s self, doc: &'v Value) -> Result<(), SchemaError<'s, 'v>> {
+        match self.dangling_references(doc).into_iter().next() {
+            Some(Reference { path, value, target }) => Err(nest_error(doc, &path, SchemaError::DanglingReference { value, target })),
+            None => Ok(())
+        }
+    }
+
+    /// Reports every dangling reference of `doc` at its location
+    pub(crate) fn report_references(&self, doc: &Value, report: &mut ValidationReport) {
+        for Reference { path, value, target } in self.dangling_references(doc) {
+            report.push(&path, SchemaError::DanglingReference { value, target });
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        let schema: TomlSchema = "
+            default_profile = {type = 'string', refers_to = 'profiles.*', default = 'default'}
+            profiles = {type = 'table', extras = [{key = '.*', schema = {inherits = {type = 'string', refers_to = 'profiles.*', default = 'default'}}}]}
+            listeners = {type = 'array', child = {name = 'string'}, default = []}
+            routes = {type = 'array', child = {listener = {type = 'string', refers_to = 'listeners[].name'}}, default = []}
+        ".parse().unwrap();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        schema.check(&doc("
+            default_profile = 'dev'
+            listeners = [{name = 'http'}, {name = 'https'}]
+            routes = [{listener = 'https'}]
+            [profiles.dev]
+            [profiles.prod]
+            inherits = 'dev'
+        ")).unwrap();
+
+        let bad = doc("
+            default_profile = 'test'
+            [profiles.dev]
+        ");
+        match schema.check(&bad) {
+            Err(SchemaError::AtKey { key, error }) => {
+                assert_eq!(key, "default_profile");
+                assert!(matches!(*error, SchemaError::DanglingReference { value: "test", target: "profiles.*" }), "{:?}", error);
+            },
+            other => panic!("expected a dangling reference but got {:?}", other)
+        }
+
+        let bad = doc("
+            default_profile = 'dev'
+            listeners = [{name = 'http'}]
+            routes = [{listener = 'http'}, {listener = 'https'}]
+            [profiles.dev]
+            inherits = 'base'
+        ");
+        schema.check(&bad).unwrap_err();
+        let report = schema.validate(&bad);
+        let paths: Vec<String> = report.errors.iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["profiles.dev.inherits", "routes[1].listener"]);
+
+        // adding the missing profile fixes a reference outside of the changed value
+        let mut fixed = bad.clone();
+        fixed["profiles"].as_table_mut().unwrap().insert("base".into(), Value::Table(Default::default()));
+        let mut report = report;
+        schema.revalidate(&fixed, &"profiles.base".parse().unwrap(), &mut report);
+        assert_eq!(report.errors.len(), 1, "{:?}", report);
+
+        // filled defaults are checked too
+        schema.check_and_complete(&mut doc("[profiles.x]")).unwrap_err();
+        schema.check_and_complete(&mut doc("[profiles.default]")).unwrap();
+
+        "type = 'string'\nrefers_to = ''".parse::<TomlSchema>().unwrap_err();
+        "type = 'string'\nrefers_to = 1".parse::<TomlSchema>().unwrap_err();
+    }
+}
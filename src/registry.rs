@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{PoisonError, RwLock};
+use crate::*;
+
+
+thread_local! {
+    /// The references being resolved by the parser on this thread, from the outermost one
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a reference as being resolved until it is dropped, references are copied into the schemas that use them so
+/// a reference met again while it is resolved would never end
+pub(crate) struct ResolvingGuard;
+
+impl ResolvingGuard {
+    /// Fails with the chain of references when `reference` is already being resolved
+    pub(crate) fn enter(reference: String) -> Result<Self, String> {
+        RESOLVING.with(|chain| {
+            let mut chain = chain.borrow_mut();
+            if chain.contains(&reference) {
+                return Err(format!("Cycle in references: {} -> {}", chain.join(" -> "), reference))
+            }
+            chain.push(reference);
+            Ok(ResolvingGuard)
+        })
+    }
+}
+
+impl Drop for ResolvingGuard {
+    fn drop(&mut self) {
+        RESOLVING.with(|chain| chain.borrow_mut().pop());
+    }
+}
+
+
+/// Compiled schemas by name, for applications that load many kinds of documents, a clone shares the same schemas so
+/// the registry can be handed to every thread
+///
+/// schemas given to the parser in [ParseOptions::registry] use the registered schemas with
+/// `{type = "reference", schema = "name"}`, references are resolved while parsing so a schema must be registered
+/// before the schemas that refer to it
+#[derive(Clone, Default)]
+pub struct SchemaRegistry(Arc<RwLock<HashMap<String, Arc<TomlSchema>>>>);
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        SchemaRegistry::default()
+    }
+
+    /// Adds a schema, the schema registered earlier with the same name is replaced and returned, schemas that already
+    /// refer to it keep the old one
+    pub fn register(&self, name: &str, schema: TomlSchema) -> Option<Arc<TomlSchema>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner).insert(name.to_string(), Arc::new(schema))
+    }
+
+    /// Parses a schema with `options` and registers it, the references of the schema are resolved against this registry,
+    /// a reference to `name` itself is a cycle
+    pub fn register_toml(&self, name: &str, text: &str, options: &ParseOptions) -> Result<Arc<TomlSchema>, String> {
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let options = ParseOptions { registry: self.clone(), ..options.clone() };
+        let (schema, _, warnings) = {
+            let _resolving = ResolvingGuard::enter(format!("schema '{}'", name))?;
+            TomlSchema::from_table_with_options(&table, &options)?
+        };
+        for warning in warnings {
+            log::warn!("{}", warning);
+        }
+        let schema = Arc::new(schema);
+        self.0.write().unwrap_or_else(PoisonError::into_inner).insert(name.to_string(), schema.clone());
+        Ok(schema)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<TomlSchema>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner).get(name).cloned()
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Arc<TomlSchema>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner).remove(name)
+    }
+
+    /// The names of the registered schemas, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.0.read().unwrap_or_else(PoisonError::into_inner).keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl fmt::Debug for SchemaRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SchemaRegistry").field(&self.names()).finish()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_references() {
+        let registry = SchemaRegistry::new();
+        registry.register("port", "type = 'int'\nmin = 1\nmax = 65535".parse().unwrap());
+        let options = ParseOptions::default();
+        let server = registry.register_toml("server", "
+            host = 'string'
+            port = {type = 'reference', schema = 'port', default = 80}
+        ", &options).unwrap();
+        assert_eq!(registry.names(), ["port", "server"]);
+
+        let shared = registry.clone();
+        std::thread::spawn(move || {
+            let schema = shared.get("server").unwrap();
+            schema.check(&Value::Table("host = 'a'\nport = 8080".parse().unwrap())).unwrap();
+        }).join().unwrap();
+        server.check(&Value::Table("host = 'a'\nport = 0".parse().unwrap())).unwrap_err();
+        let mut doc = Value::Table("host = 'a'".parse().unwrap());
+        server.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(80));
+
+        registry.register_toml("bad", "x = {type = 'reference', schema = 'missing'}", &options).unwrap_err();
+        let err = registry.register_toml("server", "x = {type = 'reference', schema = 'server'}", &options).unwrap_err();
+        assert!(err.ends_with("Cycle in references: schema 'server' -> schema 'server'"), "{}", err);
+        assert!(Arc::ptr_eq(&registry.get("server").unwrap(), &server));
+        registry.register_toml("bad", "x = {type = 'reference'}", &options).unwrap_err();
+        assert!(registry.get("bad").is_none());
+        assert!(registry.remove("port").is_some());
+    }
+}
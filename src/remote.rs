@@ -0,0 +1,202 @@
+//! Schemas shared by URL, `{type = "reference", url = "https://example.com/schemas/service.toml"}` is the schema
+//! fetched from that URL by the [SchemaFetcher] of [ParseOptions::remote]
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+use crate::*;
+use crate::registry::ResolvingGuard;
+
+
+/// Downloads the text of a schema, the crate has no HTTP client, applications implement this with the one they
+/// already use, for example with `reqwest::blocking::get(url)?.error_for_status()?.text()`
+pub trait SchemaFetcher: Send + Sync {
+    fn fetch(&self, url: &str) -> Result<String, String>;
+}
+
+/// Reads `file://` URLs, the fetcher used when none is given
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileFetcher;
+
+impl SchemaFetcher for FileFetcher {
+    fn fetch(&self, url: &str) -> Result<String, String> {
+        let Some(path) = url.strip_prefix("file://") else {
+            return Err(format!("Cannot fetch {}, only file:// URLs are supported without a SchemaFetcher", url))
+        };
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", url, e))
+    }
+}
+
+
+/// How the references by URL are resolved, a clone shares the schemas already fetched
+///
+/// each URL is fetched once and parsed with the options of the schema that refers to it, with a cache directory the
+/// fetched texts are also kept on disk for later runs, in offline mode only the cached schemas can be used
+#[derive(Clone)]
+pub struct RemoteSchemas {
+    fetcher: Arc<dyn SchemaFetcher>,
+    fetched: Arc<RwLock<HashMap<String, Arc<TomlSchema>>>>,
+    cache_dir: Option<PathBuf>,
+    offline: bool,
+}
+
+impl Default for RemoteSchemas {
+    fn default() -> Self {
+        RemoteSchemas { fetcher: Arc::new(FileFetcher), fetched: Arc::default(), cache_dir: None, offline: false }
+    }
+}
+
+impl fmt::Debug for RemoteSchemas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut urls: Vec<String> = self.fetched.read().unwrap_or_else(PoisonError::into_inner).keys().cloned().collect();
+        urls.sort();
+        f.debug_struct("RemoteSchemas")
+            .field("fetched", &urls)
+            .field("cache_dir", &self.cache_dir)
+            .field("offline", &self.offline)
+            .finish()
+    }
+}
+
+impl RemoteSchemas {
+    pub fn new(fetcher: impl SchemaFetcher + 'static) -> Self {
+        RemoteSchemas { fetcher: Arc::new(fetcher), ..RemoteSchemas::default() }
+    }
+
+    /// Keeps the fetched texts in `dir`, a text found there is used instead of fetching the URL again
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Never fetches, references to URLs that are not cached fail
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// The schema at `url`, fetched (or read from the caches) and parsed with `options`, fails with the chain of URLs
+    /// when the schema refers back to itself
+    pub(crate) fn resolve(&self, url: &str, options: &ParseOptions) -> Result<TomlSchema, String> {
+        if let Some(schema) = self.fetched.read().unwrap_or_else(PoisonError::into_inner).get(url) {
+            return Ok((**schema).clone())
+        }
+        let _resolving = ResolvingGuard::enter(url.to_string())?;
+
+        let cached = self.cache_dir.as_deref().map(|dir| cache_file(dir, url));
+        let text = match cached.as_deref().and_then(|file| std::fs::read_to_string(file).ok()) {
+            Some(text) => text,
+            None if self.offline => return Err(format!("Schema {} is not cached and fetching is disabled", url)),
+            None => {
+                let text = self.fetcher.fetch(url)?;
+                if let Some(file) = &cached {
+                    let write = file.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| std::fs::write(file, &text));
+                    if let Err(e) = write {
+                        log::warn!("Could not cache schema {} in {}: {}", url, file.display(), e);
+                    }
+                }
+                text
+            }
+        };
+
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| format!("Invalid schema at {}: {}", url, e))?;
+        let (schema, _, warnings) = TomlSchema::from_table_with_options(&table, options)
+            .map_err(|e| format!("Invalid schema at {}: {}", url, e))?;
+        for warning in warnings {
+            log::warn!("{}: {}", url, warning);
+        }
+        self.fetched.write().unwrap_or_else(PoisonError::into_inner).insert(url.to_string(), Arc::new(schema.clone()));
+        Ok(schema)
+    }
+}
+
+/// The file of `url` in a cache directory, named by a hash of the URL that is the same in every run
+fn cache_file(dir: &Path, url: &str) -> PathBuf {
+    // FNV-1a
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    dir.join(format!("{:016x}.toml", hash))
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves one schema and counts the requests
+    struct Served(Arc<AtomicUsize>);
+
+    impl SchemaFetcher for Served {
+        fn fetch(&self, url: &str) -> Result<String, String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            match url {
+                "https://example.com/port.toml" => Ok("type = 'int'\nmin = 1".to_string()),
+                "https://example.com/a.toml" => Ok("b = {type = 'reference', url = 'https://example.com/b.toml'}".to_string()),
+                "https://example.com/b.toml" => Ok("a = {type = 'reference', url = 'https://example.com/a.toml'}".to_string()),
+                _ => Err(format!("404 {}", url))
+            }
+        }
+    }
+
+    fn parse(text: &str, remote: &RemoteSchemas) -> Result<TomlSchema, String> {
+        let options = ParseOptions { remote: remote.clone(), ..ParseOptions::default() };
+        TomlSchema::from_table_with_options(&text.parse().unwrap(), &options).map(|(schema, _, _)| schema)
+    }
+
+    #[test]
+    fn fetched_once() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let remote = RemoteSchemas::new(Served(requests.clone()));
+        let text = "
+            a = {type = 'reference', url = 'https://example.com/port.toml'}
+            b = {type = 'reference', url = 'https://example.com/port.toml', default = 1}
+        ";
+        let schema = parse(text, &remote).unwrap();
+        schema.check(&Value::Table("a = 1".parse().unwrap())).unwrap();
+        schema.check(&Value::Table("a = 0".parse().unwrap())).unwrap_err();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let err = parse("a = {type = 'reference', url = 'https://example.com/missing.toml'}", &remote).unwrap_err();
+        assert!(err.contains("404"), "{}", err);
+        parse("a = {type = 'reference', url = 'https://example.com/port.toml', schema = 'port'}", &remote).unwrap_err();
+    }
+
+    #[test]
+    fn reference_cycles() {
+        let remote = RemoteSchemas::new(Served(Arc::default()));
+        let err = parse("x = {type = 'reference', url = 'https://example.com/a.toml'}", &remote).unwrap_err();
+        assert!(err.ends_with("Cycle in references: https://example.com/a.toml -> https://example.com/b.toml -> https://example.com/a.toml"), "{}", err);
+        // the failed resolution does not stay in the chain
+        parse("x = {type = 'reference', url = 'https://example.com/port.toml'}", &remote).unwrap();
+    }
+
+    #[test]
+    fn cache_and_offline() {
+        let dir = std::env::temp_dir().join(format!("toml_schema_remote_{}", std::process::id()));
+        let text = "a = {type = 'reference', url = 'https://example.com/port.toml'}";
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let offline = RemoteSchemas::new(Served(requests.clone())).cache_dir(&dir).offline(true);
+        let before = parse(text, &offline);
+        parse(text, &RemoteSchemas::new(Served(requests.clone())).cache_dir(&dir)).unwrap();
+        // a new set of remote schemas reads the cache directory
+        let after = parse(text, &RemoteSchemas::new(Served(requests.clone())).cache_dir(&dir).offline(true));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(before.unwrap_err().contains("not cached"));
+        after.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn file_urls() {
+        let path = std::env::temp_dir().join(format!("toml_schema_remote_file_{}.toml", std::process::id()));
+        std::fs::write(&path, "type = 'string'").unwrap();
+        let text = format!("a = {{type = 'reference', url = 'file://{}'}}", path.display());
+        let schema = parse(&text, &RemoteSchemas::default());
+        std::fs::remove_file(&path).unwrap();
+        schema.unwrap().check(&Value::Table("a = 'x'".parse().unwrap())).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! Readable forms of errors, one line per error for logs or an indented tree of the causes of each error
+
+use crate::*;
+
+
+impl ErrorMessage {
+    /// The errors that caused this one, like the error of each option of an alternative
+    pub fn causes(&self) -> impl Iterator<Item = &ErrorMessage> {
+        self.args.iter().flat_map(|(_, arg)| match arg {
+            MessageArg::Errors(errors) => errors.as_slice(),
+            MessageArg::Text(_) => &[]
+        })
+    }
+
+    fn arg(&self, name: &str) -> &str {
+        match self.args.iter().find(|(n, _)| *n == name) {
+            Some((_, MessageArg::Text(text))) => text,
+            _ => ""
+        }
+    }
+
+    /// The message without its causes, errors that only give the location of their cause are not headlines, see
+    /// [ErrorMessage::location]
+    pub fn headline(&self) -> String {
+        match self.code {
+            "alternative-miss" => format!("No option matched {}", self.arg("value")),
+            "table-extra" => format!("No extra matched {} = {}", self.arg("key"), self.arg("value")),
+            "at-key" | "array-element" | "table-element" => self.location().1.headline(),
+            _ => self.default.clone()
+        }
+    }
+
+    /// The path of the keys and array elements that lead to the actual error, with that error, indices are not known
+    /// and written `[]`
+    pub fn location(&self) -> (SchemaPath, &ErrorMessage) {
+        let mut path = SchemaPath::new();
+        let mut message = self;
+        loop {
+            let segment = match message.code {
+                "at-key" => PathSegment::Key(message.arg("key").to_string()),
+                "array-element" | "table-element" => PathSegment::AnyIndex,
+                _ => return (path, message)
+            };
+            match message.causes().next() {
+                Some(cause) => {
+                    path.push(segment);
+                    message = cause;
+                },
+                None => return (path, message)
+            }
+        }
+    }
+
+    /// Writes the causes of this error below it, indented by `depth` levels
+    fn write_causes(&self, depth: usize, out: &mut String) {
+        for cause in self.causes() {
+            let (path, cause) = cause.location();
+            out.push_str(&"  ".repeat(depth));
+            out.push_str("- ");
+            if !path.is_empty() {
+                out.push_str(&format!("{}: ", path));
+            }
+            out.push_str(&cause.headline());
+            out.push('\n');
+            cause.write_causes(depth + 1, out);
+        }
+    }
+}
+
+/// `path: message`, or only the message at the root
+fn line(path: &SchemaPath, message: &str) -> String {
+    if path.is_empty() {message.to_string()} else {format!("{}: {}", path, message)}
+}
+
+/// The line of an error of a report, warnings and information start with their severity
+fn reported_line(error: &ReportedError) -> String {
+    let line = line(&error.path, &error.detail.headline());
+    match error.severity {
+        Severity::Error => line,
+        severity => format!("{}: {}", severity, line)
+    }
+}
+
+
+impl SchemaError<'_, '_> {
+    /// The error on one line, the location of the actual error and its message without causes
+    pub fn compact(&self) -> String {
+        let message = self.message();
+        let (path, cause) = message.location();
+        line(&path, &cause.headline())
+    }
+
+    /// The error as a tree, the first line is [SchemaError::compact] and each cause is on its own line below the
+    /// error it caused, indented, like the error of each option of an alternative
+    pub fn verbose(&self) -> String {
+        let message = self.message();
+        let (path, cause) = message.location();
+        let mut out = line(&path, &cause.headline());
+        out.push('\n');
+        cause.write_causes(1, &mut out);
+        out
+    }
+}
+
+impl ValidationReport {
+    /// One `path: message` line per error, for logs, warnings are prefixed with `warning: ` and information with `info: `
+    pub fn compact(&self) -> String {
+        self.errors.iter().map(|e| reported_line(e) + "\n").collect()
+    }
+
+    /// Each error with its causes indented below it, see [SchemaError::verbose]
+    pub fn verbose(&self) -> String {
+        let mut out = String::new();
+        for error in &self.errors {
+            out.push_str(&reported_line(error));
+            out.push('\n');
+            error.detail.write_causes(1, &mut out);
+        }
+        out
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            [server]
+            port = {type = 'int', min = 1}
+            listen = {type = 'alternative', options = ['string', {type = 'array', child = 'string'}, {host = 'string'}]}
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn compact_errors() {
+        let doc = Value::Table("server = {port = 0, listen = 'a'}".parse().unwrap());
+        let schema = schema();
+        let error = schema.check(&doc).unwrap_err();
+        assert_eq!(error.compact(), "server.port: Int 0 does not match [1,9223372036854775807]");
+
+        let doc = Value::Table("server = {port = 1, listen = [1]}\nx = 1".parse().unwrap());
+        let report = schema.validate(&doc);
+        assert_eq!(report.compact().lines().count(), report.errors.len());
+        assert!(report.compact().contains("server.listen: No option matched [1]\n"), "{}", report.compact());
+    }
+
+    #[test]
+    fn verbose_errors() {
+        let doc = Value::Table("server = {port = 1, listen = [1]}".parse().unwrap());
+        let schema = schema();
+        let error = schema.check(&doc).unwrap_err();
+        assert_eq!(error.verbose(), "\
+server.listen: No option matched [1]
+  - Expected string but got array
+  - []: Expected string but got int
+  - Expected table but got array
+");
+        let report = schema.validate(&doc);
+        assert_eq!(report.verbose(), error.verbose());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::*;
+
+
+/// One error found by [TomlSchema::validate], with the location of the value that failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportedError {
+    pub path: SchemaPath,
+    pub message: String,
+    /// The language-neutral form of the message, see [MessageCatalog]
+    pub detail: ErrorMessage,
+    /// The lowest severity of the schemas from the root to the value, only [Severity::Error] fails the document
+    pub severity: Severity,
+}
+
+/// All the errors found in a document by [TomlSchema::validate], unlike [SchemaError] it does not borrow the
+/// document so it can be kept while the document is edited and patched with [TomlSchema::revalidate]
+///
+/// the errors of schemas with a lower `severity` are warnings and information that do not fail the document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub errors: Vec<ReportedError>,
+}
+
+impl ValidationReport {
+    /// Whether the document had no errors, warnings and information are allowed
+    pub fn is_ok(&self) -> bool {
+        self.with_severity(Severity::Error).next().is_none()
+    }
+
+    /// The errors of exactly `severity`
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &ReportedError> {
+        self.errors.iter().filter(move |e| e.severity == severity)
+    }
+
+    /// The errors located at `path` or below it
+    pub fn errors_under<'r>(&'r self, path: &'r SchemaPath) -> impl Iterator<Item = &'r ReportedError> + 'r {
+        self.errors.iter().filter(move |e| e.path.starts_with(path))
+    }
+
+    pub(crate) fn push(&mut self, path: &SchemaPath, error: SchemaError) {
+        let detail = error.message();
+        self.errors.push(ReportedError { path: path.clone(), message: detail.default.clone(), detail, severity: Severity::Error });
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Checks a whole document and collects every error instead of stopping at the first one
+    ///
+    /// each table key and array element is checked separately so errors are reported at the deepest location that
+    /// can be attributed to a single schema, values matched by alternatives or extras are reported as a whole
+    pub fn validate(&self, doc: &Value) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        self.validate_inner(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &mut report);
+        self.report_references(doc, &mut report);
+        self.report_context_validators(doc, None, &mut report);
+        report
+    }
+
+    /// Updates a report made by [TomlSchema::validate] after the value at `changed` was modified, added or removed,
+    /// only the part of the document that can be affected by the change is checked again
+    ///
+    /// the result is the same as validating the whole document again
+    pub fn revalidate(&self, doc: &Value, changed: &SchemaPath, report: &mut ValidationReport) {
+        // any change can break or fix references elsewhere in the document, and change what validators with a
+        // context see
+        if self.has_references() || self.has_context_validators() {
+            *report = self.validate(doc);
+            return
+        }
+
+        let mut schema = self;
+        let mut value = doc;
+        let mut path = SchemaPath::new();
+
+        // the parent of the changed value holds its count constraints so it is always checked again, further up
+        // the closest ancestor that is reached through a single fixed schema is checked again, an array with
+        // `unique_by` compares its elements so a change below it checks the whole array again
+        let ancestors = &changed.segments()[..changed.len().saturating_sub(1)];
+        for segment in ancestors {
+            let next = match (schema.unannotated(), value, segment) {
+                (TomlSchema::Table(table_schema), Value::Table(t), PathSegment::Key(k)) => {
+                    table_schema.entries.get(k).map(|(s, _)| s).zip(t.get(k))
+                },
+                (TomlSchema::Array { cond, unique_by: None, .. }, Value::Array(a), PathSegment::Index(i)) => {
+                    a.get(*i).map(|v| (&**cond, v))
+                },
+                (TomlSchema::Map(map), Value::Table(t), PathSegment::Key(k)) if map.key.is_match(k) && map.key_type.accepts(k) => {
+                    t.get(k).map(|v| (&map.value, v))
+                },
+                _ => None
+            };
+            match next {
+                Some((s, v)) => {
+                    schema = s;
+                    value = v;
+                    path.push(segment.clone());
+                },
+                None => break
+            }
+        }
+
+        report.errors.retain(|e| !e.path.starts_with(&path));
+        let depth = DEFAULT_MAX_DEPTH.saturating_sub(path.len());
+        schema.validate_inner(value, &mut path, depth, report);
+    }
+
+    pub(crate) fn validate_inner(&self, value: &Value, path: &mut SchemaPath, depth: usize, report: &mut ValidationReport) {
+        let Some(depth) = depth.checked_sub(1) else {
+            return report.push(path, SchemaError::DepthExceeded)
+        };
+
+        match (self.unannotated(), value) {
+            (TomlSchema::Table(table_schema), Value::Table(table)) => {
+                Self::validate_table(table_schema, table, path, depth, report);
+            },
+            (TomlSchema::Map(map_schema), Value::Table(table)) => {
+                let MapSchema { key: key_re, key_type, value: child, min, max } = &**map_schema;
+                if table.len() < *min || table.len() > *max {
+                    report.push(path, SchemaError::MapCount { count: table.len(), min: *min, max: *max });
+                }
+                for (key, value) in table {
+                    path.push(PathSegment::Key(key.clone()));
+                    if !key_re.is_match(key) {
+                        report.push(path, SchemaError::KeyMiss { key, re: key_re.as_str() });
+                    } else if !key_type.accepts(key) {
+                        report.push(path, SchemaError::KeyTypeMiss { key, expected: key_type });
+                    } else {
+                        child.validate_inner(value, path, depth, report);
+                    }
+                    path.pop();
+                }
+            },
+            (TomlSchema::Array { cond, min, max, multiple_of, unique_by, .. }, Value::Array(arr)) => {
+                if arr.len() < *min || arr.len() > *max {
+                    report.push(path, SchemaError::ArrayCount { count: arr.len(), min: *min, max: *max });
+                } else if !arr.len().is_multiple_of(*multiple_of as usize) {
+                    report.push(path, SchemaError::ArrayStep { count: arr.len(), multiple_of: *multiple_of as usize });
+                }
+                for (i, value) in arr.iter().enumerate() {
+                    path.push(PathSegment::Index(i));
+                    cond.validate_inner(value, path, depth, report);
+                    path.pop();
+                }
+                if let Some(key) = unique_by.as_deref() {
+                    if let Some((first, second)) = Self::find_duplicate(key, arr) {
+                        report.push(path, SchemaError::DuplicateValue { key, value: &arr[second][key], first, second });
+                    }
+                }
+            },
+            (TomlSchema::Graded { schema, severity }, value) => {
+                let start = report.errors.len();
+                schema.validate_inner(value, path, depth + 1, report);
+                for error in &mut report.errors[start..] {
+                    error.severity = error.severity.min(*severity);
+                }
+            },
+            // the wrapped schema is validated like any other so the root table is still the root, the validator only
+            // runs on values that the schema accepts
+            (TomlSchema::Custom { schema, validator }, value) => {
+                let start = report.errors.len();
+                schema.validate_inner(value, path, depth + 1, report);
+                if report.errors[start..].iter().all(|e| e.severity < Severity::Error) {
+                    if let Err(e) = validator.check(value) {
+                        report.push(path, e);
+                    }
+                }
+            },
+            (TomlSchema::Alternative(options), value) => {
+                // an option without diagnostics matches, otherwise the first option that only has warnings and
+                // information reports them, like `check` accepts it
+                let mut graded = None;
+                for (i, option) in options.iter().enumerate() {
+                    if !options.may_accept(i, value) {
+                        continue
+                    }
+                    let mut option_report = ValidationReport::default();
+                    option.validate_inner(value, path, depth, &mut option_report);
+                    if option_report.errors.is_empty() {
+                        return
+                    }
+                    if graded.is_none() && option_report.is_ok() {
+                        graded = Some(option_report);
+                    }
+                }
+                match graded {
+                    Some(option_report) => report.errors.extend(option_report.errors),
+                    None => if let Err(e) = self.check_depth(value, depth + 1) {
+                        report.push(path, e);
+                    }
+                }
+            },
+            (schema, value) => {
+                if let Err(e) = schema.check_depth(value, depth + 1) {
+                    report.push(path, e);
+                }
+            }
+        }
+    }
+
+    /// Validates the entries of `table` and the constraints of `table_schema` on the whole table, `path` is the location
+    /// of the table
+    pub(crate) fn validate_table(table_schema: &TableSchema, table: &toml::Table, path: &mut SchemaPath, depth: usize, report: &mut ValidationReport) {
+        let TableSchema { entries, min, max, assertions, requirements, .. } = table_schema;
+        let mut found_extras = 0;
+
+        for (key, value) in table {
+            path.push(PathSegment::Key(key.clone()));
+            if Self::validate_entry(table_schema, key, value, path, depth, report) {found_extras += 1;}
+            path.pop();
+        }
+
+        if found_extras < *min || found_extras > *max {
+            report.push(path, SchemaError::TableCount { count: found_extras, min: *min, max: *max });
+        }
+        for (key, schema) in parse_toml::missing_keys(entries, table) {
+            report.push(path, SchemaError::MissingKey { key, expected: schema.into() });
+        }
+        for assertion in assertions.iter() {
+            if let Err((left, right)) = assertion.holds(table, entries) {
+                report.push(path, SchemaError::AssertionFailed { assertion, left, right });
+            }
+        }
+        for requirement in requirements.iter() {
+            if let Some(key) = requirement.missing(table, entries) {
+                report.push(path, SchemaError::MissingRequired { key, requirement });
+            }
+        }
+    }
+
+    /// Validates the value of `key` in a table of `table_schema`, `path` is the location of the value, returns whether
+    /// the key counts in the number of extras
+    pub(crate) fn validate_entry(table_schema: &TableSchema, key: &str, value: &Value, path: &mut SchemaPath, depth: usize, report: &mut ValidationReport) -> bool {
+        let TableSchema { entries, extras, policy, .. } = table_schema;
+        match entries.get(key) {
+            Some((schema, _)) => {
+                schema.validate_inner(value, path, depth, report);
+                policy.counts_entry(extras, key)
+            },
+            // the header of the document, `path` only has the key at the root
+            None if path.len() == 1 && header::is_header(key, value) => false,
+            None => {
+                if let Some(expected) = policy.key_case.filter(|case| !case.accepts(key)) {
+                    report.push(path, SchemaError::KeyCaseMiss { key, expected });
+                }
+                match Self::find_extra_match(extras, *policy, key, value, depth) {
+                    Ok(()) => true,
+                    Err(errors) => {
+                        report.push(path, SchemaError::TableMiss { key, value, errors });
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = {type = 'string', regex = '^[a-z]+$'}
+            [servers]
+            type = 'array'
+            max = 3
+            default = []
+            child = {type = 'table', port = {type = 'int', min = 1}, extras = [{key = '^x-', schema = {type = 'bool'}}]}
+            [limits]
+            extras = [{key = '.*', schema = {type = 'int'}}]
+            max = 2
+            default = {}
+        ".parse().unwrap()
+    }
+
+    fn doc(s: &str) -> Value {
+        Value::Table(s.parse().unwrap())
+    }
+
+    fn paths(report: &ValidationReport) -> Vec<String> {
+        let mut paths: Vec<String> = report.errors.iter().map(|e| format!("{:?}", e.path.segments())).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn validate_collects_all() {
+        let schema = schema();
+
+        assert!(schema.validate(&doc("name = 'ok'\nservers = [{port = 1}]")).is_ok());
+
+        let report = schema.validate(&doc("
+            name = 'NOT OK'
+            servers = [{port = 0}, {port = 2, x-debug = 'yes'}]
+            limits = {a = 1, b = 2, c = 'x'}
+        "));
+        assert_eq!(report.errors.len(), 4, "{:#?}", report);
+
+        let servers = "servers".parse().unwrap();
+        assert_eq!(report.errors_under(&servers).count(), 2);
+
+        // validate agrees with check
+        let bad = doc("name = 'NOT OK'");
+        assert!(schema.check(&bad).is_err());
+        assert!(!schema.validate(&bad).is_ok());
+
+        // keys without a default are required
+        let missing = doc("servers = [{x-debug = true}]");
+        assert_eq!(schema.check(&doc("servers = []")), Err(SchemaError::MissingKey { key: "name", expected: SchemaType::String }));
+        let report = schema.validate(&missing);
+        assert_eq!(paths(&report), ["[Key(\"servers\"), Index(0)]", "[]"]);
+        assert!(report.errors.iter().all(|e| e.message.starts_with("Missing key")), "{:#?}", report);
+    }
+
+    #[test]
+    fn revalidate_matches_full_validation() {
+        let schema = schema();
+        let mut document = doc("
+            name = 'ok'
+            servers = [{port = 0}, {port = 2}]
+            limits = {a = 1}
+        ");
+        let mut report = schema.validate(&document);
+        assert_eq!(report.errors.len(), 1);
+
+        type Edit = Box<dyn Fn(&mut Value)>;
+        let edits: Vec<(&str, Edit)> = vec![
+            ("servers[0].port", Box::new(|d| d["servers"][0]["port"] = Value::Integer(5))),
+            ("servers[1].x-trace", Box::new(|d| {
+                d["servers"][1].as_table_mut().unwrap().insert("x-trace".into(), Value::Integer(1));
+            })),
+            ("limits.b", Box::new(|d| {
+                let limits = d["limits"].as_table_mut().unwrap();
+                limits.insert("b".into(), Value::Integer(2));
+                limits.insert("c".into(), Value::Integer(3));
+            })),
+            ("servers[1].x-trace", Box::new(|d| {d["servers"][1].as_table_mut().unwrap().remove("x-trace");})),
+            ("name", Box::new(|d| d["name"] = Value::String("BAD".into()))),
+            ("limits", Box::new(|d| {d.as_table_mut().unwrap().remove("limits");})),
+            ("name", Box::new(|d| {d.as_table_mut().unwrap().remove("name");})),
+        ];
+
+        for (changed, edit) in edits {
+            edit(&mut document);
+            schema.revalidate(&document, &changed.parse().unwrap(), &mut report);
+            assert_eq!(paths(&report), paths(&schema.validate(&document)), "after editing {}", changed);
+        }
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[test]
+    fn revalidate_unique_by() {
+        let schema: TomlSchema = "
+            [servers]
+            type = 'array'
+            unique_by = 'name'
+            child = {name = 'string'}
+        ".parse().unwrap();
+        let mut document = doc("servers = [{name = 'a'}, {name = 'b'}]");
+        let mut report = schema.validate(&document);
+        assert!(report.errors.is_empty(), "{:#?}", report);
+
+        document["servers"][1]["name"] = Value::String("a".into());
+        schema.revalidate(&document, &"servers[1].name".parse().unwrap(), &mut report);
+        assert_eq!(report, schema.validate(&document));
+        assert_eq!(report.errors.len(), 1);
+
+        document["servers"][1]["name"] = Value::String("c".into());
+        schema.revalidate(&document, &"servers[1].name".parse().unwrap(), &mut report);
+        assert_eq!(report, schema.validate(&document));
+        assert!(report.errors.is_empty(), "{:#?}", report);
+    }
+}
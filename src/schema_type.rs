@@ -1,10 +1,14 @@
 use crate::*;
 
+/// The kind of a schema or of a TOML value, its [Display](std::fmt::Display) form is the name used in the `type` key of schemas
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum SchemaType {
     Alternative, String, Integer,
-    Date, Bool, Float, Table, Array,
-    Anything, Exact
+    Date, Bool, Float, Table, Array, Map,
+    Anything, Exact,
+    /// A user-defined type, see [PluginType]
+    Plugin
 }
 
 impl From<&TomlSchema> for SchemaType {
@@ -13,13 +17,16 @@ impl From<&TomlSchema> for SchemaType {
             TomlSchema::Alternative(_) => SchemaType::Alternative,
             TomlSchema::String{..} => SchemaType::String,
             TomlSchema::Integer{..} => SchemaType::Integer,
-            TomlSchema::Date{..} => SchemaType::Date,
-            TomlSchema::Bool => SchemaType::Bool,
+            TomlSchema::Date(_) => SchemaType::Date,
+            TomlSchema::Bool { .. } => SchemaType::Bool,
             TomlSchema::Float{..} => SchemaType::Float,
-            TomlSchema::Table{..} => SchemaType::Table,
+            TomlSchema::Table(_) => SchemaType::Table,
             TomlSchema::Array{..} => SchemaType::Array,
+            TomlSchema::Map(_) => SchemaType::Map,
             TomlSchema::Anything => SchemaType::Anything,
-            TomlSchema::Exact(_) => SchemaType::Exact
+            TomlSchema::Exact { .. } | TomlSchema::Enum(_) => SchemaType::Exact,
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => SchemaType::from(&**schema),
+            TomlSchema::Plugin(_) => SchemaType::Plugin
         }
     }
 }
@@ -48,10 +55,69 @@ impl TryFrom<&str> for SchemaType {
             "date" => Ok(SchemaType::Date),
             "array" => Ok(SchemaType::Array),
             "table" => Ok(SchemaType::Table),
+            "map" => Ok(SchemaType::Map),
             "alternative" => Ok(SchemaType::Alternative),
             "anything" => Ok(SchemaType::Anything),
             "exact" =>Ok(SchemaType::Exact),
+            "plugin" => Ok(SchemaType::Plugin),
             _ => Err(format!("Invalid schema type {}", value))
         }
     }
-}
\ No newline at end of file
+}
+
+impl SchemaType {
+    /// The name of this type in the `type` key of schemas
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaType::String => "string",
+            SchemaType::Integer => "int",
+            SchemaType::Float => "float",
+            SchemaType::Bool => "bool",
+            SchemaType::Date => "date",
+            SchemaType::Array => "array",
+            SchemaType::Table => "table",
+            SchemaType::Map => "map",
+            SchemaType::Alternative => "alternative",
+            SchemaType::Anything => "anything",
+            SchemaType::Exact => "exact",
+            SchemaType::Plugin => "plugin"
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_type_names() {
+        let all = [
+            SchemaType::Alternative, SchemaType::String, SchemaType::Integer,
+            SchemaType::Date, SchemaType::Bool, SchemaType::Float, SchemaType::Table, SchemaType::Array,
+            SchemaType::Map, SchemaType::Anything, SchemaType::Exact, SchemaType::Plugin
+        ];
+        for t in all {
+            assert_eq!(SchemaType::try_from(t.to_string().as_str()), Ok(t));
+        }
+        assert_eq!(SchemaType::Integer.to_string(), "int");
+        SchemaType::try_from("integer").unwrap_err();
+    }
+
+    #[test]
+    fn schema_type_of_schema() {
+        let (anything, _) = TomlSchema::from_table(&"type = 'anything'".parse().unwrap()).unwrap();
+        let (exact, _) = TomlSchema::from_table(&"type = 'exact'\nvalue = 5".parse().unwrap()).unwrap();
+
+        assert_eq!(SchemaType::from(&anything), SchemaType::Anything);
+        assert_eq!(SchemaType::from(&exact), SchemaType::Exact);
+    }
+}
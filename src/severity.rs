@@ -0,0 +1,116 @@
+//! Severities of schema nodes, `severity = "warning"` makes the values a node rejects warnings of
+//! [TomlSchema::validate] that [TomlSchema::check] accepts, to announce a stricter schema before enforcing it
+
+use std::fmt;
+use std::str::FromStr;
+use crate::*;
+
+
+/// How bad it is for a value to fail a schema, ordered from the least to the most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Severity {
+    Info,
+    Warning,
+    #[default]
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            other => Err(format!("severity must be \"error\", \"warning\" or \"info\" but got {:?}", other))
+        }
+    }
+}
+
+
+impl TomlSchema {
+    /// The severity of the errors of this schema, [Severity::Error] unless it has a lower `severity`
+    pub fn severity(&self) -> Severity {
+        match self {
+            TomlSchema::Graded { severity, .. } => *severity,
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } => schema.severity(),
+            _ => Severity::Error
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = 'string'
+            port = {type = 'int', min = 1024, severity = 'warning'}
+            [limits]
+            severity = 'info'
+            memory = {type = 'int', max = 64}
+            threads = {type = 'int', min = 1, severity = 'warning'}
+        ".parse().unwrap()
+    }
+
+    fn doc(s: &str) -> Value {
+        Value::Table(s.parse().unwrap())
+    }
+
+    #[test]
+    fn warnings_do_not_fail() {
+        let schema = schema();
+        let value = doc("name = 'web'\nport = 80\nlimits = {memory = 128, threads = 0}");
+        schema.check(&value).unwrap();
+
+        let report = schema.validate(&value);
+        assert!(report.is_ok(), "{:?}", report);
+        let severities: Vec<(String, Severity)> = report.errors.iter().map(|e| (e.path.to_string(), e.severity)).collect();
+        // the severity of a node is an upper bound for the nodes below it
+        assert_eq!(severities, [
+            ("limits.memory".to_string(), Severity::Info),
+            ("limits.threads".to_string(), Severity::Info),
+            ("port".to_string(), Severity::Warning),
+        ]);
+        assert_eq!(report.with_severity(Severity::Info).count(), 2);
+
+        let value = doc("name = 1\nport = 80\nlimits = {}");
+        schema.check(&value).unwrap_err();
+        let report = schema.validate(&value);
+        assert!(!report.is_ok());
+        assert_eq!(report.with_severity(Severity::Error).count(), 1);
+        assert!(report.compact().contains("warning: port: "), "{}", report.compact());
+    }
+
+    #[test]
+    fn parse_severity() {
+        let schema = schema();
+        let severity = |path: &str| schema.resolve_path(&path.parse().unwrap())[0].0.severity();
+        assert_eq!(severity("port"), Severity::Warning);
+        assert_eq!(severity("name"), Severity::Error);
+        assert_eq!(severity("limits"), Severity::Info);
+        "type = 'int'\nseverity = 'fatal'".parse::<TomlSchema>().unwrap_err();
+        "type = 'int'\nseverity = 'error'".parse::<TomlSchema>().unwrap().check(&Value::Integer(1)).unwrap();
+    }
+}
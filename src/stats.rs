@@ -0,0 +1,114 @@
+//! Sizes of a compiled schema, for finding what makes a large generated schema slow to load or to check, see
+//! [TomlSchema::stats]
+
+use std::collections::HashSet;
+use std::mem::size_of;
+use crate::*;
+
+
+/// The size of a schema, returned by [TomlSchema::stats]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct SchemaStats {
+    /// The number of schema nodes of each type, wrappers like annotations are counted as the type they wrap
+    pub nodes: HashMap<SchemaType, usize>,
+    /// The number of segments of the longest path to a node, table keys, array children, extras and alternative
+    /// options are each one level
+    pub max_depth: usize,
+    /// The number of regexes, patterns that are compared as strings are not counted and identical patterns that share
+    /// their regex count once
+    pub regexes: usize,
+    /// A rough estimate of the memory used by the nodes, keys, patterns and defaults in bytes, the compiled regexes
+    /// are not included
+    pub estimated_bytes: usize,
+}
+
+impl SchemaStats {
+    /// The number of nodes of all types
+    pub fn total_nodes(&self) -> usize {
+        self.nodes.values().sum()
+    }
+}
+
+
+impl TomlSchema {
+    /// Counts the nodes of this schema, see [SchemaStats]
+    pub fn stats(&self) -> SchemaStats {
+        let mut stats = SchemaStats::default();
+        let mut regexes = HashSet::new();
+        let mut count_pattern = |pattern: &Pattern, stats: &mut SchemaStats| {
+            stats.estimated_bytes += pattern.as_str().len();
+            if let Some(id) = pattern.regex_id() {
+                regexes.insert(id);
+            }
+        };
+
+        self.visit(&mut |path, schema| {
+            *stats.nodes.entry(SchemaType::from(schema)).or_default() += 1;
+            stats.max_depth = stats.max_depth.max(path.len());
+            stats.estimated_bytes += size_of::<TomlSchema>();
+
+            match schema.structure() {
+                TomlSchema::String { regex, rules, .. } => {
+                    count_pattern(regex, &mut stats);
+                    if let Some(not_regex) = &rules.not_regex {
+                        count_pattern(not_regex, &mut stats);
+                    }
+                },
+                TomlSchema::Map(map) => count_pattern(&map.key, &mut stats),
+                TomlSchema::Table(table) => {
+                    for (key, (_, default)) in table.entries.iter() {
+                        stats.estimated_bytes += key.len() + default.as_ref().map_or(0, value_bytes);
+                    }
+                    for extra in table.extras.iter() {
+                        count_pattern(&extra.key, &mut stats);
+                    }
+                },
+                TomlSchema::Exact { value, .. } => stats.estimated_bytes += value_bytes(value),
+                TomlSchema::Enum(values) => stats.estimated_bytes += values.iter().map(value_bytes).sum::<usize>(),
+                _ => ()
+            }
+        });
+        stats.regexes = regexes.len();
+        stats
+    }
+}
+
+/// The memory used by a value, with the text of its strings and keys
+fn value_bytes(value: &Value) -> usize {
+    size_of::<Value>() + match value {
+        Value::String(s) => s.len(),
+        Value::Array(arr) => arr.iter().map(value_bytes).sum(),
+        Value::Table(table) => table.iter().map(|(key, value)| key.len() + value_bytes(value)).sum(),
+        _ => 0
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_stats() {
+        let schema: TomlSchema = "
+            name = {type = 'string', regex = '^[a-z]+$'}
+            alias = {type = 'string', regex = '^[a-z]+$'}
+            host = {type = 'string', regex = '^localhost$'}
+            ports = {type = 'array', child = {type = 'alternative', options = ['int', {type = 'string', regex = '[0-9]+-[0-9]+'}]}}
+            extras = [{key = '^x-', schema = 'anything'}]
+        ".parse().unwrap();
+
+        let stats = schema.stats();
+        assert_eq!(stats.nodes[&SchemaType::String], 4);
+        assert_eq!(stats.nodes[&SchemaType::Table], 1);
+        assert_eq!(stats.total_nodes(), 9);
+        // the options of the elements of ports
+        assert_eq!(stats.max_depth, 3);
+        // the shared name pattern and the range, `^localhost$` and `^x-` are literals
+        assert_eq!(stats.regexes, 2);
+        assert!(stats.estimated_bytes >= 9 * size_of::<TomlSchema>());
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use crate::*;
+
+
+/// Validates the top-level table of a document one entry at a time, for documents too large to be kept in memory or
+/// split in several files, see [TomlSchema::entry_validator]
+///
+/// each entry is checked like in [TomlSchema::validate] as soon as it is given, the checks that need the whole table
+/// (missing keys and the number of extras) are made by [EntryValidator::finish], assertions, requirements, `refers_to`
+/// and the custom validators with a [ValidationContext] need the whole document and are not checked
+#[derive(Debug)]
+pub struct EntryValidator<'s> {
+    schema: &'s TableSchema,
+    seen: HashSet<String>,
+    found_extras: usize,
+}
+
+impl TomlSchema {
+    /// A validator for the entries of the root table, fails when this is not a table schema
+    pub fn entry_validator(&self) -> Result<EntryValidator<'_>, String> {
+        match self.unannotated() {
+            TomlSchema::Table(schema) => Ok(EntryValidator { schema, seen: HashSet::new(), found_extras: 0 }),
+            other => Err(format!("Entries can only be validated one at a time with a table schema but got {}", SchemaType::from(other)))
+        }
+    }
+
+    /// Validates the entries of the root table as they are read from `entries`, each error is given to `on_error` as
+    /// soon as it is found, see [EntryValidator]
+    pub fn validate_entries<K: AsRef<str>>(&self, entries: impl IntoIterator<Item = (K, Value)>, mut on_error: impl FnMut(ReportedError)) -> Result<(), String> {
+        let mut validator = self.entry_validator()?;
+        for (key, value) in entries {
+            validator.check_entry(key.as_ref(), &value).errors.into_iter().for_each(&mut on_error);
+        }
+        validator.finish().errors.into_iter().for_each(on_error);
+        Ok(())
+    }
+}
+
+impl EntryValidator<'_> {
+    /// Checks one entry of the root table, a key that was already given is an error
+    pub fn check_entry(&mut self, key: &str, value: &Value) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut path = SchemaPath::new();
+        path.push(PathSegment::Key(key.to_string()));
+
+        if !self.seen.insert(key.to_string()) {
+            report.push(&path, SchemaError::DuplicateKey { key });
+            return report
+        }
+        if TomlSchema::validate_entry(self.schema, key, value, &mut path, DEFAULT_MAX_DEPTH - 1, &mut report) {
+            self.found_extras += 1;
+        }
+        report
+    }
+
+    /// Checks all the entries of a fragment of the document, like a file of a configuration split in several files
+    pub fn check_fragment(&mut self, fragment: &toml::Table) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        for (key, value) in fragment {
+            report.errors.extend(self.check_entry(key, value).errors);
+        }
+        report
+    }
+
+    /// Ends the validation with the checks that need all the keys, the keys without a default that were never given
+    /// and the number of extras
+    pub fn finish(self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let root = SchemaPath::new();
+        let TableSchema { entries, min, max, .. } = self.schema;
+
+        if self.found_extras < *min || self.found_extras > *max {
+            report.push(&root, SchemaError::TableCount { count: self.found_extras, min: *min, max: *max });
+        }
+        for (key, (schema, default)) in entries.iter() {
+            if default.is_none() && !self.seen.contains(key.as_str()) {
+                report.push(&root, SchemaError::MissingKey { key, expected: schema.into() });
+            }
+        }
+        report
+    }
+}
+
+
+
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = {type = 'string', regex = '^[a-z]+$'}
+            port = {type = 'int', min = 1, default = 80}
+            extras = [{key = '^x-', schema = 'bool'}]
+            max = 1
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn entries_one_by_one() {
+        let schema = schema();
+        let mut errors = Vec::new();
+        let entries = [("name", Value::String("web".into())), ("x-debug", Value::Boolean(true))];
+        schema.validate_entries(entries, |e| errors.push(e)).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let entries = [("port", Value::Integer(0)), ("x-a", Value::Boolean(true)), ("x-b", Value::Boolean(true)), ("x-c", Value::Integer(1))];
+        schema.validate_entries(entries, |e| errors.push(e)).unwrap();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(errors.len(), 4, "{:?}", messages);
+        assert_eq!(errors[0].path.segments(), [PathSegment::Key("port".into())]);
+        assert!(messages[2].contains("Table extra count 2"), "{}", messages[2]);
+        assert!(messages[3].contains("Missing key name"), "{}", messages[3]);
+
+        "type = 'int'".parse::<TomlSchema>().unwrap().entry_validator().unwrap_err();
+    }
+
+    #[test]
+    fn fragments() {
+        let schema = schema();
+        let mut validator = schema.entry_validator().unwrap();
+        assert!(validator.check_fragment(&"name = 'web'".parse().unwrap()).is_ok());
+        let report = validator.check_fragment(&"name = 'api'\nport = 8080".parse().unwrap());
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("more than once"), "{:?}", report);
+        assert!(validator.finish().is_ok());
+    }
+}
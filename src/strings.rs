@@ -0,0 +1,204 @@
+use std::borrow::Cow;
+use crate::*;
+
+
+/// The options of [TomlSchema::String] other than its regex and reference, they are reference counted so that the
+/// schema enum stays small
+#[derive(Debug, Clone, Default)]
+pub struct StringRules {
+    /// A pattern that must not be found in the string, from `not_regex`
+    pub not_regex: Option<Pattern>,
+    /// Literal text that the string must start with, end with or contain, compared case-sensitively
+    pub starts_with: Option<String>,
+    pub ends_with: Option<String>,
+    pub contains: Option<String>,
+    /// Whether placeholders in the string are replaced by [TomlSchema::check_and_complete]
+    pub interpolate: bool,
+    /// Whether surrounding whitespace is ignored when checking and removed by [TomlSchema::check_and_complete]
+    pub trim: bool,
+    /// The unicode normal form the string is checked in and converted to by [TomlSchema::check_and_complete]
+    #[cfg(feature = "unicode")]
+    pub normalize: Option<NormalForm>,
+    /// The syntax the string follows, from string types like `glob`
+    pub format: Option<StringFormat>,
+}
+
+
+/// A unicode normalization form, from `normalize = "nfc"`
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+#[cfg(feature = "unicode")]
+impl NormalForm {
+    pub(crate) const NAMES: [&'static str; 4] = ["nfc", "nfd", "nfkc", "nfkd"];
+    const ALL: [NormalForm; 4] = [NormalForm::Nfc, NormalForm::Nfd, NormalForm::Nfkc, NormalForm::Nfkd];
+
+    pub fn from_name(name: &str) -> Option<NormalForm> {
+        NormalForm::NAMES.iter().position(|n| *n == name).map(|i| NormalForm::ALL[i])
+    }
+
+    pub fn name(self) -> &'static str {
+        NormalForm::NAMES[self as usize]
+    }
+
+    /// `s` in this form, borrowed when it already is
+    pub fn apply(self, s: &str) -> Cow<'_, str> {
+        use unicode_normalization::{UnicodeNormalization, is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick};
+        use unicode_normalization::IsNormalized;
+
+        let quick = match self {
+            NormalForm::Nfc => is_nfc_quick(s.chars()),
+            NormalForm::Nfd => is_nfd_quick(s.chars()),
+            NormalForm::Nfkc => is_nfkc_quick(s.chars()),
+            NormalForm::Nfkd => is_nfkd_quick(s.chars()),
+        };
+        if quick == IsNormalized::Yes {
+            return Cow::Borrowed(s)
+        }
+        let normalized: String = match self {
+            NormalForm::Nfc => s.nfc().collect(),
+            NormalForm::Nfd => s.nfd().collect(),
+            NormalForm::Nfkc => s.nfkc().collect(),
+            NormalForm::Nfkd => s.nfkd().collect(),
+        };
+        if normalized == s {Cow::Borrowed(s)} else {Cow::Owned(normalized)}
+    }
+}
+
+#[cfg(feature = "unicode")]
+impl std::fmt::Display for NormalForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl StringRules {
+    /// Whether these are the default rules, that add nothing to the regex
+    pub fn is_default(&self) -> bool {
+        self.not_regex.is_none() && self.starts_with.is_none() && self.ends_with.is_none() && self.contains.is_none()
+            && !self.interpolate && !self.rewrites() && self.format.is_none()
+    }
+
+    /// Whether [TomlSchema::check_and_complete] changes the strings, because of `trim` or `normalize`
+    pub fn rewrites(&self) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.normalize.is_some() {
+            return true
+        }
+        self.trim
+    }
+
+    /// The form of `s` that is checked, without surrounding whitespace with `trim` and in the normal form of
+    /// `normalize`
+    pub fn canonical<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let s = if self.trim {s.trim()} else {s};
+        #[cfg(feature = "unicode")]
+        if let Some(form) = self.normalize {
+            return form.apply(s)
+        }
+        Cow::Borrowed(s)
+    }
+
+    /// Checks the canonical form of `s`, errors show `s` as written
+    pub(crate) fn check<'s,'v>(&'s self, s: &'v str) -> Result<(), SchemaError<'s,'v>> {
+        let canonical = self.canonical(s);
+        let checked: &str = &canonical;
+        if let Some(prefix) = self.starts_with.as_deref().filter(|prefix| !checked.starts_with(prefix)) {
+            return Err(SchemaError::PrefixMiss { string: s, prefix })
+        }
+        if let Some(suffix) = self.ends_with.as_deref().filter(|suffix| !checked.ends_with(suffix)) {
+            return Err(SchemaError::SuffixMiss { string: s, suffix })
+        }
+        if let Some(part) = self.contains.as_deref().filter(|part| !checked.contains(part)) {
+            return Err(SchemaError::ContainsMiss { string: s, part })
+        }
+        if let Some(not_regex) = self.not_regex.as_ref().filter(|not_regex| not_regex.is_match(checked)) {
+            return Err(SchemaError::NotRegexMatch { string: s, re: not_regex.as_str() })
+        }
+        match &self.format {
+            Some(format) => format.check(checked).map_err(|reason| SchemaError::FormatMiss { string: s, format, reason }),
+            None => Ok(())
+        }
+    }
+
+    /// The rewritten form of `s` when [StringRules::canonical] changes it
+    fn rewrite(&self, s: &str) -> Option<String> {
+        match self.canonical(s) {
+            Cow::Borrowed(canonical) if canonical.len() == s.len() => None,
+            canonical => Some(canonical.into_owned())
+        }
+    }
+
+    /// `s` with the literals it must start with, end with and contain added
+    #[cfg(feature = "generate")]
+    pub(crate) fn decorate(&self, s: &str) -> String {
+        [self.starts_with.as_deref(), Some(s), self.contains.as_deref(), self.ends_with.as_deref()]
+            .into_iter().flatten().collect()
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Rewrites the strings of `doc` with `trim` or `normalize` to their canonical form
+    pub(crate) fn canonicalize(&self, doc: &mut Value) {
+        let mut found = Vec::new();
+        self.collect_string_rules(doc, &mut SchemaPath::new(), DEFAULT_MAX_DEPTH, &StringRules::rewrites, &mut found);
+        for (path, rules) in found {
+            if let Some(Value::String(s)) = path.value_in_mut(doc) {
+                if let Some(canonical) = rules.rewrite(s) {
+                    *s = canonical;
+                }
+            }
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim() {
+        let schema: TomlSchema = "
+            name = {type = 'string', starts_with = 'a', trim = true, ends_with = 'd'}
+            raw = {type = 'string', starts_with = 'x'}
+        ".parse().unwrap();
+
+        schema.check_str("name = '  abcd\t'\nraw = 'x'").unwrap();
+        schema.check_str("name = ' abc '\nraw = 'x'").unwrap_err();
+        schema.check_str("name = 'abcd'\nraw = ' x'").unwrap_err();
+
+        let mut doc = Value::Table("name = '  abcd\t'\nraw = 'x'".parse().unwrap());
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["name"].as_str(), Some("abcd"));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalize() {
+        let schema: TomlSchema = "
+            ids = {type = 'array', child = {type = 'string', normalize = 'nfc', regex = '^\u{e9}t\u{e9}$'}}
+        ".parse().unwrap();
+        // decomposed accents
+        let decomposed = "e\u{301}te\u{301}";
+        let mut doc = Value::Table(toml::Table::from_iter([("ids".to_string(), Value::Array(vec![decomposed.into()]))]));
+
+        schema.check(&doc).unwrap();
+        schema.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["ids"][0].as_str(), Some("\u{e9}t\u{e9}"));
+
+        assert_eq!(NormalForm::Nfkd.apply("\u{e9}"), "e\u{301}");
+        assert!(matches!(NormalForm::Nfc.apply("plain"), Cow::Borrowed(_)));
+        "x = {type = 'string', normalize = 'nfx'}".parse::<TomlSchema>().unwrap_err();
+    }
+}
@@ -0,0 +1,128 @@
+//! Suppressed errors, a [Suppression] hides the errors of one code (see [MESSAGE_CODES]) under some locations so that
+//! a schema can be adopted on existing documents before all of them are fixed
+
+use std::fmt;
+use crate::*;
+
+
+/// Hides the errors with the message code `code` located at a path matching `path`, or below it
+///
+/// paths are written like [SchemaPath] where the key `*` matches any key and `[]` any element, `legacy.*` hides the
+/// errors of every key of the `legacy` table but not the errors of the table itself (like a missing key)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suppression {
+    code: Option<&'static str>,
+    path: SchemaPath,
+}
+
+impl Suppression {
+    /// Hides the errors with the message code `code` at `path`, `code` is `"*"` to hide every error
+    pub fn new(code: &str, path: &str) -> Result<Self, String> {
+        let code = match code {
+            "*" => None,
+            code => match MESSAGE_CODES.iter().find(|(c, _)| *c == code) {
+                Some((c, _)) => Some(*c),
+                None => return Err(format!("Unknown error code {:?} in suppression", code))
+            }
+        };
+        Ok(Suppression { code, path: path.parse()? })
+    }
+
+    /// Reads suppressions kept with a document, an array of tables like `{code = "int-range", path = "legacy.*"}`
+    pub fn from_value(value: &Value) -> Result<Vec<Self>, String> {
+        let Value::Array(entries) = value else {
+            return Err(format!("Suppressions must be an array of tables but got {}", value))
+        };
+        entries.iter().map(|entry| {
+            let (Some(Value::String(code)), Some(Value::String(path))) = (entry.get("code"), entry.get("path")) else {
+                return Err(format!("A suppression must have a code and a path but got {}", entry))
+            };
+            Suppression::new(code, path)
+        }).collect()
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        self.code
+    }
+
+    pub fn path(&self) -> &SchemaPath {
+        &self.path
+    }
+
+    /// Whether this hides `error`, an error that only gives the location of its cause is matched by its cause
+    pub fn hides(&self, error: &ReportedError) -> bool {
+        let (inner, cause) = error.detail.location();
+        if self.code.is_some_and(|code| code != cause.code) {
+            return false
+        }
+        let path = error.path.segments().iter().chain(inner.segments());
+        let mut pattern = self.path.segments().iter();
+        for segment in path {
+            let Some(expected) = pattern.next() else {return true};
+            let matches = match (expected, segment) {
+                (PathSegment::Key(k), PathSegment::Key(_)) if k == "*" => true,
+                (PathSegment::AnyIndex, PathSegment::Index(_) | PathSegment::AnyIndex) => true,
+                (expected, segment) => expected == segment
+            };
+            if !matches {
+                return false
+            }
+        }
+        pattern.next().is_none()
+    }
+}
+
+/// Writes the suppression as `code@path`
+impl fmt::Display for Suppression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.code.unwrap_or("*"), self.path)
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = 'string'
+            [legacy]
+            type = 'map'
+            value = {port = {type = 'int', min = 1}, host = 'string'}
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn suppressed_errors() {
+        let doc = Value::Table("name = 1\nlegacy = {a = {port = 0, host = 'x'}, b = {port = 1}}".parse().unwrap());
+        let validator = Validator::new(schema()).fail_fast(false);
+        assert_eq!(validator.validate(&doc).errors.len(), 3);
+
+        let validator = validator.suppress(Suppression::new("int-range", "legacy.*").unwrap());
+        let report = validator.validate(&doc);
+        assert_eq!(report.errors.len(), 2, "{:?}", report);
+
+        let validator = validator.suppress(Suppression::new("*", "legacy").unwrap());
+        let report = validator.validate(&doc);
+        assert_eq!(report.compact(), "name: Expected string but got int\n");
+
+        // with suppressions, fail-fast reports the first error that is not hidden
+        let validator = Validator::new(schema()).suppress(Suppression::new("type-mismatch", "name").unwrap());
+        let report = validator.validate(&doc);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].path.starts_with(&"legacy".parse().unwrap()), "{:?}", report);
+    }
+
+    #[test]
+    fn parse_suppressions() {
+        let value: toml::Table = "ignore = [{code = 'int-range', path = 'legacy.*.port'}, {code = '*', path = 'a[]'}]".parse().unwrap();
+        let suppressions = Suppression::from_value(&value["ignore"]).unwrap();
+        assert_eq!(suppressions.iter().map(|s| s.to_string()).collect::<Vec<_>>(), ["int-range@legacy.\"*\".port", "*@a[]"]);
+
+        Suppression::new("no-such-code", "a").unwrap_err();
+        Suppression::from_value(&Value::Array(vec![Value::Integer(1)])).unwrap_err();
+    }
+}
@@ -0,0 +1,171 @@
+use std::ops::Index;
+use crate::*;
+
+
+/// The content of [TomlSchema::Table], it is reference counted so that the schema enum stays small
+#[derive(Debug, Clone, Default)]
+pub struct TableSchema {
+    /// The keys described by the schema, with their schema and default value
+    pub entries: TableEntries,
+    /// The schemas of keys that match no entry, see [ExtrasPolicy]
+    pub extras: Box<[TableEntry]>,
+    /// The bounds on the number of keys matched by extras
+    pub min: usize,
+    pub max: usize,
+    pub assertions: Box<[Assertion]>,
+    pub requirements: Box<[Requirement]>,
+    pub policy: ExtrasPolicy,
+}
+
+/// The content of [TomlSchema::Map], it is reference counted so that the schema enum stays small
+#[derive(Debug, Clone)]
+pub struct MapSchema {
+    pub key: Pattern,
+    pub key_type: KeyType,
+    pub value: TomlSchema,
+    pub min: usize,
+    pub max: usize,
+}
+
+
+/// The entries of a table schema, a slice in the order they were declared with a sorted index for lookups by binary
+/// search, it takes less memory than a map and always iterates in the same order
+///
+/// the schemas parsed from TOML declare their keys in alphabetical order, errors and filled defaults follow this order
+///
+/// it is built with [FromIterator], a key that appears several times keeps its first position and its last schema
+/// like in a map
+#[derive(Debug, Clone, Default)]
+pub struct TableEntries {
+    entries: Box<[KeyEntry]>,
+    /// the positions of the entries, sorted by key
+    sorted: Box<[u32]>,
+    /// the number of entries without a default
+    required: u32,
+}
+
+/// A key with its schema and default value
+type KeyEntry = (String, (TomlSchema, Option<Value>));
+
+impl TableEntries {
+    /// The schema and default value of a key
+    pub fn get(&self, key: &str) -> Option<&(TomlSchema, Option<Value>)> {
+        self.get_key_value(key).map(|(_, entry)| entry)
+    }
+
+    /// Same as [TableEntries::get] with the key as stored in the schema
+    pub fn get_key_value(&self, key: &str) -> Option<(&String, &(TomlSchema, Option<Value>))> {
+        let i = self.sorted.binary_search_by(|&i| self.entries[i as usize].0.as_str().cmp(key)).ok()?;
+        let (k, entry) = &self.entries[self.sorted[i] as usize];
+        Some((k, entry))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// The entries in the order they were declared
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&String, &(TomlSchema, Option<Value>))> {
+        self.entries.iter().map(|(k, entry)| (k, entry))
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &String> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of entries without a default, a table that has that many of them is not missing any key
+    pub(crate) fn required_len(&self) -> usize {
+        self.required as usize
+    }
+}
+
+impl FromIterator<KeyEntry> for TableEntries {
+    fn from_iter<I: IntoIterator<Item = KeyEntry>>(iter: I) -> Self {
+        let mut entries: Vec<KeyEntry> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (key, entry) in iter {
+            match positions.get(&key) {
+                Some(&i) => entries[i].1 = entry,
+                None => {
+                    positions.insert(key.clone(), entries.len());
+                    entries.push((key, entry));
+                }
+            }
+        }
+        let mut sorted: Vec<u32> = (0..entries.len() as u32).collect();
+        sorted.sort_unstable_by(|&a, &b| entries[a as usize].0.cmp(&entries[b as usize].0));
+        let required = entries.iter().filter(|(_, (_, default))| default.is_none()).count() as u32;
+        TableEntries { entries: entries.into(), sorted: sorted.into(), required }
+    }
+}
+
+impl IntoIterator for TableEntries {
+    type Item = KeyEntry;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_vec().into_iter()
+    }
+}
+
+impl Index<&str> for TableEntries {
+    type Output = (TomlSchema, Option<Value>);
+
+    /// The schema and default value of a key, panics when the key has no entry
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).unwrap_or_else(|| panic!("no entry for key '{}'", key))
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_entries() {
+        let entry = |key: &str, min: i64| (key.to_string(), (TomlSchema::Integer { min, max: i64::MAX, width: None, coerce: false }, None));
+        let entries: TableEntries = [entry("b", 0), entry("a", 0), entry("c", 0), entry("a", 1)].into_iter().collect();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert!(matches!(entries["a"].0, TomlSchema::Integer { min: 1, .. }));
+        assert!(entries.contains_key("c") && !entries.contains_key("d"));
+        assert_eq!(entries.required_len(), 3);
+        assert_eq!(entries.get_key_value("b").map(|(k, _)| k.as_str()), Some("b"));
+
+        assert!(std::mem::size_of::<TomlSchema>() <= 56, "{}", std::mem::size_of::<TomlSchema>());
+    }
+
+    #[test]
+    fn declaration_order() {
+        let schema: TomlSchema = "
+            name = 'string'
+            port = 'int'
+            host = 'string'
+        ".parse().unwrap();
+        let TomlSchema::Table(table) = &schema else {panic!("schema is not a table")};
+        assert_eq!(table.entries.keys().collect::<Vec<_>>(), ["host", "name", "port"]);
+
+        // the first missing key is reported, in the same order
+        let empty = Value::Table(toml::Table::new());
+        let err = schema.check(&empty).unwrap_err();
+        assert!(matches!(err, SchemaError::MissingKey { key: "host", .. }), "{:?}", err);
+
+        // tables built by hand keep their order
+        let entries: TableEntries = table.entries.clone().into_iter().rev().collect();
+        let reversed = TomlSchema::Table(Arc::new(TableSchema { entries, ..TableSchema::default() }));
+        let err = reversed.check(&empty).unwrap_err();
+        assert!(matches!(err, SchemaError::MissingKey { key: "port", .. }), "{:?}", err);
+    }
+}
@@ -0,0 +1,161 @@
+use toml::value::Datetime;
+use crate::*;
+
+
+/// The types that [ValidatedDoc::get] can read, borrowed from the document when possible
+pub trait FromValue<'v>: Sized {
+    /// The value as this type, `None` when it has another type, ints are not converted to floats
+    fn from_value(value: &'v Value) -> Option<Self>;
+}
+
+impl<'v> FromValue<'v> for i64 {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_integer()}
+}
+
+impl<'v> FromValue<'v> for f64 {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_float()}
+}
+
+impl<'v> FromValue<'v> for bool {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_bool()}
+}
+
+impl<'v> FromValue<'v> for &'v str {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_str()}
+}
+
+impl<'v> FromValue<'v> for String {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_str().map(String::from)}
+}
+
+impl<'v> FromValue<'v> for &'v Datetime {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_datetime()}
+}
+
+impl<'v> FromValue<'v> for &'v [Value] {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_array().map(Vec::as_slice)}
+}
+
+impl<'v> FromValue<'v> for &'v toml::Table {
+    fn from_value(value: &'v Value) -> Option<Self> {value.as_table()}
+}
+
+impl<'v> FromValue<'v> for &'v Value {
+    fn from_value(value: &'v Value) -> Option<Self> {Some(value)}
+}
+
+
+/// A document that matched a schema, returned by [TomlSchema::checked], its values are read by path
+/// (`server.listeners[0].port`) with the syntax of [SchemaPath]
+///
+/// the getters return a value for every key that the schema requires with the type that it requires, missing keys
+/// take the default of their schema so optional keys with a default also always have a value
+#[derive(Debug, Clone, Copy)]
+pub struct ValidatedDoc<'s, 'v> {
+    schema: &'s TomlSchema,
+    doc: &'v Value,
+}
+
+impl<'s, 'v> ValidatedDoc<'s, 'v> where 's: 'v {
+    /// The whole document
+    pub fn value(&self) -> &'v Value {
+        self.doc
+    }
+
+    /// The schema that the document matched
+    pub fn schema(&self) -> &'s TomlSchema {
+        self.schema
+    }
+
+    /// The value at `path`, or the default of its schema when the document does not have it, `None` when there is
+    /// neither, when it is not a `T` or when the path is invalid
+    pub fn get<T: FromValue<'v>>(&self, path: &str) -> Option<T> {
+        let path: SchemaPath = path.parse().ok()?;
+        let value = match path.value_in(self.doc) {
+            Some(value) => value,
+            None => self.schema.resolve_path(&path).into_iter().find_map(|(_, default)| default)?
+        };
+        T::from_value(value)
+    }
+
+    pub fn get_int(&self, path: &str) -> Option<i64> {
+        self.get(path)
+    }
+
+    pub fn get_float(&self, path: &str) -> Option<f64> {
+        self.get(path)
+    }
+
+    pub fn get_bool(&self, path: &str) -> Option<bool> {
+        self.get(path)
+    }
+
+    pub fn get_str(&self, path: &str) -> Option<&'v str> {
+        self.get(path)
+    }
+
+    pub fn get_date(&self, path: &str) -> Option<&'v Datetime> {
+        self.get(path)
+    }
+
+    pub fn get_array(&self, path: &str) -> Option<&'v [Value]> {
+        self.get(path)
+    }
+
+    pub fn get_table(&self, path: &str) -> Option<&'v toml::Table> {
+        self.get(path)
+    }
+}
+
+
+impl TomlSchema {
+
+    /// Same as [TomlSchema::check] but wraps the document in a [ValidatedDoc] to read its values when it matches
+    pub fn checked<'s, 'v>(&'s self, doc: &'v Value) -> Result<ValidatedDoc<'s, 'v>, SchemaError<'s, 'v>> {
+        self.check(doc)?;
+        Ok(ValidatedDoc { schema: self, doc })
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_getters() {
+        let schema: TomlSchema = "
+            name = 'string'
+            ratio = {type = 'float', default = 0.5}
+            [server]
+            port = {type = 'int', default = 80}
+            debug = 'bool'
+            listeners = {type = 'table_array', host = 'string'}
+        ".parse().unwrap();
+        let doc = Value::Table("
+            name = 'app'
+            server = {debug = true, listeners = [{host = 'a'}, {host = 'b'}]}
+        ".parse().unwrap());
+        let doc = schema.checked(&doc).unwrap();
+
+        assert_eq!(doc.get_str("name"), Some("app"));
+        assert_eq!(doc.get::<String>("name"), Some("app".to_string()));
+        assert_eq!(doc.get_bool("server.debug"), Some(true));
+        assert_eq!(doc.get_str("server.listeners[1].host"), Some("b"));
+        assert_eq!(doc.get_array("server.listeners").map(<[Value]>::len), Some(2));
+        assert_eq!(doc.get_table("server").map(toml::Table::len), Some(2));
+
+        // defaults of missing keys
+        assert_eq!(doc.get_int("server.port"), Some(80));
+        assert_eq!(doc.get_float("ratio"), Some(0.5));
+
+        assert_eq!(doc.get_int("name"), None);
+        assert_eq!(doc.get_str("server.listeners[2].host"), None);
+        assert_eq!(doc.get_str("server..port"), None);
+
+        let invalid = Value::Table("name = 1\nserver = {debug = true, listeners = []}".parse().unwrap());
+        schema.checked(&invalid).unwrap_err();
+    }
+}
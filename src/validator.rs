@@ -0,0 +1,258 @@
+use std::time::{Duration, Instant};
+use crate::*;
+use crate::parse_toml::DeadlineGuard;
+use crate::memo::MemoGuard;
+
+
+/// What [Validator::from_table] does with the warnings found while parsing the schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WarningPolicy {
+    /// Warnings are logged with `log::warn!`, the default
+    #[default]
+    Log,
+    /// Warnings are kept in [Validator::warnings] without being logged
+    Ignore,
+    /// Any warning is an error
+    Deny,
+}
+
+
+/// A schema with the options of the validation, it replaces the variants of [TomlSchema::check] when several options
+/// are needed at once
+///
+/// by default a validator stops at the first error like [TomlSchema::check], with [Validator::fail_fast] set to
+/// `false` it collects every error like [TomlSchema::validate], results are always a [ValidationReport] so that
+/// changing the options does not change the code that reads them
+#[derive(Debug, Clone)]
+pub struct Validator {
+    schema: TomlSchema,
+    fail_fast: bool,
+    max_errors: Option<usize>,
+    max_depth: usize,
+    budget: Option<Duration>,
+    references: bool,
+    memoize: bool,
+    state: Option<ValidationState>,
+    suppressions: Vec<Suppression>,
+    warnings: Vec<SchemaParseWarning>,
+}
+
+impl Validator {
+    /// A validator with the default options, it stops at the first error
+    pub fn new(schema: TomlSchema) -> Self {
+        Validator {
+            schema, fail_fast: true, max_errors: None, max_depth: DEFAULT_MAX_DEPTH, budget: None, references: true,
+            memoize: false, state: None, suppressions: Vec::new(), warnings: Vec::new()
+        }
+    }
+
+    /// Parses a schema with `options` (use [ParseOptions::strict] to reject unknown schema keys), the warnings of the
+    /// parser are handled according to `policy`, a default value at the root of the schema is ignored and reported as a
+    /// [SchemaParseWarning::IgnoredDefault]
+    pub fn from_table(table: &toml::Table, options: &ParseOptions, policy: WarningPolicy) -> Result<Self, String> {
+        let (schema, dv, mut warnings) = TomlSchema::from_table_with_options(table, options)?;
+        if let Some(value) = dv {
+            warnings.push(SchemaParseWarning::IgnoredDefault { path: SchemaPath::new(), value });
+        }
+        match policy {
+            WarningPolicy::Log => warnings.iter().for_each(|warning| log::warn!("{}", warning)),
+            WarningPolicy::Ignore => (),
+            WarningPolicy::Deny => if let Some(warning) = warnings.first() {
+                return Err(warning.to_string())
+            }
+        }
+        Ok(Validator { warnings, ..Validator::new(schema) })
+    }
+
+    /// Whether to stop at the first error (the default) or to collect all of them
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Keeps only the first `max_errors` errors when collecting all of them
+    pub fn max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    /// The number of nested schemas that may be entered, [DEFAULT_MAX_DEPTH] by default, see
+    /// [TomlSchema::check_with_max_depth]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The time a validation may take, see [TomlSchema::check_with_budget]
+    pub fn budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Whether strings with `refers_to` must name a key that exists (the default), without it references are not
+    /// followed at all
+    pub fn check_references(mut self, references: bool) -> Self {
+        self.references = references;
+        self
+    }
+
+    /// Whether identical tables and arrays checked by the same schema are validated once per validation, this pays off
+    /// for generated documents that repeat the same blocks, otherwise hashing the subtrees only slows the validation
+    /// down
+    ///
+    /// only the values that passed are remembered, errors are found again for each copy
+    pub fn memoize(mut self, memoize: bool) -> Self {
+        self.memoize = memoize;
+        self
+    }
+
+    /// The state given to the custom validators registered with [CustomValidators::register_with_context], they get it
+    /// back from [ValidationContext::state]
+    pub fn state(mut self, state: ValidationState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Hides the errors matched by `suppression`, the document is then always checked whole and in fail-fast mode the
+    /// report keeps the first error that is not hidden
+    pub fn suppress(mut self, suppression: Suppression) -> Self {
+        self.suppressions.push(suppression);
+        self
+    }
+
+    /// Same as [Validator::suppress] for several suppressions, like the ones read by [Suppression::from_value]
+    pub fn suppress_all(mut self, suppressions: impl IntoIterator<Item = Suppression>) -> Self {
+        self.suppressions.extend(suppressions);
+        self
+    }
+
+    pub fn schema(&self) -> &TomlSchema {
+        &self.schema
+    }
+
+    /// The warnings found while parsing the schema with [Validator::from_table]
+    pub fn warnings(&self) -> &[SchemaParseWarning] {
+        &self.warnings
+    }
+
+    /// Checks a document, in fail-fast mode the report has at most one error, located at the root since its message
+    /// already says where it is, unless there are suppressions
+    pub fn validate(&self, doc: &Value) -> ValidationReport {
+        let _guard = DeadlineGuard::set(self.budget.and_then(|budget| Instant::now().checked_add(budget)));
+        let _memo = self.memoize.then(MemoGuard::start);
+        let mut report = ValidationReport::default();
+
+        if self.fail_fast && self.suppressions.is_empty() {
+            let result = self.schema.check_root(doc, self.max_depth)
+                .and_then(|()| if self.references {self.schema.check_references(doc)} else {Ok(())})
+                .and_then(|()| self.schema.check_context_validators(doc, self.state.as_ref()));
+            if let Err(e) = result {
+                report.push(&SchemaPath::new(), e);
+            }
+            return report
+        }
+
+        self.schema.validate_inner(doc, &mut SchemaPath::new(), self.max_depth, &mut report);
+        if self.references {
+            self.schema.report_references(doc, &mut report);
+        }
+        self.schema.report_context_validators(doc, self.state.as_ref(), &mut report);
+        if !self.suppressions.is_empty() {
+            report.errors.retain(|e| !self.suppressions.iter().any(|s| s.hides(e)));
+        }
+        if self.fail_fast {
+            // like in the fail-fast check, warnings are not reported
+            report.errors.retain(|e| e.severity == Severity::Error);
+            report.errors.truncate(1);
+        }
+        if let Some(max_errors) = self.max_errors {
+            report.errors.truncate(max_errors);
+        }
+        report
+    }
+
+    /// Same as [Validator::validate] but returns the report only when it has errors
+    pub fn check(&self, doc: &Value) -> Result<(), ValidationReport> {
+        let report = self.validate(doc);
+        if report.is_ok() {Ok(())} else {Err(report)}
+    }
+
+    /// Fills the defaults of a document like [TomlSchema::check_and_complete] then checks it with the options of the
+    /// validator
+    pub fn check_and_complete(&self, doc: &mut Value) -> Result<(), ValidationReport> {
+        if let Err((path, error)) = self.schema.complete(doc) {
+            let mut report = ValidationReport::default();
+            report.push(&path, error);
+            return Err(report)
+        }
+        self.check(doc)
+    }
+}
+
+impl From<TomlSchema> for Validator {
+    fn from(schema: TomlSchema) -> Self {
+        Validator::new(schema)
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TomlSchema {
+        "
+            name = 'string'
+            port = {type = 'int', min = 1, default = 80}
+            tags = {type = 'array', child = 'string', default = []}
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn validator_modes() {
+        let doc = Value::Table("name = 1\nport = 0\ntags = [1, 2]".parse().unwrap());
+
+        let fail_fast = Validator::new(schema());
+        assert_eq!(fail_fast.validate(&doc).errors.len(), 1);
+
+        let collect = Validator::new(schema()).fail_fast(false);
+        assert_eq!(collect.validate(&doc).errors.len(), 4);
+        assert_eq!(collect.clone().max_errors(2).validate(&doc).errors.len(), 2);
+
+        let shallow = Validator::new(schema()).max_depth(1);
+        let report = shallow.validate(&Value::Table("name = 'a'".parse().unwrap()));
+        assert!(report.errors[0].message.contains("depth"), "{:?}", report);
+
+        let mut doc = Value::Table("name = 'a'".parse().unwrap());
+        collect.check_and_complete(&mut doc).unwrap();
+        assert_eq!(doc["port"].as_integer(), Some(80));
+    }
+
+    #[test]
+    fn validator_references() {
+        let schema: TomlSchema = "
+            current = {type = 'string', refers_to = 'servers.*'}
+            servers = {type = 'map', value = 'string'}
+        ".parse().unwrap();
+        let doc = Value::Table("current = 'b'\nservers = {a = 'x'}".parse().unwrap());
+
+        Validator::new(schema.clone()).check(&doc).unwrap_err();
+        Validator::new(schema).check_references(false).check(&doc).unwrap();
+    }
+
+    #[test]
+    fn validator_warnings() {
+        let table: toml::Table = "name = {type = 'string', regx = '^a'}".parse().unwrap();
+        let options = ParseOptions::default();
+
+        let validator = Validator::from_table(&table, &options, WarningPolicy::Ignore).unwrap();
+        assert_eq!(validator.warnings().len(), 1);
+        let err = Validator::from_table(&table, &options, WarningPolicy::Deny).unwrap_err();
+        assert!(err.contains("regx"), "{}", err);
+
+        let strict = ParseOptions { strict: true, ..ParseOptions::default() };
+        Validator::from_table(&table, &strict, WarningPolicy::Log).unwrap_err();
+    }
+}
@@ -0,0 +1,174 @@
+//! Test vectors shipped with a schema, so schema authors can check that a schema accepts and rejects what it should
+//!
+//! By convention the vectors of `name.toml` are stored next to it in `name.tests.toml`, as an array of tables with
+//! the tested `value`, whether it is `valid` and an optional `description`
+//!
+//! ```toml
+//! [[tests]]
+//! description = "port out of range"
+//! value = {name = "server", port = 70000}
+//! valid = false
+//! ```
+//!
+//! [run_schema_tests] runs the vectors of a schema file, the `test_vectors` example is a small command line wrapper
+//! around it (`cargo run --example test_vectors -- schema.toml`)
+
+use std::path::{Path, PathBuf};
+use crate::*;
+
+
+/// A value and whether a schema should accept it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestVector {
+    pub description: Option<String>,
+    pub value: Value,
+    pub valid: bool,
+}
+
+/// A test vector that a schema did not handle as expected, `error` is the error of the schema when the vector
+/// was expected to be valid
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorFailure {
+    pub index: usize,
+    pub vector: TestVector,
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for VectorFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "test {}", self.index)?;
+        if let Some(description) = &self.vector.description {
+            write!(f, " ({})", description)?;
+        }
+        match &self.error {
+            Some(error) => write!(f, ": expected valid but got {}", error),
+            None => write!(f, ": expected invalid but {} was accepted", self.vector.value)
+        }
+    }
+}
+
+
+/// The conventional location of the test vectors of a schema file, `name.toml` has its tests in `name.tests.toml`
+pub fn tests_path(schema_path: impl AsRef<Path>) -> PathBuf {
+    let schema_path = schema_path.as_ref();
+    let stem = schema_path.file_stem().unwrap_or_default().to_string_lossy();
+    schema_path.with_file_name(format!("{}.tests.toml", stem))
+}
+
+/// Reads the `tests` array of a test vector file
+pub fn parse_test_vectors(table: &toml::Table) -> Result<Vec<TestVector>, String> {
+    let Some(tests) = table.get("tests") else {
+        return Err("Test vector file has no 'tests' key".to_string())
+    };
+    let Value::Array(tests) = tests else {
+        return Err("'tests' must be an array of tables".to_string())
+    };
+
+    tests.iter().enumerate().map(|(i, test)| {
+        let Value::Table(test) = test else {
+            return Err(format!("Test {} is not a table", i))
+        };
+        for key in test.keys() {
+            if !["description", "value", "valid"].contains(&key.as_str()) {
+                log::warn!("Ignored key '{}' in test {}", key, i);
+            }
+        }
+        let description = match test.get("description") {
+            None => None,
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(_) => return Err(format!("'description' of test {} must be a string", i))
+        };
+        let Some(value) = test.get("value") else {
+            return Err(format!("Test {} has no 'value' key", i))
+        };
+        let Some(Value::Boolean(valid)) = test.get("valid") else {
+            return Err(format!("Test {} must have a boolean 'valid' key", i))
+        };
+        Ok(TestVector { description, value: value.clone(), valid: *valid })
+    }).collect()
+}
+
+/// Loads the schema at `schema_path` and runs the vectors of its [tests_path], returns the vectors that failed
+pub fn run_schema_tests(schema_path: impl AsRef<Path>) -> Result<Vec<VectorFailure>, String> {
+    let schema_path = schema_path.as_ref();
+    let read = |path: &Path| -> Result<toml::Table, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        text.parse().map_err(|e| format!("{}: {}", path.display(), e))
+    };
+
+    let schema = TomlSchema::try_from(read(schema_path)?).map_err(|e| format!("{}: {}", schema_path.display(), e))?;
+    let vectors_path = tests_path(schema_path);
+    let vectors = parse_test_vectors(&read(&vectors_path)?).map_err(|e| format!("{}: {}", vectors_path.display(), e))?;
+    Ok(schema.run_test_vectors(&vectors))
+}
+
+
+impl TomlSchema {
+
+    /// Checks every vector against this schema and returns the ones that are not handled as expected
+    pub fn run_test_vectors(&self, vectors: &[TestVector]) -> Vec<VectorFailure> {
+        vectors.iter().enumerate().filter_map(|(index, vector)| {
+            let error = match (self.check(&vector.value), vector.valid) {
+                (Ok(()), true) | (Err(_), false) => return None,
+                (Err(e), true) => Some(format!("{:?}", e)),
+                (Ok(()), false) => None,
+            };
+            Some(VectorFailure { index, vector: vector.clone(), error })
+        }).collect()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn vectors_file() {
+        assert_eq!(tests_path("test_files/test_schema.toml"), Path::new("test_files/test_schema.tests.toml"));
+
+        let failures = run_schema_tests("test_files/test_schema.toml").unwrap();
+        assert!(failures.is_empty(), "{:?}", failures);
+
+        run_schema_tests("test_files/does_not_exist.toml").unwrap_err();
+    }
+
+    #[test]
+    fn vectors_failures() {
+        let schema: TomlSchema = "port = {type = 'int', min = 1, max = 65535}".parse().unwrap();
+        let vectors = parse_test_vectors(&"
+            [[tests]]
+            value = {port = 80}
+            valid = true
+
+            [[tests]]
+            description = 'too big'
+            value = {port = 70000}
+            valid = true
+
+            [[tests]]
+            value = {port = 443}
+            valid = false
+        ".parse().unwrap()).unwrap();
+
+        let failures = schema.run_test_vectors(&vectors);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].index, 1);
+        assert!(failures[0].error.is_some());
+        assert!(failures[0].to_string().starts_with("test 1 (too big): expected valid"));
+        assert_eq!(failures[1].index, 2);
+        assert_eq!(failures[1].error, None);
+    }
+
+    #[test]
+    fn vectors_malformed() {
+        parse_test_vectors(&"tests = 1".parse().unwrap()).unwrap_err();
+        parse_test_vectors(&"tests = [{value = 1}]".parse().unwrap()).unwrap_err();
+        parse_test_vectors(&"tests = [{valid = true}]".parse().unwrap()).unwrap_err();
+        parse_test_vectors(&"other = []".parse().unwrap()).unwrap_err();
+        assert!(parse_test_vectors(&"tests = []".parse().unwrap()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use crate::*;
+
+
+/// The key that declares the version of a document unless [VersionedSchema::with_key] says otherwise
+pub const DEFAULT_VERSION_KEY: &str = "schema_version";
+
+/// Why [VersionedSchema::select] could not find a schema for a document
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionError {
+    /// The document has no version key and there is no default version
+    Missing{key: String},
+    /// The version key is not a positive int
+    Invalid{key: String, value: Value},
+    /// No schema was added for this version
+    Unsupported{version: u64, supported: Vec<u64>},
+    /// The document is not a table
+    NotATable,
+}
+
+impl std::fmt::Display for VersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionError::Missing { key } => write!(f, "The document does not declare its version in '{}'", key),
+            VersionError::Invalid { key, value } => write!(f, "The version in '{}' must be a positive int but got {}", key, value),
+            VersionError::Unsupported { version, supported } => {
+                write!(f, "Version {} is not supported, supported versions are {:?}", version, supported)
+            },
+            VersionError::NotATable => f.write_str("Only tables can declare a version"),
+        }
+    }
+}
+
+
+/// Schemas for several versions of a format, a document declares its version with a key (`schema_version = 2` by
+/// default) and is checked against the schema of that version
+///
+/// when the schema of a version is a table, the version key is added to its entries so that the schema does not have to
+/// describe it, it only accepts the version of the schema
+#[derive(Debug, Clone)]
+pub struct VersionedSchema {
+    key: String,
+    default_version: Option<u64>,
+    schemas: BTreeMap<u64, TomlSchema>,
+}
+
+impl Default for VersionedSchema {
+    fn default() -> Self {
+        VersionedSchema::with_key(DEFAULT_VERSION_KEY)
+    }
+}
+
+impl VersionedSchema {
+    /// An empty container that reads versions from [DEFAULT_VERSION_KEY]
+    pub fn new() -> Self {
+        VersionedSchema::default()
+    }
+
+    /// An empty container that reads versions from `key`
+    pub fn with_key(key: &str) -> Self {
+        VersionedSchema { key: key.to_string(), default_version: None, schemas: BTreeMap::new() }
+    }
+
+    /// The version of documents that do not declare one, without it they are rejected
+    pub fn with_default_version(mut self, version: u64) -> Self {
+        self.default_version = Some(version);
+        self
+    }
+
+    /// Adds (or replaces) the schema of a version
+    pub fn add(&mut self, version: u64, schema: TomlSchema) {
+        let schema = with_version_entry(&schema, &self.key, version);
+        self.schemas.insert(version, schema);
+    }
+
+    /// The key that declares the version of documents
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The versions that have a schema, in increasing order
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.schemas.keys().copied()
+    }
+
+    /// The schema of a version
+    pub fn get(&self, version: u64) -> Option<&TomlSchema> {
+        self.schemas.get(&version)
+    }
+
+    /// The version declared by a document, or the default version
+    pub fn version_of(&self, doc: &Value) -> Result<u64, VersionError> {
+        let Value::Table(table) = doc else {
+            return Err(VersionError::NotATable)
+        };
+        match (table.get(&self.key), self.default_version) {
+            (Some(Value::Integer(v)), _) if *v > 0 => Ok(*v as u64),
+            (Some(value), _) => Err(VersionError::Invalid { key: self.key.clone(), value: value.clone() }),
+            (None, Some(version)) => Ok(version),
+            (None, None) => Err(VersionError::Missing { key: self.key.clone() })
+        }
+    }
+
+    /// The version of a document and the schema it must match
+    pub fn select(&self, doc: &Value) -> Result<(u64, &TomlSchema), VersionError> {
+        let version = self.version_of(doc)?;
+        match self.schemas.get(&version) {
+            Some(schema) => Ok((version, schema)),
+            None => Err(VersionError::Unsupported { version, supported: self.versions().collect() })
+        }
+    }
+
+    /// Checks a document against the schema of its version, returns that version, errors are returned as strings
+    /// since they can come from the version or from the schema
+    pub fn check(&self, doc: &Value) -> Result<u64, String> {
+        let (version, schema) = self.select(doc).map_err(|e| e.to_string())?;
+        schema.check(doc).map_err(|e| format!("{:?}", e))?;
+        Ok(version)
+    }
+
+    /// Same as [VersionedSchema::check] with [TomlSchema::check_and_complete], documents that use the default version
+    /// get the version key when the schema is a table
+    pub fn check_and_complete(&self, doc: &mut Value) -> Result<u64, String> {
+        let (version, schema) = self.select(doc).map_err(|e| e.to_string())?;
+        schema.check_and_complete(doc).map_err(|e| format!("{:?}", e))?;
+        Ok(version)
+    }
+}
+
+
+/// The schema with an entry `key` that only accepts `version`, and defaults to it, when it is a table and does not
+/// already have one, wrappers are kept
+fn with_version_entry(schema: &TomlSchema, key: &str, version: u64) -> TomlSchema {
+    let rewrap = |inner: &TomlSchema| Arc::new(with_version_entry(inner, key, version));
+    match schema {
+        TomlSchema::Table(table) if !table.entries.contains_key(key) => {
+            let value = Value::Integer(version as i64);
+            let version_entry = (key.to_string(), (TomlSchema::Exact { value: value.clone(), epsilon: 0.0 }, Some(value)));
+            let entries = table.entries.clone().into_iter().chain([version_entry]).collect();
+            TomlSchema::Table(Arc::new(TableSchema { entries, ..(**table).clone() }))
+        },
+        TomlSchema::Annotated { schema, extensions } => TomlSchema::Annotated { schema: rewrap(schema), extensions: extensions.clone() },
+        TomlSchema::Custom { schema, validator } => TomlSchema::Custom { schema: rewrap(schema), validator: validator.clone() },
+        TomlSchema::Graded { schema, severity } => TomlSchema::Graded { schema: rewrap(schema), severity: *severity },
+        other => other.clone()
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versioned() -> VersionedSchema {
+        let mut versioned = VersionedSchema::new().with_default_version(1);
+        versioned.add(1, "addr = 'string'".parse().unwrap());
+        versioned.add(2, "server = {host = 'string', port = 'int'}".parse().unwrap());
+        versioned
+    }
+
+    #[test]
+    fn dispatch() {
+        let versioned = versioned();
+        assert_eq!(versioned.versions().collect::<Vec<_>>(), [1, 2]);
+
+        let v1 = Value::Table("addr = 'a:1'".parse().unwrap());
+        let v2 = Value::Table("schema_version = 2\nserver = {host = 'a', port = 1}".parse().unwrap());
+        assert_eq!(versioned.check(&v1), Ok(1));
+        assert_eq!(versioned.check(&v2), Ok(2));
+
+        // documents are checked against the schema of their version only
+        let wrong = Value::Table("schema_version = 2\naddr = 'a:1'".parse().unwrap());
+        versioned.check(&wrong).unwrap_err();
+
+        let mut v1 = v1;
+        assert_eq!(versioned.check_and_complete(&mut v1), Ok(1));
+        assert_eq!(v1["schema_version"].as_integer(), Some(1));
+    }
+
+    #[test]
+    fn version_errors() {
+        let versioned = versioned();
+        let doc = |s: &str| Value::Table(s.parse().unwrap());
+
+        assert_eq!(
+            versioned.select(&doc("schema_version = 3")).unwrap_err(),
+            VersionError::Unsupported { version: 3, supported: vec![1, 2] }
+        );
+        assert!(matches!(versioned.select(&doc("schema_version = '2'")), Err(VersionError::Invalid { .. })));
+        assert!(matches!(versioned.select(&doc("schema_version = 0")), Err(VersionError::Invalid { .. })));
+        assert_eq!(versioned.select(&Value::Integer(1)).unwrap_err(), VersionError::NotATable);
+
+        let mut custom = VersionedSchema::with_key("format");
+        custom.add(1, "addr = 'string'".parse().unwrap());
+        assert_eq!(custom.select(&doc("addr = 'a'")).unwrap_err(), VersionError::Missing { key: "format".into() });
+        assert_eq!(custom.check(&doc("format = 1\naddr = 'a'")), Ok(1));
+        assert!(custom.check(&doc("format = 3")).unwrap_err().contains("[1]"));
+    }
+}
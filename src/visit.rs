@@ -0,0 +1,114 @@
+use crate::*;
+
+
+impl TomlSchema {
+
+    /// Calls `f` on this schema and on every sub-schema, parents before children, with the path leading to each of them
+    ///
+    /// table entries are visited in key order, then extras in declaration order, array children are reached
+    /// through [PathSegment::AnyIndex], extras through [PathSegment::Extra] and alternative options through
+    /// [PathSegment::Branch]
+    pub fn visit(&self, f: &mut impl FnMut(&SchemaPath, &TomlSchema)) {
+        let mut path = SchemaPath::new();
+        self.visit_inner(&mut path, f);
+    }
+
+    /// Whether `pred` holds for this schema or one of its sub-schemas, wrapped schemas included, it stops at the first
+    /// match and builds no paths so it is cheap enough to run on every check
+    pub(crate) fn any_node(&self, pred: &impl Fn(&TomlSchema) -> bool) -> bool {
+        pred(self) || match self {
+            TomlSchema::Alternative(options) => options.iter().any(|opt| opt.any_node(pred)),
+            TomlSchema::Array { cond, .. } => cond.any_node(pred),
+            TomlSchema::Map(map) => map.value.any_node(pred),
+            TomlSchema::Table(table_schema) => {
+                table_schema.entries.iter().any(|(_, (schema, _))| schema.any_node(pred))
+                    || table_schema.extras.iter().any(|extra| extra.value.any_node(pred))
+            },
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => schema.any_node(pred),
+            _ => false
+        }
+    }
+
+    fn visit_inner(&self, path: &mut SchemaPath, f: &mut impl FnMut(&SchemaPath, &TomlSchema)) {
+        f(path, self);
+        self.visit_children(path, f);
+    }
+
+    fn visit_children(&self, path: &mut SchemaPath, f: &mut impl FnMut(&SchemaPath, &TomlSchema)) {
+        let mut visit_child = |path: &mut SchemaPath, segment: PathSegment, child: &TomlSchema| {
+            path.push(segment);
+            child.visit_inner(path, f);
+            path.pop();
+        };
+
+        match self {
+            TomlSchema::Alternative(options) => {
+                for (i, opt) in options.iter().enumerate() {
+                    visit_child(path, PathSegment::Branch(i), opt);
+                }
+            },
+            TomlSchema::Array { cond, .. } => visit_child(path, PathSegment::AnyIndex, cond),
+            TomlSchema::Map(map) => visit_child(path, PathSegment::Extra(map.key.as_str().to_string()), &map.value),
+            TomlSchema::Table(table_schema) => {
+                let TableSchema { extras, entries, .. } = &**table_schema;
+                for (key, (schema, _)) in entries.iter() {
+                    visit_child(path, PathSegment::Key(key.clone()), schema);
+                }
+                for TableEntry { key, value, .. } in extras.iter() {
+                    visit_child(path, PathSegment::Extra(key.as_str().to_string()), value);
+                }
+            },
+            // the wrapped schema is at the same location, it is not visited separately
+            TomlSchema::Annotated { schema, .. } | TomlSchema::Custom { schema, .. } | TomlSchema::Defaulted { schema, .. } | TomlSchema::Graded { schema, .. } => schema.visit_children(path, f),
+            TomlSchema::String { .. } | TomlSchema::Integer { .. } | TomlSchema::Float { .. } | 
+            TomlSchema::Date(_) | TomlSchema::Bool { .. } | TomlSchema::Anything | TomlSchema::Exact { .. } | TomlSchema::Enum(_) | TomlSchema::Plugin(_) => ()
+        }
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visit_order() {
+        let schema: TomlSchema = "
+            name = {type = 'string'}
+            tags = {type = 'array', child = {type = 'alternative', options = [{type = 'int'}, {type = 'bool'}]}}
+            extras = [{key = '^x-', schema = {type = 'anything'}}]
+        ".parse().unwrap();
+
+        let mut visited = Vec::new();
+        schema.visit(&mut |path, sub| visited.push((path.clone(), SchemaType::from(sub))));
+
+        use PathSegment::*;
+        let expected: Vec<(SchemaPath, SchemaType)> = vec![
+            (vec![].into(), SchemaType::Table),
+            (vec![Key("name".into())].into(), SchemaType::String),
+            (vec![Key("tags".into())].into(), SchemaType::Array),
+            (vec![Key("tags".into()), AnyIndex].into(), SchemaType::Alternative),
+            (vec![Key("tags".into()), AnyIndex, Branch(0)].into(), SchemaType::Integer),
+            (vec![Key("tags".into()), AnyIndex, Branch(1)].into(), SchemaType::Bool),
+            (vec![Extra("^x-".into())].into(), SchemaType::Anything),
+        ];
+        assert_eq!(visited, expected);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn visit_count_types() {
+        let schema = std::fs::read_to_string("test_files/test_schema.toml").unwrap().parse::<TomlSchema>().unwrap();
+
+        let mut strings = 0;
+        let mut max_depth = 0;
+        schema.visit(&mut |path, sub| {
+            if let TomlSchema::String { .. } = sub {strings += 1;}
+            max_depth = max_depth.max(path.len());
+        });
+        assert!(strings > 10, "found {} strings", strings);
+        assert!(max_depth >= 3, "max depth is {}", max_depth);
+    }
+}
@@ -0,0 +1,321 @@
+//! Compile-time checked schemas for the `toml_schema` crate
+//!
+//! - `include_schema!("path/to/schema.toml")` reads a schema file (relative to the crate's `Cargo.toml`),
+//!   checks it while compiling and expands to an expression of type `toml_schema::TomlSchema`
+//! - `toml_schema!{ ... }` does the same with a schema written in the macro, either as a TOML string literal or
+//!   as the content of a TOML inline table (`key = value` pairs separated by commas)
+//!
+//! A schema that does not parse is reported as a compile error, so typos in schema files are caught by `cargo build`
+//! instead of at program startup, the file is still parsed once at runtime but that can no longer fail
+//!
+//! ```ignore
+//! let schema = toml_schema_macros::toml_schema!{
+//!     name = {type = "string", regex = "^[a-z]+$"},
+//!     port = {type = "int", min = 1, max = 65535, default = 8080},
+//! };
+//!
+//! static SCHEMA: std::sync::LazyLock<toml_schema::TomlSchema> = std::sync::LazyLock::new(|| {
+//!     toml_schema_macros::include_schema!("schemas/config.toml")
+//! });
+//! ```
+
+use proc_macro::{Delimiter, Group, Literal, Punct, Spacing, Span, TokenStream, TokenTree, Ident};
+use std::path::PathBuf;
+
+use toml_schema::TomlSchema;
+
+
+/// Reads, parses and checks a schema file at compile time, see the crate documentation
+#[proc_macro]
+pub fn include_schema(input: TokenStream) -> TokenStream {
+    match expand_include_schema(input) {
+        Ok(tokens) => tokens,
+        Err((msg, span)) => compile_error(&msg, span)
+    }
+}
+
+
+/// Parses and checks a schema written inline at compile time, see the crate documentation
+#[proc_macro]
+pub fn toml_schema(input: TokenStream) -> TokenStream {
+    match expand_toml_schema(input) {
+        Ok(tokens) => tokens,
+        Err((msg, span)) => compile_error(&msg, span)
+    }
+}
+
+
+fn expand_toml_schema(input: TokenStream) -> Result<TokenStream, (String, Span)> {
+    // a single string literal is a whole TOML document
+    let source = match single_string_literal(input.clone()) {
+        Ok((source, _)) => source,
+        Err(_) => inline_table_to_document(input)?
+    };
+
+    check_schema_source(&source).map_err(|e| (format!("Invalid schema: {}", e), Span::call_site()))?;
+    Ok(parse_at_runtime(TokenTree::Literal(Literal::string(&source)).into(), Span::call_site()))
+}
+
+
+/// Converts the tokens of the body of an inline table to a TOML document
+fn inline_table_to_document(input: TokenStream) -> Result<String, (String, Span)> {
+    let mut inline = String::from("schema = {");
+    write_toml_tokens(&mut inline, input)?;
+    inline.push('}');
+
+    let mut table = inline.parse::<toml::Table>()
+        .map_err(|e| (format!("Invalid TOML in schema: {}", e.message()), Span::call_site()))?;
+    match table.remove("schema") {
+        Some(toml::Value::Table(schema)) => Ok(schema.to_string()),
+        _ => unreachable!("the inline table is always a table")
+    }
+}
+
+/// Writes tokens as TOML text, punctuation is glued to its neighbours so that keys like `a-b` and
+/// dates like `1979-05-27` are kept intact
+fn write_toml_tokens(out: &mut String, input: TokenStream) -> Result<(), (String, Span)> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut prev_is_word = false;
+
+    for (i, tree) in tokens.iter().enumerate() {
+        match tree {
+            TokenTree::Ident(ident) => {
+                if prev_is_word {out.push(' ');}
+                out.push_str(&ident.to_string());
+                prev_is_word = true;
+            },
+            TokenTree::Literal(lit) => {
+                if prev_is_word {out.push(' ');}
+                let src = lit.to_string();
+                match parse_string_literal(&src) {
+                    Some(s) => write_toml_string(out, &s),
+                    None if src.starts_with(['\'', 'b', 'c']) => {
+                        return Err(("Only string, number and boolean literals are allowed in a schema".to_string(), lit.span()))
+                    },
+                    None => out.push_str(&src)
+                }
+                prev_is_word = true;
+            },
+            TokenTree::Punct(p) => {
+                // inline tables do not allow trailing commas
+                let is_last = i + 1 == tokens.len();
+                if !(p.as_char() == ',' && is_last) {
+                    out.push(p.as_char());
+                }
+                prev_is_word = false;
+            },
+            TokenTree::Group(g) => {
+                if prev_is_word {out.push(' ');}
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                    Delimiter::Parenthesis => {
+                        return Err(("Parentheses are not allowed in a schema".to_string(), g.span()))
+                    }
+                };
+                out.push_str(open);
+                write_toml_tokens(out, g.stream())?;
+                out.push_str(close);
+                prev_is_word = !close.is_empty();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a TOML basic string
+fn write_toml_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+}
+
+
+fn expand_include_schema(input: TokenStream) -> Result<TokenStream, (String, Span)> {
+    let (rel_path, span) = single_string_literal(input)?;
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| (e.to_string(), span))?;
+    let path = PathBuf::from(manifest_dir).join(&rel_path);
+    let path_str = path.to_str().ok_or_else(|| (format!("Schema path {:?} is not valid UTF-8", path), span))?;
+
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| (format!("Could not read schema {}: {}", path.display(), e), span))?;
+    check_schema_source(&source)
+        .map_err(|e| (format!("Invalid schema {}: {}", path.display(), e), span))?;
+
+    // include_str! makes cargo rebuild the crate when the schema changes
+    Ok(parse_at_runtime(call_macro("include_str", Literal::string(path_str), span), span))
+}
+
+
+/// Checks that TOML text is a valid schema
+pub(crate) fn check_schema_source(source: &str) -> Result<(), String> {
+    source.parse::<TomlSchema>().map(|_| ())
+}
+
+
+/// Expands to `<::toml_schema::TomlSchema as ::core::str::FromStr>::from_str(<source>).expect(..)`
+fn parse_at_runtime(source: TokenStream, span: Span) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    tokens.extend([TokenTree::Punct(Punct::new('<', Spacing::Alone))]);
+    tokens.extend(path(&["toml_schema", "TomlSchema"], span));
+    tokens.extend([TokenTree::Ident(Ident::new("as", span))]);
+    tokens.extend(path(&["core", "str", "FromStr"], span));
+    tokens.extend([TokenTree::Punct(Punct::new('>', Spacing::Alone))]);
+    tokens.extend(path(&["from_str"], span));
+    tokens.extend([
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, source)),
+        TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("expect", span)),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(Literal::string("schema was checked at compile time")).into()
+        )),
+    ]);
+    tokens
+}
+
+/// `::a::b::c`, also used after a qualified type
+fn path(segments: &[&str], span: Span) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for seg in segments {
+        tokens.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new(seg, span)),
+        ]);
+    }
+    tokens
+}
+
+/// `name!(arg)`
+fn call_macro(name: &str, arg: Literal, span: Span) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    tokens.extend(path(&["core", name], span));
+    tokens.extend([
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenTree::Literal(arg).into())),
+    ]);
+    tokens
+}
+
+/// `compile_error!("msg")`
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut lit = Literal::string(msg);
+    lit.set_span(span);
+    let mut tokens = call_macro("compile_error", lit, span);
+    tokens = tokens.into_iter().map(|mut t| {t.set_span(span); t}).collect();
+    tokens
+}
+
+
+/// Extracts the value of the only token of the input, which must be a string literal
+fn single_string_literal(input: TokenStream) -> Result<(String, Span), (String, Span)> {
+    let mut iter = input.into_iter();
+
+    let tree = match (iter.next(), iter.next()) {
+        (Some(t), None) => t,
+        (Some(_), Some(t)) => return Err(("Expected a single string literal".to_string(), t.span())),
+        (None, _) => return Err(("Expected a string literal".to_string(), Span::call_site()))
+    };
+
+    // the literal may be wrapped in an invisible group when it comes from another macro
+    let tree = match tree {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::None => {
+            return single_string_literal(g.stream())
+        },
+        other => other
+    };
+
+    match &tree {
+        TokenTree::Literal(lit) => match parse_string_literal(&lit.to_string()) {
+            Some(s) => Ok((s, lit.span())),
+            None => Err(("Expected a string literal".to_string(), lit.span()))
+        },
+        other => Err(("Expected a string literal".to_string(), other.span()))
+    }
+}
+
+
+/// Decodes the source of a string literal (`"..."` or `r#"..."#`), returns `None` for other literals
+pub(crate) fn parse_string_literal(src: &str) -> Option<String> {
+    if let Some(raw) = src.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = raw.get(hashes..raw.len().checked_sub(hashes)?)?;
+        return inner.strip_prefix('"')?.strip_suffix('"').map(str::to_string)
+    }
+
+    let inner = src.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' => out.push('\\'),
+            '\'' => out.push('\''),
+            '"' => out.push('"'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                out.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+            },
+            'u' => {
+                if chars.next()? != '{' {return None}
+                let hex: String = chars.by_ref().take_while(|c| *c != '}').filter(|c| *c != '_').collect();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            },
+            // line continuation, skip the newline and the leading whitespace of the next line
+            '\n' => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            },
+            _ => return None
+        }
+    }
+    Some(out)
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(parse_string_literal(r#""schema.toml""#).as_deref(), Some("schema.toml"));
+        assert_eq!(parse_string_literal(r#""a\\b\"c\n""#).as_deref(), Some("a\\b\"c\n"));
+        assert_eq!(parse_string_literal(r#""\x41\u{1F600}""#).as_deref(), Some("A\u{1F600}"));
+        assert_eq!(parse_string_literal("\"a\\\n    b\"").as_deref(), Some("ab"));
+        assert_eq!(parse_string_literal(r##"r#"raw "quoted""#"##).as_deref(), Some("raw \"quoted\""));
+        assert_eq!(parse_string_literal(r#"r"raw""#).as_deref(), Some("raw"));
+
+        assert_eq!(parse_string_literal("5"), None);
+        assert_eq!(parse_string_literal("'c'"), None);
+        assert_eq!(parse_string_literal(r#"b"bytes""#), None);
+    }
+
+    #[test]
+    fn schema_sources() {
+        check_schema_source("type = 'int'").unwrap();
+        check_schema_source("type = 'nope'").unwrap_err();
+        check_schema_source("type = ").unwrap_err();
+    }
+}
@@ -0,0 +1,18 @@
+use toml_schema_macros::include_schema;
+
+#[test]
+fn include_schema() {
+    let schema = include_schema!("tests/schemas/server.toml");
+
+    schema.check_str("name = 'api'\nport = 443").unwrap();
+    schema.check_str("name = 'API'").unwrap_err();
+
+    let completed = schema.complete_str("name = 'api'").unwrap();
+    assert!(completed.contains("port = 8080"), "completed document is {}", completed);
+}
+
+#[test]
+fn include_schema_raw_path() {
+    let schema = include_schema!(r"tests/schemas/server.toml");
+    schema.check_str("name = 'api'").unwrap();
+}
@@ -0,0 +1,35 @@
+use toml_schema_macros::toml_schema;
+
+#[test]
+fn inline_schema() {
+    let schema = toml_schema!{
+        name = {type = "string", regex = "^[a-z]+\\d*$"},
+        port = {type = "int", min = 1, max = 65535, default = 8080},
+        offset = {type = "int", min = -10, default = -1},
+        since = {type = "date", default = 1979-05-27T07:32:00Z},
+        build-dependencies = {type = "array", child = {type = "string"}, default = []},
+        mode = {type = "alternative", options = [{type = "bool"}, {type = "float", max = 1.5}]},
+    };
+
+    schema.check_str("name = 'api2'\nport = 443\nmode = 1.0").unwrap();
+    schema.check_str("name = 'API'\nmode = true").unwrap_err();
+    schema.check_str("name = 'api'\nmode = 2.0").unwrap_err();
+
+    let completed = schema.complete_str("name = 'api'\nmode = true").unwrap();
+    assert!(completed.contains("port = 8080"), "completed document is {}", completed);
+    assert!(completed.contains("offset = -1"), "completed document is {}", completed);
+    assert!(completed.contains("since = 1979-05-27T07:32:00Z"), "completed document is {}", completed);
+}
+
+#[test]
+fn literal_schema() {
+    let schema = toml_schema!(r#"
+        type = "array"
+        min = 1
+        child = {type = "string"}
+    "#);
+
+    let doc: toml::Value = toml::Value::Array(vec![toml::Value::String("a".to_string())]);
+    schema.check(&doc).unwrap();
+    schema.check(&toml::Value::Array(vec![])).unwrap_err();
+}